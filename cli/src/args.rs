@@ -0,0 +1,158 @@
+use crate::{Error, Result};
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+/// Command arguments: positional values and `--flag value` options.
+pub struct Args {
+    args: VecDeque<String>,
+}
+
+impl Args {
+    pub fn new(args: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            args: args.into_iter().collect(),
+        }
+    }
+
+    /// Take the first argument which isn't an option or an option value.
+    /// Every `--name` left is taken for an option with a value, so flags
+    /// have to be taken with [`Args::flag`] first.
+    pub fn next_positional(&mut self) -> Option<String> {
+        let mut i = 0;
+        while i < self.args.len() {
+            if self.args[i].starts_with("--") {
+                i += 2;
+            } else {
+                return self.args.remove(i);
+            }
+        }
+        None
+    }
+
+    /// Take the value of `--name` option.
+    pub fn option(&mut self, name: &str) -> Result<Option<String>> {
+        let position = match self.args.iter().position(|arg| arg == name) {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+        self.args.remove(position);
+        self.args
+            .remove(position)
+            .map(Some)
+            .ok_or_else(|| Error::Arguments(format!("missing value for {}", name)))
+    }
+
+    /// Take the values of every `--name` option, in order.
+    pub fn options(&mut self, name: &str) -> Result<Vec<String>> {
+        let mut values = Vec::new();
+        while let Some(value) = self.option(name)? {
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    /// Take `--name` flag, which has no value, returning whether it was given.
+    pub fn flag(&mut self, name: &str) -> bool {
+        match self.args.iter().position(|arg| arg == name) {
+            Some(position) => {
+                self.args.remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take the value of `--name` option parsed as `T`.
+    pub fn parsed_option<T: FromStr>(&mut self, name: &str) -> Result<Option<T>> {
+        match self.option(name)? {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| Error::Arguments(format!("invalid value for {}: {}", name, value))),
+            None => Ok(None),
+        }
+    }
+
+    /// Take the required positional argument described by `what`.
+    pub fn required(&mut self, what: &str) -> Result<String> {
+        self.next_positional()
+            .ok_or_else(|| Error::Arguments(format!("missing {}", what)))
+    }
+
+    /// Fail if there are arguments nothing has taken.
+    pub fn finish(self) -> Result<()> {
+        match self.args.front() {
+            Some(arg) => Err(Error::Arguments(format!("unexpected argument: {}", arg))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Args {
+        Args::new(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_positional_skips_options() {
+        let mut args = args(&["--frames", "10", "game.ch8", "extra"]);
+
+        assert_eq!(args.next_positional().as_deref(), Some("game.ch8"));
+        assert_eq!(args.parsed_option::<u64>("--frames").unwrap(), Some(10));
+        assert_eq!(args.next_positional().as_deref(), Some("extra"));
+        assert_eq!(args.next_positional(), None);
+    }
+
+    #[test]
+    fn test_option_missing_value() {
+        let mut args = args(&["game.ch8", "--frames"]);
+
+        assert!(args.option("--frames").is_err());
+    }
+
+    #[test]
+    fn test_repeated_options() {
+        let mut args = args(&["--patch", "a.ips", "game.ch8", "--patch", "b.ips"]);
+
+        assert_eq!(args.options("--patch").unwrap(), vec!["a.ips", "b.ips"]);
+        assert_eq!(args.required("program").unwrap(), "game.ch8");
+        assert!(args.finish().is_ok());
+    }
+
+    #[test]
+    fn test_flag() {
+        let mut args = args(&["--force", "game"]);
+
+        assert!(args.flag("--force"));
+        assert!(!args.flag("--no-launch"));
+        assert_eq!(args.required("name").unwrap(), "game");
+    }
+
+    #[test]
+    fn test_flag_before_positional() {
+        let mut args = args(&["--sandbox", "game.ch8", "--frames", "10"]);
+
+        assert!(args.flag("--sandbox"));
+        assert_eq!(args.required("program").unwrap(), "game.ch8");
+        assert_eq!(args.parsed_option::<u64>("--frames").unwrap(), Some(10));
+        assert!(args.finish().is_ok());
+    }
+
+    #[test]
+    fn test_parsed_option_invalid() {
+        let mut args = args(&["--frames", "ten"]);
+
+        assert!(args.parsed_option::<u64>("--frames").is_err());
+    }
+
+    #[test]
+    fn test_finish_unexpected() {
+        let mut args = args(&["game.ch8", "--unknown", "1"]);
+        args.required("program").unwrap();
+
+        assert!(args.finish().is_err());
+    }
+}
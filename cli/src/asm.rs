@@ -0,0 +1,53 @@
+//! `chip8 asm`: assemble a program or only check its source.
+
+use crate::{args::Args, Error, Result};
+use chip_8_emulator::asm::{assemble, Diagnostic};
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+pub fn run(mut args: Args) -> Result<()> {
+    let check = args.flag("--check");
+    let source_path = args.required("source path")?;
+    let output_path = args.option("--output")?;
+    args.finish()?;
+
+    let source = if source_path == "-" {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source)?;
+        source
+    } else {
+        fs::read_to_string(&source_path)?
+    };
+
+    let assembly = assemble(&source);
+    for diagnostic in &assembly.diagnostics {
+        println!("{}", located(&source_path, diagnostic));
+    }
+    if assembly.has_errors() {
+        return Err(Error::Assembly(source_path));
+    }
+    if check {
+        return Ok(());
+    }
+
+    let output_path = match output_path {
+        Some(path) => path,
+        None if source_path == "-" => {
+            return Err(Error::Arguments(
+                "--output is needed for standard input".into(),
+            ))
+        }
+        None => Path::new(&source_path)
+            .with_extension("ch8")
+            .to_string_lossy()
+            .into_owned(),
+    };
+    fs::write(output_path, assembly.program)?;
+    Ok(())
+}
+
+/// Diagnostic prefixed with the source path, like compiler output.
+fn located(source_path: &str, diagnostic: &Diagnostic) -> String {
+    format!("{}:{}", source_path, diagnostic)
+}
@@ -0,0 +1,124 @@
+//! `chip8 bisect`: first frame where replays of an input script differ
+//! between platforms, or from the frame hashes recorded with another build.
+
+use crate::{args::Args, debug::instruction_text, explain, render_movie, Error, Result};
+use chip_8_emulator::{
+    bisect::{bisect, Replay},
+    script::InputScript,
+    VM,
+};
+use std::fmt::Write as _;
+use std::fs;
+
+pub fn run(mut args: Args) -> Result<()> {
+    let program_path = args.required("program path")?;
+    let script_path = args.required("input script path")?;
+    let frames = args.parsed_option("--frames")?;
+    let profile = args.option("--profile")?;
+    let against = args.option("--against")?;
+    let hashes_path = args.option("--hashes")?;
+    let record_path = args.option("--record")?;
+    args.finish()?;
+
+    let program = fs::read(&program_path)?;
+    let script = InputScript::parse(&fs::read_to_string(script_path)?)?;
+    let frames = frames.unwrap_or_else(|| render_movie::default_frames(&script));
+    let mut replay = Replay::new(replay_vm(&program, profile.as_deref())?, &script);
+    let label = profile.as_deref().unwrap_or("default");
+
+    match (against, hashes_path, record_path) {
+        (None, None, Some(path)) => {
+            let hashes = replay.frame_hashes(frames);
+            fs::write(&path, format_hashes(&hashes))?;
+            println!(
+                "{}: {} frame hashes to {}",
+                program_path,
+                hashes.len(),
+                path
+            );
+        }
+        (Some(other_profile), None, None) => {
+            let mut other = Replay::new(replay_vm(&program, Some(&other_profile))?, &script);
+            match bisect(&mut replay, &mut other, frames) {
+                Some(frame) => {
+                    println!("{}: first divergent frame {}", program_path, frame);
+                    print_instruction(label, &mut replay, frame);
+                    print_instruction(&other_profile, &mut other, frame);
+                }
+                None => println!("{}: same display after {} frames", program_path, frames),
+            }
+        }
+        (None, Some(path), None) => {
+            let mut hashes = parse_hashes(&fs::read_to_string(&path)?)
+                .ok_or_else(|| Error::Arguments(format!("invalid frame hashes in {}", path)))?;
+            match bisect(&mut replay, &mut hashes[..], frames) {
+                Some(frame) => {
+                    println!("{}: first divergent frame {}", program_path, frame);
+                    print_instruction(label, &mut replay, frame);
+                }
+                None => println!("{}: same display after {} frames", program_path, frames),
+            }
+        }
+        _ => {
+            return Err(Error::Arguments(
+                "expected one of --against, --hashes or --record".into(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// VM with `program` loaded, set up like platform `profile` if given.
+fn replay_vm(program: &[u8], profile: Option<&str>) -> Result<VM> {
+    let mut vm = VM::new();
+    if let Some(profile) = profile {
+        explain::platform(profile)?.configure(&mut vm);
+    }
+    vm.load_program(program)?;
+    Ok(vm)
+}
+
+/// Print the instruction `replay` runs in `frame`.
+fn print_instruction(label: &str, replay: &mut Replay, frame: u64) {
+    match replay.instruction_of(frame) {
+        Some(entry) => println!(
+            "    {}: {:#05X}  {:04X}  {}",
+            label,
+            entry.pc,
+            entry.instruction,
+            instruction_text(entry.instruction)
+        ),
+        None => println!("    {}: no instruction, the replay stopped before", label),
+    }
+}
+
+/// One hash per line in hexadecimal, frame 0 first.
+fn format_hashes(hashes: &[u64]) -> String {
+    let mut text = String::new();
+    for hash in hashes {
+        // Writing to a `String` never fails.
+        writeln!(text, "{:016x}", hash).unwrap();
+    }
+    text
+}
+
+fn parse_hashes(text: &str) -> Option<Vec<u64>> {
+    text.lines()
+        .map(|line| u64::from_str_radix(line.trim(), 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashes_round_trip() {
+        let hashes = [0, 0xcbf29ce484222325, u64::MAX];
+        let text = format_hashes(&hashes);
+
+        assert_eq!(text.lines().count(), 3);
+        assert_eq!(parse_hashes(&text).unwrap(), hashes);
+        assert_eq!(parse_hashes("12\nxyz\n"), None);
+    }
+}
@@ -0,0 +1,186 @@
+//! `chip8 bless`: run the `chip8 compat` suite again and store the display
+//! hashes it gets as the expected ones, after a change of behavior which is
+//! meant, printing what changed so it can be reviewed before committing.
+
+use crate::compat::{check, suite_programs, Expectation, Outcome, EXPECTATION_EXTENSION};
+use crate::{args::Args, explain, Error, Result};
+use chip_8_emulator::platform::Platform;
+use std::fs;
+use std::path::Path;
+
+/// What blessing did to the expected hash of a platform.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Change {
+    Unchanged,
+    Added(u64),
+    Changed {
+        old: u64,
+        new: u64,
+    },
+    /// Program failed to load or run, its hash is left as it was.
+    Error(String),
+}
+
+#[derive(Debug, Default)]
+struct Summary {
+    unchanged: usize,
+    added: usize,
+    changed: usize,
+    errors: usize,
+}
+
+pub fn run(mut args: Args) -> Result<()> {
+    let suite = args
+        .option("--suite")?
+        .ok_or_else(|| Error::Arguments("missing --suite".into()))?;
+    let platforms = args
+        .options("--platform")?
+        .iter()
+        .map(|name| explain::platform(name))
+        .collect::<Result<Vec<_>>>()?;
+    let dry_run = args.flag("--dry-run");
+    args.finish()?;
+
+    let mut summary = Summary::default();
+    for path in suite_programs(Path::new(&suite))? {
+        let expectation_path = path.with_extension(EXPECTATION_EXTENSION);
+        let text = fs::read_to_string(&expectation_path)?;
+        let expectation = Expectation::parse(&text).ok_or_else(|| {
+            Error::Arguments(format!("invalid expectations of {}", path.display()))
+        })?;
+        let program = fs::read(&path)?;
+        let name = path.strip_prefix(&suite).unwrap_or(&path).display();
+
+        let mut hashes = Vec::new();
+        for (platform, change) in bless(&program, &expectation, &platforms) {
+            match change {
+                Change::Unchanged => summary.unchanged += 1,
+                Change::Added(hash) => {
+                    summary.added += 1;
+                    println!("{}: {} added {:016x}", name, platform, hash);
+                    hashes.push((platform, hash));
+                }
+                Change::Changed { old, new } => {
+                    summary.changed += 1;
+                    println!("{}: {} {:016x} -> {:016x}", name, platform, old, new);
+                    hashes.push((platform, new));
+                }
+                Change::Error(error) => {
+                    summary.errors += 1;
+                    println!("{}: {} kept, {}", name, platform, error);
+                }
+            }
+        }
+        if !hashes.is_empty() && !dry_run {
+            fs::write(&expectation_path, with_hashes(&text, &hashes))?;
+        }
+    }
+    println!(
+        "{} changed, {} added, {} unchanged, {} failed to run{}",
+        summary.changed,
+        summary.added,
+        summary.unchanged,
+        summary.errors,
+        if dry_run { ", nothing written" } else { "" }
+    );
+    Ok(())
+}
+
+/// Run `program` on `platforms`, or on the platforms `expectation` has a
+/// hash for when there are none, and tell how their hashes change.
+fn bless(
+    program: &[u8],
+    expectation: &Expectation,
+    platforms: &[Platform],
+) -> Vec<(&'static str, Change)> {
+    Platform::ALL
+        .iter()
+        .filter(|platform| {
+            if platforms.is_empty() {
+                expectation.hash(platform).is_some()
+            } else {
+                platforms.contains(platform)
+            }
+        })
+        .map(|platform| {
+            let change = match check(program, expectation, platform) {
+                Outcome::Pass => Change::Unchanged,
+                Outcome::Fail(new) => Change::Changed {
+                    // Only platforms with a hash can fail.
+                    old: expectation.hash(platform).unwrap_or_default(),
+                    new,
+                },
+                Outcome::Unknown(hash) => Change::Added(hash),
+                Outcome::Error(error) => Change::Error(error),
+            };
+            (platform.name, change)
+        })
+        .collect()
+}
+
+/// Expectation file `text` with the hashes of platforms in `hashes`
+/// replaced, or added at the end, keeping everything else as it was.
+fn with_hashes(text: &str, hashes: &[(&str, u64)]) -> String {
+    let mut missing = hashes.to_vec();
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let key = line.trim().split(' ').next().unwrap_or("");
+        match missing.iter().position(|&(platform, _)| platform == key) {
+            Some(i) => {
+                let (platform, hash) = missing.remove(i);
+                lines.push(format!("{} {:016x}", platform, hash));
+            }
+            None => lines.push(line.to_string()),
+        }
+    }
+    lines.extend(
+        missing
+            .iter()
+            .map(|(platform, hash)| format!("{} {:016x}", platform, hash)),
+    );
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chip_8_emulator::platform::{CHIP8, SCHIP};
+
+    // LD V0, 1; LD V1, 2; SHR V0, V1; LD F, V0; DRW V2, V2, 5; JP 0x20A
+    const SHIFT: [u8; 12] = [
+        0x60, 0x01, 0x61, 0x02, 0x80, 0x16, 0xF0, 0x29, 0xD2, 0x25, 0x12, 0x0A,
+    ];
+
+    #[test]
+    fn test_bless() {
+        let expectation = Expectation::parse("frames 2\nchip8 00ff").unwrap();
+        let changes = bless(&SHIFT, &expectation, &[]);
+        let hash = match changes.as_slice() {
+            [("chip8", Change::Changed { old: 0xFF, new })] => *new,
+            changes => panic!("{:?}", changes),
+        };
+
+        let text = format!("frames 2\nchip8 {:016x}", hash);
+        let expectation = Expectation::parse(&text).unwrap();
+        let changes = bless(&SHIFT, &expectation, &[CHIP8, SCHIP]);
+        assert_eq!(changes[0], ("chip8", Change::Unchanged));
+        assert!(matches!(changes[1], ("schip", Change::Added(_))));
+
+        assert_eq!(
+            bless(&[], &expectation, &[]),
+            [("chip8", Change::Error("program is empty".into()))]
+        );
+    }
+
+    #[test]
+    fn test_with_hashes() {
+        let text = "# shift test\nframes 10\nchip8 00000000000000ff\nschip 1\n";
+
+        assert_eq!(
+            with_hashes(text, &[("schip", 0xAB), ("xochip", 0xCD)]),
+            "# shift test\nframes 10\nchip8 00000000000000ff\nschip 00000000000000ab\n\
+             xochip 00000000000000cd\n"
+        );
+        assert_eq!(with_hashes("frames 10", &[]), "frames 10\n");
+    }
+}
@@ -0,0 +1,371 @@
+//! `chip8 compat`: run a suite of test programs on every platform profile
+//! and report which pass, as a matrix to publish and track over time.
+//!
+//! Programs of the suite are the ones with an expectation file next to
+//! them, e.g. `quirks.expect` for `quirks.ch8`, with one setting a line:
+//!
+//! ```text
+//! # Frames of 1/60 s to run, 600 by default.
+//! frames 300
+//! # Quirk the program tests, any number of them.
+//! quirk shift_uses_vy
+//! # Keys pressed as in input scripts.
+//! frame 10: press 1
+//! frame 12: release 1
+//! # Hash of the display a platform passes with, as the JSON report has it.
+//! chip8 5c0d3b1e8f2a4d67
+//! ```
+//!
+//! Platforms without a hash are run but can't pass or fail.
+
+use crate::{args::Args, disasm::json_string, scan::collect_programs, Error, Result};
+use chip_8_emulator::{platform::Platform, script::InputScript, VM};
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_FRAMES: u64 = 600;
+pub(crate) const EXPECTATION_EXTENSION: &str = "expect";
+
+/// What a program of the suite should do.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Expectation {
+    frames: u64,
+    /// Quirks the program tests.
+    quirks: Vec<String>,
+    script: InputScript,
+    /// Display hash passing on a platform, by platform name.
+    hashes: Vec<(&'static str, u64)>,
+}
+
+impl Expectation {
+    /// Parse expectation file `text`, `None` if a line is invalid.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let mut expectation = Expectation {
+            frames: DEFAULT_FRAMES,
+            ..Expectation::default()
+        };
+        let mut script = String::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(' ')?;
+            let value = value.trim();
+            match key {
+                "frames" => expectation.frames = value.parse().ok()?,
+                "quirk" => {
+                    let known = Platform::ALL[0]
+                        .quirks
+                        .list()
+                        .iter()
+                        .any(|&(name, _)| name == value);
+                    if !known {
+                        return None;
+                    }
+                    expectation.quirks.push(value.to_string());
+                }
+                "frame" => {
+                    script.push_str(line);
+                    script.push('\n');
+                }
+                name => {
+                    let platform = Platform::from_name(name)?;
+                    let hash = u64::from_str_radix(value, 16).ok()?;
+                    expectation.hashes.push((platform.name, hash));
+                }
+            }
+        }
+        expectation.script = InputScript::parse(&script).ok()?;
+        Some(expectation)
+    }
+
+    pub(crate) fn hash(&self, platform: &Platform) -> Option<u64> {
+        self.hashes
+            .iter()
+            .find(|&&(name, _)| name == platform.name)
+            .map(|&(_, hash)| hash)
+    }
+}
+
+/// Result of a program on a platform.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Pass,
+    /// Display hash isn't the expected one.
+    Fail(u64),
+    /// No hash to compare with, the display hash it got.
+    Unknown(u64),
+    /// Program failed to load or run.
+    Error(String),
+}
+
+impl Outcome {
+    fn status(&self) -> &'static str {
+        match self {
+            Outcome::Pass => "pass",
+            Outcome::Fail(_) => "fail",
+            Outcome::Unknown(_) => "unknown",
+            Outcome::Error(_) => "error",
+        }
+    }
+}
+
+/// Results of a program, one per platform of [`Platform::ALL`].
+struct Row {
+    path: String,
+    quirks: Vec<String>,
+    outcomes: Vec<Outcome>,
+}
+
+pub fn run(mut args: Args) -> Result<()> {
+    let suite = args
+        .option("--suite")?
+        .ok_or_else(|| Error::Arguments("missing --suite".into()))?;
+    let format = args.option("--format")?;
+    args.finish()?;
+    let write = match format.as_deref() {
+        None | Some("markdown") => write_markdown,
+        Some("json") => write_json,
+        Some(format) => return Err(Error::Arguments(format!("unknown format: {}", format))),
+    };
+
+    let mut rows = Vec::new();
+    for path in suite_programs(Path::new(&suite))? {
+        let text = fs::read_to_string(path.with_extension(EXPECTATION_EXTENSION))?;
+        let expectation = Expectation::parse(&text).ok_or_else(|| {
+            Error::Arguments(format!("invalid expectations of {}", path.display()))
+        })?;
+        let program = fs::read(&path)?;
+        rows.push(Row {
+            path: path
+                .strip_prefix(&suite)
+                .unwrap_or(&path)
+                .display()
+                .to_string(),
+            quirks: expectation.quirks.clone(),
+            outcomes: Platform::ALL
+                .iter()
+                .map(|platform| check(&program, &expectation, platform))
+                .collect(),
+        });
+    }
+    let mut out = BufWriter::new(io::stdout().lock());
+    write(&mut out, &rows)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Programs under `dir` with an expectation file.
+pub(crate) fn suite_programs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    Ok(collect_programs(dir)?
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .is_none_or(|ext| ext != EXPECTATION_EXTENSION)
+                && path.with_extension(EXPECTATION_EXTENSION).is_file()
+        })
+        .collect())
+}
+
+/// Run `program` on `platform` for the frames of `expectation`, pressing
+/// its keys, and compare the display.
+pub(crate) fn check(program: &[u8], expectation: &Expectation, platform: &Platform) -> Outcome {
+    let mut vm = VM::new();
+    platform.configure(&mut vm);
+    if let Err(e) = vm.load_program(program) {
+        return Outcome::Error(e.to_string());
+    }
+    let mut player = expectation.script.player();
+    for frame in 0..expectation.frames {
+        if let Some(input) = player.input_for(frame) {
+            vm.set_input(input);
+        }
+        if let Err(e) = vm.run_frame() {
+            return Outcome::Error(e.to_string());
+        }
+    }
+    let hash = vm.display_hash();
+    match expectation.hash(platform) {
+        Some(expected) if expected == hash => Outcome::Pass,
+        Some(_) => Outcome::Fail(hash),
+        None => Outcome::Unknown(hash),
+    }
+}
+
+/// Table of programs by platform, then of quirks by platform with how many
+/// of the programs testing the quirk pass.
+fn write_markdown(out: &mut dyn Write, rows: &[Row]) -> io::Result<()> {
+    let names: Vec<_> = Platform::ALL.iter().map(|platform| platform.name).collect();
+    let separator = vec!["---"; names.len()].join(" | ");
+
+    writeln!(out, "| Program | Quirks | {} |", names.join(" | "))?;
+    writeln!(out, "| --- | --- | {} |", separator)?;
+    for row in rows {
+        let statuses: Vec<_> = row.outcomes.iter().map(Outcome::status).collect();
+        writeln!(
+            out,
+            "| {} | {} | {} |",
+            row.path.replace('|', "\\|"),
+            row.quirks.join(", "),
+            statuses.join(" | ")
+        )?;
+    }
+
+    let quirks = Platform::ALL[0].quirks.list();
+    let tested: Vec<_> = quirks
+        .iter()
+        .map(|&(quirk, _)| quirk)
+        .filter(|quirk| rows.iter().any(|row| row.quirks.iter().any(|q| q == quirk)))
+        .collect();
+    if tested.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "| Quirk | {} |", names.join(" | "))?;
+    writeln!(out, "| --- | {} |", separator)?;
+    for quirk in tested {
+        let rows: Vec<_> = rows
+            .iter()
+            .filter(|row| row.quirks.iter().any(|q| q == quirk))
+            .collect();
+        let cells: Vec<_> = Platform::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, platform)| {
+                let passed = rows
+                    .iter()
+                    .filter(|row| row.outcomes[i] == Outcome::Pass)
+                    .count();
+                let on = platform
+                    .quirks
+                    .list()
+                    .iter()
+                    .any(|&(name, on)| name == quirk && on);
+                format!(
+                    "{}/{} ({})",
+                    passed,
+                    rows.len(),
+                    if on { "on" } else { "off" }
+                )
+            })
+            .collect();
+        writeln!(out, "| {} | {} |", quirk, cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+/// JSON array with an object per program, one per line, with its result on
+/// every platform.
+fn write_json(out: &mut dyn Write, rows: &[Row]) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, row) in rows.iter().enumerate() {
+        let quirks: Vec<_> = row.quirks.iter().map(|quirk| json_string(quirk)).collect();
+        let results: Vec<_> = Platform::ALL
+            .iter()
+            .zip(&row.outcomes)
+            .map(|(platform, outcome)| {
+                let detail = match outcome {
+                    Outcome::Pass => String::new(),
+                    Outcome::Fail(hash) | Outcome::Unknown(hash) => {
+                        format!(",\"frame_hash\":\"{:016x}\"", hash)
+                    }
+                    Outcome::Error(error) => format!(",\"error\":{}", json_string(error)),
+                };
+                format!(
+                    "{{\"platform\":{},\"status\":{}{}}}",
+                    json_string(platform.name),
+                    json_string(outcome.status()),
+                    detail
+                )
+            })
+            .collect();
+        let separator = if i + 1 < rows.len() { "," } else { "" };
+        writeln!(
+            out,
+            "  {{\"path\":{},\"quirks\":[{}],\"results\":[{}]}}{}",
+            json_string(&row.path),
+            quirks.join(","),
+            results.join(","),
+            separator
+        )?;
+    }
+    writeln!(out, "]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chip_8_emulator::platform::{CHIP8, SCHIP};
+
+    // LD V0, 1; LD V1, 2; SHR V0, V1; LD F, V0; DRW V2, V2, 5; JP 0x20A
+    // Draws 1 where the shift uses VY, 0 otherwise.
+    const SHIFT: [u8; 12] = [
+        0x60, 0x01, 0x61, 0x02, 0x80, 0x16, 0xF0, 0x29, 0xD2, 0x25, 0x12, 0x0A,
+    ];
+
+    #[test]
+    fn test_parse_expectation() {
+        let text = "# shift test\nframes 10\nquirk shift_uses_vy\nframe 2: press 1\nchip8 00ff\n";
+        let expectation = Expectation::parse(text).unwrap();
+
+        assert_eq!(expectation.frames, 10);
+        assert_eq!(expectation.quirks, ["shift_uses_vy"]);
+        assert_eq!(expectation.script.events().len(), 1);
+        assert_eq!(expectation.hash(&CHIP8), Some(0xFF));
+        assert_eq!(expectation.hash(&SCHIP), None);
+
+        assert_eq!(Expectation::parse("quirk bogus"), None);
+        assert_eq!(Expectation::parse("cosmac 00ff"), None);
+        assert_eq!(Expectation::parse("frames"), None);
+    }
+
+    #[test]
+    fn test_check() {
+        let unknown = |outcome| match outcome {
+            Outcome::Unknown(hash) => hash,
+            outcome => panic!("{:?}", outcome),
+        };
+        let mut expectation = Expectation::parse("frames 2\nquirk shift_uses_vy").unwrap();
+        let chip8_hash = unknown(check(&SHIFT, &expectation, &CHIP8));
+        expectation.hashes.push(("chip8", chip8_hash));
+        expectation.hashes.push(("schip", chip8_hash));
+
+        assert_eq!(check(&SHIFT, &expectation, &CHIP8), Outcome::Pass);
+        assert!(matches!(
+            check(&SHIFT, &expectation, &SCHIP),
+            Outcome::Fail(_)
+        ));
+        assert_eq!(
+            check(&[], &expectation, &CHIP8),
+            Outcome::Error("program is empty".into())
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        let row = Row {
+            path: "shift.ch8".into(),
+            quirks: vec!["shift_uses_vy".into()],
+            outcomes: vec![
+                Outcome::Pass,
+                Outcome::Fail(0),
+                Outcome::Fail(0),
+                Outcome::Unknown(0),
+            ],
+        };
+        let mut out = Vec::new();
+        write_markdown(&mut out, &[row]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "| Program | Quirks | chip8 | chip48 | schip | xochip |\n\
+             | --- | --- | --- | --- | --- | --- |\n\
+             | shift.ch8 | shift_uses_vy | pass | fail | fail | unknown |\n\
+             \n\
+             | Quirk | chip8 | chip48 | schip | xochip |\n\
+             | --- | --- | --- | --- | --- |\n\
+             | shift_uses_vy | 1/1 (on) | 0/1 (off) | 0/1 (off) | 0/1 (on) |\n"
+        );
+    }
+}
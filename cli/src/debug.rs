@@ -0,0 +1,129 @@
+//! `chip8 debug --crash`: show a crash dump written by a frontend and replay
+//! the failure from it.
+
+use crate::{args::Args, scan::word_at_pc, Error, Result};
+use chip_8_emulator::{crash::CrashDump, instruction::Instruction, vm::VM};
+use std::fs;
+use std::io::{self, Write};
+
+const DEFAULT_STEPS: usize = 1;
+
+pub fn run(mut args: Args) -> Result<()> {
+    let crash_path = args
+        .option("--crash")?
+        .ok_or_else(|| Error::Arguments("missing --crash".into()))?;
+    let steps = args.parsed_option("--steps")?.unwrap_or(DEFAULT_STEPS);
+    args.finish()?;
+
+    let dump = CrashDump::parse(&fs::read_to_string(crash_path)?)?;
+    let stdout = io::stdout();
+    report(&dump, steps, &mut stdout.lock())?;
+    Ok(())
+}
+
+/// Assembly of `word`, `????` if it isn't an instruction.
+pub(crate) fn instruction_text(word: u16) -> String {
+    Instruction::decode(word).map_or_else(|_| "????".to_string(), |i| i.to_string())
+}
+
+/// Describe `dump`, then restore it and run up to `steps` instructions,
+/// stopping at the first which fails.
+fn report(dump: &CrashDump, steps: usize, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "error: {}", dump.error)?;
+    writeln!(out, "program: {:016x}", dump.program_hash)?;
+    for (name, value) in &dump.config {
+        writeln!(out, "config: {} = {}", name, value)?;
+    }
+    let quirks: Vec<_> = dump
+        .quirks
+        .list()
+        .into_iter()
+        .filter(|&(_, on)| on)
+        .map(|(name, _)| name)
+        .collect();
+    if quirks.is_empty() {
+        writeln!(out, "quirks: none")?;
+    } else {
+        writeln!(out, "quirks: {}", quirks.join(" "))?;
+    }
+    let registers = &dump.registers;
+    writeln!(
+        out,
+        "pc: {:#05X}  i: {:#05X}  dt: {}  st: {}",
+        registers.program_counter, registers.i, registers.delay_timer, registers.sound_timer
+    )?;
+    let v: Vec<_> = registers.v.iter().map(|v| format!("{:02X}", v)).collect();
+    writeln!(out, "v: {}", v.join(" "))?;
+    let stack: Vec<_> = dump.stack.iter().map(|a| format!("{:#05X}", a)).collect();
+    writeln!(out, "stack: {}", stack.join(" "))?;
+
+    if !dump.trace.is_empty() {
+        writeln!(out, "\ntrace:")?;
+        for entry in &dump.trace {
+            writeln!(
+                out,
+                "    {:#05X}  {:04X}  {}",
+                entry.pc,
+                entry.instruction,
+                instruction_text(entry.instruction)
+            )?;
+        }
+    }
+
+    writeln!(out, "\nreplay:")?;
+    let mut vm = VM::new();
+    dump.restore(&mut vm);
+    for _ in 0..steps {
+        let pc = vm.registers().program_counter;
+        let Some(word) = word_at_pc(&vm) else {
+            return writeln!(out, "    {:#05X}  end of memory", pc);
+        };
+        writeln!(
+            out,
+            "    {:#05X}  {:04X}  {}",
+            pc,
+            word,
+            instruction_text(word)
+        )?;
+        if let Err(e) = vm.exec_current_instruction() {
+            return writeln!(out, "failed: {}", e);
+        }
+    }
+    writeln!(out, "no failure in {} instructions", steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_replays_failure() {
+        let mut vm = VM::new();
+        vm.enable_trace(8);
+        // LD V0, 1; CALL 0x206; LD V1, 2; <unknown>
+        vm.load_program(&[0x60, 0x01, 0x22, 0x06, 0x00, 0x00, 0x61, 0x02, 0xFF, 0xFF])
+            .unwrap();
+        let error = (0..4)
+            .find_map(|_| vm.exec_current_instruction().err())
+            .unwrap();
+        let mut dump = CrashDump::new(&vm, error);
+        dump.config.push(("speed".into(), "600".into()));
+        let dump = CrashDump::parse(&dump.to_text()).unwrap();
+
+        let mut out = Vec::new();
+        report(&dump, 1, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with(&format!("error: {}\n", error)), "{}", out);
+        assert!(out.contains("config: speed = 600\n"), "{}", out);
+        assert!(out.contains("stack: 0x202\n"), "{}", out);
+        assert!(out.contains("    0x202  2206  CALL 0x206\n"), "{}", out);
+        assert!(
+            out.ends_with(&format!(
+                "replay:\n    0x208  FFFF  ????\nfailed: {}\n",
+                error
+            )),
+            "{}",
+            out
+        );
+    }
+}
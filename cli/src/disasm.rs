@@ -0,0 +1,120 @@
+//! `chip8 disasm`: disassembly as text or JSON.
+
+use crate::{args::Args, Error, Result};
+use chip_8_emulator::disasm::{disassemble, Line};
+use std::fs;
+use std::io::{self, BufWriter, Write};
+
+pub fn run(mut args: Args) -> Result<()> {
+    let program_path = args.required("program path")?;
+    let format = args.option("--format")?;
+    args.finish()?;
+
+    let program = fs::read(program_path)?;
+    let lines = disassemble(&program);
+    let mut out = BufWriter::new(io::stdout().lock());
+    match format.as_deref() {
+        None | Some("text") => write_text(&mut out, &lines)?,
+        Some("json") => write_json(&mut out, &lines)?,
+        Some(format) => return Err(Error::Arguments(format!("unknown format: {}", format))),
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// One line per instruction: address, bytes and assembly text.
+fn write_text(out: &mut impl Write, lines: &[Line]) -> io::Result<()> {
+    for line in lines {
+        let bytes: Vec<_> = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        writeln!(
+            out,
+            "0x{:03X}  {:<12} {}",
+            line.address,
+            bytes.join(" "),
+            line.text()
+        )?;
+    }
+    Ok(())
+}
+
+/// JSON array with an object per instruction, one per line:
+/// `{"address":512,"bytes":[96,5],"mnemonic":"LD","operands":["V0","0x05"]}`.
+fn write_json(out: &mut impl Write, lines: &[Line]) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, line) in lines.iter().enumerate() {
+        let bytes: Vec<_> = line.bytes.iter().map(u8::to_string).collect();
+        let operands: Vec<_> = line
+            .operands()
+            .iter()
+            .map(|operand| json_string(operand))
+            .collect();
+        let separator = if i + 1 < lines.len() { "," } else { "" };
+        writeln!(
+            out,
+            "  {{\"address\":{},\"bytes\":[{}],\"mnemonic\":{},\"operands\":[{}]}}{}",
+            line.address,
+            bytes.join(","),
+            json_string(line.mnemonic()),
+            operands.join(","),
+            separator
+        )?;
+    }
+    writeln!(out, "]")
+}
+
+pub(crate) fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(write: fn(&mut Vec<u8>, &[Line]) -> io::Result<()>, program: &[u8]) -> String {
+        let mut out = Vec::new();
+        write(&mut out, &disassemble(program)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_text() {
+        let text = output(write_text, &[0x60, 0x05, 0xF0, 0x00, 0x12, 0x34]);
+
+        assert_eq!(
+            text,
+            "0x200  60 05        LD V0, 0x05\n0x202  F0 00 12 34  LDL I, 0x1234\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let json = output(write_json, &[0x60, 0x05, 0xFF, 0xFF]);
+
+        assert_eq!(
+            json,
+            "[\n  {\"address\":512,\"bytes\":[96,5],\"mnemonic\":\"LD\",\"operands\":[\"V0\",\"0x05\"]},\n  \
+             {\"address\":514,\"bytes\":[255,255],\"mnemonic\":\"DB\",\"operands\":[\"0xFF\",\"0xFF\"]}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_json_empty_program() {
+        assert_eq!(output(write_json, &[]), "[\n]\n");
+    }
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+    }
+}
@@ -0,0 +1,90 @@
+//! `chip8 explain`: descriptions of emulator concepts.
+
+use crate::{args::Args, Error, Result};
+use chip_8_emulator::palette::{Palette, Preset};
+use chip_8_emulator::platform::Platform;
+
+pub fn run(mut args: Args) -> Result<()> {
+    let texts: Vec<_> = if args.flag("--platform") {
+        let name = args.next_positional();
+        args.finish()?;
+        match name {
+            Some(name) => vec![platform(&name)?.to_string()],
+            None => Platform::ALL.iter().map(Platform::to_string).collect(),
+        }
+    } else if args.flag("--palette") {
+        let name = args.next_positional();
+        args.finish()?;
+        match name {
+            Some(name) => vec![palette(&name)?.to_string()],
+            None => Palette::PRESETS.iter().map(Preset::to_string).collect(),
+        }
+    } else {
+        return Err(Error::Arguments(
+            "nothing to explain, try --platform or --palette".into(),
+        ));
+    };
+    println!("{}", texts.join("\n\n"));
+    Ok(())
+}
+
+/// Platform called `name`, as accepted by `--profile`.
+pub fn platform(name: &str) -> Result<Platform> {
+    Platform::from_name(name).ok_or_else(|| {
+        let names: Vec<_> = Platform::ALL.iter().map(|p| p.name).collect();
+        Error::Arguments(format!(
+            "unknown platform: {}, expected one of {}",
+            name,
+            names.join(", ")
+        ))
+    })
+}
+
+/// Palette preset called `name`, as accepted by `--palette`.
+pub fn palette(name: &str) -> Result<Preset> {
+    Palette::PRESETS
+        .into_iter()
+        .find(|preset| preset.name == name)
+        .ok_or_else(|| {
+            let names: Vec<_> = Palette::PRESETS.iter().map(|p| p.name).collect();
+            Error::Arguments(format!(
+                "unknown palette: {}, expected one of {}",
+                name,
+                names.join(", ")
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform() {
+        assert_eq!(platform("schip").unwrap().name, "schip");
+        match platform("megachip") {
+            Err(Error::Arguments(message)) => {
+                assert_eq!(
+                    message,
+                    "unknown platform: megachip, expected one of chip8, chip48, schip, xochip"
+                );
+            }
+            _ => panic!("expected arguments error"),
+        }
+    }
+
+    #[test]
+    fn test_palette() {
+        assert_eq!(palette("lcd").unwrap().name, "lcd");
+        match palette("sepia") {
+            Err(Error::Arguments(message)) => {
+                assert_eq!(
+                    message,
+                    "unknown palette: sepia, expected one of grayscale, octo, lcd, \
+                     high-contrast, color-blind"
+                );
+            }
+            _ => panic!("expected arguments error"),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! `chip8 fetch`: download a program from the CHIP-8 archive and launch it.
+
+use crate::{args::Args, library, Error, Result};
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Programs of the public-domain archive by John Earnest,
+/// https://github.com/JohnEarnest/chip8Archive.
+const ARCHIVE_URL: &str = "https://johnearnest.github.io/chip8Archive/roms";
+const GUI_APP: &str = "chip-8-emulator-gui-app";
+
+pub fn run(mut args: Args) -> Result<()> {
+    let force = args.flag("--force");
+    let launch = !args.flag("--no-launch");
+    let name = args.required("program name")?;
+    args.finish()?;
+
+    let dir = library::dir()?;
+    let path = library::program_path(&dir, &name)?;
+    if force || !path.exists() {
+        let program = download(&name)?;
+        fs::create_dir_all(&dir)?;
+        fs::write(&path, program)?;
+        println!("Saved {}", path.display());
+    }
+
+    if launch {
+        launch_gui(&path)
+    } else {
+        Ok(())
+    }
+}
+
+fn download(name: &str) -> Result<Vec<u8>> {
+    let url = format!("{}/{}.{}", ARCHIVE_URL, name, library::EXTENSION);
+    let response = ureq::get(&url).call().map_err(|e| match e {
+        ureq::Error::Status(404, _) => Error::Fetch(format!("no program {} in archive", name)),
+        e => Error::Fetch(e.to_string()),
+    })?;
+    let mut program = Vec::new();
+    response.into_reader().read_to_end(&mut program)?;
+    Ok(program)
+}
+
+/// Start the GUI app installed next to this executable, or found in `PATH`.
+fn launch_gui(program_path: &Path) -> Result<()> {
+    let gui_app = env::current_exe()
+        .ok()
+        .map(|exe| {
+            exe.with_file_name(GUI_APP)
+                .with_extension(env::consts::EXE_EXTENSION)
+        })
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(GUI_APP));
+    let status = Command::new(&gui_app)
+        .arg(program_path)
+        .status()
+        .map_err(|e| Error::Fetch(format!("can't launch {}: {}", gui_app.display(), e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::Fetch(format!("{} exited with {}", GUI_APP, status)))
+    }
+}
@@ -0,0 +1,226 @@
+//! `chip8` command line tool for running and inspecting CHIP-8 programs
+//! without a window.
+
+pub mod args;
+pub mod asm;
+pub mod bisect;
+pub mod bless;
+pub mod compat;
+pub mod debug;
+pub mod disasm;
+pub mod explain;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+pub mod library;
+pub mod opcodes;
+pub mod render_movie;
+pub mod run;
+pub mod scan;
+pub mod selftest;
+
+use args::Args;
+use chip_8_emulator::{
+    crash::CrashError, memory::LoadError, patch::PatchError, script::ScriptError, vm::VmError,
+};
+
+const USAGE: &str = "\
+Usage: chip8 <command> [options]
+
+Commands:
+    run <program> [--frames N] [--dump-video FILE] [--scale N] [--palette P]
+                  [--dump-audio FILE] [--input FILE] [--timer-instructions N]
+                  [--sprite-rows N] [--patch FILE]... [--profile NAME]
+                  [--speed N] [--console ADDR] [--sandbox]
+                  [--crash-dump FILE]
+        Run program headlessly for N frames (600 by default), optionally
+        writing every frame to a Y4M video and the beeper sound to a WAV
+        file. Video colors are palette P: grayscale, octo, lcd,
+        high-contrast, color-blind or custom `RRGGBB,RRGGBB` or
+        `RRGGBB,RRGGBB,RRGGBB,RRGGBB`. Keys are pressed as the input script
+        FILE says, one `frame N: press K` or `frame N: release K` line per
+        event. With --timer-instructions timers tick every N instructions
+        instead of every frame, like some historical interpreters. With
+        --sprite-rows sprites are drawn N rows a frame, tearing like on the
+        COSMAC VIP. Patches, IPS files or patch lists, are applied to the
+        program in order before it's loaded.
+        --profile sets the VM up like platform NAME where it can be.
+        A frame is a 60th of a second: 10 instructions and a timer tick,
+        or with --speed N instructions a second like the GUI.
+        --sandbox skips instructions which fail instead of stopping, and
+        prints the last of them, for scanning untrusted programs.
+        --console prints bytes the program stores at ADDR as text, for
+        test programs reporting their results.
+        --crash-dump writes a crash dump to FILE if the program fails, see
+        debug.
+
+    render-movie <program> <input script> -o FILE [--palette P] [--scale N]
+                 [--frames N]
+        Replay the input script and encode the display as a GIF or Y4M
+        video, chosen by the FILE extension. Runs until a second after the
+        last input event unless --frames is given.
+
+    asm <source> [--output FILE] [--check]
+        Assemble source, `-` for standard input, into FILE, the source path
+        with `ch8` extension by default. Diagnostics are printed as
+        `source:line:column: severity: message`; with --check nothing is
+        written.
+
+    bisect <program> <input script> [--frames N] [--profile NAME]
+           (--against NAME | --hashes FILE | --record FILE)
+        Replay the input script like render-movie, with the VM set up like
+        platform NAME with --profile, and find the first frame where the
+        display differs from the replay set up like platform --against
+        NAME, or from the frame hashes in FILE. Prints that frame and the
+        instruction each replay runs in it. --record writes the frame
+        hashes to FILE instead, to compare with another build.
+
+    disasm <program> [--format text|json]
+        Print program instructions. JSON output is an array of objects with
+        address, bytes, mnemonic and operands fields.
+
+    debug --crash FILE [--steps N]
+        Show the crash dump FILE written by a frontend: error, program hash,
+        settings, registers and the last instructions run. Then restore the
+        VM from it and run up to N instructions (1 by default), printing
+        each, to replay the failure.
+
+    explain --platform [NAME]
+        Describe platform NAME, or all of chip8, chip48, schip and xochip:
+        display modes, memory size, opcode families and default quirks.
+
+    explain --palette [NAME]
+        Describe palette NAME, or all presets: colors, contrast against the
+        background and whether they're told apart with color blindness.
+
+    scan <directory> [--seconds N] [--format csv|json]
+        Run every program under the directory for N seconds (5 by default)
+        in sandbox mode with no keys pressed, and report per program the
+        instructions run, whether it halted, faults skipped and the last of
+        them, patterns of the instructions used, words which aren't
+        instructions, the platform it most likely targets and a hash of
+        the final display. `.txt` files are skipped.
+
+    opcodes <program> [--static | --dynamic] [--seconds N]
+        Count uses of every instruction pattern, e.g. 8xy6, in the program
+        bytes and executed running it for N seconds (5 by default) in
+        sandbox mode, then print the platform it needs and the quirks
+        which change instructions it uses. --static only reads the bytes,
+        --dynamic only runs it.
+
+    compat --suite DIR [--format markdown|json]
+        Run every program under DIR with an expectation file next to it,
+        e.g. quirks.expect for quirks.ch8, on every platform profile and
+        print whether each passes as a matrix, with how many of the
+        programs testing each quirk pass. Expectation files have `frames N`
+        to run, `quirk NAME` lines for quirks tested, input script lines
+        and `PLATFORM HASH` lines with the display hash passing on a
+        platform.
+
+    bless --suite DIR [--platform NAME]... [--dry-run]
+        Run the compat suite under DIR again and store the display hashes
+        it gets in the expectation files, for when behavior changes on
+        purpose. Only platforms with a hash are updated, or platforms NAME
+        with --platform, which get one if they had none. Prints every hash
+        added or changed and how many were; programs which fail to run
+        keep theirs. With --dry-run nothing is written.
+
+    selftest [--profile NAME] [--output FILE]
+        Run the built-in self-test program, with the VM set up like
+        platform NAME with --profile, and check that it draws the screen
+        corners, counts seconds with the delay timer, shows every key held
+        and beeps meanwhile. --output writes the program to FILE too, to
+        check the keyboard, sound and display of the GUI app, where it's
+        also among the built-in programs.
+
+    fetch <name> [--force] [--no-launch]
+        Download program from the CHIP-8 archive into the library directory
+        ($CHIP8_LIBRARY or chip-8-emulator/roms under the user data
+        directory) and open it in the GUI app. Needs the `fetch` feature.";
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Arguments(String),
+    Io(std::io::Error),
+    Patch(PatchError),
+    Script(ScriptError),
+    Vm(VmError),
+    Fetch(String),
+    /// Source with the path has errors, already printed.
+    Assembly(String),
+    Crash(CrashError),
+    Load(LoadError),
+    /// Self-test checks failed, as many as this, already printed.
+    SelfTest(usize),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<PatchError> for Error {
+    fn from(e: PatchError) -> Self {
+        Error::Patch(e)
+    }
+}
+
+impl From<ScriptError> for Error {
+    fn from(e: ScriptError) -> Self {
+        Error::Script(e)
+    }
+}
+
+impl From<VmError> for Error {
+    fn from(e: VmError) -> Self {
+        Error::Vm(e)
+    }
+}
+
+impl From<CrashError> for Error {
+    fn from(e: CrashError) -> Self {
+        Error::Crash(e)
+    }
+}
+
+impl From<LoadError> for Error {
+    fn from(e: LoadError) -> Self {
+        Error::Load(e)
+    }
+}
+
+/// Run command described by `args`, the arguments following the executable
+/// name.
+pub fn run_command(args: impl IntoIterator<Item = String>) -> Result<()> {
+    let mut args = Args::new(args);
+    match args.next_positional().as_deref() {
+        Some("run") => run::run(args),
+        Some("render-movie") => render_movie::run(args),
+        Some("asm") => asm::run(args),
+        Some("bisect") => bisect::run(args),
+        Some("bless") => bless::run(args),
+        Some("disasm") => disasm::run(args),
+        Some("compat") => compat::run(args),
+        Some("debug") => debug::run(args),
+        Some("explain") => explain::run(args),
+        Some("scan") => scan::run(args),
+        Some("opcodes") => opcodes::run(args),
+        Some("selftest") => selftest::run(args),
+        #[cfg(feature = "fetch")]
+        Some("fetch") => fetch::run(args),
+        #[cfg(not(feature = "fetch"))]
+        Some("fetch") => Err(Error::Fetch(
+            "chip8 was built without the fetch feature".into(),
+        )),
+        Some("help") | None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(Error::Arguments(format!(
+            "unknown command: {}\n\n{}",
+            command, USAGE
+        ))),
+    }
+}
@@ -0,0 +1,59 @@
+//! Local directory with downloaded programs.
+
+use crate::{Error, Result};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Extension of program files in the library.
+pub const EXTENSION: &str = "ch8";
+
+/// Library directory: `CHIP8_LIBRARY` when set, `chip-8-emulator/roms` under
+/// the user data directory otherwise.
+pub fn dir() -> Result<PathBuf> {
+    if let Some(dir) = env::var_os("CHIP8_LIBRARY") {
+        return Ok(PathBuf::from(dir));
+    }
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .ok_or_else(|| Error::Arguments("no data directory, set CHIP8_LIBRARY".into()))?;
+    Ok(data_dir.join("chip-8-emulator").join("roms"))
+}
+
+/// Path of program `name` in library `dir`.
+///
+/// Names are plain file names without extension, so a name can't point
+/// outside of the library.
+pub fn program_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(Error::Arguments(format!("invalid program name: {}", name)));
+    }
+    Ok(dir.join(name).with_extension(EXTENSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_path() {
+        let path = program_path(Path::new("roms"), "octojam1title").unwrap();
+
+        assert_eq!(path, Path::new("roms/octojam1title.ch8"));
+    }
+
+    #[test]
+    fn test_program_path_invalid_name() {
+        let dir = Path::new("roms");
+
+        assert!(program_path(dir, "").is_err());
+        assert!(program_path(dir, "../secret").is_err());
+        assert!(program_path(dir, "a/b").is_err());
+        assert!(program_path(dir, "game.ch8").is_err());
+    }
+}
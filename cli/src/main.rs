@@ -0,0 +1,6 @@
+use chip_8_emulator_cli::{run_command, Error};
+use std::env;
+
+fn main() -> Result<(), Error> {
+    run_command(env::args().skip(1))
+}
@@ -0,0 +1,213 @@
+//! `chip8 opcodes`: how often a program uses each instruction, found in its
+//! bytes and while running it, showing which platform and quirks it needs.
+
+use crate::{
+    args::Args,
+    scan::{sandboxed_vm, word_at_pc},
+    Error, Result,
+};
+use chip_8_emulator::{
+    disasm::disassemble,
+    platform::{self, OpcodeFamily, Platform},
+    vm::{INSTRUCTIONS_PER_FRAME, TIMER_FREQUENCY},
+};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+
+const DEFAULT_SECONDS: u64 = 5;
+
+/// Quirks and the instructions which behave differently under them.
+const QUIRK_OPCODES: [(&str, &[&str]); 7] = [
+    ("shift_uses_vy", &["8xy6", "8xyE"]),
+    ("load_store_increments_i", &["Fx55", "Fx65"]),
+    ("jump_uses_vx", &["Bnnn"]),
+    ("logic_resets_vf", &["8xy1", "8xy2", "8xy3"]),
+    ("clip_sprites", &["Dxyn", "Dxy0"]),
+    ("display_wait", &["Dxyn", "Dxy0"]),
+    ("get_key_on_press", &["Fx0A"]),
+];
+
+/// Uses of an instruction pattern.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Count {
+    family: Option<OpcodeFamily>,
+    /// Words of the program with the pattern, data looking like
+    /// instructions included.
+    in_program: u64,
+    /// Times executed.
+    executed: u64,
+}
+
+/// Counts by pattern, words no platform has as an instruction under `????`.
+#[derive(Debug, Default)]
+struct Histogram {
+    counts: BTreeMap<&'static str, Count>,
+    /// Whether the program was run, otherwise execution counts are all 0.
+    ran: bool,
+}
+
+impl Histogram {
+    fn count(&mut self, word: u16) -> &mut Count {
+        let (pattern, family) = match platform::opcode(word) {
+            Some((pattern, family)) => (pattern, Some(family)),
+            None => ("????", None),
+        };
+        let count = self.counts.entry(pattern).or_default();
+        count.family = family;
+        count
+    }
+
+    /// Count instructions of `program` word by word, as disassembled.
+    fn add_static(&mut self, program: &[u8]) {
+        for line in disassemble(program) {
+            if let [high, low, ..] = line.bytes[..] {
+                self.count(u16::from_be_bytes([high, low])).in_program += 1;
+            }
+        }
+    }
+
+    /// Count instructions executed running `program` in sandbox mode for up
+    /// to `instructions` instructions with no keys pressed.
+    fn add_dynamic(&mut self, program: &[u8], instructions: u64) -> Result<()> {
+        let mut vm = sandboxed_vm(program)?;
+        let mut executed = 0;
+        while executed < instructions && !vm.is_halted() {
+            if let Some(word) = word_at_pc(&vm) {
+                self.count(word).executed += 1;
+            }
+            vm.step()?;
+            executed += 1;
+        }
+        self.ran = true;
+        Ok(())
+    }
+
+    /// Platform with every instruction found.
+    fn platform(&self) -> Platform {
+        let family = self
+            .counts
+            .values()
+            .filter_map(|count| count.family)
+            .max()
+            .unwrap_or(OpcodeFamily::Chip8);
+        Platform::of_family(family)
+    }
+
+    /// Quirks changing the behavior of instructions found, with them.
+    fn quirks(&self) -> Vec<(&'static str, Vec<&'static str>)> {
+        QUIRK_OPCODES
+            .iter()
+            .filter_map(|&(quirk, patterns)| {
+                let used: Vec<_> = patterns
+                    .iter()
+                    .copied()
+                    .filter(|pattern| self.counts.contains_key(pattern))
+                    .collect();
+                (!used.is_empty()).then_some((quirk, used))
+            })
+            .collect()
+    }
+}
+
+pub fn run(mut args: Args) -> Result<()> {
+    let static_only = args.flag("--static");
+    let dynamic_only = args.flag("--dynamic");
+    let program_path = args.required("program path")?;
+    let seconds = args.parsed_option("--seconds")?.unwrap_or(DEFAULT_SECONDS);
+    args.finish()?;
+    if static_only && dynamic_only {
+        return Err(Error::Arguments(
+            "--static and --dynamic can't be used together".into(),
+        ));
+    }
+
+    let program = fs::read(program_path)?;
+    let mut histogram = Histogram::default();
+    if !dynamic_only {
+        histogram.add_static(&program);
+    }
+    if !static_only {
+        let instructions = seconds * (TIMER_FREQUENCY * INSTRUCTIONS_PER_FRAME) as u64;
+        histogram.add_dynamic(&program, instructions)?;
+    }
+    let mut out = BufWriter::new(io::stdout().lock());
+    write_text(&mut out, &histogram)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Line per pattern with its family and counts, then the platform and
+/// quirks the program needs.
+fn write_text(out: &mut impl Write, histogram: &Histogram) -> io::Result<()> {
+    writeln!(out, "pattern  family  program  executed")?;
+    for (pattern, count) in &histogram.counts {
+        let executed = if histogram.ran {
+            count.executed.to_string()
+        } else {
+            "-".to_string()
+        };
+        writeln!(
+            out,
+            "{:<8} {:<7} {:>7}  {:>8}",
+            pattern,
+            count.family.map_or("-", OpcodeFamily::name),
+            count.in_program,
+            executed
+        )?;
+    }
+    writeln!(out)?;
+    writeln!(out, "platform: {}", histogram.platform().name)?;
+    let quirks = histogram.quirks();
+    if quirks.is_empty() {
+        writeln!(out, "quirks: none")?;
+    }
+    for (quirk, patterns) in quirks {
+        writeln!(out, "quirk {}: {}", quirk, patterns.join(" "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // LD V0, 1; SHR V0; high resolution; unknown; halt.
+    const PROGRAM: [u8; 10] = [0x60, 0x01, 0x80, 0x06, 0x00, 0xFF, 0xFF, 0xFF, 0x12, 0x08];
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::default();
+        histogram.add_static(&PROGRAM);
+        histogram.add_dynamic(&PROGRAM, 10).unwrap();
+
+        let shr = histogram.counts["8xy6"];
+        assert_eq!(shr.family, Some(OpcodeFamily::Chip8));
+        assert_eq!((shr.in_program, shr.executed), (1, 1));
+        // Running stops at the jump to itself, before executing it.
+        let jump = histogram.counts["1nnn"];
+        assert_eq!((jump.in_program, jump.executed), (1, 0));
+        assert_eq!(histogram.counts["????"].in_program, 1);
+        assert_eq!(histogram.platform(), platform::SCHIP);
+        assert_eq!(histogram.quirks(), [("shift_uses_vy", vec!["8xy6"])]);
+    }
+
+    #[test]
+    fn test_write_text() {
+        let mut histogram = Histogram::default();
+        histogram.add_static(&[0x60, 0x01, 0xD0, 0x15]);
+        let mut out = Vec::new();
+        write_text(&mut out, &histogram).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "pattern  family  program  executed\n\
+             6xkk     chip8         1         -\n\
+             Dxyn     chip8         1         -\n\
+             \n\
+             platform: chip8\n\
+             quirk clip_sprites: Dxyn\n\
+             quirk display_wait: Dxyn\n"
+        );
+    }
+}
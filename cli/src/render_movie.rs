@@ -0,0 +1,95 @@
+//! `chip8 render-movie`: replay of an input script encoded as a video.
+
+use crate::{args::Args, Error, Result};
+use chip_8_emulator::{
+    capture::{GifWriter, Y4mWriter},
+    graphics::Graphics,
+    palette::Palette,
+    script::InputScript,
+    VM,
+};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Frames run after the last input event when `--frames` isn't given.
+const TAIL_FRAMES: u64 = 60;
+
+/// Frames replaying `script` takes, up to a second after its last event.
+pub(crate) fn default_frames(script: &InputScript) -> u64 {
+    script.events().last().map_or(0, |event| event.frame) + TAIL_FRAMES
+}
+
+/// Video file being written, chosen by the output extension.
+enum Movie {
+    Gif(Box<GifWriter<BufWriter<File>>>),
+    Y4m(Y4mWriter<BufWriter<File>>),
+}
+
+impl Movie {
+    fn write_frame(&mut self, graphics: &Graphics) -> io::Result<()> {
+        match self {
+            Movie::Gif(writer) => writer.write_frame(graphics),
+            Movie::Y4m(writer) => writer.write_frame(graphics),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Movie::Gif(writer) => writer.finish().map(drop),
+            Movie::Y4m(writer) => writer.into_inner().flush(),
+        }
+    }
+}
+
+pub fn run(mut args: Args) -> Result<()> {
+    let output = match args.option("-o")? {
+        Some(output) => Some(output),
+        None => args.option("--output")?,
+    };
+    let palette = args.option("--palette")?;
+    let scale = args.parsed_option("--scale")?.unwrap_or(1);
+    let frames = args.parsed_option("--frames")?;
+    let program_path = args.required("program path")?;
+    let script_path = args.required("input script path")?;
+    args.finish()?;
+    let output = output.ok_or_else(|| Error::Arguments("missing output path".into()))?;
+    if scale == 0 {
+        return Err(Error::Arguments("scale must be positive".into()));
+    }
+    let palette = match palette {
+        Some(palette) => Palette::parse(&palette)
+            .ok_or_else(|| Error::Arguments(format!("invalid palette: {}", palette)))?,
+        None => Palette::default(),
+    };
+
+    let program = fs::read(&program_path)?;
+    let script = InputScript::parse(&fs::read_to_string(script_path)?)?;
+    let frames = frames.unwrap_or_else(|| default_frames(&script));
+    let file = BufWriter::new(File::create(&output)?);
+    let mut movie = match Path::new(&output).extension().and_then(|e| e.to_str()) {
+        Some("gif") => Movie::Gif(Box::new(GifWriter::new(file, scale, &palette)?)),
+        Some("y4m") => Movie::Y4m(Y4mWriter::new(file, scale, &palette)?),
+        _ => {
+            return Err(Error::Arguments(format!(
+                "unknown video format of {}, expected gif or y4m",
+                output
+            )))
+        }
+    };
+
+    let mut vm = VM::new();
+    vm.load_program(&program)?;
+    let mut player = script.player();
+    for frame in 0..frames {
+        if let Some(input) = player.input_for(frame) {
+            vm.set_input(input);
+        }
+        vm.exec_current_instruction()?;
+        movie.write_frame(&vm.graphics)?;
+    }
+    movie.finish()?;
+
+    println!("{}: {} frames to {}", program_path, frames, output);
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! `chip8 run`: headless run of a program.
+
+use crate::{args::Args, explain, Error, Result};
+use chip_8_emulator::{
+    capture::{WavWriter, Y4mWriter},
+    crash::CrashDump,
+    memory::MEMORY_SIZE,
+    mmio::Console,
+    palette::Palette,
+    patch::Patch,
+    script::InputScript,
+    vm::TimerPolicy,
+    VM,
+};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::num::{NonZeroU32, NonZeroU8};
+
+const DEFAULT_FRAMES: u64 = 600;
+/// Instructions kept in crash dumps.
+const TRACE_LENGTH: usize = 64;
+/// Faults printed with `--sandbox`, the last ones.
+const FAULTS_SHOWN: usize = 10;
+
+pub fn run(mut args: Args) -> Result<()> {
+    let sandbox = args.flag("--sandbox");
+    let program_path = args.required("program path")?;
+    let frames = args.parsed_option("--frames")?.unwrap_or(DEFAULT_FRAMES);
+    let video_path = args.option("--dump-video")?;
+    let audio_path = args.option("--dump-audio")?;
+    let input_path = args.option("--input")?;
+    let timer_instructions = args.parsed_option::<NonZeroU32>("--timer-instructions")?;
+    let sprite_rows = args.parsed_option::<NonZeroU8>("--sprite-rows")?;
+    let speed = args.parsed_option::<NonZeroU32>("--speed")?;
+    let scale = args.parsed_option("--scale")?.unwrap_or(1);
+    let palette = match args.option("--palette")? {
+        Some(palette) => Palette::parse(&palette)
+            .ok_or_else(|| Error::Arguments(format!("invalid palette: {}", palette)))?,
+        None => Palette::default(),
+    };
+    let patch_paths = args.options("--patch")?;
+    let profile = args.option("--profile")?;
+    let console_address = args.option("--console")?;
+    let crash_path = args.option("--crash-dump")?;
+    args.finish()?;
+    let console_address = console_address.as_deref().map(parse_address).transpose()?;
+    let platform = profile.as_deref().map(explain::platform).transpose()?;
+    if scale == 0 {
+        return Err(Error::Arguments("scale must be positive".into()));
+    }
+
+    let mut program = fs::read(&program_path)?;
+    for patch_path in patch_paths {
+        Patch::parse(&fs::read(patch_path)?)?.apply(&mut program);
+    }
+    let mut vm = VM::new();
+    if let Some(platform) = platform {
+        platform.configure(&mut vm);
+    }
+    vm.load_program(&program)?;
+    if let Some(n) = timer_instructions {
+        vm.set_timer_policy(TimerPolicy::Instructions(n));
+    }
+    vm.set_sprite_rows_per_frame(sprite_rows);
+    if let Some(speed) = speed {
+        vm.set_instructions_per_second(speed);
+    }
+    if sandbox {
+        vm.enable_sandbox(FAULTS_SHOWN);
+    }
+    if crash_path.is_some() {
+        vm.enable_trace(TRACE_LENGTH);
+    }
+    let console = Console::new();
+    if let Some(address) = console_address {
+        console.attach(&mut vm, address);
+    }
+
+    let script = match input_path {
+        Some(path) => InputScript::parse(&fs::read_to_string(path)?)?,
+        None => InputScript::default(),
+    };
+    let mut player = script.player();
+
+    let mut video = match video_path {
+        Some(path) => Some(Y4mWriter::new(
+            BufWriter::new(File::create(path)?),
+            scale,
+            &palette,
+        )?),
+        None => None,
+    };
+    let mut audio = match audio_path {
+        Some(path) => Some(WavWriter::new(BufWriter::new(File::create(path)?))?),
+        None => None,
+    };
+
+    let mut frames_run = 0;
+    while frames_run < frames && !vm.is_halted() {
+        if let Some(input) = player.input_for(frames_run) {
+            vm.set_input(input);
+        }
+        if let Err(error) = vm.run_frame() {
+            if let Some(path) = &crash_path {
+                let mut dump = CrashDump::new(&vm, error);
+                dump.config.push(("program".into(), program_path.clone()));
+                dump.config.push(("frame".into(), frames_run.to_string()));
+                if let Some(speed) = speed {
+                    dump.config.push(("speed".into(), speed.to_string()));
+                }
+                if let Some(profile) = &profile {
+                    dump.config.push(("profile".into(), profile.clone()));
+                }
+                fs::write(path, dump.to_text())?;
+                eprintln!("crash dump written to {}", path);
+            }
+            return Err(error.into());
+        }
+        frames_run += 1;
+        if let Some(video) = &mut video {
+            video.write_frame(&vm.graphics)?;
+        }
+        if let Some(audio) = &mut audio {
+            audio.write_frame(vm.is_sound_playing())?;
+        }
+    }
+    if let Some(audio) = audio {
+        audio.finish()?;
+    }
+
+    print!("{}", console.take());
+    if let Some(fault_log) = vm.fault_log() {
+        for fault in fault_log.iter() {
+            eprintln!("skipped: {}", fault);
+        }
+    }
+    let status = if vm.is_halted() { ", halted" } else { "" };
+    let faults = match vm.fault_log() {
+        Some(fault_log) => format!(", {} faults skipped", fault_log.total()),
+        None => String::new(),
+    };
+    println!(
+        "{}: {} frames{}{}",
+        program_path, frames_run, status, faults
+    );
+    Ok(())
+}
+
+/// Address in memory, hexadecimal with `0x` or decimal.
+fn parse_address(address: &str) -> Result<usize> {
+    let parsed = match address.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => address.parse().ok(),
+    };
+    parsed
+        .filter(|&address| address < MEMORY_SIZE)
+        .ok_or_else(|| Error::Arguments(format!("invalid address: {}", address)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn test_flag_before_program() {
+        let path = env::temp_dir().join(format!("chip8-run-{}.ch8", process::id()));
+        // JP 0x200
+        fs::write(&path, [0x12, 0x00]).unwrap();
+        let path_arg = path.to_str().unwrap().to_string();
+
+        let args = ["--sandbox", &path_arg, "--frames", "1"];
+        let result = run(Args::new(args.iter().map(|arg| arg.to_string())));
+
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+}
@@ -0,0 +1,286 @@
+//! `chip8 scan`: headless run of every program in a directory in sandbox
+//! mode, reporting what each one did, for curating collections and finding
+//! instructions the emulator lacks.
+
+use crate::{args::Args, disasm::json_string, Error, Result};
+use chip_8_emulator::{
+    memory::{check_program, PROGRAM_START_LOCATION},
+    platform::{self, OpcodeFamily, Platform},
+    vm::{TimerPolicy, INSTRUCTIONS_PER_FRAME, TIMER_FREQUENCY},
+    VM,
+};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_SECONDS: u64 = 5;
+
+/// What a program did in a scan.
+struct Report {
+    path: String,
+    instructions: u64,
+    halted: bool,
+    /// Instructions which failed and were skipped.
+    faults: u64,
+    last_fault: Option<String>,
+    /// Patterns of the instructions executed, e.g. `8xy4`.
+    opcodes: BTreeSet<&'static str>,
+    /// Executed words no platform has as an instruction.
+    unknown: BTreeSet<u16>,
+    platform: Platform,
+    frame_hash: u64,
+}
+
+pub fn run(mut args: Args) -> Result<()> {
+    let dir = args.required("directory")?;
+    let seconds = args.parsed_option("--seconds")?.unwrap_or(DEFAULT_SECONDS);
+    let format = args.option("--format")?;
+    args.finish()?;
+    let write = match format.as_deref() {
+        None | Some("csv") => write_csv,
+        Some("json") => write_json,
+        Some(format) => return Err(Error::Arguments(format!("unknown format: {}", format))),
+    };
+
+    let instructions = seconds * (TIMER_FREQUENCY * INSTRUCTIONS_PER_FRAME) as u64;
+    let mut reports = Vec::new();
+    for path in collect_programs(Path::new(&dir))? {
+        let program = fs::read(&path)?;
+        if let Err(e) = check_program(PROGRAM_START_LOCATION, &program) {
+            eprintln!("{}: {}, skipped", path.display(), e);
+            continue;
+        }
+        reports.push(scan(&path, &program, instructions)?);
+    }
+    let mut out = BufWriter::new(io::stdout().lock());
+    write(&mut out, &reports)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Files under `dir` and its subdirectories, sorted, except `.txt` files
+/// which usually describe the programs next to them.
+pub(crate) fn collect_programs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut programs = Vec::new();
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            programs.extend(collect_programs(&path)?);
+        } else if path.is_file() && path.extension().is_none_or(|ext| ext != "txt") {
+            programs.push(path);
+        }
+    }
+    Ok(programs)
+}
+
+/// Run `program` for up to `instructions` instructions with no keys pressed,
+/// timers ticking every frame's worth of them.
+fn scan(path: &Path, program: &[u8], instructions: u64) -> Result<Report> {
+    let mut vm = sandboxed_vm(program)?;
+    let mut opcodes = BTreeSet::new();
+    let mut unknown = BTreeSet::new();
+    let mut family = OpcodeFamily::Chip8;
+    let mut executed = 0;
+    while executed < instructions && !vm.is_halted() {
+        if let Some(word) = word_at_pc(&vm) {
+            match platform::opcode(word) {
+                Some((pattern, used)) => {
+                    opcodes.insert(pattern);
+                    family = family.max(used);
+                }
+                None => {
+                    unknown.insert(word);
+                }
+            }
+        }
+        vm.step()?;
+        executed += 1;
+    }
+
+    let fault_log = vm.fault_log().unwrap();
+    Ok(Report {
+        path: path.display().to_string(),
+        instructions: executed,
+        halted: vm.is_halted(),
+        faults: fault_log.total(),
+        last_fault: fault_log.iter().last().map(ToString::to_string),
+        opcodes,
+        unknown,
+        platform: Platform::of_family(family),
+        frame_hash: vm.display_hash(),
+    })
+}
+
+/// VM with `program` loaded in sandbox mode, its timers ticking every
+/// frame's worth of instructions so it can run without a clock.
+pub(crate) fn sandboxed_vm(program: &[u8]) -> Result<VM> {
+    let mut vm = VM::new();
+    vm.enable_sandbox(1);
+    vm.set_timer_policy(TimerPolicy::Instructions(
+        NonZeroU32::new(INSTRUCTIONS_PER_FRAME).unwrap(),
+    ));
+    vm.load_program(program)?;
+    Ok(vm)
+}
+
+/// Word the program counter points to, `None` at the end of memory.
+pub(crate) fn word_at_pc(vm: &VM) -> Option<u16> {
+    let pc = vm.registers().program_counter as usize;
+    match vm.memory(pc..pc + 2) {
+        Some(&[high, low]) => Some(u16::from_be_bytes([high, low])),
+        _ => None,
+    }
+}
+
+impl Report {
+    fn opcodes(&self) -> Vec<&str> {
+        self.opcodes.iter().copied().collect()
+    }
+
+    fn unknown(&self) -> Vec<String> {
+        self.unknown
+            .iter()
+            .map(|word| format!("{:04X}", word))
+            .collect()
+    }
+}
+
+/// Header and a line per program, lists separated by spaces.
+fn write_csv(out: &mut dyn Write, reports: &[Report]) -> io::Result<()> {
+    writeln!(
+        out,
+        "path,instructions,halted,faults,last_fault,platform,opcodes,unknown,frame_hash"
+    )?;
+    for report in reports {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{:016x}",
+            csv_field(&report.path),
+            report.instructions,
+            report.halted,
+            report.faults,
+            csv_field(report.last_fault.as_deref().unwrap_or("")),
+            report.platform.name,
+            report.opcodes().join(" "),
+            report.unknown().join(" "),
+            report.frame_hash
+        )?;
+    }
+    Ok(())
+}
+
+/// JSON array with an object per program, one per line.
+fn write_json(out: &mut dyn Write, reports: &[Report]) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (i, report) in reports.iter().enumerate() {
+        let strings = |items: Vec<String>| {
+            let items: Vec<_> = items.iter().map(|item| json_string(item)).collect();
+            format!("[{}]", items.join(","))
+        };
+        let opcodes = report.opcodes().into_iter().map(String::from).collect();
+        let separator = if i + 1 < reports.len() { "," } else { "" };
+        writeln!(
+            out,
+            "  {{\"path\":{},\"instructions\":{},\"halted\":{},\"faults\":{},\
+             \"last_fault\":{},\"platform\":{},\"opcodes\":{},\"unknown\":{},\
+             \"frame_hash\":\"{:016x}\"}}{}",
+            json_string(&report.path),
+            report.instructions,
+            report.halted,
+            report.faults,
+            report
+                .last_fault
+                .as_deref()
+                .map_or("null".to_string(), json_string),
+            json_string(report.platform.name),
+            strings(opcodes),
+            strings(report.unknown()),
+            report.frame_hash,
+            separator
+        )?;
+    }
+    writeln!(out, "]")
+}
+
+/// `field` quoted if it has to be.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(write: fn(&mut dyn Write, &[Report]) -> io::Result<()>, program: &[u8]) -> String {
+        let report = scan(Path::new("roms/test.ch8"), program, 100).unwrap();
+        let mut out = Vec::new();
+        write(&mut out, &[report]).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // LD V0, 1; unknown; high resolution; RET without CALL; halt.
+    const PROGRAM: [u8; 10] = [0x60, 0x01, 0xFF, 0xFF, 0x00, 0xFF, 0x00, 0xEE, 0x12, 0x08];
+
+    #[test]
+    fn test_scan() {
+        let report = scan(Path::new("test.ch8"), &PROGRAM, 100).unwrap();
+
+        assert_eq!(report.instructions, 4);
+        assert!(report.halted);
+        assert_eq!(report.faults, 2);
+        assert_eq!(
+            report.last_fault.as_deref(),
+            Some("return without a subroutine at 0x206")
+        );
+        assert_eq!(report.opcodes(), ["00EE", "00FF", "6xkk"]);
+        assert_eq!(report.unknown(), ["FFFF"]);
+        assert_eq!(report.platform, platform::SCHIP);
+    }
+
+    #[test]
+    fn test_scan_stops_after_instructions() {
+        let report = scan(Path::new("loop.ch8"), &[0x70, 0x01, 0x12, 0x00], 100).unwrap();
+
+        assert_eq!(report.instructions, 100);
+        assert!(!report.halted);
+        assert_eq!(report.platform, platform::CHIP8);
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = output(write_csv, &PROGRAM);
+
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with(
+            "roms/test.ch8,4,true,2,return without a subroutine at 0x206,schip,00EE 00FF 6xkk,FFFF,"
+        ));
+    }
+
+    #[test]
+    fn test_json() {
+        let json = output(write_json, &PROGRAM);
+
+        assert!(json.starts_with(
+            "[\n  {\"path\":\"roms/test.ch8\",\"instructions\":4,\"halted\":true,\"faults\":2,\
+             \"last_fault\":\"return without a subroutine at 0x206\",\"platform\":\"schip\",\
+             \"opcodes\":[\"00EE\",\"00FF\",\"6xkk\"],\"unknown\":[\"FFFF\"],\"frame_hash\":\""
+        ));
+        assert!(json.ends_with("\"}\n]\n"));
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
@@ -0,0 +1,168 @@
+//! `chip8 selftest`: run the built-in self-test program and check that the
+//! emulator draws, counts, beeps and reads keys the way it should, without
+//! hunting for test programs. The program can be written out to check the
+//! keyboard, sound and display of the GUI app by hand.
+
+use crate::{args::Args, explain, Error, Result};
+use chip_8_emulator::{
+    demos::{self, SECONDS_X, SECONDS_Y},
+    input::Input,
+    VM,
+};
+use std::fs;
+
+/// Instructions a check waits for the program to react, enough for the
+/// delay timer to run out at one tick per instruction.
+const MAX_INSTRUCTIONS: usize = 10_000;
+
+/// Outcome of a check, why it failed if it did.
+type Check = std::result::Result<(), String>;
+
+pub fn run(mut args: Args) -> Result<()> {
+    let output = args.option("--output")?;
+    let profile = args.option("--profile")?;
+    args.finish()?;
+
+    let program = demos::selftest();
+    if let Some(path) = output {
+        fs::write(&path, &program)?;
+        println!("Saved {}", path);
+    }
+    let mut vm = VM::new();
+    if let Some(profile) = profile {
+        explain::platform(&profile)?.configure(&mut vm);
+    }
+    vm.load_program(&program)?;
+
+    let mut failed = 0;
+    for (name, check) in checks(&mut vm) {
+        match check {
+            Ok(()) => println!("{}: ok", name),
+            Err(reason) => {
+                failed += 1;
+                println!("{}: failed, {}", name, reason);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(Error::SelfTest(failed));
+    }
+    Ok(())
+}
+
+/// Run the checks in order on `vm`, which has the self-test program
+/// loaded.
+fn checks(vm: &mut VM) -> Vec<(&'static str, Check)> {
+    vec![
+        ("display", check_display(vm)),
+        ("timers", check_timers(vm)),
+        ("keypad", check_keypad(vm)),
+        ("sound", check_sound(vm)),
+    ]
+}
+
+fn check_display(vm: &mut VM) -> Check {
+    let corners = [(0, 0), (63, 0), (0, 31), (63, 31)];
+    let drawn = run_until(vm, |vm| {
+        corners.iter().all(|&(x, y)| vm.graphics.pixel(x, y))
+    })?;
+    if !drawn {
+        return Err("screen corners not drawn".into());
+    }
+    Ok(())
+}
+
+fn check_timers(vm: &mut VM) -> Check {
+    let (x, y) = (SECONDS_X, SECONDS_Y);
+    if !run_until(vm, |vm| digit_pixels(vm, x, y) != 0)? {
+        return Err("seconds not shown".into());
+    }
+    let first = digit_pixels(vm, x, y);
+    if !run_until(vm, |vm| digit_pixels(vm, x, y) != first)? {
+        return Err("seconds not counted, the delay timer doesn't run out".into());
+    }
+    Ok(())
+}
+
+fn check_keypad(vm: &mut VM) -> Check {
+    for key in 0..16 {
+        let (x, y) = demos::key_position(key);
+        vm.set_input(Input::new_with_key_pressed(key));
+        if !run_until(vm, |vm| digit_pixels(vm, x, y) != 0)? {
+            return Err(format!("key {:X} not shown while held", key));
+        }
+        vm.set_input(Input::new());
+        if !run_until(vm, |vm| digit_pixels(vm, x, y) == 0)? {
+            return Err(format!("key {:X} still shown once released", key));
+        }
+    }
+    Ok(())
+}
+
+fn check_sound(vm: &mut VM) -> Check {
+    vm.set_input(Input::new_with_key_pressed(0));
+    if !run_until(vm, VM::is_sound_playing)? {
+        return Err("no beep while a key is held".into());
+    }
+    vm.set_input(Input::new());
+    if !run_until(vm, |vm| !vm.is_sound_playing())? {
+        return Err("beep goes on once the key is released".into());
+    }
+    Ok(())
+}
+
+/// Pixels of the 4 by 5 digit with its top left corner at (`x`, `y`), a
+/// bit each.
+fn digit_pixels(vm: &VM, x: u8, y: u8) -> u32 {
+    let (x, y) = (x as usize, y as usize);
+    (0..5)
+        .flat_map(|row| (0..4).map(move |col| (col, row)))
+        .filter(|&(col, row)| vm.graphics.pixel(x + col, y + row))
+        .fold(0, |pixels, (col, row)| pixels | 1 << (row * 4 + col))
+}
+
+/// Run `vm` until `done` holds, false if it doesn't within
+/// [`MAX_INSTRUCTIONS`].
+fn run_until(vm: &mut VM, done: impl Fn(&VM) -> bool) -> std::result::Result<bool, String> {
+    for _ in 0..MAX_INSTRUCTIONS {
+        if done(vm) {
+            return Ok(true);
+        }
+        vm.exec_current_instruction().map_err(|e| e.to_string())?;
+    }
+    Ok(done(vm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chip_8_emulator::platform::Platform;
+
+    #[test]
+    fn test_checks_pass() {
+        let mut vm = VM::new();
+        vm.load_program(&demos::selftest()).unwrap();
+        for (name, check) in checks(&mut vm) {
+            assert_eq!(check, Ok(()), "{}", name);
+        }
+
+        for platform in Platform::ALL {
+            let mut vm = VM::new();
+            platform.configure(&mut vm);
+            vm.load_program(&demos::selftest()).unwrap();
+            for (name, check) in checks(&mut vm) {
+                assert_eq!(check, Ok(()), "{} on {}", name, platform.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_checks_fail() {
+        let mut vm = VM::new();
+        // CLS; JP 0x202
+        vm.load_program(&[0x00, 0xE0, 0x12, 0x02]).unwrap();
+        let checks = checks(&mut vm);
+        assert_eq!(checks[0].1, Err("screen corners not drawn".into()));
+        assert!(checks[2].1.is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//! Compares framebuffer layouts on sprite draws recorded from sprite-heavy
+//! programs replaying scripted input. Run with `cargo bench --bench
+//! framebuffer`.
+//!
+//! Every program is run once with the draw log enabled, then its draws are
+//! replayed onto each layout: `draw` times drawing all of them, `scan` times
+//! reading every pixel once per frame, as renderers do. Layouts must agree
+//! on every collision and on the final picture.
+
+use chip_8_emulator::graphics::{Graphics, DISPLAY_COLS, DISPLAY_ROWS};
+use chip_8_emulator::program::{Program, V0, V1, V3, V4, V5};
+use chip_8_emulator::script::InputScript;
+use chip_8_emulator::{demos, VM};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// One instruction per frame, so sprites are drawn every few frames.
+const FRAMES: u64 = 20_000;
+const RUNS: usize = 15;
+
+/// Display layout under test.
+trait Framebuffer: Default {
+    const NAME: &'static str;
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn draw_sprite(&mut self, x: usize, y: usize, sprite: &[u8]) -> bool;
+
+    fn pixel(&self, x: usize, y: usize) -> bool;
+}
+
+/// Emulator layout, one `u64` per row.
+#[derive(Default)]
+struct Packed(Graphics);
+
+impl Framebuffer for Packed {
+    const NAME: &'static str = "packed u64 rows";
+
+    fn draw_sprite(&mut self, x: usize, y: usize, sprite: &[u8]) -> bool {
+        self.0.draw_sprite(x, y, sprite)
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        self.0.pixel(x, y)
+    }
+}
+
+/// One byte per pixel, 0 or 1, row after row.
+struct Bytes([u8; DISPLAY_COLS * DISPLAY_ROWS]);
+
+impl Default for Bytes {
+    fn default() -> Self {
+        Self([0; DISPLAY_COLS * DISPLAY_ROWS])
+    }
+}
+
+impl Framebuffer for Bytes {
+    const NAME: &'static str = "byte per pixel";
+
+    fn draw_sprite(&mut self, x: usize, y: usize, sprite: &[u8]) -> bool {
+        let mut collision = false;
+        for (i, &byte) in sprite.iter().enumerate() {
+            let row = (y + i) % DISPLAY_ROWS * DISPLAY_COLS;
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    let pixel = &mut self.0[row + (x + bit) % DISPLAY_COLS];
+                    collision |= *pixel != 0;
+                    *pixel ^= 1;
+                }
+            }
+        }
+        collision
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        self.0[y * DISPLAY_COLS + x] != 0
+    }
+}
+
+/// Flat bit vector addressed by pixel index, accessed a bit at a time like a
+/// general purpose bitset.
+struct Bits(Vec<u64>);
+
+impl Default for Bits {
+    fn default() -> Self {
+        Self(vec![0; DISPLAY_COLS * DISPLAY_ROWS / 64])
+    }
+}
+
+impl Framebuffer for Bits {
+    const NAME: &'static str = "bit vector";
+
+    fn draw_sprite(&mut self, x: usize, y: usize, sprite: &[u8]) -> bool {
+        let mut collision = false;
+        for (i, &byte) in sprite.iter().enumerate() {
+            let row = (y + i) % DISPLAY_ROWS * DISPLAY_COLS;
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    let index = row + (x + bit) % DISPLAY_COLS;
+                    let mask = 1 << (index % 64);
+                    collision |= self.0[index / 64] & mask != 0;
+                    self.0[index / 64] ^= mask;
+                }
+            }
+        }
+        collision
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        let index = y * DISPLAY_COLS + x;
+        self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+}
+
+/// Sprite draw with the sprite bytes copied out of memory.
+struct Draw {
+    /// Whether `CLS` ran since the previous draw.
+    clear: bool,
+    frame: u64,
+    x: usize,
+    y: usize,
+    sprite: Vec<u8>,
+    collision: bool,
+}
+
+/// Sprites of 15 rows at random positions, plus a ship steered with keys 4
+/// and 6 on top of them.
+fn particles() -> Vec<u8> {
+    Program::new()
+        .ld(V4, 28)
+        .ld(V5, 24)
+        .label("loop")
+        .rnd(V0, 0x3F)
+        .rnd(V1, 0x1F)
+        .ld_i("ball")
+        .drw(V0, V1, 15)
+        .ld(V3, 4)
+        .sknp(V3)
+        .add(V4, 0xFF)
+        .ld(V3, 6)
+        .sknp(V3)
+        .add(V4, 1)
+        .ld_i("ship")
+        .drw(V4, V5, 8)
+        .jp("loop")
+        .label("ball")
+        .data(&[
+            0x3C, 0x7E, 0xFF, 0xFF, 0xFF, 0xFF, 0x7E, 0x3C, 0x3C, 0x7E, 0xFF, 0xFF, 0xFF, 0x7E,
+            0x3C,
+        ])
+        .label("ship")
+        .data(&[0x18, 0x18, 0x3C, 0x7E, 0xFF, 0xFF, 0x24, 0x24])
+        .build()
+}
+
+/// Tiles the whole display with 8 x 8 blocks, row by row, forever.
+fn tiles() -> Vec<u8> {
+    Program::new()
+        .ld_i("tile")
+        .label("frame")
+        .ld(V1, 0)
+        .label("row")
+        .ld(V0, 0)
+        .label("column")
+        .drw(V0, V1, 8)
+        .add(V0, 8)
+        .se(V0, 64)
+        .jp("column")
+        .add(V1, 8)
+        .se(V1, 32)
+        .jp("row")
+        .jp("frame")
+        .label("tile")
+        .data(&[0xFF, 0x81, 0xBD, 0xA5, 0xA5, 0xBD, 0x81, 0xFF])
+        .build()
+}
+
+const STEER: &str = "
+frame 1000: press 4
+frame 3000: press 6
+frame 7000: release 6
+frame 9000: press 4
+frame 13000: press 6
+frame 17000: release 6
+";
+
+const TYPING: &str = "
+frame 100: press 1
+frame 200: press 2
+frame 300: press A
+frame 400: press F
+frame 500: press 0
+frame 600: press 8
+frame 700: release 8
+";
+
+fn record(program: &[u8], script: &str) -> Vec<Draw> {
+    let script = InputScript::parse(script).unwrap();
+    let mut vm = VM::new();
+    vm.set_rng_seed(0);
+    vm.load_program(program).unwrap();
+    vm.enable_draw_log(FRAMES + 1);
+
+    let mut draws = Vec::new();
+    let mut player = script.player();
+    let mut clear = false;
+    for frame in 0..FRAMES {
+        if let Some(input) = player.input_for(frame) {
+            vm.set_input(input);
+        }
+        let pc = vm.registers().program_counter as usize;
+        clear |= vm.memory(pc..pc + 2) == Some(&[0x00, 0xE0]);
+        vm.exec_current_instruction().unwrap();
+        let log = vm.draw_log().unwrap();
+        if log.len() > draws.len() {
+            let record = log.iter().last().unwrap();
+            let address = record.address as usize;
+            draws.push(Draw {
+                clear,
+                frame: record.frame,
+                x: record.x as usize,
+                y: record.y as usize,
+                sprite: vm
+                    .memory(address..address + record.height as usize)
+                    .unwrap()
+                    .to_vec(),
+                collision: record.collision,
+            });
+            clear = false;
+        }
+    }
+    draws
+}
+
+/// Time drawing every sprite, and drawing plus scanning the display after
+/// every frame. Returns the fastest of [`RUNS`] runs of each.
+fn measure<F: Framebuffer>(draws: &[Draw]) -> (Duration, Duration) {
+    let mut draw = Duration::MAX;
+    let mut scan = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let mut display = F::default();
+        for sprite in draws {
+            if sprite.clear {
+                display.clear();
+            }
+            black_box(display.draw_sprite(sprite.x, sprite.y, &sprite.sprite));
+        }
+        black_box(&display);
+        draw = draw.min(start.elapsed());
+
+        let start = Instant::now();
+        let mut display = F::default();
+        let mut lit = 0;
+        for (i, sprite) in draws.iter().enumerate() {
+            if sprite.clear {
+                display.clear();
+            }
+            black_box(display.draw_sprite(sprite.x, sprite.y, &sprite.sprite));
+            if draws
+                .get(i + 1)
+                .is_none_or(|next| next.frame != sprite.frame)
+            {
+                for y in 0..DISPLAY_ROWS {
+                    for x in 0..DISPLAY_COLS {
+                        lit += black_box(&display).pixel(x, y) as usize;
+                    }
+                }
+            }
+        }
+        black_box(lit);
+        scan = scan.min(start.elapsed());
+    }
+    (draw, scan)
+}
+
+/// Check the layout against the recorded collisions, returning the final
+/// picture.
+fn replay<F: Framebuffer>(draws: &[Draw]) -> Vec<bool> {
+    let mut display = F::default();
+    for (i, sprite) in draws.iter().enumerate() {
+        if sprite.clear {
+            display.clear();
+        }
+        let collision = display.draw_sprite(sprite.x, sprite.y, &sprite.sprite);
+        assert_eq!(collision, sprite.collision, "{}: draw {}", F::NAME, i);
+    }
+    (0..DISPLAY_ROWS)
+        .flat_map(|y| (0..DISPLAY_COLS).map(move |x| (x, y)))
+        .map(|(x, y)| display.pixel(x, y))
+        .collect()
+}
+
+fn report<F: Framebuffer>(draws: &[Draw]) {
+    let (draw, scan) = measure::<F>(draws);
+    let per_draw = draw.as_nanos() as f64 / draws.len().max(1) as f64;
+    println!(
+        "  {:<16} draw {:>9.1?} ({:>5.1} ns/sprite)   draw + scan {:>9.1?}",
+        F::NAME,
+        draw,
+        per_draw,
+        scan
+    );
+}
+
+fn main() {
+    let workloads = [
+        ("particles", particles(), STEER),
+        ("tiles", tiles(), ""),
+        ("keypad echo", demos::keypad_echo(), TYPING),
+    ];
+    for (name, program, script) in &workloads {
+        let draws = record(program, script);
+        let picture = replay::<Packed>(&draws);
+        assert_eq!(replay::<Bytes>(&draws), picture, "{}", name);
+        assert_eq!(replay::<Bits>(&draws), picture, "{}", name);
+
+        println!("{}: {} sprites in {} frames", name, draws.len(), FRAMES);
+        report::<Packed>(&draws);
+        report::<Bytes>(&draws);
+        report::<Bits>(&draws);
+    }
+}
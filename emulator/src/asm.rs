@@ -0,0 +1,581 @@
+//! Assembler for programs written with the mnemonics of Cowgod's technical
+//! reference, as printed by the disassembler.
+//!
+//! ```text
+//! ; Comments start with a semicolon.
+//! start:  LD V0, 0x05       ; numbers: 0x05, #05, 0b101 or 5
+//!         LD I, sprite      ; labels can be used wherever an address is
+//! loop:   DRW V0, V0, 2
+//!         JP loop
+//! sprite: DB 0xF0, 0x90     ; raw bytes
+//! ```
+//!
+//! Assembling never stops at the first problem: every line is checked and
+//! reported as a [`Diagnostic`] with its position, so editors can show all
+//! of them while the source is typed.
+
+use super::instruction::{Instruction, Reg};
+use super::memory::{MEMORY_SIZE, PROGRAM_START_LOCATION};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Problem found in the source, with the position of the text it's about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Line number, starting from 1.
+    pub line: usize,
+    /// Column of the first character, starting from 1.
+    pub column: usize,
+    /// Length of the text in characters.
+    pub len: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    /// `line:column: severity: message`, which editors can match like
+    /// compiler output when prefixed with the file name.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, severity, self.message
+        )
+    }
+}
+
+pub struct Assembly {
+    /// Assembled program, only complete when there are no errors.
+    pub program: Vec<u8>,
+    /// Errors and warnings in source order.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Assembly {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+/// Piece of a source line with its position.
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+struct Statement<'a> {
+    mnemonic: Token<'a>,
+    operands: Vec<Token<'a>>,
+}
+
+struct Label<'a> {
+    address: usize,
+    token: Token<'a>,
+    used: bool,
+}
+
+enum Operand {
+    V(Reg),
+    Number(u32),
+    Label(String),
+    I,
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    R,
+    B,
+    IndirectI,
+}
+
+/// Assemble `source` into a program loaded at the program start location.
+pub fn assemble(source: &str) -> Assembly {
+    Assembler::default().assemble(source)
+}
+
+/// Only check `source`, returning its diagnostics.
+pub fn check(source: &str) -> Vec<Diagnostic> {
+    assemble(source).diagnostics
+}
+
+#[derive(Default)]
+struct Assembler<'a> {
+    labels: HashMap<String, Label<'a>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Assembler<'a> {
+    fn assemble(mut self, source: &'a str) -> Assembly {
+        // First pass: split lines and find label addresses.
+        let mut statements = Vec::new();
+        let mut address = PROGRAM_START_LOCATION;
+        for (index, line) in source.lines().enumerate() {
+            let code = line.split(';').next().unwrap_or_default();
+            let (label, statement) = split_line(code, index + 1);
+            if let Some(label) = label {
+                self.define_label(label, address);
+            }
+            if let Some(statement) = statement {
+                address += size(&statement);
+                statements.push(statement);
+            }
+        }
+
+        // Second pass: encode with every label known.
+        let mut program = Vec::new();
+        for statement in &statements {
+            match self.encode(statement) {
+                Ok(bytes) => program.extend(bytes),
+                Err(diagnostic) => self.diagnostics.push(diagnostic),
+            }
+        }
+        if PROGRAM_START_LOCATION + program.len() > MEMORY_SIZE {
+            let last = statements.last().map(|statement| statement.mnemonic);
+            if let Some(token) = last {
+                self.error(
+                    token,
+                    format!("program is {} bytes, too large", program.len()),
+                );
+            }
+        }
+
+        let mut unused: Vec<_> = self.labels.values().filter(|label| !label.used).collect();
+        unused.sort_by_key(|label| (label.token.line, label.token.column));
+        let unused: Vec<_> = unused
+            .iter()
+            .map(|label| {
+                warning(
+                    label.token,
+                    format!("label {} is never used", label.token.text),
+                )
+            })
+            .collect();
+        self.diagnostics.extend(unused);
+        self.diagnostics
+            .sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+
+        Assembly {
+            program,
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    fn define_label(&mut self, token: Token<'a>, address: usize) {
+        if !is_identifier(token.text)
+            || parse_operand(token.text).is_ok_and(|operand| !matches!(operand, Operand::Label(_)))
+        {
+            self.error(token, format!("invalid label name {}", token.text));
+            return;
+        }
+        if let Some(previous) = self.labels.get(token.text) {
+            let line = previous.token.line;
+            self.error(
+                token,
+                format!("label {} is already defined on line {}", token.text, line),
+            );
+            return;
+        }
+        self.labels.insert(
+            token.text.to_string(),
+            Label {
+                address,
+                token,
+                used: false,
+            },
+        );
+    }
+
+    fn error(&mut self, token: Token, message: String) {
+        self.diagnostics.push(error(token, message));
+    }
+
+    fn encode(&mut self, statement: &Statement) -> Result<Vec<u8>, Diagnostic> {
+        use Instruction::*;
+        use Operand::*;
+
+        let mnemonic = statement.mnemonic.text.to_ascii_uppercase();
+        let mut operands = Vec::with_capacity(statement.operands.len());
+        for &token in &statement.operands {
+            operands.push((
+                parse_operand(token.text).map_err(|e| error(token, e))?,
+                token,
+            ));
+        }
+
+        if mnemonic == "DB" {
+            if operands.is_empty() {
+                return Err(error(
+                    statement.mnemonic,
+                    "DB needs at least one byte".into(),
+                ));
+            }
+            return operands
+                .iter()
+                .map(|(operand, token)| self.value(operand, *token, 0xFF).map(|b| b as u8))
+                .collect();
+        }
+        if mnemonic == "SYS" {
+            self.diagnostics.push(warning(
+                statement.mnemonic,
+                "SYS is ignored by modern interpreters".into(),
+            ));
+        }
+
+        let kinds: Vec<&Operand> = operands.iter().map(|(operand, _)| operand).collect();
+        let token = |i: usize| operands[i].1;
+        let instruction = match (mnemonic.as_str(), kinds.as_slice()) {
+            ("CLS", []) => Cls,
+            ("RET", []) => Ret,
+            ("LOW", []) => Low,
+            ("HIGH", []) => High,
+            ("SCD", [n]) => Scd(self.value(n, token(0), 0xF)? as u8),
+            ("SCR", []) => Scr,
+            ("SCL", []) => Scl,
+            ("SYS", [a]) => Sys(self.addr(a, token(0))?),
+            ("JP", [V(0), a]) => JpV0(self.addr(a, token(1))?),
+            ("JP", [a]) => Jp(self.addr(a, token(0))?),
+            ("CALL", [a]) => Call(self.addr(a, token(0))?),
+            ("SE", [V(x), V(y)]) => SeReg(*x, *y),
+            ("SE", [V(x), b]) => SeByte(*x, self.byte(b, token(1))?),
+            ("SNE", [V(x), V(y)]) => SneReg(*x, *y),
+            ("SNE", [V(x), b]) => SneByte(*x, self.byte(b, token(1))?),
+            ("LD", [V(x), V(y)]) => LdReg(*x, *y),
+            ("LD", [V(x), Dt]) => LdVxDt(*x),
+            ("LD", [V(x), K]) => LdVxK(*x),
+            ("LD", [V(x), IndirectI]) => LdVxMem(*x),
+            ("LD", [V(x), R]) => LdVxR(*x),
+            ("LD", [V(x), b]) => LdByte(*x, self.byte(b, token(1))?),
+            ("LD", [I, a]) => LdI(self.addr(a, token(1))?),
+            ("LD", [Dt, V(x)]) => LdDtVx(*x),
+            ("LD", [St, V(x)]) => LdStVx(*x),
+            ("LD", [F, V(x)]) => LdFVx(*x),
+            ("LD", [Hf, V(x)]) => LdHfVx(*x),
+            ("LD", [R, V(x)]) => LdRVx(*x),
+            ("LD", [B, V(x)]) => LdBVx(*x),
+            ("LD", [IndirectI, V(x)]) => LdMemVx(*x),
+            ("LDL", [I, a]) => LdILong(self.value(a, token(1), 0xFFFF)? as u16),
+            ("ADD", [V(x), V(y)]) => AddReg(*x, *y),
+            ("ADD", [V(x), b]) => AddByte(*x, self.byte(b, token(1))?),
+            ("ADD", [I, V(x)]) => AddIVx(*x),
+            ("OR", [V(x), V(y)]) => Or(*x, *y),
+            ("AND", [V(x), V(y)]) => And(*x, *y),
+            ("XOR", [V(x), V(y)]) => Xor(*x, *y),
+            ("SUB", [V(x), V(y)]) => Sub(*x, *y),
+            ("SUBN", [V(x), V(y)]) => Subn(*x, *y),
+            ("SHR", [V(x)]) => Shr(*x, 0),
+            ("SHR", [V(x), V(y)]) => Shr(*x, *y),
+            ("SHL", [V(x)]) => Shl(*x, 0),
+            ("SHL", [V(x), V(y)]) => Shl(*x, *y),
+            ("RND", [V(x), b]) => Rnd(*x, self.byte(b, token(1))?),
+            ("DRW", [V(x), V(y), n]) => Drw(*x, *y, self.value(n, token(2), 0xF)? as u8),
+            ("SKP", [V(x)]) => Skp(*x),
+            ("SKNP", [V(x)]) => Sknp(*x),
+            _ if is_mnemonic(&mnemonic) => {
+                return Err(error(
+                    statement.mnemonic,
+                    format!("invalid operands for {}", mnemonic),
+                ))
+            }
+            _ => {
+                return Err(error(
+                    statement.mnemonic,
+                    format!("unknown instruction {}", statement.mnemonic.text),
+                ))
+            }
+        };
+        Ok(instruction.to_bytes())
+    }
+
+    fn addr(&mut self, operand: &Operand, token: Token) -> Result<u16, Diagnostic> {
+        self.value(operand, token, 0xFFF).map(|value| value as u16)
+    }
+
+    fn byte(&mut self, operand: &Operand, token: Token) -> Result<u8, Diagnostic> {
+        self.value(operand, token, 0xFF).map(|value| value as u8)
+    }
+
+    /// Number or label address of `operand`, at most `max`.
+    fn value(&mut self, operand: &Operand, token: Token, max: u32) -> Result<u32, Diagnostic> {
+        let value = match operand {
+            Operand::Number(value) => *value,
+            Operand::Label(name) => match self.labels.get_mut(name) {
+                Some(label) => {
+                    label.used = true;
+                    label.address as u32
+                }
+                None => return Err(error(token, format!("undefined label {}", name))),
+            },
+            _ => {
+                return Err(error(
+                    token,
+                    format!("expected a number, got {}", token.text),
+                ))
+            }
+        };
+        if value > max {
+            return Err(error(
+                token,
+                format!("{} doesn't fit, at most {:#X} is allowed", token.text, max),
+            ));
+        }
+        Ok(value)
+    }
+}
+
+/// Split a line without its comment into an optional label and statement.
+fn split_line(code: &str, line: usize) -> (Option<Token<'_>>, Option<Statement<'_>>) {
+    let token = |start: usize, end: usize| Token {
+        text: code[start..end].trim(),
+        line,
+        column: code[..start].chars().count() + 1,
+    };
+    let trimmed_start = code.len() - code.trim_start().len();
+    let mut rest_start = trimmed_start;
+
+    let mut label = None;
+    let first_word_end = code[trimmed_start..]
+        .find(char::is_whitespace)
+        .map_or(code.len(), |end| trimmed_start + end);
+    if let Some(colon) = code[trimmed_start..first_word_end].find(':') {
+        let end = trimmed_start + colon;
+        label = Some(token(trimmed_start, end));
+        rest_start = end + 1;
+    }
+
+    let rest = &code[rest_start..];
+    let mnemonic_start = rest_start + (rest.len() - rest.trim_start().len());
+    if code[mnemonic_start..].trim().is_empty() {
+        return (label, None);
+    }
+    let mnemonic_end = code[mnemonic_start..]
+        .find(char::is_whitespace)
+        .map_or(code.len(), |end| mnemonic_start + end);
+    let mnemonic = token(mnemonic_start, mnemonic_end);
+
+    let mut operands = Vec::new();
+    if !code[mnemonic_end..].trim().is_empty() {
+        let mut start = mnemonic_end;
+        for part in code[mnemonic_end..].split(',') {
+            let offset = part.len() - part.trim_start().len();
+            operands.push(token(start + offset, start + part.len()));
+            start += part.len() + 1;
+        }
+    }
+    (label, Some(Statement { mnemonic, operands }))
+}
+
+/// Bytes `statement` takes, known before labels are.
+fn size(statement: &Statement) -> usize {
+    match statement.mnemonic.text.to_ascii_uppercase().as_str() {
+        "DB" => statement.operands.len(),
+        "LDL" => 4,
+        _ => 2,
+    }
+}
+
+fn parse_operand(text: &str) -> Result<Operand, String> {
+    if text.is_empty() {
+        return Err("missing operand".into());
+    }
+    let upper = text.to_ascii_uppercase();
+    let operand = match upper.as_str() {
+        "I" => Operand::I,
+        "DT" => Operand::Dt,
+        "ST" => Operand::St,
+        "K" => Operand::K,
+        "F" => Operand::F,
+        "HF" => Operand::Hf,
+        "R" => Operand::R,
+        "B" => Operand::B,
+        "[I]" => Operand::IndirectI,
+        _ if upper.len() == 2 && upper.starts_with('V') => {
+            let x = u8::from_str_radix(&upper[1..], 16)
+                .map_err(|_| format!("invalid register {}", text))?;
+            Operand::V(x)
+        }
+        _ if upper.starts_with(|c: char| c.is_ascii_digit() || c == '#') => {
+            let parsed = if let Some(hex) = upper.strip_prefix("0X").or(upper.strip_prefix('#')) {
+                u32::from_str_radix(hex, 16)
+            } else if let Some(bin) = upper.strip_prefix("0B") {
+                u32::from_str_radix(bin, 2)
+            } else {
+                upper.parse()
+            };
+            Operand::Number(parsed.map_err(|_| format!("invalid number {}", text))?)
+        }
+        _ if is_identifier(text) => Operand::Label(text.to_string()),
+        _ => return Err(format!("invalid operand {}", text)),
+    };
+    Ok(operand)
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_mnemonic(mnemonic: &str) -> bool {
+    const MNEMONICS: [&str; 26] = [
+        "SYS", "CLS", "RET", "LOW", "HIGH", "SCD", "SCR", "SCL", "JP", "CALL", "SE", "SNE", "LD",
+        "ADD", "OR", "AND", "XOR", "SUB", "SHR", "SUBN", "SHL", "RND", "DRW", "SKP", "SKNP", "LDL",
+    ];
+    MNEMONICS.contains(&mnemonic)
+}
+
+fn error(token: Token, message: String) -> Diagnostic {
+    diagnostic(token, Severity::Error, message)
+}
+
+fn warning(token: Token, message: String) -> Diagnostic {
+    diagnostic(token, Severity::Warning, message)
+}
+
+fn diagnostic(token: Token, severity: Severity, message: String) -> Diagnostic {
+    Diagnostic {
+        line: token.line,
+        column: token.column,
+        len: token.text.chars().count(),
+        severity,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::disassemble;
+
+    #[test]
+    fn test_assemble() {
+        let source = "\
+; draw a sprite forever
+start:  LD V0, 0x05
+        ld i, sprite    ; mnemonics are case-insensitive
+loop:   DRW V0, V0, 2
+        JP loop
+        LDL I, #BEEF
+sprite: DB 0b11110000, 144
+";
+        let assembly = assemble(source);
+
+        assert_eq!(
+            assembly.diagnostics,
+            vec![Diagnostic {
+                line: 2,
+                column: 1,
+                len: 5,
+                severity: Severity::Warning,
+                message: "label start is never used".into(),
+            }]
+        );
+        assert!(!assembly.has_errors());
+        assert_eq!(
+            assembly.program,
+            [0x60, 0x05, 0xA2, 0x0C, 0xD0, 0x02, 0x12, 0x04, 0xF0, 0x00, 0xBE, 0xEF, 0xF0, 0x90]
+        );
+    }
+
+    #[test]
+    fn test_disassembly_round_trip() {
+        let program = [
+            0x00, 0xE0, 0x6A, 0x05, 0x8A, 0xB6, 0xA2, 0x34, 0xB3, 0x00, 0xD0, 0x1F, 0xE4, 0xA1,
+            0xF5, 0x0A, 0xF6, 0x55, 0xF7, 0x65, 0xF0, 0x00, 0x12, 0x34, 0x00, 0xEE, 0xFF, 0xFF,
+            0xF8, 0x30, 0xF3, 0x75, 0xF3, 0x85,
+        ];
+        let source: Vec<_> = disassemble(&program)
+            .iter()
+            .map(|line| line.text())
+            .collect();
+
+        let assembly = assemble(&source.join("\n"));
+
+        assert!(!assembly.has_errors(), "{:?}", assembly.diagnostics);
+        assert_eq!(assembly.program, program);
+    }
+
+    #[test]
+    fn test_diagnostics_positions() {
+        let source = [
+            "    LD V0, 0x100",
+            "    FOO V1",
+            "    JP nowhere",
+            "    DRW V0, V1",
+            "x:  CLS",
+            "x:  RET",
+        ]
+        .join("\n");
+        let diagnostics = check(&source);
+
+        let summary: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.line, d.column, d.len, d.severity, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    1,
+                    12,
+                    5,
+                    Severity::Error,
+                    "0x100 doesn't fit, at most 0xFF is allowed"
+                ),
+                (2, 5, 3, Severity::Error, "unknown instruction FOO"),
+                (3, 8, 7, Severity::Error, "undefined label nowhere"),
+                (4, 5, 3, Severity::Error, "invalid operands for DRW"),
+                (5, 1, 1, Severity::Warning, "label x is never used"),
+                (
+                    6,
+                    1,
+                    1,
+                    Severity::Error,
+                    "label x is already defined on line 5"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_operands() {
+        let diagnostics = check("LD VG, 1\nLD V1, 12ab\nLD V1, , 2\nstart: SYS start");
+
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "invalid register VG",
+                "invalid number 12ab",
+                "missing operand",
+                "SYS is ignored by modern interpreters",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = &check("  JP 0x1000")[0];
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "1:6: error: 0x1000 doesn't fit, at most 0xFFF is allowed"
+        );
+    }
+}
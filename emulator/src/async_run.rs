@@ -0,0 +1,214 @@
+//! Run loop for embedding the VM in async applications without dedicating a
+//! thread to it. It doesn't depend on any particular executor: the loop
+//! yields to the executor between frames and sleeps using the timer passed in
+//! by the caller, e.g. `tokio::time::sleep` or `async_std::task::sleep`.
+
+#[cfg(feature = "system-clock")]
+use super::clock::SystemClock;
+use super::clock::{Clock, FramePacer};
+use super::vm::{VmError, VM};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+/// Future which is pending once, giving other tasks a chance to run.
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// Run up to `frames` frames as fast as possible, yielding between frames,
+/// see [`VM::run_frame`]. Stops early when the program halts or an
+/// instruction fails.
+///
+/// Returns number of frames run.
+pub async fn run(vm: &mut VM, frames: u64) -> Result<u64, VmError> {
+    for frame in 0..frames {
+        if vm.is_halted() {
+            return Ok(frame);
+        }
+        vm.run_frame()?;
+        yield_now().await;
+    }
+    Ok(frames)
+}
+
+/// Run up to `frames` frames, one every `frame_time`, waiting between frames
+/// with `sleep`. Stops early when the program halts or an instruction
+/// fails.
+///
+/// Returns number of frames run.
+#[cfg(feature = "system-clock")]
+pub async fn run_paced<S, F>(
+    vm: &mut VM,
+    frames: u64,
+    frame_time: Duration,
+    sleep: S,
+) -> Result<u64, VmError>
+where
+    S: FnMut(Duration) -> F,
+    F: Future<Output = ()>,
+{
+    run_paced_by(vm, frames, frame_time, SystemClock::new(), sleep).await
+}
+
+/// [`run_paced`] keeping time with `clock` rather than the system clock.
+pub async fn run_paced_by<C, S, F>(
+    vm: &mut VM,
+    frames: u64,
+    frame_time: Duration,
+    clock: C,
+    mut sleep: S,
+) -> Result<u64, VmError>
+where
+    C: Clock,
+    S: FnMut(Duration) -> F,
+    F: Future<Output = ()>,
+{
+    let mut pacer = FramePacer::new(clock, frame_time);
+    for frame in 0..frames {
+        if vm.is_halted() {
+            return Ok(frame);
+        }
+        vm.run_frame()?;
+        let wait = pacer.next_frame();
+        if !wait.is_zero() {
+            sleep(wait).await;
+        } else {
+            // Running late, skip sleeping but still let other tasks run.
+            yield_now().await;
+        }
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::sync::Arc;
+    use std::task::Wake;
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor, returns result and number of times `future` was
+    /// pending.
+    fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+        let mut future = Box::pin(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut pending = 0;
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return (output, pending),
+                Poll::Pending => {
+                    pending += 1;
+                    thread::park();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_yields_between_frames() {
+        let mut vm = VM::new();
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+
+        let (frames, pending) = block_on(run(&mut vm, 10));
+
+        assert_eq!(frames, Ok(10));
+        assert_eq!(pending, 10);
+        // 5 additions a frame.
+        assert_eq!(vm.snapshot().registers().v[0], 50);
+    }
+
+    #[test]
+    fn test_run_stops_on_halt() {
+        let mut vm = VM::new();
+        vm.load_program(&[0x70, 0x01, 0x12, 0x02]).unwrap();
+
+        let (frames, _) = block_on(run(&mut vm, 10));
+
+        assert_eq!(frames, Ok(1));
+    }
+
+    #[test]
+    fn test_run_stops_on_error() {
+        let mut vm = VM::new();
+        vm.load_program(&[0x70, 0x01, 0xFF, 0xFF]).unwrap();
+
+        let (frames, _) = block_on(run(&mut vm, 10));
+
+        assert_eq!(
+            frames,
+            Err(VmError::UnknownInstruction {
+                pc: 0x202,
+                instruction: 0xFFFF
+            })
+        );
+    }
+
+    #[cfg(feature = "system-clock")]
+    #[test]
+    fn test_run_paced() {
+        let mut vm = VM::new();
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        let mut sleeps = Vec::new();
+
+        let frame_time = Duration::from_secs(60);
+        let (frames, _) = block_on(run_paced(&mut vm, 3, frame_time, |duration| {
+            sleeps.push(duration);
+            async {}
+        }));
+
+        assert_eq!(frames, Ok(3));
+        assert_eq!(sleeps.len(), 3);
+        assert!(sleeps[0] <= frame_time);
+        // Sleeping above returns immediately, so every deadline is further
+        // ahead.
+        assert!(sleeps[2] > frame_time * 2);
+    }
+
+    #[test]
+    fn test_run_paced_by_manual_clock() {
+        let mut vm = VM::new();
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        let clock = ManualClock::new();
+        let mut sleeps = Vec::new();
+
+        let frame_time = Duration::from_millis(10);
+        let (frames, _) = block_on(run_paced_by(&mut vm, 3, frame_time, &clock, |duration| {
+            sleeps.push(duration);
+            clock.advance(duration);
+            async {}
+        }));
+
+        assert_eq!(frames, Ok(3));
+        assert_eq!(sleeps, [frame_time; 3]);
+        assert_eq!(clock.now(), frame_time * 3);
+    }
+}
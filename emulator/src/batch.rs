@@ -0,0 +1,184 @@
+//! Running many programs in parallel, e.g. for corpus-wide regression tests
+//! or sweeps over RNG seeds.
+
+use super::vm::VM;
+use rayon::prelude::*;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Single program run.
+#[derive(Clone)]
+pub struct Job {
+    pub name: String,
+    pub program: Vec<u8>,
+    pub seed: u64,
+    /// Number of frames to run at most, see [`VM::run_frame`].
+    pub frames: u64,
+    /// Skip failing instructions rather than stop, see
+    /// [`VM::enable_sandbox`].
+    pub sandbox: bool,
+}
+
+impl Job {
+    pub fn new(name: impl Into<String>, program: Vec<u8>, frames: u64) -> Self {
+        Self {
+            name: name.into(),
+            program,
+            seed: 0,
+            frames,
+            sandbox: false,
+        }
+    }
+
+    /// Same job using another RNG seed.
+    pub fn with_seed(&self, seed: u64) -> Self {
+        Self {
+            seed,
+            ..self.clone()
+        }
+    }
+
+    /// Same job run in sandbox mode.
+    pub fn sandboxed(&self) -> Self {
+        Self {
+            sandbox: true,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// All the frames were run.
+    Completed,
+    /// Program stopped in a jump to itself.
+    Halted,
+    /// Program failed an instruction or crashed the VM with the message.
+    Error(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct JobResult {
+    pub name: String,
+    pub seed: u64,
+    pub outcome: Outcome,
+    pub frames_run: u64,
+    /// Hash of the final display contents.
+    pub display_hash: u64,
+    /// Instructions skipped in sandbox mode.
+    pub faults: u64,
+}
+
+/// Run `jobs` on all available threads, results are in the order of `jobs`.
+pub fn run(jobs: &[Job]) -> Vec<JobResult> {
+    jobs.par_iter().map(run_job).collect()
+}
+
+/// Run a single `job` on the current thread.
+pub fn run_job(job: &Job) -> JobResult {
+    let mut vm = VM::new();
+    vm.set_rng_seed(job.seed);
+    if job.sandbox {
+        vm.enable_sandbox(1);
+    }
+    let mut frames_run = 0;
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        if let Err(e) = vm.load_program(&job.program) {
+            return Outcome::Error(e.to_string());
+        }
+        while frames_run < job.frames {
+            if vm.is_halted() {
+                return Outcome::Halted;
+            }
+            if let Err(e) = vm.run_frame() {
+                return Outcome::Error(e.to_string());
+            }
+            frames_run += 1;
+        }
+        Outcome::Completed
+    }));
+    let outcome = result.unwrap_or_else(|payload| Outcome::Error(panic_message(&*payload)));
+
+    JobResult {
+        name: job.name.clone(),
+        seed: job.seed,
+        outcome,
+        frames_run,
+        display_hash: vm.display_hash(),
+        faults: vm.fault_log().map_or(0, |log| log.total()),
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown error".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let jobs = [
+            // Draw digit 0 and halt.
+            Job::new("halt", vec![0xD0, 0x05, 0x12, 0x02], 100),
+            // Loop forever.
+            Job::new("loop", vec![0x70, 0x01, 0x12, 0x00], 100),
+            Job::new("error", vec![0xFF, 0xFF], 100),
+        ];
+
+        let results = run(&jobs);
+
+        assert_eq!(results[0].name, "halt");
+        assert_eq!(results[0].outcome, Outcome::Halted);
+        assert_eq!(results[0].frames_run, 1);
+        assert_ne!(results[0].display_hash, results[1].display_hash);
+        assert_eq!(results[1].outcome, Outcome::Completed);
+        assert_eq!(results[1].frames_run, 100);
+        assert_eq!(
+            results[2].outcome,
+            Outcome::Error("unknown instruction 0xFFFF at 0x200".to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_sandboxed() {
+        // Garbage, then draw digit 0 and halt.
+        let job = Job::new(
+            "garbage",
+            vec![0xFF, 0xFF, 0x00, 0xEE, 0xD0, 0x05, 0x12, 0x06],
+            100,
+        );
+
+        let result = run_job(&job.sandboxed());
+
+        assert_eq!(result.outcome, Outcome::Halted);
+        assert_eq!(result.faults, 2);
+        assert_eq!(run_job(&job).faults, 0);
+    }
+
+    #[test]
+    fn test_run_seeds() {
+        // Store random byte at 0x300 and draw it.
+        let job = Job::new(
+            "random",
+            vec![0xC0, 0xFF, 0xA3, 0x00, 0xF0, 0x55, 0xD1, 0x11, 0x12, 0x08],
+            100,
+        );
+        let jobs = (0..4).map(|seed| job.with_seed(seed)).collect::<Vec<_>>();
+
+        let results = run(&jobs);
+
+        assert!(results.iter().all(|r| r.outcome == Outcome::Halted));
+        assert_eq!(results[0].display_hash, run_job(&jobs[0]).display_hash);
+        assert!(results
+            .iter()
+            .any(|r| r.display_hash != results[0].display_hash));
+    }
+}
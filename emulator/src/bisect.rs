@@ -0,0 +1,202 @@
+//! Find the first frame where two replays of an input script part ways, e.g.
+//! a recording which played fine before a change and doesn't anymore. The
+//! two sides are replays with different platforms or programs, or the frame
+//! hashes recorded with another build of the emulator.
+//!
+//! A frame is one executed instruction, as in `chip8 render-movie` replays,
+//! and its hash is the one of the display after it ran. Frames are
+//! bisected, taking that once displays differ they keep differing, which
+//! holds unless the difference is drawn over.
+
+use super::{script::InputScript, snapshot::StateSnapshot, trace::TraceEntry, vm::VM};
+
+/// Frames between states kept to go back in a replay.
+const CHECKPOINT_STRIDE: u64 = 256;
+
+/// Hash of the display after some frames of a run, `None` if the run didn't
+/// get that far.
+pub trait FrameSource {
+    fn frame_hash(&mut self, frame: u64) -> Option<u64>;
+}
+
+/// Hashes recorded frame by frame, the first one before any instruction.
+impl FrameSource for [u64] {
+    fn frame_hash(&mut self, frame: u64) -> Option<u64> {
+        usize::try_from(frame)
+            .ok()
+            .and_then(|frame| self.get(frame).copied())
+    }
+}
+
+/// Program run with an input script which can go to any frame, going back
+/// from the state kept every few hundred frames.
+pub struct Replay<'a> {
+    vm: VM,
+    script: &'a InputScript,
+    frame: u64,
+    /// States every [`CHECKPOINT_STRIDE`] frames from frame 0, as far as
+    /// the replay got.
+    checkpoints: Vec<StateSnapshot>,
+    /// Frame whose instruction failed, the replay can't go past it.
+    failed_at: Option<u64>,
+}
+
+impl<'a> Replay<'a> {
+    /// Replay of `script` on `vm`, which has the program loaded.
+    pub fn new(vm: VM, script: &'a InputScript) -> Self {
+        Self {
+            checkpoints: vec![vm.snapshot()],
+            vm,
+            script,
+            frame: 0,
+            failed_at: None,
+        }
+    }
+
+    /// Frames run so far in the current state.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn vm(&self) -> &VM {
+        &self.vm
+    }
+
+    /// Go to the state after `frame` frames, false if an instruction before
+    /// it failed.
+    pub fn seek(&mut self, frame: u64) -> bool {
+        if self.failed_at.is_some_and(|failed_at| frame > failed_at) {
+            return false;
+        }
+        if frame < self.frame {
+            let checkpoint = frame / CHECKPOINT_STRIDE;
+            self.vm.restore(&self.checkpoints[checkpoint as usize]);
+            self.frame = checkpoint * CHECKPOINT_STRIDE;
+        }
+        while self.frame < frame {
+            self.vm.set_input(self.script.input_at(self.frame));
+            if self.vm.exec_current_instruction().is_err() {
+                self.failed_at = Some(self.frame);
+                return false;
+            }
+            self.frame += 1;
+            let kept = self.checkpoints.len() as u64 * CHECKPOINT_STRIDE;
+            if self.frame == kept {
+                self.checkpoints.push(self.vm.snapshot());
+            }
+        }
+        true
+    }
+
+    /// Instruction run by frame `frame`, the one going from the state
+    /// after `frame - 1` frames to the state after `frame`.
+    pub fn instruction_of(&mut self, frame: u64) -> Option<TraceEntry> {
+        if frame == 0 || !self.seek(frame - 1) {
+            return None;
+        }
+        let pc = self.vm.registers().program_counter;
+        let address = pc as usize;
+        match self.vm.memory(address..address + 2) {
+            Some(&[high, low]) => Some(TraceEntry {
+                pc,
+                instruction: u16::from_be_bytes([high, low]),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Hashes of frames 0 to `frames`, fewer if an instruction fails.
+    pub fn frame_hashes(&mut self, frames: u64) -> Vec<u64> {
+        (0..=frames)
+            .map_while(|frame| self.frame_hash(frame))
+            .collect()
+    }
+}
+
+impl FrameSource for Replay<'_> {
+    fn frame_hash(&mut self, frame: u64) -> Option<u64> {
+        self.seek(frame).then(|| self.vm.display_hash())
+    }
+}
+
+/// First frame up to `frames` whose hashes differ between `a` and `b`,
+/// `None` if they're the same after `frames` frames.
+pub fn bisect(
+    a: &mut (impl FrameSource + ?Sized),
+    b: &mut (impl FrameSource + ?Sized),
+    frames: u64,
+) -> Option<u64> {
+    let mut differ = |frame| a.frame_hash(frame) != b.frame_hash(frame);
+    if !differ(frames) {
+        return None;
+    }
+    if differ(0) {
+        return Some(0);
+    }
+    // Same after `same` frames, different after `different`.
+    let (mut same, mut different) = (0, frames);
+    while different - same > 1 {
+        let middle = same + (different - same) / 2;
+        if differ(middle) {
+            different = middle;
+        } else {
+            same = middle;
+        }
+    }
+    Some(different)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws digits 0, `step`, 2 * `step`... at the same place, one every 4
+    /// frames, the first by frame 3.
+    fn counter(step: u8) -> VM {
+        let mut vm = VM::new();
+        // LD V0, 0; LD F, V0; DRW V1, V1, 5; ADD V0, step; JP 0x202
+        vm.load_program(&[0x60, 0x00, 0xF0, 0x29, 0xD1, 0x15, 0x70, step, 0x12, 0x02])
+            .unwrap();
+        vm
+    }
+
+    #[test]
+    fn test_bisect_replays() {
+        let script = InputScript::default();
+        let mut a = Replay::new(counter(1), &script);
+        let mut b = Replay::new(counter(2), &script);
+
+        assert_eq!(bisect(&mut a, &mut b, 1000), Some(7));
+        assert_eq!(
+            a.instruction_of(7),
+            Some(TraceEntry {
+                pc: 0x204,
+                instruction: 0xD115
+            })
+        );
+
+        let mut same = Replay::new(counter(1), &script);
+        assert_eq!(bisect(&mut a, &mut same, 1000), None);
+    }
+
+    #[test]
+    fn test_bisect_recorded_hashes() {
+        let script = InputScript::default();
+        let mut recorded = Replay::new(counter(1), &script).frame_hashes(600);
+        assert_eq!(recorded.len(), 601);
+
+        let mut replay = Replay::new(counter(2), &script);
+        assert_eq!(bisect(&mut replay, &mut recorded[..], 600), Some(7));
+        // Going back to a checkpoint replays the same frames.
+        assert!(replay.seek(1000));
+        assert!(replay.seek(300));
+        assert_eq!(replay.frame(), 300);
+        assert_eq!(
+            replay.frame_hash(600),
+            Replay::new(counter(2), &script).frame_hash(600)
+        );
+        // Recorded hashes end, the replay doesn't.
+        let mut replay = Replay::new(counter(1), &script);
+        assert_eq!(bisect(&mut replay, &mut recorded[..], 700), Some(601));
+    }
+}
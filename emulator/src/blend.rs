@@ -0,0 +1,200 @@
+//! Blending of consecutive frames to hide the flicker of sprites which
+//! programs erase and redraw with XOR every frame, for frontends to apply
+//! before showing the display.
+
+use super::graphics::{Graphics, DISPLAY_COLS};
+use super::palette::{Palette, Rgb, RGBA_SIZE};
+
+/// How the last two frames are combined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Only the last frame is shown.
+    #[default]
+    Off,
+    /// Pixels lit in one of the frames are half lit.
+    Average,
+    /// Pixels lit in either frame are lit.
+    Max,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 3] = [BlendMode::Off, BlendMode::Average, BlendMode::Max];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BlendMode::Off => "off",
+            BlendMode::Average => "average",
+            BlendMode::Max => "max",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    /// Mode after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            BlendMode::Off => BlendMode::Average,
+            BlendMode::Average => BlendMode::Max,
+            BlendMode::Max => BlendMode::Off,
+        }
+    }
+}
+
+/// Last two frames shown and how they're blended.
+#[derive(Clone, Default)]
+pub struct FrameBlender {
+    mode: BlendMode,
+    previous: Graphics,
+    current: Graphics,
+}
+
+impl FrameBlender {
+    pub fn new(mode: BlendMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    pub fn mode(&self) -> BlendMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: BlendMode) {
+        self.mode = mode;
+    }
+
+    /// Make `graphics` the current frame, the current one becoming the
+    /// previous. Call it once per frame shown.
+    pub fn push(&mut self, graphics: &Graphics) {
+        self.previous = self.current.clone();
+        self.current = graphics.clone();
+    }
+
+    /// Forget the previous frame, e.g. after loading a state, so it doesn't
+    /// show through.
+    pub fn reset(&mut self, graphics: &Graphics) {
+        self.previous = graphics.clone();
+        self.current = graphics.clone();
+    }
+
+    /// Brightness of pixel (`x`, `y`) from 0, off, to 255, on.
+    pub fn intensity(&self, x: usize, y: usize) -> u8 {
+        let current = self.current.pixel(x, y);
+        let previous = self.previous.pixel(x, y);
+        match self.mode {
+            BlendMode::Off => current as u8 * u8::MAX,
+            BlendMode::Average => match (current, previous) {
+                (true, true) => u8::MAX,
+                (false, false) => 0,
+                _ => u8::MAX / 2 + 1,
+            },
+            BlendMode::Max => (current || previous) as u8 * u8::MAX,
+        }
+    }
+
+    /// Color of pixel (`x`, `y`) in `palette`.
+    pub fn color(&self, palette: &Palette, x: usize, y: usize) -> Rgb {
+        mix(
+            palette.pixel_color(false),
+            palette.pixel_color(true),
+            self.intensity(x, y),
+        )
+    }
+
+    /// Blended frame as RGBA bytes like [`Palette::render_rgba_into`].
+    ///
+    /// # Panics
+    ///
+    /// If `rgba` isn't [`RGBA_SIZE`] bytes long.
+    pub fn render_rgba_into(&self, palette: &Palette, rgba: &mut [u8]) {
+        assert_eq!(rgba.len(), RGBA_SIZE);
+        for (i, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (i % DISPLAY_COLS, i / DISPLAY_COLS);
+            pixel[..3].copy_from_slice(&self.color(palette, x, y));
+            pixel[3] = 0xFF;
+        }
+    }
+}
+
+/// Color `amount` of the way from `from` to `to`, 255 being all the way.
+fn mix(from: Rgb, to: Rgb, amount: u8) -> Rgb {
+    let amount = amount as u16;
+    let channel = |from: u8, to: u8| {
+        ((from as u16 * (u8::MAX as u16 - amount) + to as u16 * amount) / u8::MAX as u16) as u8
+    };
+    [
+        channel(from[0], to[0]),
+        channel(from[1], to[1]),
+        channel(from[2], to[2]),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::GRAYSCALE;
+
+    /// Blender with pixel (0, 0) lit in the previous frame and (1, 0) in the
+    /// current one.
+    fn flickering(mode: BlendMode) -> FrameBlender {
+        let mut blender = FrameBlender::new(mode);
+        let mut graphics = Graphics::new();
+        graphics.draw_sprite(0, 0, &[0x80]);
+        blender.push(&graphics);
+        let mut graphics = Graphics::new();
+        graphics.draw_sprite(1, 0, &[0x80]);
+        blender.push(&graphics);
+        blender
+    }
+
+    #[test]
+    fn test_intensity() {
+        let intensities = |blender: &FrameBlender| [0, 1, 2].map(|x| blender.intensity(x, 0));
+
+        assert_eq!(intensities(&flickering(BlendMode::Off)), [0, 255, 0]);
+        assert_eq!(intensities(&flickering(BlendMode::Average)), [128, 128, 0]);
+        assert_eq!(intensities(&flickering(BlendMode::Max)), [255, 255, 0]);
+    }
+
+    #[test]
+    fn test_average_both_lit() {
+        let mut blender = FrameBlender::new(BlendMode::Average);
+        let mut graphics = Graphics::new();
+        graphics.draw_sprite(0, 0, &[0x80]);
+        blender.reset(&graphics);
+
+        assert_eq!(blender.intensity(0, 0), 255);
+    }
+
+    #[test]
+    fn test_render_rgba_into() {
+        let blender = flickering(BlendMode::Average);
+        let mut rgba = vec![0; RGBA_SIZE];
+
+        blender.render_rgba_into(&GRAYSCALE, &mut rgba);
+
+        let off = GRAYSCALE.pixel_color(false);
+        let on = GRAYSCALE.pixel_color(true);
+        assert_eq!(rgba[0..3], mix(off, on, 128));
+        assert_eq!(rgba[8..11], off);
+        assert_eq!(rgba[3], 0xFF);
+    }
+
+    #[test]
+    fn test_mix() {
+        assert_eq!(mix([0, 0, 0], [255, 100, 10], 0), [0, 0, 0]);
+        assert_eq!(mix([0, 0, 0], [255, 100, 10], 255), [255, 100, 10]);
+        assert_eq!(mix([0, 200, 0], [255, 0, 0], 128), [128, 99, 0]);
+    }
+
+    #[test]
+    fn test_names() {
+        for mode in BlendMode::ALL {
+            assert_eq!(BlendMode::from_name(mode.name()), Some(mode));
+        }
+        assert_eq!(BlendMode::from_name("blur"), None);
+    }
+}
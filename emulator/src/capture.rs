@@ -0,0 +1,668 @@
+//! Writing display frames and sound in formats external tools understand.
+
+use super::graphics::{Framebuffer, Graphics, DISPLAY_COLS, DISPLAY_ROWS};
+use super::palette::{Palette, Rgb};
+use std::collections::HashMap;
+use std::io::{self, Seek, SeekFrom, Write};
+
+const FRAME_RATE: u32 = 60;
+
+pub const SAMPLE_RATE: u32 = 44_100;
+const SAMPLES_PER_FRAME: usize = (SAMPLE_RATE / FRAME_RATE) as usize;
+const TONE_FREQUENCY: f32 = 440.0;
+const AMPLITUDE: i16 = i16::MAX / 4;
+const WAV_HEADER_SIZE: u32 = 44;
+
+/// Writer of uncompressed YUV4MPEG2 video, which can be piped into ffmpeg for
+/// high quality captures, e.g. `ffmpeg -i out.y4m out.mp4`.
+///
+/// Frames are full range YCbCr 4:4:4 in `palette` colors, every CHIP-8
+/// pixel becomes a `scale`x`scale` square.
+pub struct Y4mWriter<W: Write> {
+    writer: W,
+    scale: usize,
+    /// Y, Cb and Cr of the palette colors.
+    colors: [[u8; 3]; 4],
+}
+
+impl<W: Write> Y4mWriter<W> {
+    /// Write stream header to `writer`.
+    pub fn new(mut writer: W, scale: usize, palette: &Palette) -> io::Result<Self> {
+        assert!(scale > 0);
+        writeln!(
+            writer,
+            "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444 XCOLORRANGE=FULL",
+            DISPLAY_COLS * scale,
+            DISPLAY_ROWS * scale,
+            FRAME_RATE
+        )?;
+        let colors = palette.colors.map(ycbcr);
+        Ok(Self {
+            writer,
+            scale,
+            colors,
+        })
+    }
+
+    pub fn write_frame(&mut self, graphics: &Graphics) -> io::Result<()> {
+        self.writer.write_all(b"FRAME\n")?;
+        let mut line = Vec::with_capacity(DISPLAY_COLS * self.scale);
+        for component in 0..3 {
+            for y in 0..DISPLAY_ROWS {
+                line.clear();
+                for x in 0..DISPLAY_COLS {
+                    let value = self.colors[graphics.color_index(x, y)][component];
+                    line.extend(std::iter::repeat_n(value, self.scale));
+                }
+                for _ in 0..self.scale {
+                    self.writer.write_all(&line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Delays between GIF frames are in hundredths of a second, and viewers
+/// slow down frames shorter than this.
+const GIF_MIN_DELAY: u64 = 2;
+
+/// Writer of looping animated GIF images in `palette` colors, every CHIP-8
+/// pixel becoming a `scale`x`scale` square.
+///
+/// GIF can't show 60 frames a second, so only frames where the display
+/// changed are written, and ones replaced sooner than viewers can show them
+/// are dropped.
+pub struct GifWriter<W: Write> {
+    writer: W,
+    scale: usize,
+    /// Frames written so far.
+    frame: u64,
+    /// Display waiting to be written once it's known how long it's shown,
+    /// with the frame it appeared in.
+    pending: Option<(Graphics, u64)>,
+}
+
+impl<W: Write> GifWriter<W> {
+    /// Write image header with the palette as color table to `writer`.
+    pub fn new(mut writer: W, scale: usize, palette: &Palette) -> io::Result<Self> {
+        assert!(scale > 0);
+        let width = (DISPLAY_COLS * scale) as u16;
+        let height = (DISPLAY_ROWS * scale) as u16;
+        writer.write_all(b"GIF89a")?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        // Global color table of 4 entries, background color 0, square pixels.
+        writer.write_all(&[0x81, 0, 0])?;
+        for color in palette.colors {
+            writer.write_all(&color)?;
+        }
+        // Loop forever.
+        writer.write_all(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+        Ok(Self {
+            writer,
+            scale,
+            frame: 0,
+            pending: None,
+        })
+    }
+
+    pub fn write_frame(&mut self, graphics: &Graphics) -> io::Result<()> {
+        let frame = self.frame;
+        self.frame += 1;
+        match &self.pending {
+            Some((pending, _)) if pending.display == graphics.display => return Ok(()),
+            Some((_, start)) if gif_time(frame) - gif_time(*start) < GIF_MIN_DELAY => {
+                // Replaced too soon to be seen, show the new display instead.
+                self.pending = Some((graphics.clone(), *start));
+                return Ok(());
+            }
+            _ => {}
+        }
+        self.flush_pending(frame)?;
+        self.pending = Some((graphics.clone(), frame));
+        Ok(())
+    }
+
+    /// Write the last frame and the trailer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_pending(self.frame)?;
+        self.writer.write_all(&[0x3B])?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Write the pending display as shown until `frame`.
+    fn flush_pending(&mut self, frame: u64) -> io::Result<()> {
+        let (graphics, start) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+        let delay = (gif_time(frame) - gif_time(start)).max(GIF_MIN_DELAY);
+        // Graphic control extension with the delay, no transparency.
+        self.writer.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+        self.writer.write_all(&(delay as u16).to_le_bytes())?;
+        self.writer.write_all(&[0x00, 0x00])?;
+
+        let width = DISPLAY_COLS * self.scale;
+        let height = DISPLAY_ROWS * self.scale;
+        self.writer.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.writer.write_all(&(width as u16).to_le_bytes())?;
+        self.writer.write_all(&(height as u16).to_le_bytes())?;
+        self.writer.write_all(&[0x00])?;
+
+        let mut indices = Vec::with_capacity(width * height);
+        for y in 0..DISPLAY_ROWS {
+            let start = indices.len();
+            for x in 0..DISPLAY_COLS {
+                let index = graphics.color_index(x, y) as u8;
+                indices.extend(std::iter::repeat_n(index, self.scale));
+            }
+            for _ in 1..self.scale {
+                indices.extend_from_within(start..start + width);
+            }
+        }
+        self.writer.write_all(&[GIF_MIN_CODE_SIZE])?;
+        for block in lzw_encode(&indices).chunks(255) {
+            self.writer.write_all(&[block.len() as u8])?;
+            self.writer.write_all(block)?;
+        }
+        self.writer.write_all(&[0x00])
+    }
+}
+
+/// Hundredths of a second since the start at `frame`.
+fn gif_time(frame: u64) -> u64 {
+    (frame * 100 + FRAME_RATE as u64 / 2) / FRAME_RATE as u64
+}
+
+/// Smallest LZW code size GIF allows, enough for the four colors.
+const GIF_MIN_CODE_SIZE: u8 = 2;
+const LZW_MAX_CODE: u16 = 4095;
+
+/// Compress color `indices` with the variable code length LZW of GIF.
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << GIF_MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut encoder = LzwOutput {
+        out: Vec::new(),
+        bits: 0,
+        bit_count: 0,
+        code_size: GIF_MIN_CODE_SIZE as u32 + 1,
+        next_code: end + 1,
+    };
+    let mut table = HashMap::new();
+    encoder.emit(clear);
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let current = match prefix {
+            Some(current) => current,
+            None => {
+                prefix = Some(index as u16);
+                continue;
+            }
+        };
+        if let Some(&code) = table.get(&(current, index)) {
+            prefix = Some(code);
+            continue;
+        }
+        encoder.emit(current);
+        if encoder.next_code >= LZW_MAX_CODE {
+            encoder.emit(clear);
+            table.clear();
+            encoder.code_size = GIF_MIN_CODE_SIZE as u32 + 1;
+            encoder.next_code = end + 1;
+        } else {
+            table.insert((current, index), encoder.next_code);
+            encoder.next_code += 1;
+        }
+        prefix = Some(index as u16);
+    }
+    if let Some(current) = prefix {
+        encoder.emit(current);
+    }
+    encoder.emit(end);
+    if encoder.bit_count > 0 {
+        encoder.out.push(encoder.bits as u8);
+    }
+    encoder.out
+}
+
+/// Bit packer of LZW codes, least significant bit first.
+struct LzwOutput {
+    out: Vec<u8>,
+    bits: u32,
+    bit_count: u32,
+    code_size: u32,
+    /// Code the next table entry gets.
+    next_code: u16,
+}
+
+impl LzwOutput {
+    fn emit(&mut self, code: u16) {
+        self.bits |= (code as u32) << self.bit_count;
+        self.bit_count += self.code_size;
+        while self.bit_count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.bit_count -= 8;
+        }
+        // The decoder adds entries a code later, so the code size grows
+        // only after a code is written with the table already full.
+        if self.next_code >= 1 << self.code_size && self.code_size < 12 {
+            self.code_size += 1;
+        }
+    }
+}
+
+/// Writer of the beeper sound as 16-bit mono PCM WAV: a square wave in
+/// frames the sound timer is active, silence in the rest.
+///
+/// Sizes in the header are only known at the end, so the writer seeks back
+/// to fill them in [`WavWriter::finish`].
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    samples: u32,
+    /// Position within the tone period, from 0 to 1.
+    phase: f32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    /// Write header with empty sizes to `writer`.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let header = wav_header(0);
+        writer.write_all(&header)?;
+        Ok(Self {
+            writer,
+            samples: 0,
+            phase: 0.0,
+        })
+    }
+
+    /// Write one frame of sound.
+    pub fn write_frame(&mut self, sound_playing: bool) -> io::Result<()> {
+        let mut frame = Vec::with_capacity(SAMPLES_PER_FRAME * 2);
+        for _ in 0..SAMPLES_PER_FRAME {
+            let sample = match (sound_playing, self.phase < 0.5) {
+                (false, _) => 0,
+                (true, true) => AMPLITUDE,
+                (true, false) => -AMPLITUDE,
+            };
+            frame.extend(sample.to_le_bytes());
+            self.phase = (self.phase + TONE_FREQUENCY / SAMPLE_RATE as f32).fract();
+        }
+        self.writer.write_all(&frame)?;
+        self.samples += SAMPLES_PER_FRAME as u32;
+        Ok(())
+    }
+
+    /// Fill in sizes in the header.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(&wav_header(self.samples * 2))?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+fn wav_header(data_size: u32) -> [u8; WAV_HEADER_SIZE as usize] {
+    let mut header = [0; WAV_HEADER_SIZE as usize];
+    let fields: [&[u8]; 13] = [
+        b"RIFF",
+        &(WAV_HEADER_SIZE - 8 + data_size).to_le_bytes(),
+        b"WAVE",
+        b"fmt ",
+        &16u32.to_le_bytes(),
+        // PCM, mono.
+        &1u16.to_le_bytes(),
+        &1u16.to_le_bytes(),
+        &SAMPLE_RATE.to_le_bytes(),
+        // Byte rate, block align and bits per sample.
+        &(SAMPLE_RATE * 2).to_le_bytes(),
+        &2u16.to_le_bytes(),
+        &16u16.to_le_bytes(),
+        b"data",
+        &data_size.to_le_bytes(),
+    ];
+    let mut offset = 0;
+    for field in fields {
+        header[offset..offset + field.len()].copy_from_slice(field);
+        offset += field.len();
+    }
+    header
+}
+
+/// Encode the display as an RGB PNG image in `palette` colors, every CHIP-8
+/// pixel becoming a `scale`x`scale` square.
+///
+/// Image data is stored without compression, which keeps the encoder tiny;
+/// even at large scales the images are a few hundred kilobytes at most.
+pub fn encode_png(
+    graphics: &(impl Framebuffer + ?Sized),
+    scale: usize,
+    palette: &Palette,
+) -> Vec<u8> {
+    assert!(scale > 0);
+    let width = graphics.width() * scale;
+    let height = graphics.height() * scale;
+
+    // Scanlines, each prefixed with filter type 0 (none).
+    let mut raw = Vec::with_capacity((3 * width + 1) * height);
+    for y in 0..graphics.height() {
+        let mut line = Vec::with_capacity(3 * width + 1);
+        line.push(0);
+        for x in 0..graphics.width() {
+            let rgb = palette.screen_color(graphics, x, y);
+            for _ in 0..scale {
+                line.extend(rgb);
+            }
+        }
+        for _ in 0..scale {
+            raw.extend_from_slice(&line);
+        }
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend((width as u32).to_be_bytes());
+    ihdr.extend((height as u32).to_be_bytes());
+    // 8 bit RGB, deflate, adaptive filtering, no interlace.
+    ihdr.extend([8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_png_chunk(&mut png, b"IHDR", &ihdr);
+    write_png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Full range BT.601 Y, Cb and Cr of `rgb`.
+fn ycbcr([r, g, b]: Rgb) -> [u8; 3] {
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    let cb = 128.0 - 0.168_736 * r - 0.331_264 * g + 0.5 * b;
+    let cr = 128.0 + 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+    [y, cb, cr].map(|value| value.round().clamp(0.0, 255.0) as u8)
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// Zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xFFFF;
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        out.push(last as u8);
+        let len = block.len() as u16;
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % MOD;
+        b = (b + a) % MOD;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::graphics::SchipGraphics;
+    use super::super::palette::OCTO;
+    use super::*;
+
+    #[test]
+    fn test_y4m_header() {
+        let writer = Y4mWriter::new(Vec::new(), 2, &Palette::default()).unwrap();
+
+        let output = writer.into_inner();
+
+        assert_eq!(
+            output,
+            b"YUV4MPEG2 W128 H64 F60:1 Ip A1:1 C444 XCOLORRANGE=FULL\n"
+        );
+    }
+
+    #[test]
+    fn test_y4m_frame() {
+        let mut graphics = Graphics::new();
+        graphics.draw_sprite(1, 0, &[0x80]);
+        let mut writer = Y4mWriter::new(Vec::new(), 2, &Palette::default()).unwrap();
+
+        writer.write_frame(&graphics).unwrap();
+        writer.write_frame(&Graphics::new()).unwrap();
+
+        let output = writer.into_inner();
+        let header_len = output.iter().position(|&b| b == b'\n').unwrap() + 1;
+        let plane_len = DISPLAY_COLS * DISPLAY_ROWS * 4;
+        let frame_len = b"FRAME\n".len() + 3 * plane_len;
+        assert_eq!(output.len(), header_len + 2 * frame_len);
+        let frame = &output[header_len..header_len + frame_len];
+        assert!(frame.starts_with(b"FRAME\n"));
+        let (luma, chroma) = frame[6..].split_at(plane_len);
+        let width = DISPLAY_COLS * 2;
+        assert_eq!(luma[0..4], [0x00, 0x00, 0xFF, 0xFF]);
+        assert_eq!(luma[width..width + 4], [0x00, 0x00, 0xFF, 0xFF]);
+        assert!(luma[2 * width..].iter().all(|&luma| luma == 0));
+        // Gray has no color.
+        assert!(chroma.iter().all(|&value| value == 0x80));
+    }
+
+    #[test]
+    fn test_ycbcr() {
+        assert_eq!(ycbcr([0, 0, 0]), [0, 128, 128]);
+        assert_eq!(ycbcr([0xFF, 0xFF, 0xFF]), [255, 128, 128]);
+        assert_eq!(ycbcr([0xFF, 0, 0]), [76, 85, 255]);
+    }
+
+    /// Decoder of GIF LZW data, the way image viewers read it.
+    fn lzw_decode(data: &[u8]) -> Vec<u8> {
+        let clear = 1 << GIF_MIN_CODE_SIZE;
+        let end = clear + 1;
+        let initial: Vec<Vec<u8>> = (0..=end).map(|code| vec![code as u8]).collect();
+        let mut table = initial.clone();
+        let mut code_size = GIF_MIN_CODE_SIZE as usize + 1;
+        let mut position = 0;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        loop {
+            let code = (0..code_size)
+                .map(|bit| {
+                    let at = position + bit;
+                    ((data[at / 8] >> (at % 8)) as usize & 1) << bit
+                })
+                .sum::<usize>();
+            position += code_size;
+            if code == clear {
+                table = initial.clone();
+                code_size = GIF_MIN_CODE_SIZE as usize + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => [&previous[..], &previous[..1]].concat(),
+                (None, None) => panic!("unknown first code {}", code),
+            };
+            out.extend(&entry);
+            if let Some(previous) = previous {
+                if table.len() < 4096 {
+                    table.push([&previous[..], &entry[..1]].concat());
+                }
+            }
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        let mut state = 0x2545_F491_u32;
+        let indices: Vec<u8> = (0..100_000)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                // Long runs like a display has, and noise.
+                if i % 3000 < 1500 {
+                    (i / 40 % 2) as u8
+                } else {
+                    (state & 1) as u8
+                }
+            })
+            .collect();
+
+        assert_eq!(lzw_decode(&lzw_encode(&indices)), indices);
+        assert_eq!(lzw_decode(&lzw_encode(&[1])), [1]);
+        assert_eq!(lzw_decode(&lzw_encode(&[])), []);
+    }
+
+    #[test]
+    fn test_gif() {
+        let mut graphics = Graphics::new();
+        let mut writer = GifWriter::new(Vec::new(), 2, &OCTO).unwrap();
+        for _ in 0..3 {
+            writer.write_frame(&graphics).unwrap();
+        }
+        graphics.draw_sprite(0, 0, &[0x80]);
+        for _ in 0..3 {
+            writer.write_frame(&graphics).unwrap();
+        }
+
+        let output = writer.finish().unwrap();
+
+        assert!(output.starts_with(b"GIF89a\x80\x00\x40\x00\x81\x00\x00"));
+        assert_eq!(output[13..25], OCTO.colors.concat());
+        assert_eq!(output.last(), Some(&0x3B));
+        let delays: Vec<_> = output
+            .windows(8)
+            .filter(|w| w[..4] == [0x21, 0xF9, 0x04, 0x00])
+            .map(|w| u16::from_le_bytes([w[4], w[5]]))
+            .collect();
+        assert_eq!(delays, [5, 5]);
+    }
+
+    #[test]
+    fn test_gif_drops_short_frames() {
+        let mut graphics = Graphics::new();
+        let mut writer = GifWriter::new(Vec::new(), 1, &OCTO).unwrap();
+        writer.write_frame(&graphics).unwrap();
+        // Shown for a hundredth of a second only.
+        graphics.draw_sprite(0, 0, &[0x80]);
+        writer.write_frame(&graphics).unwrap();
+        graphics.draw_sprite(0, 0, &[0x80]);
+        for _ in 0..4 {
+            writer.write_frame(&graphics).unwrap();
+        }
+
+        let output = writer.finish().unwrap();
+
+        let delays: Vec<_> = output
+            .windows(8)
+            .filter(|w| w[..4] == [0x21, 0xF9, 0x04, 0x00])
+            .map(|w| u16::from_le_bytes([w[4], w[5]]))
+            .collect();
+        assert_eq!(delays, [2, 8]);
+    }
+
+    #[test]
+    fn test_wav() {
+        let mut writer = WavWriter::new(io::Cursor::new(Vec::new())).unwrap();
+
+        writer.write_frame(false).unwrap();
+        writer.write_frame(true).unwrap();
+
+        let output = writer.finish().unwrap().into_inner();
+        let data_size = 2 * SAMPLES_PER_FRAME * 2;
+        assert_eq!(output.len(), 44 + data_size);
+        assert_eq!(&output[0..4], b"RIFF");
+        assert_eq!(output[4..8], (36 + data_size as u32).to_le_bytes());
+        assert_eq!(&output[8..16], b"WAVEfmt ");
+        assert_eq!(output[24..28], 44_100u32.to_le_bytes());
+        assert_eq!(&output[36..40], b"data");
+        assert_eq!(output[40..44], (data_size as u32).to_le_bytes());
+
+        let samples = output[44..]
+            .chunks(2)
+            .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect::<Vec<_>>();
+        let (silence, tone) = samples.split_at(SAMPLES_PER_FRAME);
+        assert!(silence.iter().all(|&sample| sample == 0));
+        assert!(tone.iter().all(|&sample| sample.abs() == AMPLITUDE));
+        // 440 Hz square wave, about 100 samples per period.
+        let changes = tone.windows(2).filter(|pair| pair[0] != pair[1]).count();
+        assert!((13..=15).contains(&changes), "{} changes", changes);
+    }
+
+    #[test]
+    fn test_crc32_and_adler32() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_encode_png() {
+        let mut graphics = Graphics::new();
+        graphics.draw_sprite(0, 0, &[0x80]);
+
+        let png = encode_png(&graphics, 2, &OCTO);
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+        assert_eq!(png[16..24], [0, 0, 0, 128, 0, 0, 0, 64]);
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+        // Raw data is stored uncompressed right after zlib and block headers.
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap() + 4;
+        let raw = &png[idat + 2 + 5..];
+        assert_eq!(
+            raw[..10],
+            [0, 0xFF, 0xCC, 0x00, 0xFF, 0xCC, 0x00, 0x99, 0x66, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_encode_png_hires() {
+        let png = encode_png(&SchipGraphics::default(), 1, &OCTO);
+
+        assert_eq!(png[16..24], [0, 0, 0, 128, 0, 0, 0, 64]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Time sources for pacing frames and ticking timers. Frontends use
+//! `SystemClock`, with the `system-clock` feature, tests and targets without
+//! `std::time::Instant`, like wasm in a browser, drive time themselves with
+//! [`ManualClock`] or their own [`Clock`].
+
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+#[cfg(feature = "system-clock")]
+use std::time::Instant;
+
+/// Source of the current time.
+pub trait Clock {
+    /// Time since some fixed point, e.g. the creation of the clock. It never
+    /// goes back.
+    fn now(&self) -> Duration;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+/// Real time, since the clock was created.
+#[cfg(feature = "system-clock")]
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+#[cfg(feature = "system-clock")]
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "system-clock")]
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "system-clock")]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Time which only passes when told to. It can be shared, e.g. in an `Arc`,
+/// between whatever advances it and whatever reads it.
+#[cfg(target_has_atomic = "64")]
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    nanos: AtomicU64,
+}
+
+#[cfg(target_has_atomic = "64")]
+impl ManualClock {
+    /// Clock starting at 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_has_atomic = "64")]
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// Keeps a frame every `frame_time` of `clock`, telling how long to wait
+/// for the next frame and how much time passed between frames, to advance
+/// timers by.
+#[derive(Debug)]
+pub struct FramePacer<C> {
+    clock: C,
+    frame_time: Duration,
+    /// When the next frame is due.
+    deadline: Duration,
+    /// When [`FramePacer::elapsed`] was last called.
+    last: Duration,
+}
+
+impl<C: Clock> FramePacer<C> {
+    /// Pacer with the first frame due now.
+    pub fn new(clock: C, frame_time: Duration) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            frame_time,
+            deadline: now,
+            last: now,
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Time passed since the last call, or the creation of the pacer.
+    pub fn elapsed(&mut self) -> Duration {
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last);
+        self.last = now;
+        elapsed
+    }
+
+    /// Time to wait for the next frame, zero if running late. When more
+    /// than a frame late, the missed frames are dropped rather than run in
+    /// a hurry to catch up.
+    pub fn next_frame(&mut self) -> Duration {
+        let now = self.clock.now();
+        self.deadline += self.frame_time;
+        if self.deadline + self.frame_time < now {
+            self.deadline = now;
+        }
+        self.deadline.saturating_sub(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = Arc::new(ManualClock::new());
+        let shared = Arc::clone(&clock);
+
+        shared.advance(Duration::from_millis(5));
+        shared.advance(Duration::from_millis(10));
+
+        assert_eq!(clock.now(), Duration::from_millis(15));
+    }
+
+    #[cfg(feature = "system-clock")]
+    #[test]
+    fn test_system_clock() {
+        let clock = SystemClock::new();
+        let before = clock.now();
+        assert!(clock.now() >= before);
+    }
+
+    #[test]
+    fn test_frame_pacer() {
+        let clock = ManualClock::new();
+        let mut pacer = FramePacer::new(&clock, Duration::from_millis(10));
+
+        clock.advance(Duration::from_millis(4));
+        assert_eq!(pacer.elapsed(), Duration::from_millis(4));
+        assert_eq!(pacer.next_frame(), Duration::from_millis(6));
+
+        clock.advance(Duration::from_millis(6));
+        clock.advance(Duration::from_millis(13));
+        assert_eq!(pacer.elapsed(), Duration::from_millis(19));
+        // 3 ms late.
+        assert_eq!(pacer.next_frame(), Duration::ZERO);
+        assert_eq!(pacer.next_frame(), Duration::from_millis(7));
+    }
+
+    #[test]
+    fn test_frame_pacer_drops_missed_frames() {
+        let clock = ManualClock::new();
+        let mut pacer = FramePacer::new(&clock, Duration::from_millis(10));
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(pacer.next_frame(), Duration::ZERO);
+        assert_eq!(pacer.next_frame(), Duration::from_millis(10));
+    }
+}
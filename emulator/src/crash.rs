@@ -0,0 +1,373 @@
+//! Crash dumps: the state of a VM whose program failed, the instructions
+//! which led there and the frontend settings, written by frontends to a
+//! `.c8crash` file to attach to bug reports and loaded back to replay the
+//! failure with `chip8 debug --crash`.
+//!
+//! The file is text, one `key = value` line per field: `error`, `program`
+//! (FNV-1a hash of the program as loaded), `config.<name>` frontend settings,
+//! `quirk.<name>`, registers `pc`, `i`, `v` (16 hex bytes), `dt` and `st`,
+//! `stack` with the addresses of the `CALL`s being run, `keys` held, one
+//! `trace` line per instruction (address and word), one `display` line per
+//! row and one `memory` line per 64 bytes, in hex. The random number
+//! generator isn't kept, replays use whatever the VM has.
+
+use super::{
+    graphics::{fnv1a, Graphics, DISPLAY_ROWS},
+    input::Input,
+    memory::MEMORY_SIZE,
+    platform::Quirks,
+    registers::Registers,
+    stack::{Stack, STACK_SIZE},
+    trace::TraceEntry,
+    vm::VM,
+};
+use std::fmt::{self, Write};
+
+/// Extension of crash dump files.
+pub const EXTENSION: &str = "c8crash";
+
+const HEADER: &str = "# chip-8-emulator crash dump";
+/// Bytes of memory per `memory` line.
+const MEMORY_LINE: usize = 64;
+
+/// Why a crash dump couldn't be read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrashError {
+    /// Line with its number, starting from 1.
+    InvalidLine(usize),
+    /// Field which isn't in the dump, or not all of it.
+    Missing(&'static str),
+}
+
+impl fmt::Display for CrashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrashError::InvalidLine(line) => write!(f, "invalid crash dump line {}", line),
+            CrashError::Missing(field) => write!(f, "crash dump has no {}", field),
+        }
+    }
+}
+
+impl std::error::Error for CrashError {}
+
+/// Everything known about a VM when its program failed.
+#[derive(Clone)]
+pub struct CrashDump {
+    /// What went wrong, e.g. the [`VmError`](super::vm::VmError).
+    pub error: String,
+    /// FNV-1a hash of the program as loaded, to tell which one it was.
+    pub program_hash: u64,
+    /// Frontend settings as names and values, e.g. `speed` and `600`.
+    pub config: Vec<(String, String)>,
+    pub quirks: Quirks,
+    /// Last instructions executed, oldest first, empty unless the trace was
+    /// enabled.
+    pub trace: Vec<TraceEntry>,
+    pub registers: Registers,
+    /// Addresses of the `CALL`s being run, outermost first.
+    pub stack: Vec<u16>,
+    pub input: Input,
+    pub graphics: Graphics,
+    pub memory: Vec<u8>,
+}
+
+impl CrashDump {
+    /// Dump of `vm` which failed with `error`, with no settings.
+    pub fn new(vm: &VM, error: impl fmt::Display) -> Self {
+        Self {
+            error: error.to_string(),
+            program_hash: fnv1a(vm.program().iter().copied()),
+            config: Vec::new(),
+            quirks: vm.quirks(),
+            trace: vm
+                .trace()
+                .map_or_else(Vec::new, |trace| trace.iter().copied().collect()),
+            registers: vm.registers().clone(),
+            stack: vm.stack_frames().to_vec(),
+            input: vm.input(),
+            graphics: vm.graphics.clone(),
+            memory: vm.memory(0..MEMORY_SIZE).unwrap().to_vec(),
+        }
+    }
+
+    /// Put the state of the dump back into `vm`, with its quirks.
+    pub fn restore(&self, vm: &mut VM) {
+        let mut snapshot = vm.snapshot();
+        snapshot
+            .memory
+            .get_slice_mut(0, MEMORY_SIZE)
+            .copy_from_slice(&self.memory);
+        snapshot.registers = self.registers.clone();
+        snapshot.stack = Stack::new();
+        for &address in &self.stack {
+            // Parsing keeps no more addresses than the stack has levels.
+            snapshot.stack.push(address).unwrap();
+        }
+        snapshot.graphics = self.graphics.clone();
+        // Dumps only keep the low resolution display.
+        snapshot.hires = None;
+        snapshot.input = self.input;
+        vm.restore(&snapshot);
+        vm.set_quirks(self.quirks);
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        // Writing to a `String` never fails.
+        self.write(&mut text).unwrap();
+        text
+    }
+
+    fn write(&self, out: &mut String) -> fmt::Result {
+        writeln!(out, "{}", HEADER)?;
+        writeln!(out, "error = {}", self.error)?;
+        writeln!(out, "program = {:016x}", self.program_hash)?;
+        for (name, value) in &self.config {
+            writeln!(out, "config.{} = {}", name, value)?;
+        }
+        for (name, value) in self.quirks.list() {
+            writeln!(out, "quirk.{} = {}", name, if value { "yes" } else { "no" })?;
+        }
+        let registers = &self.registers;
+        writeln!(out, "pc = {:#05X}", registers.program_counter)?;
+        writeln!(out, "i = {:#05X}", registers.i)?;
+        writeln!(out, "v = {}", hex_bytes(&registers.v, " "))?;
+        writeln!(out, "dt = {}", registers.delay_timer)?;
+        writeln!(out, "st = {}", registers.sound_timer)?;
+        let stack: Vec<_> = self.stack.iter().map(|a| format!("{:#05X}", a)).collect();
+        writeln!(out, "stack = {}", stack.join(" "))?;
+        let keys: Vec<_> = (0..16)
+            .filter(|&key| self.input.is_pressed(key))
+            .map(|key| format!("{:X}", key))
+            .collect();
+        writeln!(out, "keys = {}", keys.join(" "))?;
+        for entry in &self.trace {
+            writeln!(out, "trace = {:#05X} {:04X}", entry.pc, entry.instruction)?;
+        }
+        for row in &self.graphics.display {
+            writeln!(out, "display = {:016X}", row)?;
+        }
+        for (i, line) in self.memory.chunks(MEMORY_LINE).enumerate() {
+            writeln!(
+                out,
+                "memory = {:#05X} {}",
+                i * MEMORY_LINE,
+                hex_bytes(line, "")
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, CrashError> {
+        let mut error = None;
+        let mut program_hash = None;
+        let mut pc = None;
+        let mut dump = Self {
+            error: String::new(),
+            program_hash: 0,
+            config: Vec::new(),
+            quirks: Quirks::default(),
+            trace: Vec::new(),
+            registers: Registers::new(),
+            stack: Vec::new(),
+            input: Input::new(),
+            graphics: Graphics::new(),
+            memory: Vec::with_capacity(MEMORY_SIZE),
+        };
+        let mut rows = 0;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = CrashError::InvalidLine(i + 1);
+            let (key, value) = line.split_once('=').ok_or(invalid)?;
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "error" => error = Some(value.to_string()),
+                "program" => {
+                    program_hash = Some(u64::from_str_radix(value, 16).map_err(|_| invalid)?)
+                }
+                "pc" => pc = Some(parse_address(value).ok_or(invalid)?),
+                "i" => {
+                    let i = value.strip_prefix("0x").ok_or(invalid)?;
+                    dump.registers.i = u16::from_str_radix(i, 16).map_err(|_| invalid)?;
+                }
+                "v" => {
+                    let v = parse_hex_bytes(&value.replace(' ', "")).ok_or(invalid)?;
+                    dump.registers.v = v.try_into().map_err(|_| invalid)?;
+                }
+                "dt" => dump.registers.delay_timer = value.parse().map_err(|_| invalid)?,
+                "st" => dump.registers.sound_timer = value.parse().map_err(|_| invalid)?,
+                "stack" => {
+                    dump.stack = value
+                        .split_whitespace()
+                        .map(parse_address)
+                        .collect::<Option<_>>()
+                        .filter(|stack: &Vec<u16>| stack.len() <= STACK_SIZE)
+                        .ok_or(invalid)?;
+                }
+                "keys" => {
+                    for key in value.split_whitespace() {
+                        let key = u8::from_str_radix(key, 16)
+                            .ok()
+                            .filter(|&key| key < 16)
+                            .ok_or(invalid)?;
+                        dump.input.press(key);
+                    }
+                }
+                "trace" => {
+                    let (pc, instruction) = value.split_once(' ').ok_or(invalid)?;
+                    dump.trace.push(TraceEntry {
+                        pc: parse_address(pc).ok_or(invalid)?,
+                        instruction: u16::from_str_radix(instruction, 16).map_err(|_| invalid)?,
+                    });
+                }
+                "display" => {
+                    let row = dump.graphics.display.get_mut(rows).ok_or(invalid)?;
+                    *row = u64::from_str_radix(value, 16).map_err(|_| invalid)?;
+                    rows += 1;
+                }
+                "memory" => {
+                    let (address, bytes) = value.split_once(' ').ok_or(invalid)?;
+                    let bytes = parse_hex_bytes(bytes).ok_or(invalid)?;
+                    if parse_address(address) != Some(dump.memory.len() as u16)
+                        || dump.memory.len() + bytes.len() > MEMORY_SIZE
+                    {
+                        return Err(invalid);
+                    }
+                    dump.memory.extend(bytes);
+                }
+                _ => {
+                    if let Some(name) = key.strip_prefix("config.") {
+                        dump.config.push((name.to_string(), value.to_string()));
+                    } else if let Some(name) = key.strip_prefix("quirk.") {
+                        let value = match value {
+                            "yes" => true,
+                            "no" => false,
+                            _ => return Err(invalid),
+                        };
+                        if !dump.quirks.set(name, value) {
+                            return Err(invalid);
+                        }
+                    } else {
+                        return Err(invalid);
+                    }
+                }
+            }
+        }
+        dump.error = error.ok_or(CrashError::Missing("error"))?;
+        dump.program_hash = program_hash.ok_or(CrashError::Missing("program"))?;
+        dump.registers.program_counter = pc.ok_or(CrashError::Missing("pc"))?;
+        if rows != DISPLAY_ROWS {
+            return Err(CrashError::Missing("display"));
+        }
+        if dump.memory.len() != MEMORY_SIZE {
+            return Err(CrashError::Missing("memory"));
+        }
+        Ok(dump)
+    }
+}
+
+fn hex_bytes(bytes: &[u8], separator: &str) -> String {
+    let bytes: Vec<_> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+    bytes.join(separator)
+}
+
+fn parse_hex_bytes(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// `0x`-prefixed hex address in memory.
+fn parse_address(text: &str) -> Option<u16> {
+    let address = u16::from_str_radix(text.strip_prefix("0x")?, 16).ok()?;
+    ((address as usize) < MEMORY_SIZE).then_some(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Key;
+    use crate::vm::VmError;
+
+    /// VM which called a subroutine, drew and then failed on `FFFF`.
+    fn crashed() -> (VM, VmError) {
+        let mut vm = VM::new();
+        vm.enable_trace(4);
+        vm.set_quirks(Quirks {
+            clip_sprites: true,
+            ..Quirks::default()
+        });
+        // CALL 0x204; ???; V3 := 0x2A, draw digit 0, ???
+        vm.load_program(&[0x22, 0x04, 0x00, 0x00, 0x63, 0x2A, 0xD0, 0x05, 0xFF, 0xFF])
+            .unwrap();
+        vm.key_down(Key::new(0xB).unwrap());
+        let error = loop {
+            if let Err(e) = vm.exec_current_instruction() {
+                break e;
+            }
+        };
+        (vm, error)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (vm, error) = crashed();
+        let mut dump = CrashDump::new(&vm, error);
+        dump.config.push(("speed".to_string(), "600".to_string()));
+
+        let text = dump.to_text();
+        assert!(text.contains("error = unknown instruction 0xFFFF at 0x208\n"));
+        assert!(text.contains("quirk.clip_sprites = yes\n"));
+        assert!(text.contains("stack = 0x200\n"));
+        assert!(text.contains("keys = B\n"));
+        assert!(text.contains("trace = 0x206 D005\ntrace = 0x208 FFFF\n"));
+
+        let parsed = CrashDump::parse(&text).unwrap();
+        assert_eq!(parsed.to_text(), text);
+        assert_eq!(parsed.config, dump.config);
+        assert_eq!(parsed.program_hash, fnv1a(vm.program().iter().copied()));
+    }
+
+    #[test]
+    fn test_restore_replays() {
+        let (vm, error) = crashed();
+        let dump = CrashDump::parse(&CrashDump::new(&vm, error).to_text()).unwrap();
+        let mut replay = VM::new();
+
+        dump.restore(&mut replay);
+
+        assert_eq!(replay.exec_current_instruction(), Err(error));
+        assert_eq!(replay.registers().v[3], 0x2A);
+        assert_eq!(replay.stack_frames(), [0x200]);
+        assert_eq!(replay.graphics.hash(), vm.graphics.hash());
+        assert!(replay.quirks().clip_sprites);
+        assert!(replay.input().is_pressed(0xB));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let (vm, error) = crashed();
+        let text = CrashDump::new(&vm, error).to_text();
+
+        let without_memory: String = text
+            .lines()
+            .filter(|line| !line.starts_with("memory"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        assert_eq!(
+            CrashDump::parse(&without_memory).err(),
+            Some(CrashError::Missing("memory"))
+        );
+        assert_eq!(
+            CrashDump::parse(&text.replace("quirk.clip_sprites", "quirk.wrap")).err(),
+            Some(CrashError::InvalidLine(8))
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Tiny HTTP server exposing emulator state, so external dashboards and
+//! test harnesses can poll a running emulator without linking against the
+//! crate.
+//!
+//! Endpoints:
+//!
+//! * `/state`: registers, timers and stack as JSON;
+//! * `/display.png?scale=N`: the screen as a PNG image, 1 by default;
+//! * `/memory?start=A&len=N`: `N` bytes from address `A` as JSON, 256 bytes
+//!   from 0 by default. Numbers may be decimal or `0x` prefixed hexadecimal.
+//!
+//! The server answers with the state last passed to [`DebugServer::update`],
+//! so the frontend decides how often it is published.
+
+use super::{
+    capture::encode_png, memory::MEMORY_SIZE, palette::Palette, snapshot::StateSnapshot, vm::VM,
+};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const DEFAULT_MEMORY_LEN: usize = 256;
+const MAX_SCALE: usize = 32;
+
+type Latest = Arc<Mutex<Option<StateSnapshot>>>;
+
+pub struct DebugServer {
+    addr: SocketAddr,
+    latest: Latest,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl DebugServer {
+    /// Listen on `addr` in a background thread, which lives as long as the
+    /// process.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let latest = Latest::default();
+        let server_latest = Arc::clone(&latest);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A misbehaving client only affects its own request.
+                let _ = handle(stream, &server_latest);
+            }
+        });
+        Ok(Self { addr, latest })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Publish the current state of `vm`.
+    pub fn update(&self, vm: &VM) {
+        let snapshot = vm.snapshot();
+        *self.latest.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot);
+    }
+}
+
+fn handle(stream: TcpStream, latest: &Latest) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip headers, nothing in them is used.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => {
+            let snapshot = latest.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            match snapshot {
+                Some(snapshot) => respond(target, &snapshot),
+                None => error("503 Service Unavailable", "no state published yet"),
+            }
+        }
+        _ => error("405 Method Not Allowed", "only GET is supported"),
+    };
+
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+fn respond(target: &str, snapshot: &StateSnapshot) -> Response {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let result = match path {
+        "/state" => Ok(json(state_json(snapshot))),
+        "/display.png" => query_number(query, "scale", 1).and_then(|scale| {
+            if scale == 0 || scale > MAX_SCALE {
+                return Err(format!("scale must be from 1 to {}", MAX_SCALE));
+            }
+            Ok(Response {
+                status: "200 OK",
+                content_type: "image/png",
+                body: encode_png(snapshot.screen(), scale, &Palette::default()),
+            })
+        }),
+        "/memory" => query_number(query, "start", 0).and_then(|start| {
+            let len = query_number(query, "len", DEFAULT_MEMORY_LEN)?;
+            if start >= MEMORY_SIZE {
+                return Err(format!("start must be below {:#X}", MEMORY_SIZE));
+            }
+            let end = start.saturating_add(len).min(MEMORY_SIZE);
+            let bytes = snapshot.memory.get_slice(start, end);
+            Ok(json(format!(
+                "{{\"start\":{},\"bytes\":{}}}",
+                start,
+                json_array(bytes)
+            )))
+        }),
+        _ => return error("404 Not Found", "unknown endpoint"),
+    };
+    result.unwrap_or_else(|message| error("400 Bad Request", &message))
+}
+
+fn state_json(snapshot: &StateSnapshot) -> String {
+    let registers = snapshot.registers();
+    let stack = &snapshot.stack;
+    format!(
+        "{{\"pc\":{},\"i\":{},\"v\":{},\"delay_timer\":{},\"sound_timer\":{},\"stack\":{}}}",
+        registers.program_counter,
+        registers.i,
+        json_array(&registers.v),
+        registers.delay_timer,
+        registers.sound_timer,
+        json_array(stack.frames())
+    )
+}
+
+fn json_array<T: ToString>(values: &[T]) -> String {
+    let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+fn json(body: String) -> Response {
+    Response {
+        status: "200 OK",
+        content_type: "application/json",
+        body: body.into_bytes(),
+    }
+}
+
+fn error(status: &'static str, message: &str) -> Response {
+    Response {
+        status,
+        content_type: "text/plain",
+        body: message.as_bytes().to_vec(),
+    }
+}
+
+/// Value of `name` in `query`, or `default` when it's missing.
+fn query_number(query: &str, name: &str, default: usize) -> Result<usize, String> {
+    let value = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|&(key, _)| key == name)
+        .map(|(_, value)| value);
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(default),
+    };
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| format!("invalid {}: {}", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn snapshot() -> StateSnapshot {
+        let mut vm = VM::new();
+        vm.load_program(&[0x60, 0x05, 0xA2, 0x34]).unwrap();
+        vm.exec_current_instruction().unwrap();
+        vm.exec_current_instruction().unwrap();
+        vm.snapshot()
+    }
+
+    fn body(response: Response) -> String {
+        String::from_utf8(response.body).unwrap()
+    }
+
+    #[test]
+    fn test_state() {
+        let response = respond("/state", &snapshot());
+
+        assert_eq!(response.status, "200 OK");
+        assert_eq!(
+            body(response),
+            "{\"pc\":516,\"i\":564,\"v\":[5,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0],\
+             \"delay_timer\":0,\"sound_timer\":0,\"stack\":[]}"
+        );
+    }
+
+    #[test]
+    fn test_memory() {
+        let response = respond("/memory?start=0x200&len=3", &snapshot());
+
+        assert_eq!(body(response), "{\"start\":512,\"bytes\":[96,5,162]}");
+    }
+
+    #[test]
+    fn test_memory_clamped_to_end() {
+        let response = respond("/memory?start=4094", &snapshot());
+
+        assert_eq!(body(response), "{\"start\":4094,\"bytes\":[0,0]}");
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        let snapshot = snapshot();
+
+        assert_eq!(
+            respond("/memory?start=x", &snapshot).status,
+            "400 Bad Request"
+        );
+        assert_eq!(
+            respond("/memory?start=4096", &snapshot).status,
+            "400 Bad Request"
+        );
+        assert_eq!(
+            respond("/display.png?scale=0", &snapshot).status,
+            "400 Bad Request"
+        );
+        assert_eq!(respond("/nothing", &snapshot).status, "404 Not Found");
+    }
+
+    #[test]
+    fn test_display_png() {
+        let response = respond("/display.png?scale=2", &snapshot());
+
+        assert_eq!(response.content_type, "image/png");
+        assert!(response.body.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn test_server() {
+        let server = DebugServer::bind("127.0.0.1:0").unwrap();
+        let mut vm = VM::new();
+        vm.load_program(&[0x60, 0x05]).unwrap();
+        server.update(&vm);
+
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+        stream
+            .write_all(b"GET /state HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: application/json"));
+        assert!(response.ends_with("\"stack\":[]}"));
+        assert!(response.contains("\"pc\":512"));
+    }
+}
@@ -0,0 +1,212 @@
+//! Every instruction encoding the emulator knows, as data, for tools which
+//! have to agree with it on what an instruction is, like syntax highlighters
+//! or generated reference tables.
+
+use super::platform::OpcodeFamily;
+
+/// Field of an instruction word, or the word after it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    /// Register `Vx`, bits 8 to 11.
+    X,
+    /// Register `Vy`, bits 4 to 7.
+    Y,
+    /// 4-bit number `n`, bits 0 to 3: sprite height or scroll distance.
+    Nibble,
+    /// 8-bit number `kk`, bits 0 to 7.
+    Byte,
+    /// 12-bit address `nnn`, bits 0 to 11.
+    Address,
+    /// 16-bit address `nnnn`, the whole next word.
+    LongAddress,
+    /// Mask of bit planes `n`, bits 8 to 11.
+    Planes,
+}
+
+impl Operand {
+    /// Bits of the instruction word holding the operand, 0 for
+    /// [`Operand::LongAddress`] which follows it.
+    pub fn mask(self) -> u16 {
+        match self {
+            Operand::X | Operand::Planes => 0x0F00,
+            Operand::Y => 0x00F0,
+            Operand::Nibble => 0x000F,
+            Operand::Byte => 0x00FF,
+            Operand::Address => 0x0FFF,
+            Operand::LongAddress => 0,
+        }
+    }
+
+    /// Value of the operand in `word`.
+    pub fn value(self, word: u16) -> u16 {
+        let mask = self.mask();
+        (word & mask) >> mask.trailing_zeros().min(15)
+    }
+}
+
+/// Encoding of an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opcode {
+    /// Bits which identify the instruction.
+    pub mask: u16,
+    /// Value of those bits.
+    pub value: u16,
+    /// Word with operands as letters, e.g. `8xy4`.
+    pub pattern: &'static str,
+    /// Assembly syntax with operands as in Cowgod's reference, e.g.
+    /// `ADD Vx, Vy`.
+    pub syntax: &'static str,
+    pub operands: &'static [Operand],
+    /// First family with the instruction.
+    pub family: OpcodeFamily,
+}
+
+impl Opcode {
+    /// Mnemonic as in Cowgod's reference and the assembler, e.g. `ADD`.
+    pub fn mnemonic(&self) -> &'static str {
+        self.syntax.split(' ').next().unwrap_or(self.syntax)
+    }
+
+    pub fn matches(&self, word: u16) -> bool {
+        word & self.mask == self.value
+    }
+
+    /// Size in bytes, 4 for instructions followed by a word.
+    pub fn size(&self) -> usize {
+        if self.operands.contains(&Operand::LongAddress) {
+            4
+        } else {
+            2
+        }
+    }
+}
+
+/// Table of [`Opcode`]s, one `mask value pattern syntax [operands] family;`
+/// line each.
+macro_rules! opcodes {
+    ($($mask:literal $value:literal $pattern:literal $syntax:literal [$($operand:ident),*] $family:ident;)*) => {
+        [$(Opcode {
+            mask: $mask,
+            value: $value,
+            pattern: $pattern,
+            syntax: $syntax,
+            operands: &[$(Operand::$operand),*],
+            family: OpcodeFamily::$family,
+        },)*]
+    };
+}
+
+/// Every instruction, more specific encodings first, so the first one
+/// matching a word is its instruction. `Dxy0` is taken for the SUPER-CHIP
+/// 16 x 16 sprite rather than an empty CHIP-8 one.
+pub static OPCODE_TABLE: [Opcode; 52] = opcodes! {
+    0xFFFF 0x00E0 "00E0" "CLS"                []             Chip8;
+    0xFFFF 0x00EE "00EE" "RET"                []             Chip8;
+    0xFFF0 0x00C0 "00Cn" "SCD nibble"         [Nibble]       SuperChip;
+    0xFFFF 0x00FB "00FB" "SCR"                []             SuperChip;
+    0xFFFF 0x00FC "00FC" "SCL"                []             SuperChip;
+    0xFFFF 0x00FD "00FD" "EXIT"               []             SuperChip;
+    0xFFFF 0x00FE "00FE" "LOW"                []             SuperChip;
+    0xFFFF 0x00FF "00FF" "HIGH"               []             SuperChip;
+    0xFFF0 0x00D0 "00Dn" "SCU nibble"         [Nibble]       XoChip;
+    0xF000 0x0000 "0nnn" "SYS addr"           [Address]      Chip8;
+    0xF000 0x1000 "1nnn" "JP addr"            [Address]      Chip8;
+    0xF000 0x2000 "2nnn" "CALL addr"          [Address]      Chip8;
+    0xF000 0x3000 "3xkk" "SE Vx, byte"        [X, Byte]      Chip8;
+    0xF000 0x4000 "4xkk" "SNE Vx, byte"       [X, Byte]      Chip8;
+    0xF00F 0x5000 "5xy0" "SE Vx, Vy"          [X, Y]         Chip8;
+    0xF00F 0x5002 "5xy2" "SAVE Vx - Vy"       [X, Y]         XoChip;
+    0xF00F 0x5003 "5xy3" "LOAD Vx - Vy"       [X, Y]         XoChip;
+    0xF000 0x6000 "6xkk" "LD Vx, byte"        [X, Byte]      Chip8;
+    0xF000 0x7000 "7xkk" "ADD Vx, byte"       [X, Byte]      Chip8;
+    0xF00F 0x8000 "8xy0" "LD Vx, Vy"          [X, Y]         Chip8;
+    0xF00F 0x8001 "8xy1" "OR Vx, Vy"          [X, Y]         Chip8;
+    0xF00F 0x8002 "8xy2" "AND Vx, Vy"         [X, Y]         Chip8;
+    0xF00F 0x8003 "8xy3" "XOR Vx, Vy"         [X, Y]         Chip8;
+    0xF00F 0x8004 "8xy4" "ADD Vx, Vy"         [X, Y]         Chip8;
+    0xF00F 0x8005 "8xy5" "SUB Vx, Vy"         [X, Y]         Chip8;
+    0xF00F 0x8006 "8xy6" "SHR Vx, Vy"         [X, Y]         Chip8;
+    0xF00F 0x8007 "8xy7" "SUBN Vx, Vy"        [X, Y]         Chip8;
+    0xF00F 0x800E "8xyE" "SHL Vx, Vy"         [X, Y]         Chip8;
+    0xF00F 0x9000 "9xy0" "SNE Vx, Vy"         [X, Y]         Chip8;
+    0xF000 0xA000 "Annn" "LD I, addr"         [Address]      Chip8;
+    0xF000 0xB000 "Bnnn" "JP V0, addr"        [Address]      Chip8;
+    0xF000 0xC000 "Cxkk" "RND Vx, byte"       [X, Byte]      Chip8;
+    0xF00F 0xD000 "Dxy0" "DRW Vx, Vy, 0"      [X, Y]         SuperChip;
+    0xF000 0xD000 "Dxyn" "DRW Vx, Vy, nibble" [X, Y, Nibble] Chip8;
+    0xF0FF 0xE09E "Ex9E" "SKP Vx"             [X]            Chip8;
+    0xF0FF 0xE0A1 "ExA1" "SKNP Vx"            [X]            Chip8;
+    0xFFFF 0xF000 "F000" "LDL I, addr"        [LongAddress]  XoChip;
+    0xFFFF 0xF002 "F002" "AUDIO"              []             XoChip;
+    0xF0FF 0xF001 "Fn01" "PLANE n"            [Planes]       XoChip;
+    0xF0FF 0xF007 "Fx07" "LD Vx, DT"          [X]            Chip8;
+    0xF0FF 0xF00A "Fx0A" "LD Vx, K"           [X]            Chip8;
+    0xF0FF 0xF015 "Fx15" "LD DT, Vx"          [X]            Chip8;
+    0xF0FF 0xF018 "Fx18" "LD ST, Vx"          [X]            Chip8;
+    0xF0FF 0xF01E "Fx1E" "ADD I, Vx"          [X]            Chip8;
+    0xF0FF 0xF029 "Fx29" "LD F, Vx"           [X]            Chip8;
+    0xF0FF 0xF030 "Fx30" "LD HF, Vx"          [X]            SuperChip;
+    0xF0FF 0xF033 "Fx33" "LD B, Vx"           [X]            Chip8;
+    0xF0FF 0xF03A "Fx3A" "PITCH Vx"           [X]            XoChip;
+    0xF0FF 0xF055 "Fx55" "LD [I], Vx"         [X]            Chip8;
+    0xF0FF 0xF065 "Fx65" "LD Vx, [I]"         [X]            Chip8;
+    0xF0FF 0xF075 "Fx75" "LD R, Vx"           [X]            SuperChip;
+    0xF0FF 0xF085 "Fx85" "LD Vx, R"           [X]            SuperChip;
+};
+
+/// Encoding of instruction `word`, or `None` if no platform has it.
+pub fn lookup(word: u16) -> Option<&'static Opcode> {
+    OPCODE_TABLE.iter().find(|opcode| opcode.matches(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Instruction;
+
+    #[test]
+    fn test_lookup() {
+        let drw = lookup(0xD125).unwrap();
+        assert_eq!(drw.pattern, "Dxyn");
+        assert_eq!(
+            drw.operands
+                .iter()
+                .map(|operand| operand.value(0xD125))
+                .collect::<Vec<_>>(),
+            [1, 2, 5]
+        );
+        assert_eq!(lookup(0xD120).unwrap().pattern, "Dxy0");
+        assert_eq!(lookup(0xF000).unwrap().size(), 4);
+        assert_eq!(lookup(0xE1FF), None);
+    }
+
+    #[test]
+    fn test_table_is_consistent() {
+        for opcode in &OPCODE_TABLE {
+            assert_eq!(opcode.value & !opcode.mask, 0, "{}", opcode.pattern);
+            let operand_bits = opcode
+                .operands
+                .iter()
+                .fold(0, |bits, operand| bits | operand.mask());
+            assert_eq!(opcode.mask & operand_bits, 0, "{}", opcode.pattern);
+            // Reachable, not hidden by a more general encoding before it.
+            let word = opcode.value | 0x0123 & !opcode.mask;
+            assert_eq!(lookup(word), Some(opcode), "{}", opcode.pattern);
+        }
+    }
+
+    #[test]
+    fn test_agrees_with_instruction_decoder() {
+        for opcode in &OPCODE_TABLE {
+            let word = opcode.value | 0x0123 & !opcode.mask;
+            // Words of instructions the decoder lacks read as others, e.g.
+            // `00FD` as `SYS`.
+            let decoded = Instruction::decode(word)
+                .ok()
+                .filter(|instruction| instruction.family() == opcode.family);
+            if let Some(instruction) = decoded {
+                assert_eq!(instruction.mnemonic(), opcode.mnemonic(), "{:04X}", word);
+            }
+        }
+    }
+}
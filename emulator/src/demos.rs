@@ -0,0 +1,201 @@
+//! Tiny built-in programs, shown when no program is given and used to test
+//! the emulator end to end.
+
+use super::program::{Program, V0, V1, V2, V6, V7, V8, V9, VA, VB};
+use alloc::vec::Vec;
+
+/// Built-in program.
+#[derive(Clone, Copy)]
+pub struct Demo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub build: fn() -> Vec<u8>,
+}
+
+pub const ALL: [Demo; 4] = [
+    Demo {
+        name: "logo",
+        description: "Draws CHIP8 in the middle of the screen.",
+        build: logo,
+    },
+    Demo {
+        name: "keypad-echo",
+        description: "Shows the last pressed key.",
+        build: keypad_echo,
+    },
+    Demo {
+        name: "timer-beep",
+        description: "Beeps every 60 frames using the delay and sound timers.",
+        build: timer_beep,
+    },
+    Demo {
+        name: "selftest",
+        description: "Shows held keys on a keypad grid with a beep, counts seconds and marks \
+                      the screen corners.",
+        build: selftest,
+    },
+];
+
+/// Left edge of the logo.
+pub const LOGO_X: u8 = 20;
+/// Top edge of the logo and of the digit drawn by [`keypad_echo`].
+pub const LOGO_Y: u8 = 13;
+/// Left edge of the digit drawn by [`keypad_echo`].
+pub const KEY_X: u8 = 30;
+/// Top left corner of the seconds counted by [`selftest`].
+pub const SECONDS_X: u8 = 44;
+pub const SECONDS_Y: u8 = 13;
+
+/// Top left corner of the keypad grid of [`selftest`].
+const GRID_X: u8 = 8;
+const GRID_Y: u8 = 4;
+/// Keys of the COSMAC VIP keypad, row by row.
+const KEYPAD_LAYOUT: [u8; 16] = [
+    0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF,
+];
+
+/// Top left corner of the digit [`selftest`] shows while `key` is held, at
+/// its place on the keypad.
+pub fn key_position(key: u8) -> (u8, u8) {
+    let slot = KEYPAD_LAYOUT.iter().position(|&k| k == key).unwrap() as u8;
+    (GRID_X + slot % 4 * 6, GRID_Y + slot / 4 * 6)
+}
+
+/// Draw "CHIP8" with 5-row letters at ([`LOGO_X`], [`LOGO_Y`]), then halt.
+pub fn logo() -> Vec<u8> {
+    let mut program = Program::new().ld(V0, LOGO_X).ld(V1, LOGO_Y);
+    for letter in ["c", "h", "i", "p"] {
+        program = program.ld_i(letter).drw(V0, V1, 5).add(V0, 5);
+    }
+    program
+        .ld(V2, 8)
+        .ld_f(V2)
+        .drw(V0, V1, 5)
+        .label("end")
+        .jp("end")
+        .label("c")
+        .data(&[0xF0, 0x80, 0x80, 0x80, 0xF0])
+        .label("h")
+        .data(&[0x90, 0x90, 0xF0, 0x90, 0x90])
+        .label("i")
+        .data(&[0xE0, 0x40, 0x40, 0x40, 0xE0])
+        .label("p")
+        .data(&[0xF0, 0x90, 0xF0, 0x80, 0x80])
+        .build()
+}
+
+/// Wait for a key and show its digit at ([`KEY_X`], [`LOGO_Y`]), forever.
+pub fn keypad_echo() -> Vec<u8> {
+    Program::new()
+        .ld(V0, KEY_X)
+        .ld(V1, LOGO_Y)
+        .label("loop")
+        .ld_vx_k(V2)
+        .cls()
+        .ld_f(V2)
+        .drw(V0, V1, 5)
+        .jp("loop")
+        .build()
+}
+
+/// Check the keypad, sound, timers and display of an emulator: mark the
+/// four corners of the screen, then show the digit of every key held at
+/// its place on the keypad, beeping until it's released, and count seconds
+/// at ([`SECONDS_X`], [`SECONDS_Y`]) with the delay timer, 0 to 9 over and
+/// over. Counting pauses while a key is held.
+pub fn selftest() -> Vec<u8> {
+    let positions: Vec<u8> = (0..16)
+        .flat_map(|key| {
+            let (x, y) = key_position(key);
+            [x, y]
+        })
+        .collect();
+    Program::new()
+        .cls()
+        .ld(V0, 0)
+        .ld(V1, 0)
+        .ld_i("top-left")
+        .drw(V0, V1, 4)
+        .ld(V0, 60)
+        .ld_i("top-right")
+        .drw(V0, V1, 4)
+        .ld(V1, 28)
+        .ld_i("bottom-right")
+        .drw(V0, V1, 4)
+        .ld(V0, 0)
+        .ld_i("bottom-left")
+        .drw(V0, V1, 4)
+        .ld(V6, 0)
+        .ld(V7, SECONDS_X)
+        .ld(V8, SECONDS_Y)
+        .ld_f(V6)
+        .drw(V7, V8, 5)
+        .ld(VA, 60)
+        .ld_dt(VA)
+        .ld(VB, 4)
+        .label("loop")
+        .ld(V2, 0)
+        .label("keys")
+        .sknp(V2)
+        .call("held")
+        .add(V2, 1)
+        .sne(V2, 16)
+        .jp("timer")
+        .jp("keys")
+        .label("timer")
+        .ld_vx_dt(V9)
+        .se(V9, 0)
+        .jp("loop")
+        .ld_f(V6)
+        .drw(V7, V8, 5)
+        .add(V6, 1)
+        .sne(V6, 10)
+        .ld(V6, 0)
+        .ld_f(V6)
+        .drw(V7, V8, 5)
+        .ld_dt(VA)
+        .jp("loop")
+        // Key V2 is held: show it and beep until it's released.
+        .label("held")
+        .ld_i("positions")
+        .add_i(V2)
+        .add_i(V2)
+        .ld_vx_i(V1)
+        .ld_f(V2)
+        .drw(V0, V1, 5)
+        .label("hold")
+        .ld_st(VB)
+        .skp(V2)
+        .jp("released")
+        .jp("hold")
+        .label("released")
+        .drw(V0, V1, 5)
+        .ret()
+        .label("top-left")
+        .data(&[0xF0, 0x80, 0x80, 0x80])
+        .label("top-right")
+        .data(&[0xF0, 0x10, 0x10, 0x10])
+        .label("bottom-right")
+        .data(&[0x10, 0x10, 0x10, 0xF0])
+        .label("bottom-left")
+        .data(&[0x80, 0x80, 0x80, 0xF0])
+        .label("positions")
+        .data(&positions)
+        .build()
+}
+
+/// Wait 60 ticks of the delay timer, beep for 10 ticks, repeat.
+pub fn timer_beep() -> Vec<u8> {
+    Program::new()
+        .label("loop")
+        .ld(V0, 60)
+        .ld_dt(V0)
+        .label("wait")
+        .ld_vx_dt(V1)
+        .se(V1, 0)
+        .jp("wait")
+        .ld(V2, 10)
+        .ld_st(V2)
+        .jp("loop")
+        .build()
+}
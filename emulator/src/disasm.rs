@@ -0,0 +1,95 @@
+//! Disassembly of programs into instructions and data.
+
+use super::instruction::Instruction;
+use super::memory::PROGRAM_START_LOCATION;
+
+/// What a run of program bytes decodes to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Instruction(Instruction),
+    /// Bytes that aren't a valid instruction, e.g. sprites, written as `DB`.
+    Data,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub address: usize,
+    pub bytes: Vec<u8>,
+    pub item: Item,
+}
+
+impl Line {
+    pub fn mnemonic(&self) -> &'static str {
+        match &self.item {
+            Item::Instruction(instruction) => instruction.mnemonic(),
+            Item::Data => "DB",
+        }
+    }
+
+    pub fn operands(&self) -> Vec<String> {
+        match &self.item {
+            Item::Instruction(instruction) => instruction.operands(),
+            Item::Data => self.bytes.iter().map(|b| format!("0x{:02X}", b)).collect(),
+        }
+    }
+
+    /// Assembly text of the line, e.g. `LD V0, 0x05`.
+    pub fn text(&self) -> String {
+        match &self.item {
+            Item::Instruction(instruction) => instruction.to_string(),
+            Item::Data => format!("DB {}", self.operands().join(", ")),
+        }
+    }
+}
+
+/// Decode `program` loaded at the program start location, word by word.
+///
+/// Words which aren't instructions become data lines, as does an odd byte at
+/// the end.
+pub fn disassemble(program: &[u8]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < program.len() {
+        let rest = &program[offset..];
+        let (item, size) = match Instruction::read(rest) {
+            Some((instruction, size)) => (Item::Instruction(instruction), size),
+            None => (Item::Data, rest.len().min(2)),
+        };
+        lines.push(Line {
+            address: PROGRAM_START_LOCATION + offset,
+            bytes: rest[..size].to_vec(),
+            item,
+        });
+        offset += size;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble() {
+        let program = [0x60, 0x05, 0xF0, 0x00, 0x12, 0x34, 0xFF, 0xFF, 0x12];
+
+        let lines = disassemble(&program);
+
+        let texts: Vec<_> = lines
+            .iter()
+            .map(|line| (line.address, line.text()))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                (0x200, "LD V0, 0x05".to_string()),
+                (0x202, "LDL I, 0x1234".to_string()),
+                (0x206, "DB 0xFF, 0xFF".to_string()),
+                (0x208, "DB 0x12".to_string()),
+            ]
+        );
+        assert_eq!(lines[1].bytes, [0xF0, 0x00, 0x12, 0x34]);
+        assert_eq!(lines[1].mnemonic(), "LDL");
+        assert_eq!(lines[1].operands(), ["I", "0x1234"]);
+    }
+}
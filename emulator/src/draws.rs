@@ -0,0 +1,169 @@
+//! Record of recent sprite draws, for finding out why a sprite flickers or
+//! disappears: a sprite drawn twice erases itself, and XOR collisions are
+//! invisible once the frame is done.
+
+use alloc::collections::VecDeque;
+
+/// `DRW` instruction as it was executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DrawRecord {
+    /// Frame, counted in timer ticks, the sprite was drawn in.
+    pub frame: u64,
+    /// Address of the sprite, `I` at the time of drawing.
+    pub address: u16,
+    /// Screen coordinates, already wrapped around the display.
+    pub x: u8,
+    pub y: u8,
+    pub height: u8,
+    /// Whether drawing erased any pixel, i.e. set `VF` to 1.
+    pub collision: bool,
+}
+
+/// `DRW` which erased pixels, reported to [`crate::VM::on_collision`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Collision {
+    /// Address of the instruction.
+    pub pc: u16,
+    /// Address of the sprite.
+    pub address: u16,
+    /// Screen coordinates, already wrapped around the display.
+    pub x: u8,
+    pub y: u8,
+    pub height: u8,
+}
+
+/// `DRW` as it was executed, reported to
+/// [`VmObserver::on_draw`](crate::observer::VmObserver::on_draw).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Draw {
+    /// Address of the instruction.
+    pub pc: u16,
+    /// Address of the sprite.
+    pub address: u16,
+    /// Screen coordinates, already wrapped around the display.
+    pub x: u8,
+    pub y: u8,
+    pub height: u8,
+    /// Whether drawing erased any pixel, i.e. set `VF` to 1.
+    pub collision: bool,
+}
+
+/// Sprite draws of the last few frames.
+pub struct DrawLog {
+    frames: u64,
+    frame: u64,
+    records: VecDeque<DrawRecord>,
+}
+
+impl DrawLog {
+    /// Create log keeping draws of `frames` last frames, the current one
+    /// included.
+    pub fn new(frames: u64) -> Self {
+        assert!(frames > 0);
+        Self {
+            frames,
+            frame: 0,
+            records: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, address: u16, x: u8, y: u8, height: u8, collision: bool) {
+        self.records.push_back(DrawRecord {
+            frame: self.frame,
+            address,
+            x,
+            y,
+            height,
+            collision,
+        });
+    }
+
+    /// Start a new frame, forgetting draws which are now too old.
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+        while let Some(record) = self.records.front() {
+            if record.frame + self.frames > self.frame {
+                break;
+            }
+            self.records.pop_front();
+        }
+    }
+
+    /// Current frame.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Draws, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &DrawRecord> {
+        self.records.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Bytes allocated on the heap for the records.
+    pub fn heap_size(&self) -> usize {
+        self.records.capacity() * size_of::<DrawRecord>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut log = DrawLog::new(2);
+
+        log.record(0x300, 10, 20, 5, false);
+        log.next_frame();
+        log.record(0x305, 0, 0, 1, true);
+
+        let records = log.iter().copied().collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            [
+                DrawRecord {
+                    frame: 0,
+                    address: 0x300,
+                    x: 10,
+                    y: 20,
+                    height: 5,
+                    collision: false,
+                },
+                DrawRecord {
+                    frame: 1,
+                    address: 0x305,
+                    x: 0,
+                    y: 0,
+                    height: 1,
+                    collision: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_old_frames_are_dropped() {
+        let mut log = DrawLog::new(2);
+        log.record(0x300, 0, 0, 1, false);
+        log.next_frame();
+        log.record(0x301, 0, 0, 1, false);
+
+        log.next_frame();
+
+        assert_eq!(log.frame(), 2);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log.iter().next().unwrap().address, 0x301);
+
+        log.next_frame();
+
+        assert!(log.is_empty());
+    }
+}
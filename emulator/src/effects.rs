@@ -0,0 +1,130 @@
+//! What an instruction did, as returned by [`crate::VM::step`], so frontends
+//! can redraw, start the beeper or show a prompt only when they have to.
+
+use super::instruction::Instruction;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{BitOr, BitOrAssign};
+
+/// Set of effects of one step, combined with `|`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Effects(u8);
+
+impl Effects {
+    pub const NONE: Effects = Effects(0);
+    /// Some pixel changed.
+    pub const DISPLAY_CHANGED: Effects = Effects(1 << 0);
+    /// Sound timer became active.
+    pub const SOUND_STARTED: Effects = Effects(1 << 1);
+    /// Sound timer ran out or was set to 0.
+    pub const SOUND_STOPPED: Effects = Effects(1 << 2);
+    /// `Fx0A` is waiting for a key press.
+    pub const WAITING_FOR_KEY: Effects = Effects(1 << 3);
+    /// The program jumped to itself, see [`crate::VM::is_halted`].
+    pub const HALTED: Effects = Effects(1 << 4);
+    /// A subroutine was called.
+    pub const STACK_PUSHED: Effects = Effects(1 << 5);
+    /// A subroutine returned.
+    pub const STACK_POPPED: Effects = Effects(1 << 6);
+
+    const NAMES: [(Effects, &'static str); 7] = [
+        (Effects::DISPLAY_CHANGED, "DISPLAY_CHANGED"),
+        (Effects::SOUND_STARTED, "SOUND_STARTED"),
+        (Effects::SOUND_STOPPED, "SOUND_STOPPED"),
+        (Effects::WAITING_FOR_KEY, "WAITING_FOR_KEY"),
+        (Effects::HALTED, "HALTED"),
+        (Effects::STACK_PUSHED, "STACK_PUSHED"),
+        (Effects::STACK_POPPED, "STACK_POPPED"),
+    ];
+
+    /// Whether all effects in `other` are in `self`.
+    pub fn contains(self, other: Effects) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+impl BitOr for Effects {
+    type Output = Effects;
+
+    fn bitor(self, other: Effects) -> Effects {
+        Effects(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for Effects {
+    fn bitor_assign(&mut self, other: Effects) {
+        self.0 |= other.0;
+    }
+}
+
+impl fmt::Debug for Effects {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "NONE");
+        }
+        let names: Vec<_> = Self::NAMES
+            .iter()
+            .filter(|(effect, _)| self.contains(*effect))
+            .map(|(_, name)| *name)
+            .collect();
+        write!(f, "{}", names.join(" | "))
+    }
+}
+
+/// Instruction run by [`crate::VM::step`] and what it did, for debuggers
+/// and tracers which would otherwise decode memory themselves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepInfo {
+    /// Instruction at `pc`, `None` if the word there isn't one and sandbox
+    /// mode skipped it.
+    pub instruction: Option<Instruction>,
+    /// Program counter before the step.
+    pub pc: u16,
+    /// Program counter after the step.
+    pub next_pc: u16,
+    pub effects: Effects,
+    /// Whether the delay or sound timer changed, set by the instruction or
+    /// ticking after it.
+    pub timers_changed: bool,
+}
+
+impl StepInfo {
+    pub fn display_changed(&self) -> bool {
+        self.effects.contains(Effects::DISPLAY_CHANGED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let effects = Effects::DISPLAY_CHANGED | Effects::HALTED;
+
+        assert!(effects.contains(Effects::DISPLAY_CHANGED));
+        assert!(effects.contains(Effects::HALTED));
+        assert!(!effects.contains(Effects::SOUND_STARTED));
+        assert!(effects.contains(Effects::NONE));
+        assert!(!effects.is_empty());
+        assert!(Effects::default().is_empty());
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut effects = Effects::NONE;
+        assert_eq!(format!("{:?}", effects), "NONE");
+
+        effects |= Effects::STACK_POPPED;
+        effects |= Effects::SOUND_STARTED;
+        assert_eq!(format!("{:?}", effects), "SOUND_STARTED | STACK_POPPED");
+    }
+}
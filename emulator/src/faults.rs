@@ -0,0 +1,77 @@
+//! Record of instructions skipped in sandbox mode, see
+//! [`VM::enable_sandbox`](super::vm::VM::enable_sandbox).
+
+use super::vm::VmError;
+use alloc::collections::VecDeque;
+
+/// Last faults, and how many there were in total.
+pub struct FaultLog {
+    capacity: usize,
+    total: u64,
+    faults: VecDeque<VmError>,
+}
+
+impl FaultLog {
+    /// Create log keeping up to `capacity` last faults.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            capacity,
+            total: 0,
+            faults: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, fault: VmError) {
+        if self.faults.len() == self.capacity {
+            self.faults.pop_front();
+        }
+        self.faults.push_back(fault);
+        self.total += 1;
+    }
+
+    /// Faults kept, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &VmError> {
+        self.faults.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.faults.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faults.is_empty()
+    }
+
+    /// Faults recorded, including the ones no longer kept.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Bytes allocated on the heap for the faults.
+    pub fn heap_size(&self) -> usize {
+        self.faults.capacity() * size_of::<VmError>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_last_faults() {
+        let mut log = FaultLog::new(2);
+        for pc in [0x200, 0x202, 0x204] {
+            log.record(VmError::StackUnderflow { pc });
+        }
+
+        assert_eq!(log.total(), 3);
+        assert_eq!(
+            log.iter().copied().collect::<Vec<_>>(),
+            [
+                VmError::StackUnderflow { pc: 0x202 },
+                VmError::StackUnderflow { pc: 0x204 }
+            ]
+        );
+    }
+}
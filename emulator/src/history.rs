@@ -0,0 +1,158 @@
+use super::registers::{Registers, V_REGISTERS_SIZE};
+use alloc::collections::VecDeque;
+
+/// Value written to a register by the instruction at `pc`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterWrite<T> {
+    pub pc: u16,
+    pub value: T,
+}
+
+/// Last values written to `V0`-`VF` and `I` together with the instructions
+/// which wrote them, even when the value stays the same, so questions like
+/// "when did `V7` become 0?" can be answered without re-running the program
+/// with watchpoints. The GUI debugger shows the latest write of each.
+pub struct RegisterHistory {
+    capacity: usize,
+    v: [VecDeque<RegisterWrite<u8>>; V_REGISTERS_SIZE],
+    i: VecDeque<RegisterWrite<u16>>,
+}
+
+impl RegisterHistory {
+    /// Create history keeping up to `capacity` last writes per register.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            capacity,
+            v: Default::default(),
+            i: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record writes of the instruction at `pc`, which left registers
+    /// `before` as `after`: `Vx` with bit `x` of `written_v` set, `I` if
+    /// `writes_i`, and any other register which changed.
+    pub fn record_writes(
+        &mut self,
+        pc: u16,
+        written_v: u16,
+        writes_i: bool,
+        before: &Registers,
+        after: &Registers,
+    ) {
+        for x in 0..V_REGISTERS_SIZE {
+            if written_v & 1 << x != 0 || before.v[x] != after.v[x] {
+                push(&mut self.v[x], self.capacity, pc, after.v[x]);
+            }
+        }
+        if writes_i || before.i != after.i {
+            push(&mut self.i, self.capacity, pc, after.i);
+        }
+    }
+
+    /// Bytes allocated on the heap for the recorded writes.
+    pub fn heap_size(&self) -> usize {
+        let v: usize = self.v.iter().map(VecDeque::capacity).sum();
+        v * size_of::<RegisterWrite<u8>>() + self.i.capacity() * size_of::<RegisterWrite<u16>>()
+    }
+
+    /// Writes to `Vx`, oldest first.
+    pub fn v(&self, x: u8) -> impl Iterator<Item = &RegisterWrite<u8>> {
+        self.v[x as usize].iter()
+    }
+
+    /// Writes to `I`, oldest first.
+    pub fn i(&self) -> impl Iterator<Item = &RegisterWrite<u16>> {
+        self.i.iter()
+    }
+
+    /// Latest write of `value` to `Vx`.
+    pub fn last_write_of_v(&self, x: u8, value: u8) -> Option<&RegisterWrite<u8>> {
+        self.v[x as usize]
+            .iter()
+            .rev()
+            .find(|write| write.value == value)
+    }
+
+    pub fn clear(&mut self) {
+        self.v.iter_mut().for_each(VecDeque::clear);
+        self.i.clear();
+    }
+}
+
+fn push<T>(writes: &mut VecDeque<RegisterWrite<T>>, capacity: usize, pc: u16, value: T) {
+    if writes.len() == capacity {
+        writes.pop_front();
+    }
+    writes.push_back(RegisterWrite { pc, value });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_writes() {
+        let mut history = RegisterHistory::new(4);
+        let before = Registers::new();
+        let mut after = Registers::new();
+        after.v[0x7] = 0x10;
+        after.i = 0x300;
+
+        history.record_writes(0x200, 0, false, &before, &after);
+
+        let writes = history.v(0x7).copied().collect::<Vec<_>>();
+        assert_eq!(
+            writes,
+            [RegisterWrite {
+                pc: 0x200,
+                value: 0x10
+            }]
+        );
+        assert_eq!(history.v(0x6).count(), 0);
+        assert_eq!(history.i().next().map(|write| write.value), Some(0x300));
+
+        // Writes of the same value are recorded too.
+        history.record_writes(0x202, 1 << 0x6 | 1 << 0x7, true, &after, &after);
+        assert_eq!(history.v(0x6).map(|write| write.pc).last(), Some(0x202));
+        assert_eq!(history.v(0x7).count(), 2);
+        assert_eq!(history.v(0x5).count(), 0);
+        assert_eq!(history.i().count(), 2);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut history = RegisterHistory::new(2);
+        let mut registers = Registers::new();
+        for pc in 0..3 {
+            let before = registers.clone();
+            registers.v[0x1] += 1;
+            history.record_writes(pc, 0, false, &before, &registers);
+        }
+
+        let values = history.v(0x1).map(|write| write.value).collect::<Vec<_>>();
+        assert_eq!(values, [2, 3]);
+    }
+
+    #[test]
+    fn test_last_write_of_v() {
+        let mut history = RegisterHistory::new(4);
+        let mut before = Registers::new();
+        before.v[0x7] = 1;
+        let after = Registers::new();
+
+        history.record_writes(0x204, 0, false, &before, &after);
+        history.record_writes(0x208, 0, false, &after, &before);
+        history.record_writes(0x20C, 0, false, &before, &after);
+
+        assert_eq!(history.last_write_of_v(0x7, 0).map(|w| w.pc), Some(0x20C));
+        assert_eq!(history.last_write_of_v(0x7, 1).map(|w| w.pc), Some(0x208));
+        assert_eq!(history.last_write_of_v(0x7, 2), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_capacity() {
+        RegisterHistory::new(0);
+    }
+}
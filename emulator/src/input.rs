@@ -1,8 +1,68 @@
+use core::fmt;
+
 const KEYS: u8 = 16;
 
-#[derive(Default)]
+/// Key of the hexadecimal keypad, 0x0 to 0xF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key(u8);
+
+impl Key {
+    /// Key `value`, if it's one of the 16.
+    pub const fn new(value: u8) -> Option<Self> {
+        if value < KEYS {
+            Some(Key(value))
+        } else {
+            None
+        }
+    }
+
+    pub const fn value(self) -> u8 {
+        self.0
+    }
+
+    /// Every key, 0x0 first.
+    pub fn all() -> impl Iterator<Item = Key> {
+        (0..KEYS).map(Key)
+    }
+}
+
+impl TryFrom<u8> for Key {
+    type Error = InvalidKey;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Key::new(value).ok_or(InvalidKey(value))
+    }
+}
+
+impl From<Key> for u8 {
+    fn from(key: Key) -> u8 {
+        key.0
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:X}", self.0)
+    }
+}
+
+/// Value which isn't a key of the keypad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidKey(pub u8);
+
+impl fmt::Display for InvalidKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid key {:#X}, expected 0x0 to 0xF", self.0)
+    }
+}
+
+impl core::error::Error for InvalidKey {}
+
+/// Keypad state, any number of the 16 keys held at once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Input {
-    key_pressed: Option<u8>,
+    /// Bit `n` is set when key `n` is held.
+    keys: u16,
 }
 
 impl Input {
@@ -11,14 +71,29 @@ impl Input {
     }
 
     pub fn new_with_key_pressed(key: u8) -> Self {
+        let mut input = Self::new();
+        input.press(key);
+        input
+    }
+
+    pub fn press(&mut self, key: u8) {
         assert!(key < KEYS);
-        Self {
-            key_pressed: Some(key),
-        }
+        self.keys |= 1 << key;
     }
 
-    pub fn get_pressed_key(&self) -> Option<u8> {
-        self.key_pressed
+    pub fn release(&mut self, key: u8) {
+        assert!(key < KEYS);
+        self.keys &= !(1 << key);
+    }
+
+    /// Whether `key` is held. Values which aren't keys never are.
+    pub fn is_pressed(&self, key: u8) -> bool {
+        key < KEYS && self.keys & (1 << key) != 0
+    }
+
+    /// Lowest key held.
+    pub fn first_pressed(&self) -> Option<u8> {
+        (self.keys != 0).then(|| self.keys.trailing_zeros() as u8)
     }
 }
 
@@ -29,17 +104,49 @@ mod tests {
     #[test]
     fn test_is_key_pressed_clear_state() {
         let input = Input::new();
-        assert_eq!(input.get_pressed_key(), None);
+        assert_eq!(input.first_pressed(), None);
+        assert!((0..=u8::MAX).all(|key| !input.is_pressed(key)));
     }
 
     #[test]
     fn test_is_key_pressed_with_key_pressed() {
         for pressed_key in 0..KEYS {
             let input = Input::new_with_key_pressed(pressed_key);
-            assert_eq!(input.get_pressed_key(), Some(pressed_key));
+            assert_eq!(input.first_pressed(), Some(pressed_key));
+            assert!(input.is_pressed(pressed_key));
         }
     }
 
+    #[test]
+    fn test_chord() {
+        let mut input = Input::new();
+        input.press(0xC);
+        input.press(0x4);
+
+        assert!(input.is_pressed(0x4));
+        assert!(input.is_pressed(0xC));
+        assert!(!input.is_pressed(0x5));
+        assert_eq!(input.first_pressed(), Some(0x4));
+
+        input.release(0x4);
+
+        assert!(!input.is_pressed(0x4));
+        assert_eq!(input.first_pressed(), Some(0xC));
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!(Key::new(0xF).map(Key::value), Some(0xF));
+        assert_eq!(Key::new(0x10), None);
+        assert_eq!(Key::try_from(0x20), Err(InvalidKey(0x20)));
+        assert_eq!(Key::all().count(), KEYS as usize);
+        assert_eq!(Key::new(0xA).unwrap().to_string(), "A");
+        assert_eq!(
+            InvalidKey(0x20).to_string(),
+            "invalid key 0x20, expected 0x0 to 0xF"
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_is_key_pressed_invalid_input() {
@@ -0,0 +1,567 @@
+//! Decoded CHIP-8 instructions, using the mnemonics of Cowgod's technical
+//! reference.
+
+use super::platform::OpcodeFamily;
+use super::rpl::RPL_FLAGS;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt;
+
+/// Register index `x` or `y`, 0 to F.
+pub type Reg = u8;
+
+/// Word which isn't any instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    pub word: u16,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown instruction {:#06X}", self.word)
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    /// `0nnn`: machine code routine, ignored by modern interpreters.
+    Sys(u16),
+    /// `00E0`
+    Cls,
+    /// `00EE`
+    Ret,
+    /// `00FE`: SUPER-CHIP low resolution, 64 x 32.
+    Low,
+    /// `00FF`: SUPER-CHIP high resolution, 128 x 64.
+    High,
+    /// `00Cn`: SUPER-CHIP scroll down `n` rows.
+    Scd(u8),
+    /// `00FB`: SUPER-CHIP scroll right 4 columns.
+    Scr,
+    /// `00FC`: SUPER-CHIP scroll left 4 columns.
+    Scl,
+    /// `1nnn`
+    Jp(u16),
+    /// `2nnn`
+    Call(u16),
+    /// `3xkk`
+    SeByte(Reg, u8),
+    /// `4xkk`
+    SneByte(Reg, u8),
+    /// `5xy0`
+    SeReg(Reg, Reg),
+    /// `6xkk`
+    LdByte(Reg, u8),
+    /// `7xkk`
+    AddByte(Reg, u8),
+    /// `8xy0`
+    LdReg(Reg, Reg),
+    /// `8xy1`
+    Or(Reg, Reg),
+    /// `8xy2`
+    And(Reg, Reg),
+    /// `8xy3`
+    Xor(Reg, Reg),
+    /// `8xy4`
+    AddReg(Reg, Reg),
+    /// `8xy5`
+    Sub(Reg, Reg),
+    /// `8xy6`
+    Shr(Reg, Reg),
+    /// `8xy7`
+    Subn(Reg, Reg),
+    /// `8xyE`
+    Shl(Reg, Reg),
+    /// `9xy0`
+    SneReg(Reg, Reg),
+    /// `Annn`
+    LdI(u16),
+    /// `Bnnn`
+    JpV0(u16),
+    /// `Cxkk`
+    Rnd(Reg, u8),
+    /// `Dxyn`
+    Drw(Reg, Reg, u8),
+    /// `Ex9E`
+    Skp(Reg),
+    /// `ExA1`
+    Sknp(Reg),
+    /// `Fx07`
+    LdVxDt(Reg),
+    /// `Fx0A`
+    LdVxK(Reg),
+    /// `Fx15`
+    LdDtVx(Reg),
+    /// `Fx18`
+    LdStVx(Reg),
+    /// `Fx1E`
+    AddIVx(Reg),
+    /// `Fx29`
+    LdFVx(Reg),
+    /// `Fx30`: SUPER-CHIP `I` = location of the big digit sprite for `Vx`.
+    LdHfVx(Reg),
+    /// `Fx33`
+    LdBVx(Reg),
+    /// `Fx55`
+    LdMemVx(Reg),
+    /// `Fx65`
+    LdVxMem(Reg),
+    /// `Fx75`: SUPER-CHIP store `V0` through `Vx` in the RPL flags.
+    LdRVx(Reg),
+    /// `Fx85`: SUPER-CHIP read `V0` through `Vx` from the RPL flags.
+    LdVxR(Reg),
+    /// `F000 nnnn`: XO-CHIP 16-bit `I` load, written `LDL I, nnnn`.
+    LdILong(u16),
+}
+
+impl Instruction {
+    /// Decode a 2-byte instruction. `F000`, the first word of
+    /// [`Instruction::LdILong`], needs the next word too, see
+    /// [`Instruction::read`].
+    pub fn decode(word: u16) -> Result<Self, DecodeError> {
+        use Instruction::*;
+        let nnn = word & 0x0FFF;
+        let x = ((word & 0x0F00) >> 8) as Reg;
+        let y = ((word & 0x00F0) >> 4) as Reg;
+        let kk = (word & 0x00FF) as u8;
+        let n = (word & 0x000F) as u8;
+        let instruction = match (word >> 12, n) {
+            _ if word == 0x00E0 => Cls,
+            _ if word == 0x00EE => Ret,
+            _ if word == 0x00FE => Low,
+            _ if word == 0x00FF => High,
+            _ if word & 0xFFF0 == 0x00C0 => Scd(n),
+            _ if word == 0x00FB => Scr,
+            _ if word == 0x00FC => Scl,
+            (0x0, _) => Sys(nnn),
+            (0x1, _) => Jp(nnn),
+            (0x2, _) => Call(nnn),
+            (0x3, _) => SeByte(x, kk),
+            (0x4, _) => SneByte(x, kk),
+            (0x5, 0x0) => SeReg(x, y),
+            (0x6, _) => LdByte(x, kk),
+            (0x7, _) => AddByte(x, kk),
+            (0x8, 0x0) => LdReg(x, y),
+            (0x8, 0x1) => Or(x, y),
+            (0x8, 0x2) => And(x, y),
+            (0x8, 0x3) => Xor(x, y),
+            (0x8, 0x4) => AddReg(x, y),
+            (0x8, 0x5) => Sub(x, y),
+            (0x8, 0x6) => Shr(x, y),
+            (0x8, 0x7) => Subn(x, y),
+            (0x8, 0xE) => Shl(x, y),
+            (0x9, 0x0) => SneReg(x, y),
+            (0xA, _) => LdI(nnn),
+            (0xB, _) => JpV0(nnn),
+            (0xC, _) => Rnd(x, kk),
+            (0xD, _) => Drw(x, y, n),
+            (0xE, _) if kk == 0x9E => Skp(x),
+            (0xE, _) if kk == 0xA1 => Sknp(x),
+            (0xF, _) => match kk {
+                0x07 => LdVxDt(x),
+                0x0A => LdVxK(x),
+                0x15 => LdDtVx(x),
+                0x18 => LdStVx(x),
+                0x1E => AddIVx(x),
+                0x29 => LdFVx(x),
+                0x30 => LdHfVx(x),
+                0x33 => LdBVx(x),
+                0x55 => LdMemVx(x),
+                0x65 => LdVxMem(x),
+                0x75 => LdRVx(x),
+                0x85 => LdVxR(x),
+                _ => return Err(DecodeError { word }),
+            },
+            _ => return Err(DecodeError { word }),
+        };
+        Ok(instruction)
+    }
+
+    /// Earliest family of instructions which has the instruction.
+    pub fn family(&self) -> OpcodeFamily {
+        match self {
+            Instruction::Low
+            | Instruction::High
+            | Instruction::Scd(_)
+            | Instruction::Scr
+            | Instruction::Scl
+            | Instruction::LdHfVx(_)
+            | Instruction::LdRVx(_)
+            | Instruction::LdVxR(_) => OpcodeFamily::SuperChip,
+            Instruction::LdILong(_) => OpcodeFamily::XoChip,
+            _ => OpcodeFamily::Chip8,
+        }
+    }
+
+    /// Registers `V0`-`VF` the instruction writes, a bit each, `VF` being
+    /// the highest. `VF` of logic instructions, written only with the
+    /// `logic_resets_vf` quirk, is left out.
+    pub fn written_v(&self) -> u16 {
+        use Instruction::*;
+        const VF: u16 = 1 << 0xF;
+        let through = |x: Reg| (1u32 << (x as u32 + 1)) as u16 - 1;
+        match *self {
+            LdByte(x, _)
+            | AddByte(x, _)
+            | LdReg(x, _)
+            | Or(x, _)
+            | And(x, _)
+            | Xor(x, _)
+            | Rnd(x, _)
+            | LdVxDt(x)
+            | LdVxK(x) => 1 << x,
+            AddReg(x, _) | Sub(x, _) | Shr(x, _) | Subn(x, _) | Shl(x, _) => 1 << x | VF,
+            Drw(..) => VF,
+            LdVxMem(x) => through(x),
+            LdVxR(x) => through(x.min(RPL_FLAGS as Reg - 1)),
+            _ => 0,
+        }
+    }
+
+    /// Whether the instruction writes `I`. `Fx55` and `Fx65`, which only
+    /// move it with the `load_store_increments_i` quirk, are left out.
+    pub fn writes_i(&self) -> bool {
+        use Instruction::*;
+        matches!(self, LdI(_) | AddIVx(_) | LdFVx(_) | LdHfVx(_) | LdILong(_))
+    }
+
+    /// Decode the instruction at the start of `bytes`, returning it with its
+    /// size in bytes.
+    pub fn read(bytes: &[u8]) -> Option<(Self, usize)> {
+        let word = u16::from_be_bytes([*bytes.first()?, *bytes.get(1)?]);
+        if word == 0xF000 {
+            let address = u16::from_be_bytes([*bytes.get(2)?, *bytes.get(3)?]);
+            return Some((Instruction::LdILong(address), 4));
+        }
+        Self::decode(word).ok().map(|instruction| (instruction, 2))
+    }
+
+    /// Encode the instruction, the inverse of [`Instruction::decode`].
+    ///
+    /// For [`Instruction::LdILong`] this is the first word, `F000`, use
+    /// [`Instruction::to_bytes`] to get the address following it too.
+    pub fn encode(&self) -> u16 {
+        use Instruction::*;
+        let xy = |base: u16, x: Reg, y: Reg| base | (x as u16) << 8 | (y as u16) << 4;
+        let xkk = |base: u16, x: Reg, kk: u8| base | (x as u16) << 8 | kk as u16;
+        match *self {
+            Sys(nnn) => nnn,
+            Cls => 0x00E0,
+            Ret => 0x00EE,
+            Low => 0x00FE,
+            High => 0x00FF,
+            Scd(n) => 0x00C0 | n as u16,
+            Scr => 0x00FB,
+            Scl => 0x00FC,
+            Jp(nnn) => 0x1000 | nnn,
+            Call(nnn) => 0x2000 | nnn,
+            SeByte(x, kk) => xkk(0x3000, x, kk),
+            SneByte(x, kk) => xkk(0x4000, x, kk),
+            SeReg(x, y) => xy(0x5000, x, y),
+            LdByte(x, kk) => xkk(0x6000, x, kk),
+            AddByte(x, kk) => xkk(0x7000, x, kk),
+            LdReg(x, y) => xy(0x8000, x, y),
+            Or(x, y) => xy(0x8001, x, y),
+            And(x, y) => xy(0x8002, x, y),
+            Xor(x, y) => xy(0x8003, x, y),
+            AddReg(x, y) => xy(0x8004, x, y),
+            Sub(x, y) => xy(0x8005, x, y),
+            Shr(x, y) => xy(0x8006, x, y),
+            Subn(x, y) => xy(0x8007, x, y),
+            Shl(x, y) => xy(0x800E, x, y),
+            SneReg(x, y) => xy(0x9000, x, y),
+            LdI(nnn) => 0xA000 | nnn,
+            JpV0(nnn) => 0xB000 | nnn,
+            Rnd(x, kk) => xkk(0xC000, x, kk),
+            Drw(x, y, n) => xy(0xD000, x, y) | n as u16,
+            Skp(x) => xkk(0xE000, x, 0x9E),
+            Sknp(x) => xkk(0xE000, x, 0xA1),
+            LdVxDt(x) => xkk(0xF000, x, 0x07),
+            LdVxK(x) => xkk(0xF000, x, 0x0A),
+            LdDtVx(x) => xkk(0xF000, x, 0x15),
+            LdStVx(x) => xkk(0xF000, x, 0x18),
+            AddIVx(x) => xkk(0xF000, x, 0x1E),
+            LdFVx(x) => xkk(0xF000, x, 0x29),
+            LdHfVx(x) => xkk(0xF000, x, 0x30),
+            LdBVx(x) => xkk(0xF000, x, 0x33),
+            LdMemVx(x) => xkk(0xF000, x, 0x55),
+            LdVxMem(x) => xkk(0xF000, x, 0x65),
+            LdRVx(x) => xkk(0xF000, x, 0x75),
+            LdVxR(x) => xkk(0xF000, x, 0x85),
+            LdILong(_) => 0xF000,
+        }
+    }
+
+    /// Whether every operand fits its field of the encoding: registers 0 to
+    /// F, addresses 12 bits and nibbles 4. Decoded instructions always do,
+    /// ones built by hand may not.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Instruction::LdILong(_) => true,
+            _ => Instruction::decode(self.encode()).ok() == Some(*self),
+        }
+    }
+
+    /// Bytes of the instruction as stored in memory.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.encode().to_be_bytes().to_vec();
+        if let Instruction::LdILong(nnnn) = self {
+            bytes.extend(nnnn.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        use Instruction::*;
+        match self {
+            Sys(_) => "SYS",
+            Cls => "CLS",
+            Ret => "RET",
+            Low => "LOW",
+            High => "HIGH",
+            Scd(_) => "SCD",
+            Scr => "SCR",
+            Scl => "SCL",
+            Jp(_) | JpV0(_) => "JP",
+            Call(_) => "CALL",
+            SeByte(..) | SeReg(..) => "SE",
+            SneByte(..) | SneReg(..) => "SNE",
+            LdByte(..) | LdReg(..) | LdI(_) | LdVxDt(_) | LdVxK(_) | LdDtVx(_) | LdStVx(_)
+            | LdFVx(_) | LdHfVx(_) | LdBVx(_) | LdMemVx(_) | LdVxMem(_) | LdRVx(_) | LdVxR(_) => {
+                "LD"
+            }
+            AddByte(..) | AddReg(..) | AddIVx(_) => "ADD",
+            Or(..) => "OR",
+            And(..) => "AND",
+            Xor(..) => "XOR",
+            Sub(..) => "SUB",
+            Shr(..) => "SHR",
+            Subn(..) => "SUBN",
+            Shl(..) => "SHL",
+            Rnd(..) => "RND",
+            Drw(..) => "DRW",
+            Skp(_) => "SKP",
+            Sknp(_) => "SKNP",
+            LdILong(_) => "LDL",
+        }
+    }
+
+    /// Operands as written in assembly: registers as `V1`, addresses as
+    /// `0x2A4`, bytes as `0x05` and sprite heights in decimal.
+    pub fn operands(&self) -> Vec<String> {
+        use Instruction::*;
+        let v = |x: &Reg| format!("V{:X}", x);
+        let addr = |nnn: &u16| format!("0x{:03X}", nnn);
+        let byte = |kk: &u8| format!("0x{:02X}", kk);
+        let fixed = |name: &str| name.to_string();
+        match self {
+            Cls | Ret | Low | High | Scr | Scl => vec![],
+            Scd(n) => vec![n.to_string()],
+            Sys(nnn) | Jp(nnn) | Call(nnn) => vec![addr(nnn)],
+            SeByte(x, kk) | SneByte(x, kk) | LdByte(x, kk) | AddByte(x, kk) | Rnd(x, kk) => {
+                vec![v(x), byte(kk)]
+            }
+            SeReg(x, y)
+            | LdReg(x, y)
+            | Or(x, y)
+            | And(x, y)
+            | Xor(x, y)
+            | AddReg(x, y)
+            | Sub(x, y)
+            | Shr(x, y)
+            | Subn(x, y)
+            | Shl(x, y)
+            | SneReg(x, y) => vec![v(x), v(y)],
+            LdI(nnn) => vec![fixed("I"), addr(nnn)],
+            JpV0(nnn) => vec![fixed("V0"), addr(nnn)],
+            Drw(x, y, n) => vec![v(x), v(y), n.to_string()],
+            Skp(x) | Sknp(x) => vec![v(x)],
+            LdVxDt(x) => vec![v(x), fixed("DT")],
+            LdVxK(x) => vec![v(x), fixed("K")],
+            LdDtVx(x) => vec![fixed("DT"), v(x)],
+            LdStVx(x) => vec![fixed("ST"), v(x)],
+            AddIVx(x) => vec![fixed("I"), v(x)],
+            LdFVx(x) => vec![fixed("F"), v(x)],
+            LdHfVx(x) => vec![fixed("HF"), v(x)],
+            LdBVx(x) => vec![fixed("B"), v(x)],
+            LdMemVx(x) => vec![fixed("[I]"), v(x)],
+            LdVxMem(x) => vec![v(x), fixed("[I]")],
+            LdRVx(x) => vec![fixed("R"), v(x)],
+            LdVxR(x) => vec![v(x), fixed("R")],
+            LdILong(nnnn) => vec![fixed("I"), format!("0x{:04X}", nnnn)],
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operands = self.operands();
+        if operands.is_empty() {
+            write!(f, "{}", self.mnemonic())
+        } else {
+            write!(f, "{} {}", self.mnemonic(), operands.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(Instruction::decode(0x00E0), Ok(Instruction::Cls));
+        assert_eq!(Instruction::decode(0x00EE), Ok(Instruction::Ret));
+        assert_eq!(Instruction::decode(0x0123), Ok(Instruction::Sys(0x123)));
+        assert_eq!(
+            Instruction::decode(0x6A05),
+            Ok(Instruction::LdByte(0xA, 0x05))
+        );
+        assert_eq!(Instruction::decode(0x812E), Ok(Instruction::Shl(1, 2)));
+        assert_eq!(Instruction::decode(0xD125), Ok(Instruction::Drw(1, 2, 5)));
+        assert_eq!(Instruction::decode(0xF365), Ok(Instruction::LdVxMem(3)));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        for word in [0x5001, 0x8008, 0x9001, 0xE000, 0xF000, 0xF0FF] {
+            assert_eq!(
+                Instruction::decode(word),
+                Err(DecodeError { word }),
+                "{:#06X}",
+                word
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        for word in 0..=u16::MAX {
+            if let Ok(instruction) = Instruction::decode(word) {
+                assert_eq!(instruction.encode(), word, "{}", instruction);
+                assert_eq!(
+                    Instruction::read(&instruction.to_bytes()),
+                    Some((instruction, 2))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(Instruction::Jp(0xFFF).is_valid());
+        assert!(Instruction::Drw(0xF, 0xE, 0xF).is_valid());
+        assert!(Instruction::LdILong(0xFFFF).is_valid());
+        assert!(!Instruction::Jp(0x1234).is_valid());
+        assert!(!Instruction::LdByte(0x20, 1).is_valid());
+        assert!(!Instruction::Drw(0, 0, 16).is_valid());
+    }
+
+    #[test]
+    fn test_decode_covers_every_encoding() {
+        // Every variant but the 4-byte one, with distinct operands.
+        let instructions = [
+            Instruction::Sys(0x123),
+            Instruction::Cls,
+            Instruction::Ret,
+            Instruction::Low,
+            Instruction::High,
+            Instruction::Scd(0xB),
+            Instruction::Scr,
+            Instruction::Scl,
+            Instruction::Jp(0x234),
+            Instruction::Call(0x345),
+            Instruction::SeByte(1, 0x12),
+            Instruction::SneByte(2, 0x23),
+            Instruction::SeReg(3, 4),
+            Instruction::LdByte(5, 0x34),
+            Instruction::AddByte(6, 0x45),
+            Instruction::LdReg(7, 8),
+            Instruction::Or(9, 0xA),
+            Instruction::And(0xB, 0xC),
+            Instruction::Xor(0xD, 0xE),
+            Instruction::AddReg(0xF, 0),
+            Instruction::Sub(1, 2),
+            Instruction::Shr(3, 4),
+            Instruction::Subn(5, 6),
+            Instruction::Shl(7, 8),
+            Instruction::SneReg(9, 0xA),
+            Instruction::LdI(0x456),
+            Instruction::JpV0(0x567),
+            Instruction::Rnd(0xB, 0x56),
+            Instruction::Drw(0xC, 0xD, 0xE),
+            Instruction::Skp(0xE),
+            Instruction::Sknp(0xF),
+            Instruction::LdVxDt(1),
+            Instruction::LdVxK(2),
+            Instruction::LdDtVx(3),
+            Instruction::LdStVx(4),
+            Instruction::AddIVx(5),
+            Instruction::LdFVx(6),
+            Instruction::LdHfVx(7),
+            Instruction::LdBVx(7),
+            Instruction::LdMemVx(8),
+            Instruction::LdVxMem(9),
+            Instruction::LdRVx(5),
+            Instruction::LdVxR(6),
+        ];
+        for instruction in instructions {
+            assert_eq!(
+                Instruction::decode(instruction.encode()),
+                Ok(instruction),
+                "{:#06X}",
+                instruction.encode()
+            );
+        }
+    }
+
+    #[test]
+    fn test_long_load_bytes() {
+        let instruction = Instruction::LdILong(0xBEEF);
+
+        assert_eq!(instruction.encode(), 0xF000);
+        assert_eq!(instruction.to_bytes(), [0xF0, 0x00, 0xBE, 0xEF]);
+        assert_eq!(
+            Instruction::read(&instruction.to_bytes()),
+            Some((instruction, 4))
+        );
+    }
+
+    #[test]
+    fn test_read() {
+        assert_eq!(
+            Instruction::read(&[0xA2, 0x34, 0xFF]),
+            Some((Instruction::LdI(0x234), 2))
+        );
+        assert_eq!(
+            Instruction::read(&[0xF0, 0x00, 0x12, 0x34]),
+            Some((Instruction::LdILong(0x1234), 4))
+        );
+        assert_eq!(Instruction::read(&[0xF0, 0x00, 0x12]), None);
+        assert_eq!(Instruction::read(&[0xA2]), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (0x00E0, "CLS"),
+            (0x1228, "JP 0x228"),
+            (0x3A05, "SE VA, 0x05"),
+            (0x8126, "SHR V1, V2"),
+            (0xA2F0, "LD I, 0x2F0"),
+            (0xB300, "JP V0, 0x300"),
+            (0xD01F, "DRW V0, V1, 15"),
+            (0xE4A1, "SKNP V4"),
+            (0xF50A, "LD V5, K"),
+            (0xF655, "LD [I], V6"),
+            (0xF765, "LD V7, [I]"),
+        ];
+        for (word, text) in cases {
+            assert_eq!(Instruction::decode(word).unwrap().to_string(), text);
+        }
+        assert_eq!(Instruction::LdILong(0xBEEF).to_string(), "LDL I, 0xBEEF");
+    }
+}
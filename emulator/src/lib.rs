@@ -17,12 +17,86 @@
 //! games at the time, and Chip-8 was the answer. Chip-48 later begat Super
 //! Chip-48, a modification of Chip-48 which allowed higher resolution
 //! graphics, as well as other graphical enhancements.
+//!
+//! # `no_std`
+//!
+//! Without the default `std` feature the crate needs only `alloc`. The VM
+//! and everything it's made of are there; modules built on files, sockets
+//! or threads aren't. `examples/embedded` runs it on an RP2040.
+//!
+//! # Stability
+//!
+//! [`prelude`] exports the supported surface: the VM and its builder,
+//! quirks, instructions, errors and the traits frontends implement. Modules
+//! hidden from the documentation are internals and may change in any
+//! release.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "std")]
+pub mod asm;
+#[cfg(feature = "async")]
+pub mod async_run;
+#[cfg(feature = "batch")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod bisect;
+#[cfg(feature = "std")]
+pub mod blend;
+#[cfg(feature = "std")]
+pub mod capture;
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod crash;
+#[cfg(feature = "debug-server")]
+pub mod debug_server;
+pub mod decoder;
+pub mod demos;
+#[cfg(feature = "std")]
+pub mod disasm;
+pub mod draws;
+pub mod effects;
+pub mod faults;
 pub mod graphics;
+#[doc(hidden)]
+pub mod history;
 pub mod input;
+pub mod instruction;
 pub mod memory;
+pub mod mmio;
+pub mod observer;
+#[cfg(feature = "std")]
+pub mod overlay;
+#[cfg(feature = "std")]
+pub mod palette;
+#[cfg(feature = "std")]
+pub mod patch;
+pub mod platform;
+pub mod prelude;
+pub mod program;
+#[doc(hidden)]
 pub mod registers;
+pub mod rng;
+pub mod rng_log;
+pub mod rpl;
+#[cfg(feature = "std")]
+pub mod script;
+#[cfg(feature = "std")]
+pub mod shared;
+pub mod snapshot;
+#[doc(hidden)]
 pub mod stack;
+#[cfg(feature = "std")]
+pub mod storage;
+#[cfg(feature = "std")]
+pub mod testing;
+pub mod trace;
 pub mod vm;
 
+/// Version of `rand` whose traits [`rng::RngSource`] builds on.
+pub use rand;
+#[cfg(feature = "std")]
+pub use shared::SharedVm;
 pub use vm::VM;
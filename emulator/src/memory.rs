@@ -1,15 +1,28 @@
-const MEMORY_SIZE: usize = 4096;
+use alloc::borrow::Cow;
+use core::fmt;
+use core::ops::Range;
+
+pub const MEMORY_SIZE: usize = 4096;
 pub const SPRITE_SIZE: usize = 5;
 const SPRITE_NUM: usize = 16;
 pub const SPRITE_START_LOCATION: usize = 0;
+pub const FONT_SIZE: usize = SPRITE_SIZE * SPRITE_NUM;
+pub const BIG_SPRITE_SIZE: usize = 10;
+pub const BIG_SPRITE_START_LOCATION: usize = SPRITE_START_LOCATION + FONT_SIZE;
+pub const BIG_FONT_SIZE: usize = BIG_SPRITE_SIZE * SPRITE_NUM;
 pub const PROGRAM_START_LOCATION: usize = 0x200;
+/// Where ETI 660 programs start, see
+/// [`VM::set_program_start`](super::vm::VM::set_program_start).
+pub const ETI_660_PROGRAM_START_LOCATION: usize = 0x600;
 pub const INSTRUCTION_SIZE: usize = 2;
+pub const MAX_PROGRAM_SIZE: usize = MEMORY_SIZE - PROGRAM_START_LOCATION;
 
-static INITIAL_SPRITES: [u8; SPRITE_SIZE * SPRITE_NUM] = [
+/// CHIP-48 digits, also used by SUPER-CHIP and Octo.
+static INITIAL_SPRITES: [u8; FONT_SIZE] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
     0x20, 0x60, 0x20, 0x20, 0x70, // 1
     0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 3
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
     0x90, 0x90, 0xF0, 0x10, 0x10, // 4
     0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
     0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
@@ -24,40 +37,260 @@ static INITIAL_SPRITES: [u8; SPRITE_SIZE * SPRITE_NUM] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+/// SUPER-CHIP 8 x 10 digits for high resolution, with Octo's A to F, which
+/// SUPER-CHIP lacks.
+static INITIAL_BIG_SPRITES: [u8; BIG_FONT_SIZE] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+/// Digits of the original COSMAC VIP interpreter.
+static VIP_SPRITES: [u8; FONT_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x60, 0x20, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0xA0, 0xA0, 0xF0, 0x20, 0x20, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x10, 0x10, 0x10, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xF0, 0x50, 0x70, 0x50, 0xF0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xF0, 0x50, 0x50, 0x50, 0xF0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+/// Narrow 3 pixels wide digits of the DREAM 6800 CHIPOS.
+static DREAM_6800_SPRITES: [u8; FONT_SIZE] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x40, 0x40, 0x40, 0x40, 0x40, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+/// Built-in hexadecimal font variants of known interpreters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FontStyle {
+    Vip,
+    #[default]
+    Chip48,
+    Dream6800,
+    Octo,
+}
+
+impl FontStyle {
+    pub const ALL: [FontStyle; 4] = [
+        FontStyle::Vip,
+        FontStyle::Chip48,
+        FontStyle::Dream6800,
+        FontStyle::Octo,
+    ];
+
+    /// Digit sprites of the font, 5 bytes per digit.
+    pub fn sprites(self) -> &'static [u8; FONT_SIZE] {
+        match self {
+            FontStyle::Vip => &VIP_SPRITES,
+            FontStyle::Chip48 | FontStyle::Octo => &INITIAL_SPRITES,
+            FontStyle::Dream6800 => &DREAM_6800_SPRITES,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FontStyle::Vip => "vip",
+            FontStyle::Chip48 => "chip48",
+            FontStyle::Dream6800 => "dream6800",
+            FontStyle::Octo => "octo",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|style| style.name() == name)
+    }
+}
+
+/// What reading past the end of memory does, e.g. drawing a sprite whose
+/// bytes start at `I` near `0xFFF`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutOfRange {
+    /// Addresses wrap around to the start of memory, like the 12-bit
+    /// address bus of the COSMAC VIP.
+    #[default]
+    Wrap,
+    /// Only bytes inside memory are read, so the sprite is cut short.
+    Clamp,
+    /// Reading past the end is a program error, reported as
+    /// [`VmError::MemoryOutOfBounds`](crate::vm::VmError::MemoryOutOfBounds)
+    /// without running the instruction.
+    Error,
+}
+
+/// Why a program couldn't be loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// Program has no bytes.
+    Empty,
+    /// Program of `size` bytes doesn't fit in the `available` bytes from
+    /// its start address to the end of memory.
+    TooLarge { size: usize, available: usize },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Empty => write!(f, "program is empty"),
+            LoadError::TooLarge { size, available } => write!(
+                f,
+                "program of {} bytes is larger than the {} bytes of memory for it",
+                size, available
+            ),
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
+
+/// Check `program` fits in memory from address `start`.
+pub fn check_program(start: usize, program: &[u8]) -> Result<(), LoadError> {
+    let available = MEMORY_SIZE.saturating_sub(start);
+    if program.is_empty() {
+        Err(LoadError::Empty)
+    } else if program.len() > available {
+        Err(LoadError::TooLarge {
+            size: program.len(),
+            available,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Internal, the VM's address space is read through [`crate::VM::memory`].
+#[doc(hidden)]
+#[derive(Clone)]
 pub struct Memory {
     memory: [u8; MEMORY_SIZE],
 }
 
 impl Memory {
     pub fn new_with_initial_sprites() -> Self {
-        let mut memory = [0; MEMORY_SIZE];
+        let mut memory = Memory {
+            memory: [0; MEMORY_SIZE],
+        };
+        memory.load_font(&INITIAL_SPRITES);
+        memory.load_big_font(&INITIAL_BIG_SPRITES);
+        memory
+    }
+
+    /// Replace hexadecimal digit sprites with `font`, 5 bytes per digit.
+    pub fn load_font(&mut self, font: &[u8; FONT_SIZE]) {
+        self.memory[SPRITE_START_LOCATION..SPRITE_START_LOCATION + FONT_SIZE].copy_from_slice(font);
+    }
+
+    /// Replace high-resolution digit sprites with `font`, 10 bytes per digit.
+    pub fn load_big_font(&mut self, font: &[u8; BIG_FONT_SIZE]) {
+        self.memory[BIG_SPRITE_START_LOCATION..BIG_SPRITE_START_LOCATION + BIG_FONT_SIZE]
+            .copy_from_slice(font);
+    }
 
-        let sprites_chunk =
-            &mut memory[SPRITE_START_LOCATION..SPRITE_START_LOCATION + INITIAL_SPRITES.len()];
-        sprites_chunk.copy_from_slice(&INITIAL_SPRITES);
+    /// Bytes at addresses in `range`, `None` if it runs past the end.
+    pub fn get(&self, range: Range<usize>) -> Option<&[u8]> {
+        self.memory.get(range)
+    }
 
-        Memory { memory }
+    /// Mutable bytes at addresses in `range`, `None` if it runs past the
+    /// end.
+    pub fn get_mut(&mut self, range: Range<usize>) -> Option<&mut [u8]> {
+        self.memory.get_mut(range)
     }
 
     /// TODO: replace finish with len
     pub fn get_slice(&self, start: usize, finish: usize) -> &[u8] {
         assert!(start < MEMORY_SIZE);
-        assert!(finish < MEMORY_SIZE);
+        assert!(finish <= MEMORY_SIZE);
         &self.memory[start..finish]
     }
 
     /// TODO: replace finish with len
     pub fn get_slice_mut(&mut self, start: usize, finish: usize) -> &mut [u8] {
         assert!(start < MEMORY_SIZE);
-        assert!(finish < MEMORY_SIZE);
+        assert!(finish <= MEMORY_SIZE);
         &mut self.memory[start..finish]
     }
 
-    pub fn load_program(&mut self, program: &[u8]) {
-        let start = PROGRAM_START_LOCATION;
+    /// Read `len` bytes starting at `start`, which may run past the end of
+    /// memory, handled according to `policy`: `None` if they do with
+    /// [`OutOfRange::Error`].
+    pub fn read(&self, start: usize, len: usize, policy: OutOfRange) -> Option<Cow<'_, [u8]>> {
+        let end = start + len;
+        if end <= MEMORY_SIZE {
+            return Some(Cow::Borrowed(&self.memory[start..end]));
+        }
+        match policy {
+            OutOfRange::Wrap => Some(Cow::Owned(
+                (start..end)
+                    .map(|addr| self.memory[addr % MEMORY_SIZE])
+                    .collect(),
+            )),
+            OutOfRange::Clamp => Some(Cow::Borrowed(&self.memory[start.min(MEMORY_SIZE)..])),
+            OutOfRange::Error => None,
+        }
+    }
+
+    /// Set every byte in `range` to `byte`.
+    pub fn fill(&mut self, range: Range<usize>, byte: u8) {
+        assert!(range.start <= range.end);
+        assert!(range.end <= MEMORY_SIZE);
+        self.memory[range].fill(byte);
+    }
+
+    /// Zero memory from the program start location to the end.
+    pub fn clear_program_area(&mut self) {
+        self.fill(PROGRAM_START_LOCATION..MEMORY_SIZE, 0);
+    }
+
+    pub fn load_program(&mut self, program: &[u8]) -> Result<(), LoadError> {
+        self.load_program_at(PROGRAM_START_LOCATION, program)
+    }
+
+    /// Copy `program` to memory starting at address `start`, leaving memory
+    /// as it was if it's empty or doesn't fit.
+    pub fn load_program_at(&mut self, start: usize, program: &[u8]) -> Result<(), LoadError> {
+        check_program(start, program)?;
         let finish = start + program.len();
         let program_chunk = self.get_slice_mut(start, finish);
         program_chunk.copy_from_slice(program);
+        Ok(())
     }
 
     /// Fetch instruction at `addr` address.
@@ -79,7 +312,61 @@ mod tests {
         for (i, &byte) in memory.memory[0..80].iter().enumerate() {
             assert_eq!(byte, INITIAL_SPRITES[i]);
         }
-        assert!(memory.memory[80..].iter().all(|&byte| byte == 0));
+        let big_font_end = BIG_SPRITE_START_LOCATION + BIG_FONT_SIZE;
+        assert_eq!(
+            memory.memory[BIG_SPRITE_START_LOCATION..big_font_end],
+            INITIAL_BIG_SPRITES
+        );
+        assert!(memory.memory[big_font_end..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_load_font() {
+        let mut memory = Memory::new_with_initial_sprites();
+        let font = [0xAA; FONT_SIZE];
+
+        memory.load_font(&font);
+
+        assert_eq!(memory.memory[0..FONT_SIZE], font);
+        assert_eq!(
+            memory.memory[BIG_SPRITE_START_LOCATION..BIG_SPRITE_START_LOCATION + BIG_FONT_SIZE],
+            INITIAL_BIG_SPRITES
+        );
+    }
+
+    #[test]
+    fn test_load_font_style() {
+        let mut memory = Memory::new_with_initial_sprites();
+
+        memory.load_font(FontStyle::Dream6800.sprites());
+
+        assert_eq!(memory.memory[0..5], [0xE0, 0xA0, 0xA0, 0xA0, 0xE0]);
+        assert_eq!(
+            memory.memory[FONT_SIZE..FONT_SIZE + 5],
+            INITIAL_BIG_SPRITES[0..5]
+        );
+    }
+
+    #[test]
+    fn test_font_style_names() {
+        for &style in FontStyle::ALL.iter() {
+            assert_eq!(FontStyle::from_name(style.name()), Some(style));
+        }
+        assert_eq!(FontStyle::from_name("unknown"), None);
+    }
+
+    #[test]
+    fn test_load_big_font() {
+        let mut memory = Memory::new_with_initial_sprites();
+        let font = [0xAA; BIG_FONT_SIZE];
+
+        memory.load_big_font(&font);
+
+        assert_eq!(memory.memory[0..FONT_SIZE], INITIAL_SPRITES);
+        assert_eq!(
+            memory.memory[BIG_SPRITE_START_LOCATION..BIG_SPRITE_START_LOCATION + BIG_FONT_SIZE],
+            font
+        );
     }
 
     #[test]
@@ -87,7 +374,7 @@ mod tests {
         let mut memory = Memory::new_with_initial_sprites();
         let test_program_code = [0x1, 0x2, 0x3];
 
-        memory.load_program(&test_program_code);
+        memory.load_program(&test_program_code).unwrap();
 
         let program_in_memory = memory.get_slice(
             PROGRAM_START_LOCATION,
@@ -96,6 +383,130 @@ mod tests {
         assert_eq!(program_in_memory, test_program_code);
     }
 
+    #[test]
+    fn test_load_program_of_max_size() {
+        let mut memory = Memory::new_with_initial_sprites();
+
+        memory.load_program(&[0xAB; MAX_PROGRAM_SIZE]).unwrap();
+
+        assert_eq!(memory.get_slice(MEMORY_SIZE - 1, MEMORY_SIZE), [0xAB]);
+    }
+
+    #[test]
+    fn test_load_program_errors() {
+        let mut memory = Memory::new_with_initial_sprites();
+
+        assert_eq!(memory.load_program(&[]), Err(LoadError::Empty));
+        assert_eq!(
+            memory.load_program(&[0xAB; MAX_PROGRAM_SIZE + 1]),
+            Err(LoadError::TooLarge {
+                size: MAX_PROGRAM_SIZE + 1,
+                available: MAX_PROGRAM_SIZE
+            })
+        );
+        assert_eq!(
+            memory.load_program_at(ETI_660_PROGRAM_START_LOCATION, &[0xAB; MAX_PROGRAM_SIZE]),
+            Err(LoadError::TooLarge {
+                size: MAX_PROGRAM_SIZE,
+                available: MEMORY_SIZE - ETI_660_PROGRAM_START_LOCATION
+            })
+        );
+        assert!(memory
+            .get_slice(PROGRAM_START_LOCATION, MEMORY_SIZE)
+            .iter()
+            .all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_read_inside_memory() {
+        let memory = Memory::new_with_initial_sprites();
+
+        for policy in [OutOfRange::Wrap, OutOfRange::Clamp, OutOfRange::Error] {
+            let bytes = memory.read(0, 5, policy).unwrap();
+            assert!(matches!(bytes, Cow::Borrowed(_)));
+            assert_eq!(*bytes, INITIAL_SPRITES[..5]);
+        }
+    }
+
+    #[test]
+    fn test_read_wrap() {
+        let mut memory = Memory::new_with_initial_sprites();
+        memory.memory[MEMORY_SIZE - 2..].copy_from_slice(&[0xAA, 0xBB]);
+
+        let bytes = memory.read(MEMORY_SIZE - 2, 4, OutOfRange::Wrap).unwrap();
+
+        assert_eq!(*bytes, [0xAA, 0xBB, 0xF0, 0x90]);
+        assert_eq!(
+            *memory.read(MEMORY_SIZE + 1, 2, OutOfRange::Wrap).unwrap(),
+            [0x90, 0x90]
+        );
+    }
+
+    #[test]
+    fn test_read_clamp() {
+        let mut memory = Memory::new_with_initial_sprites();
+        memory.memory[MEMORY_SIZE - 2..].copy_from_slice(&[0xAA, 0xBB]);
+
+        assert_eq!(
+            *memory.read(MEMORY_SIZE - 2, 4, OutOfRange::Clamp).unwrap(),
+            [0xAA, 0xBB]
+        );
+        assert!(memory
+            .read(MEMORY_SIZE + 1, 2, OutOfRange::Clamp)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_read_error() {
+        let memory = Memory::new_with_initial_sprites();
+
+        assert_eq!(memory.read(MEMORY_SIZE - 2, 4, OutOfRange::Error), None);
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut memory = Memory::new_with_initial_sprites();
+
+        memory.fill(0x300..0x310, 0xAB);
+
+        assert!(memory.memory[0x300..0x310].iter().all(|&byte| byte == 0xAB));
+        assert_eq!(memory.memory[0x2FF], 0);
+        assert_eq!(memory.memory[0x310], 0);
+    }
+
+    #[test]
+    fn test_fill_till_end() {
+        let mut memory = Memory::new_with_initial_sprites();
+
+        memory.fill(0xFF0..MEMORY_SIZE, 0xAB);
+
+        assert!(memory.memory[0xFF0..].iter().all(|&byte| byte == 0xAB));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fill_out_of_bounds() {
+        let mut memory = Memory::new_with_initial_sprites();
+        memory.fill(0xFF0..MEMORY_SIZE + 1, 0xAB);
+    }
+
+    #[test]
+    fn test_clear_program_area() {
+        let mut memory = Memory::new_with_initial_sprites();
+        memory.fill(0x100..MEMORY_SIZE, 0xAB);
+
+        memory.clear_program_area();
+
+        assert!(memory.memory[0x100..PROGRAM_START_LOCATION]
+            .iter()
+            .all(|&byte| byte == 0xAB));
+        assert!(memory.memory[PROGRAM_START_LOCATION..]
+            .iter()
+            .all(|&byte| byte == 0));
+        assert_eq!(memory.memory[0..FONT_SIZE], INITIAL_SPRITES);
+    }
+
     #[test]
     fn test_fetch_instruction() {
         let mut memory = Memory::new_with_initial_sprites();
@@ -0,0 +1,125 @@
+//! Memory-mapped I/O: bytes programs store in a reserved range of memory
+//! are passed to the host as they're written, e.g. for test programs to
+//! print their results. Nothing is mapped unless asked with
+//! [`VM::on_io_write`].
+
+#[cfg(feature = "std")]
+use super::vm::VM;
+use alloc::boxed::Box;
+#[cfg(feature = "std")]
+use alloc::{string::String, sync::Arc};
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+/// Byte stored by the program in mapped memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoWrite {
+    pub address: usize,
+    pub value: u8,
+}
+
+/// Text output of a program, one character per byte it stores at an
+/// address. Clones share the text, so one can be kept to read it while
+/// another is attached to the VM.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct Console {
+    text: Arc<Mutex<String>>,
+}
+
+#[cfg(feature = "std")]
+impl Console {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `address` of `vm` to the console, replacing whatever was
+    /// mapped. Bytes are taken as Latin-1, ASCII for test programs.
+    pub fn attach(&self, vm: &mut VM, address: usize) {
+        let text = Arc::clone(&self.text);
+        vm.on_io_write(address..address + 1, move |write| {
+            text.lock().unwrap().push(write.value as char);
+        });
+    }
+
+    /// Text written so far.
+    pub fn text(&self) -> String {
+        self.text.lock().unwrap().clone()
+    }
+
+    /// Text written since the last call, clearing it.
+    pub fn take(&self) -> String {
+        core::mem::take(&mut *self.text.lock().unwrap())
+    }
+}
+
+/// Range of memory mapped by [`VM::on_io_write`] with its handler.
+pub(crate) struct IoMap {
+    pub(crate) range: Range<usize>,
+    pub(crate) handler: Box<dyn FnMut(&IoWrite) + Send + Sync>,
+}
+
+impl IoMap {
+    /// Pass the bytes of `memory` starting at `address` which are in the
+    /// mapped range to the handler, in order.
+    pub(crate) fn written(&mut self, address: usize, memory: &[u8]) {
+        for (offset, &value) in memory.iter().enumerate() {
+            let address = address + offset;
+            if self.range.contains(&address) {
+                (self.handler)(&IoWrite { address, value });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_console() {
+        let mut vm = VM::new();
+        let console = Console::new();
+        console.attach(&mut vm, 0xF00);
+        // LD I, 0xF00; LD V0, 'o'; LD [I], V0; LD V0, 'k'; LD [I], V0
+        vm.load_program(&[0xAF, 0x00, 0x60, b'o', 0xF0, 0x55, 0x60, b'k', 0xF0, 0x55])
+            .unwrap();
+        for _ in 0..5 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        assert_eq!(console.text(), "ok");
+        assert_eq!(console.take(), "ok");
+        assert_eq!(console.text(), "");
+    }
+
+    #[test]
+    fn test_io_write_range() {
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let mut vm = VM::new();
+        let log = Arc::clone(&writes);
+        vm.on_io_write(0xF01..0xF03, move |write| log.lock().unwrap().push(*write));
+        // LD I, 0xF00; LD V0, 1; LD V1, 2; LD V2, 3; LD [I], V2; LD B, V1
+        vm.load_program(&[
+            0xAF, 0x00, 0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0xF2, 0x55, 0xF1, 0x33,
+        ])
+        .unwrap();
+        for _ in 0..6 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        let write = |address, value| IoWrite { address, value };
+        assert_eq!(
+            *writes.lock().unwrap(),
+            [
+                write(0xF01, 2),
+                write(0xF02, 3),
+                write(0xF01, 0),
+                write(0xF02, 2)
+            ]
+        );
+        // Memory is written too.
+        assert_eq!(vm.memory(0xF00..0xF03).unwrap(), [0, 0, 2]);
+    }
+}
@@ -0,0 +1,111 @@
+//! Hooks into the execution loop for tracers, profilers, debuggers and
+//! scripting, see [`VM::add_observer`].
+
+use super::{draws::Draw, vm::VM};
+
+/// Receives events as the VM runs, with the VM as it is at the time. Every
+/// method does nothing by default, so observers implement the ones they
+/// need. Like other callbacks of the VM, observers are shared between
+/// threads with it.
+pub trait VmObserver: Send + Sync {
+    /// `opcode` at `pc` is about to be executed.
+    fn before_instruction(&mut self, _vm: &VM, _pc: u16, _opcode: u16) {}
+
+    /// `opcode` at `pc` was executed, not called if it failed.
+    fn after_instruction(&mut self, _vm: &VM, _pc: u16, _opcode: u16) {}
+
+    /// `DRW` drew a sprite, once all of its rows are drawn.
+    fn on_draw(&mut self, _vm: &VM, _draw: &Draw) {}
+
+    /// `CLS` cleared the display.
+    fn on_clear(&mut self, _vm: &VM) {}
+
+    /// Delay and sound timers ticked, once a frame.
+    fn on_timer_tick(&mut self, _vm: &VM) {}
+}
+
+/// Observer added by [`VM::add_observer`], to remove it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObserverId(pub(crate) u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Events as text, shared with the test.
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl VmObserver for Recorder {
+        fn before_instruction(&mut self, _vm: &VM, pc: u16, opcode: u16) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("before {:#05X} {:04X}", pc, opcode));
+        }
+
+        fn after_instruction(&mut self, vm: &VM, pc: u16, _opcode: u16) {
+            let next = vm.registers().program_counter;
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("after {:#05X} -> {:#05X}", pc, next));
+        }
+
+        fn on_draw(&mut self, _vm: &VM, draw: &Draw) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("draw {},{} {} rows", draw.x, draw.y, draw.height));
+        }
+
+        fn on_clear(&mut self, _vm: &VM) {
+            self.0.lock().unwrap().push("clear".into());
+        }
+
+        fn on_timer_tick(&mut self, vm: &VM) {
+            let delay = vm.registers().delay_timer;
+            self.0.lock().unwrap().push(format!("tick {}", delay));
+        }
+    }
+
+    #[test]
+    fn test_observer_events() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut vm = VM::new();
+        let id = vm.add_observer(Recorder(Arc::clone(&events)));
+        // CLS; LD V0, 3; LD DT, V0; DRW V1, V1, 5; <unknown>
+        vm.load_program(&[0x00, 0xE0, 0x60, 0x03, 0xF0, 0x15, 0xD1, 0x15, 0xFF, 0xFF])
+            .unwrap();
+        for _ in 0..4 {
+            vm.exec_current_instruction().unwrap();
+        }
+        vm.exec_current_instruction().unwrap_err();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "before 0x200 00E0",
+                "clear",
+                "after 0x200 -> 0x202",
+                "tick 0",
+                "before 0x202 6003",
+                "after 0x202 -> 0x204",
+                "tick 0",
+                "before 0x204 F015",
+                "after 0x204 -> 0x206",
+                "tick 2",
+                "before 0x206 D115",
+                "draw 0,0 5 rows",
+                "after 0x206 -> 0x208",
+                "tick 1",
+                "before 0x208 FFFF",
+            ]
+        );
+
+        assert!(vm.remove_observer(id));
+        assert!(!vm.remove_observer(id));
+        vm.tick_timers();
+        assert_eq!(events.lock().unwrap().len(), 15);
+    }
+}
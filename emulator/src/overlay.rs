@@ -0,0 +1,219 @@
+//! Text drawn over the display by the emulator rather than the program,
+//! e.g. toasts, frame rate or debug values. It's composited by
+//! [`Palette::render_rgba`](super::palette::Palette::render_rgba), so every
+//! frontend rendering through it shows the same text.
+//!
+//! Hexadecimal digits use the built-in CHIP-48 font, so values read the same
+//! as when a program draws them. Other letters and a little punctuation use
+//! a 5 x 7 font, lowercase letters drawn as uppercase, and characters
+//! without a glyph as `?`.
+
+use super::graphics::{Graphics, DISPLAY_COLS, DISPLAY_ROWS};
+use super::memory::{FontStyle, SPRITE_SIZE};
+
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+/// Horizontal distance between glyphs, one column of spacing.
+pub const ADVANCE: usize = GLYPH_WIDTH + 1;
+pub const LINE_HEIGHT: usize = GLYPH_HEIGHT + 1;
+
+const UNKNOWN: [u8; GLYPH_HEIGHT] = [0x70, 0x88, 0x08, 0x10, 0x20, 0x00, 0x20];
+
+/// Characters other than hexadecimal digits, rows of 5 pixels in the high
+/// bits.
+static GLYPHS: [(char, [u8; GLYPH_HEIGHT]); 33] = [
+    ('G', [0x70, 0x88, 0x80, 0xB8, 0x88, 0x88, 0x78]),
+    ('H', [0x88, 0x88, 0x88, 0xF8, 0x88, 0x88, 0x88]),
+    ('I', [0x70, 0x20, 0x20, 0x20, 0x20, 0x20, 0x70]),
+    ('J', [0x38, 0x10, 0x10, 0x10, 0x10, 0x90, 0x60]),
+    ('K', [0x88, 0x90, 0xA0, 0xC0, 0xA0, 0x90, 0x88]),
+    ('L', [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xF8]),
+    ('M', [0x88, 0xD8, 0xA8, 0xA8, 0x88, 0x88, 0x88]),
+    ('N', [0x88, 0x88, 0xC8, 0xA8, 0x98, 0x88, 0x88]),
+    ('O', [0x70, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70]),
+    ('P', [0xF0, 0x88, 0x88, 0xF0, 0x80, 0x80, 0x80]),
+    ('Q', [0x70, 0x88, 0x88, 0x88, 0xA8, 0x90, 0x68]),
+    ('R', [0xF0, 0x88, 0x88, 0xF0, 0xA0, 0x90, 0x88]),
+    ('S', [0x78, 0x80, 0x80, 0x70, 0x08, 0x08, 0xF0]),
+    ('T', [0xF8, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20]),
+    ('U', [0x88, 0x88, 0x88, 0x88, 0x88, 0x88, 0x70]),
+    ('V', [0x88, 0x88, 0x88, 0x88, 0x88, 0x50, 0x20]),
+    ('W', [0x88, 0x88, 0x88, 0xA8, 0xA8, 0xA8, 0x50]),
+    ('X', [0x88, 0x88, 0x50, 0x20, 0x50, 0x88, 0x88]),
+    ('Y', [0x88, 0x88, 0x50, 0x20, 0x20, 0x20, 0x20]),
+    ('Z', [0xF8, 0x08, 0x10, 0x20, 0x40, 0x80, 0xF8]),
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x40]),
+    (':', [0x00, 0x00, 0x20, 0x00, 0x00, 0x20, 0x00]),
+    ('-', [0x00, 0x00, 0x00, 0x70, 0x00, 0x00, 0x00]),
+    ('+', [0x00, 0x20, 0x20, 0xF8, 0x20, 0x20, 0x00]),
+    ('=', [0x00, 0x00, 0xF8, 0x00, 0xF8, 0x00, 0x00]),
+    ('!', [0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x20]),
+    ('?', UNKNOWN),
+    ('/', [0x08, 0x08, 0x10, 0x20, 0x40, 0x80, 0x80]),
+    ('%', [0xC0, 0xC8, 0x10, 0x20, 0x40, 0x98, 0x18]),
+    ('(', [0x10, 0x20, 0x40, 0x40, 0x40, 0x20, 0x10]),
+    (')', [0x40, 0x20, 0x10, 0x10, 0x10, 0x20, 0x40]),
+];
+
+/// Rows of the glyph of `c`, top to bottom.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    let c = c.to_ascii_uppercase();
+    if let Some(digit) = c.to_digit(16) {
+        // Digits are 5 rows high, sit them on the baseline.
+        let start = digit as usize * SPRITE_SIZE;
+        let mut rows = [0; GLYPH_HEIGHT];
+        rows[GLYPH_HEIGHT - SPRITE_SIZE..]
+            .copy_from_slice(&FontStyle::Chip48.sprites()[start..start + SPRITE_SIZE]);
+        return rows;
+    }
+    GLYPHS
+        .iter()
+        .find(|(glyph, _)| *glyph == c)
+        .map_or(UNKNOWN, |(_, rows)| *rows)
+}
+
+/// Size in pixels of `text` drawn by [`Overlay::text`], `(width, height)`.
+pub fn text_size(text: &str) -> (usize, usize) {
+    let width = text
+        .lines()
+        .map(|line| (line.chars().count() * ADVANCE).saturating_sub(1))
+        .max()
+        .unwrap_or(0);
+    let height = (text.lines().count() * LINE_HEIGHT).saturating_sub(1);
+    (width, height)
+}
+
+/// Layer of text over the display. Text is drawn on a backdrop one pixel
+/// larger on every side, in the palette background color, so it stays
+/// readable over anything the program draws.
+#[derive(Clone, Default)]
+pub struct Overlay {
+    text: Graphics,
+    backdrop: Graphics,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.backdrop.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backdrop.display.iter().all(|&row| row == 0)
+    }
+
+    /// Draw `text` with its top left corner at column `x` and row `y`, lines
+    /// separated by `\n`. Text running off the display is cut, not wrapped.
+    pub fn text(&mut self, x: usize, y: usize, text: &str) {
+        let (width, height) = text_size(text);
+        if width == 0 {
+            return;
+        }
+        for row in y.saturating_sub(1)..(y + height + 1).min(DISPLAY_ROWS) {
+            for column in x.saturating_sub(1)..(x + width + 1).min(DISPLAY_COLS) {
+                self.backdrop.display[row] |= 1 << column;
+            }
+        }
+        for (line, chars) in text.lines().enumerate() {
+            for (i, c) in chars.chars().enumerate() {
+                self.glyph(x + i * ADVANCE, y + line * LINE_HEIGHT, c);
+            }
+        }
+    }
+
+    fn glyph(&mut self, x: usize, y: usize, c: char) {
+        for (i, bits) in glyph(c).into_iter().enumerate() {
+            for bit in 0..GLYPH_WIDTH {
+                let (column, row) = (x + bit, y + i);
+                if bits & (0x80 >> bit) != 0 && column < DISPLAY_COLS && row < DISPLAY_ROWS {
+                    self.text.display[row] |= 1 << column;
+                }
+            }
+        }
+    }
+
+    /// Whether the overlay covers pixel at column `x` and row `y`, and if so
+    /// whether it's lit.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<bool> {
+        self.backdrop.pixel(x, y).then(|| self.text.pixel(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(overlay: &Overlay, y: usize, columns: std::ops::Range<usize>) -> String {
+        columns
+            .map(|x| match overlay.pixel(x, y) {
+                None => ' ',
+                Some(false) => '.',
+                Some(true) => '#',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_text() {
+        let mut overlay = Overlay::new();
+        overlay.text(1, 1, "H1");
+
+        assert_eq!(row(&overlay, 0, 0..14), "............. ");
+        assert_eq!(row(&overlay, 1, 0..14), ".#...#....... ");
+        assert_eq!(row(&overlay, 3, 0..14), ".#...#...#... ");
+        assert_eq!(row(&overlay, 4, 0..14), ".#####..##... ");
+        assert_eq!(row(&overlay, 7, 0..14), ".#...#..###.. ");
+        assert_eq!(row(&overlay, 8, 0..14), "............. ");
+        assert_eq!(row(&overlay, 9, 0..14), "              ");
+    }
+
+    #[test]
+    fn test_lowercase_and_unknown_characters() {
+        let mut lower = Overlay::new();
+        lower.text(0, 0, "fps");
+        let mut upper = Overlay::new();
+        upper.text(0, 0, "FPS");
+        assert_eq!(lower.text.display, upper.text.display);
+
+        let mut unknown = Overlay::new();
+        unknown.text(0, 0, "~");
+        let mut question = Overlay::new();
+        question.text(0, 0, "?");
+        assert_eq!(unknown.text.display, question.text.display);
+    }
+
+    #[test]
+    fn test_text_is_cut_at_the_edges() {
+        let mut overlay = Overlay::new();
+        overlay.text(60, 28, "HH\nHH");
+
+        assert_eq!(row(&overlay, 28, 59..64), ".#...");
+        assert!(overlay.pixel(0, 28).is_none());
+        assert!(overlay.pixel(60, 0).is_none());
+    }
+
+    #[test]
+    fn test_text_size() {
+        assert_eq!(text_size(""), (0, 0));
+        assert_eq!(text_size("60"), (11, 7));
+        assert_eq!(text_size("FPS 60\nOK"), (35, 15));
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut overlay = Overlay::new();
+        assert!(overlay.is_empty());
+        overlay.text(0, 0, "OK");
+        assert!(!overlay.is_empty());
+
+        overlay.clear();
+
+        assert!(overlay.is_empty());
+    }
+}
@@ -0,0 +1,457 @@
+//! Colors the display is shown in.
+//!
+//! XO-CHIP draws to two bit planes, so a pixel is one of four colors indexed
+//! by its plane bits: 0 for the background, 1 for the first plane only, 2
+//! for the second plane only and 3 for both. Single plane programs only ever
+//! use the first two.
+//!
+//! Presets say how accessible they are, measured rather than claimed: the
+//! WCAG contrast ratio of lit pixels against the background, and how far
+//! apart the four colors are, as CIE76 color difference, for normal vision
+//! and as simulated for protanopia and deuteranopia (Viénot, Brettel and
+//! Mollon, 1999).
+
+use super::graphics::{Framebuffer, Graphics, DISPLAY_COLS, DISPLAY_ROWS};
+use super::overlay::Overlay;
+use std::fmt;
+
+pub type Rgb = [u8; 3];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [Rgb; 4],
+}
+
+/// White on black, planes blending into shades of gray.
+pub const GRAYSCALE: Palette = Palette {
+    colors: [
+        [0x00, 0x00, 0x00],
+        [0xFF, 0xFF, 0xFF],
+        [0x55, 0x55, 0x55],
+        [0xAA, 0xAA, 0xAA],
+    ],
+};
+
+/// Default colors of the Octo IDE.
+pub const OCTO: Palette = Palette {
+    colors: [
+        [0x99, 0x66, 0x00],
+        [0xFF, 0xCC, 0x00],
+        [0xFF, 0x66, 0x00],
+        [0x66, 0x22, 0x00],
+    ],
+};
+
+/// White and saturated yellow and blue on black, every color at least 7:1
+/// against the background.
+pub const HIGH_CONTRAST: Palette = Palette {
+    colors: [
+        [0x00, 0x00, 0x00],
+        [0xFF, 0xFF, 0xFF],
+        [0xFF, 0xD7, 0x00],
+        [0x00, 0xBF, 0xFF],
+    ],
+};
+
+/// Orange, sky blue and bluish green of the Okabe-Ito palette on black, told
+/// apart with protanopia and deuteranopia.
+pub const COLOR_BLIND: Palette = Palette {
+    colors: [
+        [0x00, 0x00, 0x00],
+        [0xE6, 0x9F, 0x00],
+        [0x56, 0xB4, 0xE9],
+        [0x00, 0x9E, 0x73],
+    ],
+};
+
+/// Greens of an early handheld LCD.
+pub const LCD: Palette = Palette {
+    colors: [
+        [0x9B, 0xBC, 0x0F],
+        [0x0F, 0x38, 0x0F],
+        [0x8B, 0xAC, 0x0F],
+        [0x30, 0x62, 0x30],
+    ],
+};
+
+/// Smallest contrast ratio of a high contrast palette, WCAG level AAA.
+pub const HIGH_CONTRAST_RATIO: f64 = 7.0;
+/// Smallest difference of colors which are easy to tell apart at a glance.
+pub const DISTINGUISHABLE_DIFFERENCE: f64 = 20.0;
+/// Bytes of the display rendered by [`Palette::render_rgba`].
+pub const RGBA_SIZE: usize = DISPLAY_COLS * DISPLAY_ROWS * 4;
+
+/// Palette selectable by name.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub palette: Palette,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorVision {
+    Normal,
+    /// No red cones.
+    Protanopia,
+    /// No green cones.
+    Deuteranopia,
+}
+
+impl ColorVision {
+    pub const ALL: [ColorVision; 3] = [
+        ColorVision::Normal,
+        ColorVision::Protanopia,
+        ColorVision::Deuteranopia,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorVision::Normal => "normal",
+            ColorVision::Protanopia => "protanopia",
+            ColorVision::Deuteranopia => "deuteranopia",
+        }
+    }
+
+    /// Linear RGB `color` as seen with this vision.
+    fn simulate(self, [r, g, b]: [f64; 3]) -> [f64; 3] {
+        let (red, blue) = match self {
+            ColorVision::Normal => return [r, g, b],
+            ColorVision::Protanopia => (0.11238 * r + 0.88762 * g, 0.00401 * (r - g) + b),
+            ColorVision::Deuteranopia => (0.29275 * r + 0.70725 * g, 0.02234 * (g - r) + b),
+        };
+        [red, red, blue.clamp(0.0, 1.0)]
+    }
+}
+
+impl Palette {
+    pub const PRESETS: [Preset; 5] = [
+        Preset {
+            name: "grayscale",
+            description: "white on black, planes in shades of gray",
+            palette: GRAYSCALE,
+        },
+        Preset {
+            name: "octo",
+            description: "default colors of the Octo IDE",
+            palette: OCTO,
+        },
+        Preset {
+            name: "lcd",
+            description: "greens of an early handheld LCD",
+            palette: LCD,
+        },
+        Preset {
+            name: "high-contrast",
+            description: "white, yellow and blue on black",
+            palette: HIGH_CONTRAST,
+        },
+        Preset {
+            name: "color-blind",
+            description: "Okabe-Ito orange, sky blue and green on black",
+            palette: COLOR_BLIND,
+        },
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::PRESETS
+            .iter()
+            .find(|preset| preset.name == name)
+            .map(|preset| preset.palette)
+    }
+
+    /// Parse preset name or custom colors written as `RRGGBB,RRGGBB` for
+    /// single plane programs, the second plane using the foreground color,
+    /// or `RRGGBB,RRGGBB,RRGGBB,RRGGBB` for all four.
+    pub fn parse(s: &str) -> Option<Self> {
+        if let Some(palette) = Self::from_name(s) {
+            return Some(palette);
+        }
+        let colors = s
+            .split(',')
+            .map(|color| parse_rgb(color.trim()))
+            .collect::<Option<Vec<_>>>()?;
+        match colors[..] {
+            [background, foreground] => Some(Self {
+                colors: [background, foreground, foreground, foreground],
+            }),
+            [background, first, second, both] => Some(Self {
+                colors: [background, first, second, both],
+            }),
+            _ => None,
+        }
+    }
+
+    /// Color of a single plane pixel.
+    pub fn pixel_color(&self, on: bool) -> Rgb {
+        self.colors[on as usize]
+    }
+
+    /// Color of pixel at column `x` and row `y` of `screen`, from its
+    /// planes, for captures to keep every color of the display.
+    pub fn screen_color(&self, screen: &(impl Framebuffer + ?Sized), x: usize, y: usize) -> Rgb {
+        self.colors[screen.color_index(x, y)]
+    }
+
+    /// Smallest contrast ratio between the background and the other colors,
+    /// from 1 for none to 21 for black and white.
+    pub fn background_contrast(&self) -> f64 {
+        let background = relative_luminance(self.colors[0]);
+        self.colors[1..]
+            .iter()
+            .map(|&color| contrast_ratio(background, relative_luminance(color)))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Smallest difference between any two colors as seen with `vision`.
+    pub fn min_difference(&self, vision: ColorVision) -> f64 {
+        let lab = self
+            .colors
+            .map(|color| lab(vision.simulate(color.map(linear))));
+        let mut min = f64::INFINITY;
+        for (i, a) in lab.iter().enumerate() {
+            for b in &lab[i + 1..] {
+                let distance = a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f64>();
+                min = min.min(distance.sqrt());
+            }
+        }
+        min
+    }
+
+    pub fn is_high_contrast(&self) -> bool {
+        self.background_contrast() >= HIGH_CONTRAST_RATIO
+    }
+
+    /// Whether all four colors are distinguishable with protanopia and
+    /// deuteranopia as well as with normal vision.
+    pub fn is_color_blind_safe(&self) -> bool {
+        ColorVision::ALL
+            .iter()
+            .all(|&vision| self.min_difference(vision) >= DISTINGUISHABLE_DIFFERENCE)
+    }
+
+    /// Display with `overlay` on top as RGBA bytes, row by row, fully
+    /// opaque.
+    pub fn render_rgba(&self, graphics: &Graphics, overlay: Option<&Overlay>) -> Vec<u8> {
+        let mut rgba = vec![0; RGBA_SIZE];
+        self.render_rgba_into(graphics, overlay, &mut rgba);
+        rgba
+    }
+
+    /// [`Palette::render_rgba`] into `rgba`, e.g. the pixels of a texture
+    /// kept between frames, without allocating.
+    ///
+    /// # Panics
+    ///
+    /// If `rgba` isn't [`RGBA_SIZE`] bytes long.
+    pub fn render_rgba_into(
+        &self,
+        graphics: &Graphics,
+        overlay: Option<&Overlay>,
+        rgba: &mut [u8],
+    ) {
+        assert_eq!(rgba.len(), RGBA_SIZE);
+        for (i, pixel) in rgba.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (i % DISPLAY_COLS, i / DISPLAY_COLS);
+            let color = match overlay.and_then(|overlay| overlay.pixel(x, y)) {
+                Some(on) => self.pixel_color(on),
+                None => self.screen_color(graphics, x, y),
+            };
+            pixel[..3].copy_from_slice(&color);
+            pixel[3] = 0xFF;
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        GRAYSCALE
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}: {}", self.name, self.description)?;
+        let colors: Vec<_> = self
+            .palette
+            .colors
+            .iter()
+            .map(|[r, g, b]| format!("{:02X}{:02X}{:02X}", r, g, b))
+            .collect();
+        writeln!(f, "  colors: {}", colors.join(", "))?;
+        writeln!(
+            f,
+            "  contrast: {:.1}:1{}",
+            self.palette.background_contrast(),
+            if self.palette.is_high_contrast() {
+                ", high"
+            } else {
+                ""
+            }
+        )?;
+        write!(f, "  color difference:")?;
+        for vision in ColorVision::ALL {
+            write!(
+                f,
+                " {} {:.0}",
+                vision.name(),
+                self.palette.min_difference(vision)
+            )?;
+        }
+        if self.palette.is_color_blind_safe() {
+            write!(f, ", color blind safe")?;
+        }
+        Ok(())
+    }
+}
+
+/// sRGB component as linear light, 0 to 1.
+fn linear(component: u8) -> f64 {
+    let c = component as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn relative_luminance(color: Rgb) -> f64 {
+    let [r, g, b] = color.map(linear);
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+fn contrast_ratio(a: f64, b: f64) -> f64 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// CIELAB coordinates of linear RGB, D65 white.
+fn lab([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        const DELTA: f64 = 6.0 / 29.0;
+        if t > DELTA.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Parse color written as `RRGGBB` or `#RRGGBB`.
+fn parse_rgb(s: &str) -> Option<Rgb> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Palette::parse("octo"), Some(OCTO));
+        assert_eq!(
+            Palette::parse("#102030, 405060"),
+            Some(Palette {
+                colors: [
+                    [0x10, 0x20, 0x30],
+                    [0x40, 0x50, 0x60],
+                    [0x40, 0x50, 0x60],
+                    [0x40, 0x50, 0x60],
+                ],
+            })
+        );
+        assert_eq!(
+            Palette::parse("000000,111111,222222,333333").map(|p| p.colors[3]),
+            Some([0x33, 0x33, 0x33])
+        );
+        assert_eq!(Palette::parse("000000"), None);
+        assert_eq!(Palette::parse("000000,111111,222222"), None);
+        assert_eq!(Palette::parse("sepia"), None);
+    }
+
+    #[test]
+    fn test_presets() {
+        for preset in Palette::PRESETS {
+            assert_eq!(Palette::from_name(preset.name), Some(preset.palette));
+        }
+    }
+
+    #[test]
+    fn test_accessible_presets() {
+        assert!(HIGH_CONTRAST.is_high_contrast());
+        assert!(HIGH_CONTRAST.is_color_blind_safe());
+        assert!(COLOR_BLIND.is_color_blind_safe());
+        assert!(COLOR_BLIND.background_contrast() >= 4.5);
+
+        assert!(!OCTO.is_color_blind_safe());
+        assert!(!LCD.is_high_contrast());
+    }
+
+    #[test]
+    fn test_background_contrast() {
+        let black_on_white = Palette::parse("FFFFFF,000000").unwrap();
+        assert!((black_on_white.background_contrast() - 21.0).abs() < 1e-9);
+        let gray = Palette::parse("777777,777777").unwrap();
+        assert!((gray.background_contrast() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_min_difference() {
+        // Red and green differ in hue only with normal vision.
+        let palette = Palette::parse("000000,FFFFFF,D00000,009000").unwrap();
+
+        assert!(palette.min_difference(ColorVision::Normal) > 50.0);
+        assert!(palette.min_difference(ColorVision::Deuteranopia) < 20.0);
+    }
+
+    #[test]
+    fn test_render_rgba() {
+        let mut graphics = Graphics::new();
+        graphics.draw_sprite(1, 0, &[0x80]);
+
+        let rgba = OCTO.render_rgba(&graphics, None);
+
+        assert_eq!(rgba.len(), DISPLAY_COLS * DISPLAY_ROWS * 4);
+        assert_eq!(rgba[0..8], [0x99, 0x66, 0x00, 0xFF, 0xFF, 0xCC, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn test_render_rgba_into() {
+        let mut graphics = Graphics::new();
+        graphics.draw_sprite(1, 0, &[0x80]);
+        let mut rgba = vec![0xAB; RGBA_SIZE];
+
+        OCTO.render_rgba_into(&graphics, None, &mut rgba);
+
+        assert_eq!(rgba, OCTO.render_rgba(&graphics, None));
+    }
+
+    #[test]
+    fn test_render_rgba_overlay() {
+        let mut graphics = Graphics::new();
+        graphics.draw_sprite(0, 0, &[0xFF]);
+        graphics.draw_sprite(0, 20, &[0xFF]);
+        let mut overlay = Overlay::new();
+        overlay.text(1, 1, "I");
+
+        let rgba = OCTO.render_rgba(&graphics, Some(&overlay));
+        let color = |x: usize, y: usize| rgba[(y * DISPLAY_COLS + x) * 4..][..3].to_vec();
+
+        // Backdrop hides the program's pixels, text is in the foreground.
+        assert_eq!(color(0, 0), OCTO.colors[0]);
+        assert_eq!(color(1, 1), OCTO.colors[0]);
+        assert_eq!(color(3, 1), OCTO.colors[1]);
+        // Outside of the overlay the display shows through.
+        assert_eq!(color(7, 0), OCTO.colors[1]);
+        assert_eq!(color(0, 20), OCTO.colors[1]);
+    }
+}
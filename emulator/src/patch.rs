@@ -0,0 +1,279 @@
+//! Byte patches applied to a program before it is loaded, so fixes for
+//! classic programs can be shared without distributing modified binaries.
+//!
+//! Two formats are understood:
+//!
+//! * IPS, the common binary patch format, with plain and run-length encoded
+//!   records;
+//! * a patch list, a TOML-compatible text file with one `offset = "bytes"`
+//!   line per change, e.g. `0x2A = "60 05"`. Offsets are decimal or `0x`
+//!   prefixed hexadecimal, bytes are hexadecimal, spaces between them are
+//!   ignored. Lines starting with `#` are comments.
+//!
+//! Offsets in both formats are relative to the start of the program file.
+
+use super::memory::MAX_PROGRAM_SIZE;
+use std::fmt;
+
+const IPS_HEADER: &[u8] = b"PATCH";
+const IPS_FOOTER: &[u8] = b"EOF";
+
+/// Bytes written to the program starting at `offset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub offset: usize,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+    pub records: Vec<Record>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// IPS patch doesn't start with `PATCH`.
+    MissingHeader,
+    /// IPS patch ends in the middle of a record or without `EOF`.
+    Truncated,
+    /// Patch list line with its number, starting from 1.
+    InvalidLine(usize),
+    /// Record writes past the end of program memory.
+    OutOfRange { offset: usize, len: usize },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::MissingHeader => write!(f, "missing IPS header"),
+            PatchError::Truncated => write!(f, "truncated IPS patch"),
+            PatchError::InvalidLine(line) => write!(f, "invalid patch list line {}", line),
+            PatchError::OutOfRange { offset, len } => write!(
+                f,
+                "{} bytes at offset {:#X} don't fit in program memory",
+                len, offset
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl Patch {
+    /// Parse `bytes` as IPS if it has the IPS header, as patch list
+    /// otherwise.
+    pub fn parse(bytes: &[u8]) -> Result<Self, PatchError> {
+        if bytes.starts_with(IPS_HEADER) {
+            Self::parse_ips(bytes)
+        } else {
+            let text = std::str::from_utf8(bytes).map_err(|e| {
+                let line = bytes[..e.valid_up_to()].iter().filter(|&&b| b == b'\n');
+                PatchError::InvalidLine(line.count() + 1)
+            })?;
+            Self::parse_list(text)
+        }
+    }
+
+    pub fn parse_ips(bytes: &[u8]) -> Result<Self, PatchError> {
+        let mut rest = bytes
+            .strip_prefix(IPS_HEADER)
+            .ok_or(PatchError::MissingHeader)?;
+        let mut records = Vec::new();
+        loop {
+            if rest.starts_with(IPS_FOOTER) {
+                break;
+            }
+            let offset = take(&mut rest, 3)?;
+            let offset = usize::from_be_bytes([0, 0, 0, 0, 0, offset[0], offset[1], offset[2]]);
+            let size = take(&mut rest, 2)?;
+            let size = u16::from_be_bytes([size[0], size[1]]) as usize;
+            let data = if size == 0 {
+                let run = take(&mut rest, 2)?;
+                let run = u16::from_be_bytes([run[0], run[1]]) as usize;
+                let value = take(&mut rest, 1)?[0];
+                vec![value; run]
+            } else {
+                take(&mut rest, size)?.to_vec()
+            };
+            records.push(Record::checked(offset, data)?);
+        }
+        Ok(Self { records })
+    }
+
+    pub fn parse_list(text: &str) -> Result<Self, PatchError> {
+        let mut records = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = PatchError::InvalidLine(number + 1);
+            let (offset, data) = line.split_once('=').ok_or(invalid.clone())?;
+            let offset = parse_offset(offset.trim()).ok_or(invalid.clone())?;
+            let data = data
+                .trim()
+                .strip_prefix('"')
+                .and_then(|data| data.strip_suffix('"'))
+                .and_then(parse_hex_bytes)
+                .ok_or(invalid)?;
+            records.push(Record::checked(offset, data)?);
+        }
+        Ok(Self { records })
+    }
+
+    /// Apply records in order to `program`, growing it when a record writes
+    /// past its end.
+    pub fn apply(&self, program: &mut Vec<u8>) {
+        for record in &self.records {
+            let end = record.offset + record.data.len();
+            if program.len() < end {
+                program.resize(end, 0);
+            }
+            program[record.offset..end].copy_from_slice(&record.data);
+        }
+    }
+}
+
+impl Record {
+    fn checked(offset: usize, data: Vec<u8>) -> Result<Self, PatchError> {
+        if offset + data.len() > MAX_PROGRAM_SIZE {
+            return Err(PatchError::OutOfRange {
+                offset,
+                len: data.len(),
+            });
+        }
+        Ok(Self { offset, data })
+    }
+}
+
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], PatchError> {
+    if bytes.len() < n {
+        return Err(PatchError::Truncated);
+    }
+    let (taken, rest) = bytes.split_at(n);
+    *bytes = rest;
+    Ok(taken)
+}
+
+fn parse_offset(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn parse_hex_bytes(s: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ips() {
+        let mut ips = b"PATCH".to_vec();
+        ips.extend([0x00, 0x00, 0x02, 0x00, 0x02, 0x60, 0x05]);
+        ips.extend([0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x03, 0xAB]);
+        ips.extend(b"EOF");
+
+        let patch = Patch::parse(&ips).unwrap();
+
+        assert_eq!(
+            patch.records,
+            vec![
+                Record {
+                    offset: 2,
+                    data: vec![0x60, 0x05]
+                },
+                Record {
+                    offset: 0x10,
+                    data: vec![0xAB; 3]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_ips_missing_header() {
+        assert_eq!(Patch::parse_ips(b"EOF"), Err(PatchError::MissingHeader));
+    }
+
+    #[test]
+    fn test_parse_ips_truncated() {
+        assert_eq!(
+            Patch::parse_ips(b"PATCH\x00\x00\x02\x00\x04\x60"),
+            Err(PatchError::Truncated)
+        );
+        assert_eq!(Patch::parse_ips(b"PATCH"), Err(PatchError::Truncated));
+    }
+
+    #[test]
+    fn test_parse_ips_out_of_range() {
+        let mut ips = b"PATCH".to_vec();
+        ips.extend([0x00, 0x0D, 0xFF, 0x00, 0x02, 0x60, 0x05]);
+        ips.extend(b"EOF");
+
+        assert_eq!(
+            Patch::parse_ips(&ips),
+            Err(PatchError::OutOfRange {
+                offset: 0xDFF,
+                len: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let text = "# fix score\n\n0x2A = \"60 05\"\n16 = \"ABCD\"\n";
+
+        let patch = Patch::parse(text.as_bytes()).unwrap();
+
+        assert_eq!(
+            patch.records,
+            vec![
+                Record {
+                    offset: 0x2A,
+                    data: vec![0x60, 0x05]
+                },
+                Record {
+                    offset: 16,
+                    data: vec![0xAB, 0xCD]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_list_invalid_line() {
+        assert_eq!(
+            Patch::parse_list("0x2A = \"60 05\"\n0x2C = 6005\n"),
+            Err(PatchError::InvalidLine(2))
+        );
+        assert_eq!(
+            Patch::parse_list("0x2A = \"605\""),
+            Err(PatchError::InvalidLine(1))
+        );
+        assert_eq!(
+            Patch::parse_list("zz = \"60\""),
+            Err(PatchError::InvalidLine(1))
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let patch = Patch::parse_list("1 = \"AA\"\n3 = \"BB CC\"").unwrap();
+        let mut program = vec![0, 1, 2];
+
+        patch.apply(&mut program);
+
+        assert_eq!(program, vec![0, 0xAA, 2, 0xBB, 0xCC]);
+    }
+}
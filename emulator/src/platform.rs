@@ -0,0 +1,354 @@
+//! Descriptions of the CHIP-8 platforms programs are written for: what the
+//! display and memory look like, which instructions exist and how the
+//! ambiguous ones behave.
+
+use super::decoder;
+use super::memory::FontStyle;
+use super::vm::VM;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Group of instructions a platform understands, each family extending the
+/// previous one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OpcodeFamily {
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl OpcodeFamily {
+    pub fn name(self) -> &'static str {
+        match self {
+            OpcodeFamily::Chip8 => "chip8",
+            OpcodeFamily::SuperChip => "schip",
+            OpcodeFamily::XoChip => "xochip",
+        }
+    }
+
+    /// Instructions of the family.
+    pub fn opcodes(self) -> &'static str {
+        match self {
+            OpcodeFamily::Chip8 => "the 35 original instructions",
+            OpcodeFamily::SuperChip => "00Cn 00FB 00FC 00FD 00FE 00FF Dxy0 Fx30 Fx75 Fx85",
+            OpcodeFamily::XoChip => "00Dn 5xy2 5xy3 F000 nnnn Fn01 F002 Fx3A",
+        }
+    }
+}
+
+/// Pattern of instruction `word`, e.g. `8xy4`, and the family it's from,
+/// or `None` if no platform has it. `Dxy0` is taken for the SUPER-CHIP
+/// 16 x 16 sprite rather than an empty CHIP-8 one.
+pub fn opcode(word: u16) -> Option<(&'static str, OpcodeFamily)> {
+    decoder::lookup(word).map(|opcode| (opcode.pattern, opcode.family))
+}
+
+/// Behavior of instructions interpreters disagree on. The default has none
+/// of the quirks, which is how the VM behaves unless told otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8xy6` and `8xyE` shift `Vy` into `Vx` rather than `Vx` in place.
+    pub shift_uses_vy: bool,
+    /// `Fx55` and `Fx65` leave `I` past the last register accessed.
+    pub load_store_increments_i: bool,
+    /// `Bnnn` jumps to `nnn` + `Vx` where `x` is the top digit of `nnn`,
+    /// rather than + `V0`.
+    pub jump_uses_vx: bool,
+    /// `8xy1`, `8xy2` and `8xy3` reset `VF` to 0.
+    pub logic_resets_vf: bool,
+    /// Sprites are cut at the display edges rather than wrapping around.
+    pub clip_sprites: bool,
+    /// `Dxyn` waits for the next display refresh.
+    pub display_wait: bool,
+    /// `Fx0A` resumes as soon as a key is pressed rather than when it's
+    /// released.
+    pub get_key_on_press: bool,
+}
+
+impl Quirks {
+    /// Quirks by name, in the order they're declared.
+    pub fn list(&self) -> [(&'static str, bool); 7] {
+        [
+            ("shift_uses_vy", self.shift_uses_vy),
+            ("load_store_increments_i", self.load_store_increments_i),
+            ("jump_uses_vx", self.jump_uses_vx),
+            ("logic_resets_vf", self.logic_resets_vf),
+            ("clip_sprites", self.clip_sprites),
+            ("display_wait", self.display_wait),
+            ("get_key_on_press", self.get_key_on_press),
+        ]
+    }
+
+    /// Set quirk `name` as in [`Quirks::list`], false if there's none.
+    #[cfg(feature = "std")]
+    pub(crate) fn set(&mut self, name: &str, value: bool) -> bool {
+        let quirk = match name {
+            "shift_uses_vy" => &mut self.shift_uses_vy,
+            "load_store_increments_i" => &mut self.load_store_increments_i,
+            "jump_uses_vx" => &mut self.jump_uses_vx,
+            "logic_resets_vf" => &mut self.logic_resets_vf,
+            "clip_sprites" => &mut self.clip_sprites,
+            "display_wait" => &mut self.display_wait,
+            "get_key_on_press" => &mut self.get_key_on_press,
+            _ => return false,
+        };
+        *quirk = value;
+        true
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Platform {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Display sizes in pixels, width by height, low resolution first.
+    pub display_modes: &'static [(usize, usize)],
+    /// Bytes of addressable memory.
+    pub memory_size: usize,
+    pub opcode_families: &'static [OpcodeFamily],
+    pub quirks: Quirks,
+    pub font_style: FontStyle,
+}
+
+pub const CHIP8: Platform = Platform {
+    name: "chip8",
+    description: "CHIP-8 on the COSMAC VIP",
+    display_modes: &[(64, 32)],
+    memory_size: 4096,
+    opcode_families: &[OpcodeFamily::Chip8],
+    quirks: Quirks {
+        shift_uses_vy: true,
+        load_store_increments_i: true,
+        jump_uses_vx: false,
+        logic_resets_vf: true,
+        clip_sprites: true,
+        display_wait: true,
+        get_key_on_press: false,
+    },
+    font_style: FontStyle::Vip,
+};
+
+pub const CHIP48: Platform = Platform {
+    name: "chip48",
+    description: "CHIP-48 on the HP48 calculators",
+    display_modes: &[(64, 32)],
+    memory_size: 4096,
+    opcode_families: &[OpcodeFamily::Chip8],
+    quirks: Quirks {
+        shift_uses_vy: false,
+        load_store_increments_i: false,
+        jump_uses_vx: true,
+        logic_resets_vf: false,
+        clip_sprites: true,
+        display_wait: false,
+        get_key_on_press: false,
+    },
+    font_style: FontStyle::Chip48,
+};
+
+pub const SCHIP: Platform = Platform {
+    name: "schip",
+    description: "SUPER-CHIP 1.1 on the HP48 calculators",
+    display_modes: &[(64, 32), (128, 64)],
+    memory_size: 4096,
+    opcode_families: &[OpcodeFamily::Chip8, OpcodeFamily::SuperChip],
+    quirks: Quirks {
+        shift_uses_vy: false,
+        load_store_increments_i: false,
+        jump_uses_vx: true,
+        logic_resets_vf: false,
+        clip_sprites: true,
+        display_wait: false,
+        get_key_on_press: false,
+    },
+    font_style: FontStyle::Chip48,
+};
+
+pub const XOCHIP: Platform = Platform {
+    name: "xochip",
+    description: "XO-CHIP as implemented by Octo",
+    display_modes: &[(64, 32), (128, 64)],
+    memory_size: 65536,
+    opcode_families: &[
+        OpcodeFamily::Chip8,
+        OpcodeFamily::SuperChip,
+        OpcodeFamily::XoChip,
+    ],
+    quirks: Quirks {
+        shift_uses_vy: true,
+        load_store_increments_i: true,
+        jump_uses_vx: false,
+        logic_resets_vf: false,
+        clip_sprites: false,
+        display_wait: false,
+        get_key_on_press: false,
+    },
+    font_style: FontStyle::Octo,
+};
+
+impl Platform {
+    pub const ALL: [Platform; 4] = [CHIP8, CHIP48, SCHIP, XOCHIP];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|platform| platform.name == name)
+    }
+
+    /// Earliest platform with every instruction of `family`.
+    pub fn of_family(family: OpcodeFamily) -> Self {
+        match family {
+            OpcodeFamily::Chip8 => CHIP8,
+            OpcodeFamily::SuperChip => SCHIP,
+            OpcodeFamily::XoChip => XOCHIP,
+        }
+    }
+
+    /// Set up `vm` the way the platform is, as far as the VM is
+    /// configurable.
+    pub fn configure(&self, vm: &mut VM) {
+        vm.set_font_style(self.font_style);
+        vm.set_quirks(self.quirks);
+        vm.set_opcode_family(self.opcode_family());
+    }
+
+    /// Most recent family of instructions the platform has.
+    pub fn opcode_family(&self) -> OpcodeFamily {
+        self.opcode_families
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(OpcodeFamily::Chip8)
+    }
+}
+
+/// Interpreter a program is written for, for those who'd rather not pick
+/// quirks one by one. See [`VM::with_variant`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    #[default]
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 3] = [Variant::Chip8, Variant::SuperChip, Variant::XoChip];
+
+    /// Platform the variant runs as: quirks, font, display and
+    /// instructions.
+    pub fn platform(self) -> Platform {
+        match self {
+            Variant::Chip8 => CHIP8,
+            Variant::SuperChip => SCHIP,
+            Variant::XoChip => XOCHIP,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        self.platform().name
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|variant| variant.name() == name)
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}: {}", self.name, self.description)?;
+        let modes: Vec<_> = self
+            .display_modes
+            .iter()
+            .map(|(width, height)| format!("{}x{}", width, height))
+            .collect();
+        writeln!(f, "  display: {}", modes.join(", "))?;
+        writeln!(f, "  memory: {} bytes", self.memory_size)?;
+        writeln!(f, "  font: {}", self.font_style.name())?;
+        writeln!(f, "  opcodes:")?;
+        for family in self.opcode_families {
+            writeln!(f, "    {}: {}", family.name(), family.opcodes())?;
+        }
+        write!(f, "  quirks:")?;
+        for (name, enabled) in self.quirks.list() {
+            write!(f, "\n    {}: {}", name, if enabled { "yes" } else { "no" })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        for platform in Platform::ALL {
+            assert_eq!(Platform::from_name(platform.name), Some(platform));
+        }
+        assert_eq!(Platform::from_name("megachip"), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let text = SCHIP.to_string();
+
+        assert!(text.starts_with("schip: SUPER-CHIP 1.1 on the HP48 calculators\n"));
+        assert!(text.contains("\n  display: 64x32, 128x64\n"));
+        assert!(text.contains("\n    schip: 00Cn 00FB"));
+        assert!(text.contains("\n    display_wait: no\n"));
+        assert!(text.ends_with("\n    get_key_on_press: no"));
+    }
+
+    #[test]
+    fn test_opcode() {
+        assert_eq!(opcode(0x8124), Some(("8xy4", OpcodeFamily::Chip8)));
+        assert_eq!(opcode(0x0123), Some(("0nnn", OpcodeFamily::Chip8)));
+        assert_eq!(opcode(0x00FF), Some(("00FF", OpcodeFamily::SuperChip)));
+        assert_eq!(opcode(0xD120), Some(("Dxy0", OpcodeFamily::SuperChip)));
+        assert_eq!(opcode(0xD125), Some(("Dxyn", OpcodeFamily::Chip8)));
+        assert_eq!(opcode(0xF375), Some(("Fx75", OpcodeFamily::SuperChip)));
+        assert_eq!(opcode(0xF000), Some(("F000", OpcodeFamily::XoChip)));
+        assert_eq!(opcode(0x5124), None);
+        assert_eq!(opcode(0xFFFF), None);
+    }
+
+    #[test]
+    fn test_of_family() {
+        for platform in Platform::ALL {
+            let family = *platform.opcode_families.last().unwrap();
+            if platform != CHIP48 {
+                assert_eq!(Platform::of_family(family), platform);
+            }
+        }
+    }
+
+    #[test]
+    fn test_variant() {
+        for variant in Variant::ALL {
+            assert_eq!(Variant::from_name(variant.name()), Some(variant));
+        }
+        assert_eq!(Variant::SuperChip.platform(), SCHIP);
+        assert_eq!(
+            Variant::XoChip.platform().opcode_family(),
+            OpcodeFamily::XoChip
+        );
+        assert_eq!(CHIP48.opcode_family(), OpcodeFamily::Chip8);
+    }
+
+    #[test]
+    fn test_configure() {
+        let mut vm = VM::new();
+
+        CHIP8.configure(&mut vm);
+
+        let digit_1 = &FontStyle::Vip.sprites()[5..10];
+        assert_eq!(vm.memory(5..10).unwrap(), digit_1);
+        assert_eq!(vm.quirks(), CHIP8.quirks);
+    }
+}
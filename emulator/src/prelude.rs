@@ -0,0 +1,34 @@
+//! Supported surface of the crate, for frontends to import at once:
+//!
+//! ```
+//! use chip_8_emulator::prelude::*;
+//!
+//! let mut vm = VM::builder().quirks(Quirks::default()).build();
+//! vm.load_program(&[0x12, 0x00]).unwrap();
+//! vm.run_frame().unwrap();
+//! ```
+//!
+//! Items here only change in breaking releases. Modules hidden from the
+//! documentation, like the layout of memory, registers and the stack, are
+//! internals which may change in any release; their types reachable from
+//! the VM are exported here instead.
+
+pub use crate::effects::{Effects, StepInfo};
+pub use crate::graphics::{Framebuffer, Resolution};
+pub use crate::input::{Input, InvalidKey, Key};
+pub use crate::instruction::{DecodeError, Instruction};
+pub use crate::memory::{FontStyle, LoadError, OutOfRange};
+pub use crate::observer::{ObserverId, VmObserver};
+#[cfg(feature = "std")]
+pub use crate::palette::Palette;
+pub use crate::platform::{OpcodeFamily, Platform, Quirks, Variant};
+pub use crate::registers::Registers;
+pub use crate::rng::RngSource;
+pub use crate::rpl::RplFlags;
+#[cfg(feature = "std")]
+pub use crate::shared::SharedVm;
+pub use crate::snapshot::StateSnapshot;
+pub use crate::stack::StackError;
+#[cfg(feature = "std")]
+pub use crate::storage::Storage;
+pub use crate::vm::{FrameResult, RunResult, RunStatus, TimerPolicy, VmBuilder, VmError, VM};
@@ -0,0 +1,393 @@
+//! Builder of programs in Rust, for focused emulator tests and examples.
+//!
+//! ```
+//! use chip_8_emulator::program::{Program, V0, V1};
+//!
+//! let program = Program::new()
+//!     .ld(V0, 0)
+//!     .ld(V1, 0)
+//!     .ld_i("sprite")
+//!     .label("loop")
+//!     .drw(V0, V1, 2)
+//!     .jp("loop")
+//!     .label("sprite")
+//!     .data(&[0xF0, 0x90])
+//!     .build();
+//! assert_eq!(program.len(), 12);
+//! ```
+
+use super::instruction::{Instruction, Reg};
+use super::memory::PROGRAM_START_LOCATION;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+pub const V0: Reg = 0x0;
+pub const V1: Reg = 0x1;
+pub const V2: Reg = 0x2;
+pub const V3: Reg = 0x3;
+pub const V4: Reg = 0x4;
+pub const V5: Reg = 0x5;
+pub const V6: Reg = 0x6;
+pub const V7: Reg = 0x7;
+pub const V8: Reg = 0x8;
+pub const V9: Reg = 0x9;
+pub const VA: Reg = 0xA;
+pub const VB: Reg = 0xB;
+pub const VC: Reg = 0xC;
+pub const VD: Reg = 0xD;
+pub const VE: Reg = 0xE;
+pub const VF: Reg = 0xF;
+
+/// Address operand: a number or a label defined anywhere in the program.
+pub enum Target {
+    Address(u16),
+    Label(String),
+}
+
+impl From<u16> for Target {
+    fn from(address: u16) -> Self {
+        Target::Address(address)
+    }
+}
+
+impl From<&str> for Target {
+    fn from(label: &str) -> Self {
+        Target::Label(label.to_string())
+    }
+}
+
+enum Piece {
+    Bytes(Vec<u8>),
+    /// Instruction with an address resolved when the program is built.
+    WithTarget(fn(u16) -> Instruction, String),
+}
+
+#[derive(Default)]
+pub struct Program {
+    pieces: Vec<Piece>,
+    labels: BTreeMap<String, u16>,
+    len: usize,
+}
+
+impl Program {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Name the address of the next instruction or data.
+    pub fn label(mut self, name: &str) -> Self {
+        let address = (PROGRAM_START_LOCATION + self.len) as u16;
+        let previous = self.labels.insert(name.to_string(), address);
+        assert!(previous.is_none(), "label {} is defined twice", name);
+        self
+    }
+
+    pub fn instruction(mut self, instruction: Instruction) -> Self {
+        let bytes = instruction.to_bytes();
+        self.len += bytes.len();
+        self.pieces.push(Piece::Bytes(bytes));
+        self
+    }
+
+    /// Raw bytes, e.g. sprites.
+    pub fn data(mut self, bytes: &[u8]) -> Self {
+        self.len += bytes.len();
+        self.pieces.push(Piece::Bytes(bytes.to_vec()));
+        self
+    }
+
+    fn with_target(mut self, make: fn(u16) -> Instruction, target: Target) -> Self {
+        match target {
+            Target::Address(address) => self.instruction(make(address)),
+            Target::Label(label) => {
+                self.len += 2;
+                self.pieces.push(Piece::WithTarget(make, label));
+                self
+            }
+        }
+    }
+
+    /// Program bytes.
+    ///
+    /// Panics if a used label is never defined.
+    pub fn build(self) -> Vec<u8> {
+        let mut program = Vec::with_capacity(self.len);
+        for piece in self.pieces {
+            match piece {
+                Piece::Bytes(bytes) => program.extend(bytes),
+                Piece::WithTarget(make, label) => {
+                    let address = *self
+                        .labels
+                        .get(&label)
+                        .unwrap_or_else(|| panic!("label {} is not defined", label));
+                    program.extend(make(address).to_bytes());
+                }
+            }
+        }
+        program
+    }
+
+    pub fn cls(self) -> Self {
+        self.instruction(Instruction::Cls)
+    }
+
+    pub fn ret(self) -> Self {
+        self.instruction(Instruction::Ret)
+    }
+
+    /// SUPER-CHIP low resolution.
+    pub fn low(self) -> Self {
+        self.instruction(Instruction::Low)
+    }
+
+    /// SUPER-CHIP high resolution.
+    pub fn high(self) -> Self {
+        self.instruction(Instruction::High)
+    }
+
+    /// SUPER-CHIP scroll down `n` rows.
+    pub fn scd(self, n: u8) -> Self {
+        self.instruction(Instruction::Scd(n))
+    }
+
+    /// SUPER-CHIP scroll right 4 columns.
+    pub fn scr(self) -> Self {
+        self.instruction(Instruction::Scr)
+    }
+
+    /// SUPER-CHIP scroll left 4 columns.
+    pub fn scl(self) -> Self {
+        self.instruction(Instruction::Scl)
+    }
+
+    pub fn jp(self, target: impl Into<Target>) -> Self {
+        self.with_target(Instruction::Jp, target.into())
+    }
+
+    pub fn call(self, target: impl Into<Target>) -> Self {
+        self.with_target(Instruction::Call, target.into())
+    }
+
+    /// Skip next instruction if `Vx` == `kk`.
+    pub fn se(self, x: Reg, kk: u8) -> Self {
+        self.instruction(Instruction::SeByte(x, kk))
+    }
+
+    /// Skip next instruction if `Vx` != `kk`.
+    pub fn sne(self, x: Reg, kk: u8) -> Self {
+        self.instruction(Instruction::SneByte(x, kk))
+    }
+
+    /// Skip next instruction if `Vx` == `Vy`.
+    pub fn se_v(self, x: Reg, y: Reg) -> Self {
+        self.instruction(Instruction::SeReg(x, y))
+    }
+
+    /// Skip next instruction if `Vx` != `Vy`.
+    pub fn sne_v(self, x: Reg, y: Reg) -> Self {
+        self.instruction(Instruction::SneReg(x, y))
+    }
+
+    /// `Vx` = `kk`.
+    pub fn ld(self, x: Reg, kk: u8) -> Self {
+        self.instruction(Instruction::LdByte(x, kk))
+    }
+
+    /// `Vx` = `Vy`.
+    pub fn ld_v(self, x: Reg, y: Reg) -> Self {
+        self.instruction(Instruction::LdReg(x, y))
+    }
+
+    /// `Vx` += `kk`.
+    pub fn add(self, x: Reg, kk: u8) -> Self {
+        self.instruction(Instruction::AddByte(x, kk))
+    }
+
+    /// `Vx` += `Vy`.
+    pub fn add_v(self, x: Reg, y: Reg) -> Self {
+        self.instruction(Instruction::AddReg(x, y))
+    }
+
+    pub fn or(self, x: Reg, y: Reg) -> Self {
+        self.instruction(Instruction::Or(x, y))
+    }
+
+    pub fn and(self, x: Reg, y: Reg) -> Self {
+        self.instruction(Instruction::And(x, y))
+    }
+
+    pub fn xor(self, x: Reg, y: Reg) -> Self {
+        self.instruction(Instruction::Xor(x, y))
+    }
+
+    pub fn sub(self, x: Reg, y: Reg) -> Self {
+        self.instruction(Instruction::Sub(x, y))
+    }
+
+    pub fn subn(self, x: Reg, y: Reg) -> Self {
+        self.instruction(Instruction::Subn(x, y))
+    }
+
+    pub fn shift_right(self, x: Reg) -> Self {
+        self.instruction(Instruction::Shr(x, 0))
+    }
+
+    pub fn shift_left(self, x: Reg) -> Self {
+        self.instruction(Instruction::Shl(x, 0))
+    }
+
+    pub fn ld_i(self, target: impl Into<Target>) -> Self {
+        self.with_target(Instruction::LdI, target.into())
+    }
+
+    pub fn jp_v0(self, target: impl Into<Target>) -> Self {
+        self.with_target(Instruction::JpV0, target.into())
+    }
+
+    pub fn rnd(self, x: Reg, mask: u8) -> Self {
+        self.instruction(Instruction::Rnd(x, mask))
+    }
+
+    pub fn drw(self, x: Reg, y: Reg, n: u8) -> Self {
+        self.instruction(Instruction::Drw(x, y, n))
+    }
+
+    pub fn skp(self, x: Reg) -> Self {
+        self.instruction(Instruction::Skp(x))
+    }
+
+    pub fn sknp(self, x: Reg) -> Self {
+        self.instruction(Instruction::Sknp(x))
+    }
+
+    /// `Vx` = delay timer.
+    pub fn ld_vx_dt(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdVxDt(x))
+    }
+
+    /// Wait for a key press, store its value in `Vx`.
+    pub fn ld_vx_k(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdVxK(x))
+    }
+
+    /// Delay timer = `Vx`.
+    pub fn ld_dt(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdDtVx(x))
+    }
+
+    /// Sound timer = `Vx`.
+    pub fn ld_st(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdStVx(x))
+    }
+
+    /// `I` += `Vx`.
+    pub fn add_i(self, x: Reg) -> Self {
+        self.instruction(Instruction::AddIVx(x))
+    }
+
+    /// `I` = location of the digit sprite for `Vx`.
+    pub fn ld_f(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdFVx(x))
+    }
+
+    /// SUPER-CHIP `I` = location of the big digit sprite for `Vx`.
+    pub fn ld_hf(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdHfVx(x))
+    }
+
+    /// Store BCD representation of `Vx` at `I`.
+    pub fn ld_b(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdBVx(x))
+    }
+
+    /// Store `V0` to `Vx` at `I`.
+    pub fn ld_i_vx(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdMemVx(x))
+    }
+
+    /// Read `V0` to `Vx` from `I`.
+    pub fn ld_vx_i(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdVxMem(x))
+    }
+
+    /// SUPER-CHIP store `V0` to `Vx` in the RPL flags.
+    pub fn ld_r_vx(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdRVx(x))
+    }
+
+    /// SUPER-CHIP read `V0` to `Vx` from the RPL flags.
+    pub fn ld_vx_r(self, x: Reg) -> Self {
+        self.instruction(Instruction::LdVxR(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VM;
+
+    #[test]
+    fn test_build() {
+        let program = Program::new()
+            .cls()
+            .ld(V1, 0x20)
+            .ld_i(0x300)
+            .drw(V1, V2, 5)
+            .ld_st(VF)
+            .build();
+
+        assert_eq!(
+            program,
+            [0x00, 0xE0, 0x61, 0x20, 0xA3, 0x00, 0xD1, 0x25, 0xFF, 0x18]
+        );
+    }
+
+    #[test]
+    fn test_labels() {
+        let program = Program::new()
+            .label("start")
+            .call("subroutine")
+            .jp("start")
+            .label("subroutine")
+            .ld_i("sprite")
+            .ret()
+            .label("sprite")
+            .data(&[0x80])
+            .build();
+
+        assert_eq!(
+            program,
+            [0x22, 0x04, 0x12, 0x00, 0xA2, 0x08, 0x00, 0xEE, 0x80]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "label nowhere is not defined")]
+    fn test_undefined_label() {
+        Program::new().jp("nowhere").build();
+    }
+
+    #[test]
+    #[should_panic(expected = "label start is defined twice")]
+    fn test_label_defined_twice() {
+        Program::new().label("start").cls().label("start");
+    }
+
+    #[test]
+    fn test_run_built_program() {
+        let program = Program::new()
+            .ld(V0, 5)
+            .ld(V1, 7)
+            .add_v(V0, V1)
+            .label("end")
+            .jp("end")
+            .build();
+        let mut vm = VM::new();
+        vm.load_program(&program).unwrap();
+
+        vm.run_until_halted(None).unwrap();
+
+        assert_eq!(vm.snapshot().registers().v[0], 12);
+    }
+}
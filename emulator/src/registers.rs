@@ -1,6 +1,6 @@
 pub const V_REGISTERS_SIZE: usize = 16;
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Registers {
     pub v: [u8; V_REGISTERS_SIZE],
     pub i: u16,
@@ -0,0 +1,67 @@
+//! Random number generators for `Cxkk`. The VM uses `SmallRng` seeded with
+//! 0 unless told otherwise, so runs are reproducible and need no entropy
+//! source; frontends wanting different numbers every run can pass one seeded
+//! from OS entropy, e.g. `SmallRng::from_entropy()` with the `entropy`
+//! feature.
+
+use alloc::boxed::Box;
+use rand::rngs::SmallRng;
+use rand::{Error, RngCore, SeedableRng};
+
+/// Random number generator the VM can use. Any `RngCore` which can be
+/// cloned is one; cloning lets snapshots restore the sequence of numbers
+/// along with the rest of the state.
+pub trait RngSource: RngCore + Send + Sync {
+    fn clone_box(&self) -> Box<dyn RngSource>;
+}
+
+impl<R: RngCore + Clone + Send + Sync + 'static> RngSource for R {
+    fn clone_box(&self) -> Box<dyn RngSource> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn RngSource> {
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+/// Generator of a VM: the default one inline, so a new VM doesn't
+/// allocate, or any other boxed.
+#[derive(Clone)]
+pub(crate) enum Generator {
+    Seeded(SmallRng),
+    Custom(Box<dyn RngSource>),
+}
+
+impl Generator {
+    pub fn seeded(seed: u64) -> Self {
+        Generator::Seeded(SmallRng::seed_from_u64(seed))
+    }
+
+    fn rng(&mut self) -> &mut dyn RngCore {
+        match self {
+            Generator::Seeded(rng) => rng,
+            Generator::Custom(rng) => &mut **rng,
+        }
+    }
+}
+
+impl RngCore for Generator {
+    fn next_u32(&mut self) -> u32 {
+        self.rng().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng().try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,139 @@
+//! Record of random numbers the program asked for, so a replay diverging
+//! from its recording can be pinned to a difference in RNG consumption.
+
+use alloc::collections::VecDeque;
+
+/// `RND` instruction as it was executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RndCall {
+    /// Frame, counted in timer ticks, of the call.
+    pub frame: u64,
+    pub pc: u16,
+    pub mask: u8,
+    /// Random byte ANDed with the mask, as stored in `Vx`.
+    pub value: u8,
+}
+
+/// Last `RND` calls.
+pub struct RngLog {
+    capacity: usize,
+    frame: u64,
+    calls: VecDeque<RndCall>,
+}
+
+impl RngLog {
+    /// Create log keeping up to `capacity` last calls.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            capacity,
+            frame: 0,
+            calls: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, pc: u16, mask: u8, value: u8) {
+        if self.calls.len() == self.capacity {
+            self.calls.pop_front();
+        }
+        self.calls.push_back(RndCall {
+            frame: self.frame,
+            pc,
+            mask,
+            value,
+        });
+    }
+
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Calls, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &RndCall> {
+        self.calls.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Bytes allocated on the heap for the calls.
+    pub fn heap_size(&self) -> usize {
+        self.calls.capacity() * size_of::<RndCall>()
+    }
+}
+
+/// First call where `a` and `b` differ, with its position, or the first
+/// call only one of them has.
+pub fn first_difference<'a>(
+    a: &'a RngLog,
+    b: &'a RngLog,
+) -> Option<(usize, Option<&'a RndCall>, Option<&'a RndCall>)> {
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| (i, a.calls.get(i), b.calls.get(i)))
+        .find(|(_, a, b)| a != b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut log = RngLog::new(2);
+
+        log.record(0x200, 0xFF, 0x12);
+        log.next_frame();
+        log.record(0x202, 0x0F, 0x03);
+        log.record(0x204, 0x01, 0x01);
+
+        let calls = log.iter().copied().collect::<Vec<_>>();
+        assert_eq!(
+            calls,
+            [
+                RndCall {
+                    frame: 1,
+                    pc: 0x202,
+                    mask: 0x0F,
+                    value: 0x03,
+                },
+                RndCall {
+                    frame: 1,
+                    pc: 0x204,
+                    mask: 0x01,
+                    value: 0x01,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_first_difference() {
+        let mut a = RngLog::new(8);
+        let mut b = RngLog::new(8);
+        a.record(0x200, 0xFF, 1);
+        b.record(0x200, 0xFF, 1);
+        assert_eq!(first_difference(&a, &b), None);
+
+        a.next_frame();
+        a.record(0x202, 0xFF, 2);
+        b.record(0x202, 0xFF, 2);
+
+        let (i, in_a, in_b) = first_difference(&a, &b).unwrap();
+        assert_eq!(i, 1);
+        assert_eq!(in_a.unwrap().frame, 1);
+        assert_eq!(in_b.unwrap().frame, 0);
+
+        b.next_frame();
+        b.calls.pop_back();
+        b.record(0x202, 0xFF, 2);
+        a.record(0x206, 0x0F, 4);
+
+        assert_eq!(first_difference(&a, &b), Some((2, a.calls.get(2), None)));
+    }
+}
@@ -0,0 +1,130 @@
+//! SUPER-CHIP RPL user flags, named after the HP48 calculator registers
+//! they were kept in: bytes `Fx75` stores registers to and `Fx85` reads
+//! them back from, which games use to keep high scores between runs. They
+//! live in memory, and also in a [`Storage`] when given one, for them to
+//! outlive the VM.
+
+#[cfg(feature = "std")]
+use super::storage::Storage;
+#[cfg(feature = "std")]
+use alloc::{string::String, sync::Arc};
+#[cfg(feature = "std")]
+use std::io;
+
+/// Error storing flags: I/O with the `std` feature, none without it since
+/// there's no [`Storage`] then.
+#[cfg(feature = "std")]
+pub type StoreError = io::Error;
+#[cfg(not(feature = "std"))]
+pub type StoreError = core::convert::Infallible;
+
+/// Flags SUPER-CHIP has, `V0` through `V7` fitting in them.
+pub const RPL_FLAGS: usize = 8;
+
+#[derive(Clone, Default)]
+pub struct RplFlags {
+    flags: [u8; RPL_FLAGS],
+    /// Where flags are stored when they change, and under which key.
+    #[cfg(feature = "std")]
+    storage: Option<(Arc<dyn Storage>, String)>,
+}
+
+impl RplFlags {
+    /// Flags kept in memory only, all zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flags kept in `storage` under `key`, starting from the ones stored
+    /// there, all zero if there are none.
+    #[cfg(feature = "std")]
+    pub fn with_storage(storage: Arc<dyn Storage>, key: impl Into<String>) -> io::Result<Self> {
+        let key = key.into();
+        let mut flags = [0; RPL_FLAGS];
+        if let Some(stored) = storage.load(&key)? {
+            let len = stored.len().min(RPL_FLAGS);
+            flags[..len].copy_from_slice(&stored[..len]);
+        }
+        Ok(Self {
+            flags,
+            storage: Some((storage, key)),
+        })
+    }
+
+    pub fn flags(&self) -> &[u8; RPL_FLAGS] {
+        &self.flags
+    }
+
+    /// Set the first flags to `values`, at most [`RPL_FLAGS`] of them. The
+    /// flags are stored first, and left as they were if that fails.
+    pub fn set(&mut self, values: &[u8]) -> Result<(), StoreError> {
+        let mut flags = self.flags;
+        let len = values.len().min(RPL_FLAGS);
+        flags[..len].copy_from_slice(&values[..len]);
+        #[cfg(feature = "std")]
+        if let Some((storage, key)) = &self.storage {
+            storage.store(key, &flags)?;
+        }
+        self.flags = flags;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    /// Storage refusing every write.
+    struct ReadOnly;
+
+    impl Storage for ReadOnly {
+        fn load(&self, _key: &str) -> io::Result<Option<Vec<u8>>> {
+            Ok(None)
+        }
+
+        fn store(&self, _key: &str, _data: &[u8]) -> io::Result<()> {
+            Err(io::ErrorKind::PermissionDenied.into())
+        }
+
+        fn remove(&self, _key: &str) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_set() {
+        let mut flags = RplFlags::new();
+
+        flags.set(&[1, 2, 3]).unwrap();
+        flags.set(&[4; 10]).unwrap();
+        flags.set(&[5]).unwrap();
+
+        assert_eq!(flags.flags(), &[5, 4, 4, 4, 4, 4, 4, 4]);
+    }
+
+    #[test]
+    fn test_storage() {
+        let storage = Arc::new(MemoryStorage::new());
+        storage.store("flags/pong.rpl", &[9, 8]).unwrap();
+
+        let mut flags = RplFlags::with_storage(storage.clone(), "flags/pong.rpl").unwrap();
+        assert_eq!(flags.flags(), &[9, 8, 0, 0, 0, 0, 0, 0]);
+        flags.set(&[1, 2, 3]).unwrap();
+
+        assert_eq!(
+            storage.load("flags/pong.rpl").unwrap(),
+            Some(vec![1, 2, 3, 0, 0, 0, 0, 0])
+        );
+        let flags = RplFlags::with_storage(storage, "flags/pong.rpl").unwrap();
+        assert_eq!(flags.flags(), &[1, 2, 3, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_storage_error() {
+        let mut flags = RplFlags::with_storage(Arc::new(ReadOnly), "flags").unwrap();
+
+        assert!(flags.set(&[1]).is_err());
+        assert_eq!(flags.flags(), &[0; RPL_FLAGS]);
+    }
+}
@@ -0,0 +1,229 @@
+//! Hand-written, frame-perfect keypad input for headless runs and tests.
+//!
+//! One event per line, `frame N: press K` or `frame N: release K`, where `N`
+//! is the number of frames run before the event takes effect and `K` is the
+//! hexadecimal keypad key. Lines starting with `#` are comments:
+//!
+//! ```text
+//! # Jump over the first obstacle.
+//! frame 120: press 5
+//! frame 130: release 5
+//! ```
+//!
+//! The keypad holds one key at a time, so pressing a key releases the
+//! previous one.
+
+use super::input::Input;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub frame: u64,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputScript {
+    /// Events ordered by frame.
+    events: Vec<InputEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    /// Line with its number, starting from 1.
+    InvalidLine(usize),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScriptError::InvalidLine(line) => write!(f, "invalid input script line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl InputScript {
+    pub fn parse(text: &str) -> Result<Self, ScriptError> {
+        let mut events = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            events.push(parse_event(line).ok_or(ScriptError::InvalidLine(i + 1))?);
+        }
+        // Stable, so events of the same frame keep their order.
+        events.sort_by_key(|event| event.frame);
+        Ok(Self { events })
+    }
+
+    pub fn events(&self) -> &[InputEvent] {
+        &self.events
+    }
+
+    /// Keypad state while running `frame`, with the events up to it applied.
+    pub fn input_at(&self, frame: u64) -> Input {
+        let mut player = self.player();
+        player.input_for(frame);
+        player.input
+    }
+
+    pub fn player(&self) -> Player<'_> {
+        Player {
+            events: &self.events,
+            input: Input::new(),
+        }
+    }
+}
+
+fn parse_event(line: &str) -> Option<InputEvent> {
+    let (frame, action) = line.strip_prefix("frame")?.split_once(':')?;
+    let frame = frame.trim().parse().ok()?;
+    let mut words = action.split_whitespace();
+    let pressed = match words.next()? {
+        "press" => true,
+        "release" => false,
+        _ => return None,
+    };
+    let key = words.next()?;
+    let key = u8::from_str_radix(key.strip_prefix("0x").unwrap_or(key), 16).ok()?;
+    if key > 0xF || words.next().is_some() {
+        return None;
+    }
+    Some(InputEvent {
+        frame,
+        key,
+        pressed,
+    })
+}
+
+/// Keypad state of a script as frames are run.
+pub struct Player<'a> {
+    /// Events not applied yet.
+    events: &'a [InputEvent],
+    input: Input,
+}
+
+impl Player<'_> {
+    /// Apply events up to `frame`, returning the new keypad state if it
+    /// changed. Call it before running every frame, with frames increasing.
+    pub fn input_for(&mut self, frame: u64) -> Option<Input> {
+        let before = self.input;
+        while let Some((event, rest)) = self.events.split_first() {
+            if event.frame > frame {
+                break;
+            }
+            if event.pressed {
+                self.input.press(event.key);
+            } else {
+                self.input.release(event.key);
+            }
+            self.events = rest;
+        }
+        (self.input != before).then_some(self.input)
+    }
+
+    /// Whether all events were applied.
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let script = InputScript::parse(
+            "# comment\n\nframe 130: release 5\nframe 120: press 5\nframe 120: press 0xA\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            script.events(),
+            [
+                InputEvent {
+                    frame: 120,
+                    key: 5,
+                    pressed: true,
+                },
+                InputEvent {
+                    frame: 120,
+                    key: 0xA,
+                    pressed: true,
+                },
+                InputEvent {
+                    frame: 130,
+                    key: 5,
+                    pressed: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for line in [
+            "frame 1 press 5",
+            "frame x: press 5",
+            "frame 1: hold 5",
+            "frame 1: press 10",
+            "frame 1: press",
+            "frame 1: press 5 6",
+            "1: press 5",
+        ] {
+            let text = format!("frame 0: press 1\n{}", line);
+            assert_eq!(
+                InputScript::parse(&text),
+                Err(ScriptError::InvalidLine(2)),
+                "{}",
+                line
+            );
+        }
+    }
+
+    fn pressed(input: Option<Input>) -> Option<Vec<u8>> {
+        input.map(|input| (0..16).filter(|&key| input.is_pressed(key)).collect())
+    }
+
+    #[test]
+    fn test_player() {
+        let script = InputScript::parse(
+            "frame 2: press 5\nframe 4: press 6\nframe 5: release 5\nframe 6: release 6",
+        )
+        .unwrap();
+        let mut player = script.player();
+
+        assert_eq!(pressed(player.input_for(0)), None);
+        assert_eq!(pressed(player.input_for(2)), Some(vec![5]));
+        assert_eq!(pressed(player.input_for(3)), None);
+        assert_eq!(pressed(player.input_for(4)), Some(vec![5, 6]));
+        assert_eq!(pressed(player.input_for(5)), Some(vec![6]));
+        assert!(!player.is_finished());
+        assert_eq!(pressed(player.input_for(6)), Some(vec![]));
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn test_player_skipped_frames() {
+        let script = InputScript::parse("frame 2: press 5\nframe 3: release 5").unwrap();
+        let mut player = script.player();
+
+        assert_eq!(pressed(player.input_for(10)), None);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn test_input_at() {
+        let script =
+            InputScript::parse("frame 2: press 5\nframe 4: press 6\nframe 5: release 5").unwrap();
+
+        assert_eq!(pressed(Some(script.input_at(1))), Some(vec![]));
+        assert_eq!(pressed(Some(script.input_at(4))), Some(vec![5, 6]));
+        assert_eq!(pressed(Some(script.input_at(9))), Some(vec![6]));
+    }
+}
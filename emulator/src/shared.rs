@@ -0,0 +1,154 @@
+//! Sharing a VM between threads, e.g. when a frontend renders on one thread
+//! and emulates on another.
+//!
+//! Lock once per frame rather than per instruction: run the whole frame in a
+//! single [`SharedVm::with`] call on the emulation thread and copy the display
+//! out with [`SharedVm::display`] on the render thread. Holding the lock while
+//! presenting or sleeping stalls the other side.
+
+use super::{
+    graphics::{Framebuffer, Graphics, SchipGraphics},
+    vm::VM,
+};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Cloneable handle to a VM behind a mutex.
+#[derive(Clone)]
+pub struct SharedVm {
+    vm: Arc<Mutex<VM>>,
+}
+
+impl SharedVm {
+    pub fn new(vm: VM) -> Self {
+        Self {
+            vm: Arc::new(Mutex::new(vm)),
+        }
+    }
+
+    /// Lock the VM for the guard lifetime.
+    ///
+    /// A panic while the VM was locked, e.g. in a collision handler,
+    /// doesn't make the VM unusable for reading, so the lock is taken anyway.
+    pub fn lock(&self) -> MutexGuard<'_, VM> {
+        self.vm.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Call `f` with the VM locked.
+    pub fn with<R>(&self, f: impl FnOnce(&mut VM) -> R) -> R {
+        f(&mut self.lock())
+    }
+
+    /// Copy of the display in use, holding the lock only for the copy.
+    pub fn display(&self) -> Screen {
+        let vm = self.lock();
+        match vm.hires_graphics() {
+            Some(hires) => Screen::High(hires.clone()),
+            None => Screen::Low(vm.graphics.clone()),
+        }
+    }
+}
+
+/// Copy of the display a shared VM had in use, see [`SharedVm::display`].
+/// Not boxed, so copying it out of the lock doesn't allocate.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+pub enum Screen {
+    Low(Graphics),
+    /// SUPER-CHIP high resolution.
+    High(SchipGraphics),
+}
+
+impl Framebuffer for Screen {
+    fn width(&self) -> usize {
+        match self {
+            Screen::Low(graphics) => graphics.width(),
+            Screen::High(graphics) => graphics.width(),
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self {
+            Screen::Low(graphics) => graphics.height(),
+            Screen::High(graphics) => graphics.height(),
+        }
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        match self {
+            Screen::Low(graphics) => graphics.pixel(x, y),
+            Screen::High(graphics) => graphics.pixel(x, y),
+        }
+    }
+}
+
+impl From<VM> for SharedVm {
+    fn from(vm: VM) -> Self {
+        Self::new(vm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_send_sync() {
+        assert_send_sync::<VM>();
+        assert_send_sync::<SharedVm>();
+    }
+
+    #[test]
+    fn test_emulate_on_another_thread() {
+        let mut vm = VM::new();
+        // Draw digit 0 at (0, 0) and halt.
+        vm.load_program(&[0xD0, 0x05, 0x12, 0x02]).unwrap();
+        let shared = SharedVm::new(vm);
+
+        let emulator = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    shared.with(|vm| vm.exec_current_instruction().unwrap());
+                }
+            })
+        };
+        emulator.join().unwrap();
+
+        let screen = shared.display();
+        assert!(matches!(screen, Screen::Low(_)));
+        assert!((0..4).all(|x| screen.pixel(x, 0)));
+        assert!(!screen.pixel(4, 0));
+    }
+
+    #[test]
+    fn test_display_hires() {
+        let mut vm = VM::new();
+        // HIGH; draw digit 0 at (0, 0) and halt.
+        vm.load_program(&[0x00, 0xFF, 0xD0, 0x05, 0x12, 0x04])
+            .unwrap();
+        let shared = SharedVm::new(vm);
+
+        shared.with(|vm| vm.run_frame().unwrap());
+
+        let screen = shared.display();
+        assert_eq!((screen.width(), screen.height()), (128, 64));
+        assert!(screen.pixel(0, 0));
+    }
+
+    #[test]
+    fn test_lock_after_panic() {
+        let shared = SharedVm::new(VM::new());
+
+        let crashed = {
+            let shared = shared.clone();
+            thread::spawn(move || shared.with(|vm| vm.exec_instruction(0xFFFF).unwrap()))
+        };
+        assert!(crashed.join().is_err());
+
+        let screen = shared.display();
+        assert!((0..screen.height()).all(|y| (0..screen.width()).all(|x| !screen.pixel(x, y))));
+    }
+}
@@ -0,0 +1,207 @@
+use super::{
+    graphics::{Framebuffer, Graphics, SchipGraphics},
+    input::Input,
+    memory::Memory,
+    registers::Registers,
+    rng::Generator,
+    stack::Stack,
+    vm::VM,
+};
+use alloc::collections::VecDeque;
+
+/// Complete copy of the VM state which can be restored later.
+#[derive(Clone)]
+pub struct StateSnapshot {
+    pub(crate) memory: Memory,
+    pub(crate) registers: Registers,
+    pub(crate) stack: Stack,
+    pub(crate) graphics: Graphics,
+    pub(crate) hires: Option<SchipGraphics>,
+    pub(crate) input: Input,
+    pub(crate) rng: Generator,
+}
+
+impl StateSnapshot {
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    pub fn graphics(&self) -> &Graphics {
+        &self.graphics
+    }
+
+    /// High resolution display, if it was in use.
+    pub fn hires_graphics(&self) -> Option<&SchipGraphics> {
+        self.hires.as_ref()
+    }
+
+    /// Display in use, low or high resolution.
+    pub fn screen(&self) -> &dyn Framebuffer {
+        match &self.hires {
+            Some(hires) => hires,
+            None => &self.graphics,
+        }
+    }
+}
+
+/// Snapshot taken after `frame` frames of a run.
+#[derive(Clone)]
+pub struct FrameSnapshot {
+    pub frame: u64,
+    pub state: StateSnapshot,
+}
+
+/// Endless iterator running the VM and yielding a snapshot every `stride`
+/// frames, see [`VM::snapshots`].
+///
+/// Frames are run with [`VM::run_frame`], timers ticking once each. The
+/// iterator ends when an instruction fails.
+pub struct Snapshots<'a> {
+    vm: &'a mut VM,
+    stride: u64,
+    frame: u64,
+}
+
+impl<'a> Snapshots<'a> {
+    pub(crate) fn new(vm: &'a mut VM, stride: u64) -> Self {
+        assert!(stride > 0);
+        Self {
+            vm,
+            stride,
+            frame: 0,
+        }
+    }
+}
+
+impl Iterator for Snapshots<'_> {
+    type Item = FrameSnapshot;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for _ in 0..self.stride {
+            self.vm.run_frame().ok()?;
+        }
+        self.frame += self.stride;
+        Some(FrameSnapshot {
+            frame: self.frame,
+            state: self.vm.snapshot(),
+        })
+    }
+}
+
+/// Keeps the latest `retention` snapshots, e.g. for timelines or rewinding.
+pub struct SnapshotBuffer {
+    retention: usize,
+    snapshots: VecDeque<FrameSnapshot>,
+}
+
+impl SnapshotBuffer {
+    pub fn new(retention: usize) -> Self {
+        assert!(retention > 0);
+        Self {
+            retention,
+            snapshots: VecDeque::with_capacity(retention),
+        }
+    }
+
+    /// Add `snapshot`, dropping the oldest one when full.
+    pub fn push(&mut self, snapshot: FrameSnapshot) {
+        if self.snapshots.len() == self.retention {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// Remove and return the latest snapshot.
+    pub fn pop(&mut self) -> Option<FrameSnapshot> {
+        self.snapshots.pop_back()
+    }
+
+    pub fn latest(&self) -> Option<&FrameSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// Snapshots, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &FrameSnapshot> {
+        self.snapshots.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Program incrementing `V0` forever.
+    const COUNTER: [u8; 4] = [0x70, 0x01, 0x12, 0x00];
+
+    #[test]
+    fn test_snapshots_stride() {
+        let mut vm = VM::new();
+        vm.load_program(&COUNTER).unwrap();
+
+        let snapshots = vm.snapshots(4).take(3).collect::<Vec<_>>();
+
+        let frames = snapshots.iter().map(|s| s.frame).collect::<Vec<_>>();
+        assert_eq!(frames, [4, 8, 12]);
+        let counters = snapshots
+            .iter()
+            .map(|s| s.state.registers().v[0])
+            .collect::<Vec<_>>();
+        // 5 additions a frame.
+        assert_eq!(counters, [20, 40, 60]);
+    }
+
+    #[test]
+    fn test_snapshots_tick_timers_once_a_frame() {
+        let mut vm = VM::new();
+        // LD V0, 30; LD DT, V0; then count with V1.
+        vm.load_program(&[0x60, 0x1E, 0xF0, 0x15, 0x71, 0x01, 0x12, 0x04])
+            .unwrap();
+
+        let snapshot = vm.snapshots(2).next().unwrap();
+
+        assert_eq!(snapshot.state.registers().delay_timer, 28);
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut vm = VM::new();
+        vm.load_program(&COUNTER).unwrap();
+        let snapshot = vm.snapshots(2).next().unwrap();
+        vm.snapshots(10).next();
+
+        vm.restore(&snapshot.state);
+
+        assert_eq!(vm.snapshot().registers().v[0], 10);
+        assert_eq!(vm.snapshot().registers().program_counter, 0x200);
+    }
+
+    #[test]
+    fn test_buffer_retention() {
+        let mut vm = VM::new();
+        vm.load_program(&COUNTER).unwrap();
+        let mut buffer = SnapshotBuffer::new(2);
+
+        vm.snapshots(1).take(5).for_each(|s| buffer.push(s));
+
+        let frames = buffer.iter().map(|s| s.frame).collect::<Vec<_>>();
+        assert_eq!(frames, [4, 5]);
+        assert_eq!(buffer.pop().map(|s| s.frame), Some(5));
+        assert_eq!(buffer.latest().map(|s| s.frame), Some(4));
+        assert_eq!(buffer.len(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_stride() {
+        let mut vm = VM::new();
+        vm.snapshots(0);
+    }
+}
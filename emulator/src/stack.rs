@@ -1,9 +1,32 @@
-const STACK_SIZE: usize = 16;
+use core::fmt;
 
-#[derive(Default)]
+/// Levels of subroutine calls.
+pub const STACK_SIZE: usize = 16;
+
+/// Why a return address couldn't be pushed or popped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StackError {
+    /// Every level is in use.
+    Overflow,
+    /// There's no return address.
+    Underflow,
+}
+
+impl fmt::Display for StackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StackError::Overflow => write!(f, "stack overflow"),
+            StackError::Underflow => write!(f, "stack underflow"),
+        }
+    }
+}
+
+impl core::error::Error for StackError {}
+
+#[derive(Clone, Default)]
 pub struct Stack {
-    pub stack: [u16; STACK_SIZE],
-    pub pointer: u8,
+    stack: [u16; STACK_SIZE],
+    pointer: u8,
 }
 
 impl Stack {
@@ -11,15 +34,60 @@ impl Stack {
         Default::default()
     }
 
-    pub fn push(&mut self, value: u16) {
-        assert!((self.pointer as usize) < STACK_SIZE - 1);
-        self.stack[(self.pointer as usize)] = value;
+    pub fn is_empty(&self) -> bool {
+        self.pointer == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.pointer as usize == STACK_SIZE
+    }
+
+    /// Return addresses pushed, up to [`STACK_SIZE`].
+    pub fn depth(&self) -> usize {
+        self.pointer as usize
+    }
+
+    /// Return addresses pushed, outermost first.
+    pub fn frames(&self) -> &[u16] {
+        &self.stack[..self.depth()]
+    }
+
+    pub fn push(&mut self, value: u16) -> Result<(), StackError> {
+        if self.is_full() {
+            return Err(StackError::Overflow);
+        }
+        self.stack[self.pointer as usize] = value;
         self.pointer += 1;
+        Ok(())
     }
 
-    pub fn pop(&mut self) -> u16 {
-        assert!(self.pointer > 0);
+    pub fn pop(&mut self) -> Result<u16, StackError> {
+        if self.is_empty() {
+            return Err(StackError::Underflow);
+        }
         self.pointer -= 1;
-        self.stack[(self.pointer as usize)]
+        Ok(self.stack[self.pointer as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        let mut stack = Stack::new();
+        assert_eq!(stack.pop(), Err(StackError::Underflow));
+
+        for address in 0..STACK_SIZE as u16 {
+            stack.push(0x200 + address * 2).unwrap();
+        }
+        assert_eq!(stack.push(0x300), Err(StackError::Overflow));
+        assert_eq!(stack.depth(), STACK_SIZE);
+        assert_eq!(stack.frames()[..2], [0x200, 0x202]);
+
+        assert_eq!(stack.pop(), Ok(0x21E));
+        assert_eq!(stack.depth(), STACK_SIZE - 1);
+        assert_eq!(stack.frames().last(), Some(&0x21C));
     }
 }
@@ -0,0 +1,175 @@
+//! Where frontends keep data between runs, like settings and memory programs
+//! save, so each of them doesn't handle files its own way. Data is stored
+//! under keys which are `/`-separated paths, e.g. `saves/pong.sav`.
+//!
+//! [`FileStorage`] keeps each key in a file under a directory and
+//! [`MemoryStorage`] keeps them in memory, for tests. A frontend without a
+//! file system, like wasm in a browser, implements [`Storage`] on what it
+//! has, e.g. `localStorage`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Store of byte strings by key.
+pub trait Storage: Send + Sync {
+    /// Data stored under `key`, `None` if there's none.
+    fn load(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Store `data` under `key`, replacing what was there.
+    fn store(&self, key: &str, data: &[u8]) -> io::Result<()>;
+
+    /// Remove the data under `key`, if any.
+    fn remove(&self, key: &str) -> io::Result<()>;
+}
+
+/// Keys as files under a directory, created when first stored to.
+#[derive(Clone, Debug)]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File of `key`. Keys with empty, `.` or `..` components are refused,
+    /// so they can't name files outside of the directory.
+    pub fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let valid = key
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != ".." && !part.contains('\\'));
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid storage key: {}", key),
+            ));
+        }
+        Ok(key
+            .split('/')
+            .fold(self.dir.clone(), |path, part| path.join(part)))
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)?) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn store(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, data)
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(key)?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Keys kept in memory, lost when dropped.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    entries: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys with data, in order.
+    pub fn keys(&self) -> Vec<String> {
+        self.entries.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    fn store(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> io::Result<()> {
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn round_trip(storage: &dyn Storage) {
+        assert_eq!(storage.load("saves/pong.sav").unwrap(), None);
+        storage.store("saves/pong.sav", &[1, 2, 3]).unwrap();
+        storage.store("config", b"muted = true").unwrap();
+        assert_eq!(storage.load("saves/pong.sav").unwrap(), Some(vec![1, 2, 3]));
+
+        storage.remove("saves/pong.sav").unwrap();
+        storage.remove("saves/pong.sav").unwrap();
+        assert_eq!(storage.load("saves/pong.sav").unwrap(), None);
+        assert_eq!(
+            storage.load("config").unwrap(),
+            Some(b"muted = true".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_memory_storage() {
+        let storage = MemoryStorage::new();
+        round_trip(&storage);
+        assert_eq!(storage.keys(), ["config"]);
+    }
+
+    #[test]
+    fn test_file_storage() {
+        let dir = env::temp_dir().join(format!("chip8-storage-{}", process::id()));
+        let storage = FileStorage::new(&dir);
+        round_trip(&storage);
+        assert!(dir.join("config").is_file());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_storage_invalid_keys() {
+        let storage = FileStorage::new("saves");
+        for key in ["", "../config", "a//b", "/etc/passwd", "a\\..\\b"] {
+            assert_eq!(
+                storage.path(key).unwrap_err().kind(),
+                io::ErrorKind::InvalidInput,
+                "{}",
+                key
+            );
+        }
+        assert_eq!(
+            storage.path("saves/pong.sav").unwrap(),
+            Path::new("saves").join("saves").join("pong.sav")
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Expectations about a program run, for homebrew authors writing tests for
+//! their own games.
+//!
+//! ```
+//! use chip_8_emulator::testing::Expectations;
+//!
+//! // V3 := 5, then draw the top of digit 0 at (0, 0).
+//! let program = [0x63, 0x05, 0x64, 0x00, 0xF4, 0x29, 0xD4, 0x41];
+//! Expectations::new(&program)
+//!     .expect_v(2, 3, 5)
+//!     .expect_pixel(4, 0, 0, true)
+//!     .run()
+//!     .assert_ok();
+//! ```
+//!
+//! A frame is a single executed instruction, matching the GUI loop.
+
+use super::graphics::{Graphics, DISPLAY_COLS, DISPLAY_ROWS};
+use super::script::InputScript;
+use super::vm::VM;
+use std::fmt;
+
+enum Check {
+    Pixel { x: usize, y: usize, on: bool },
+    V { x: usize, value: u8 },
+}
+
+/// Program with checks of the VM state at chosen frames.
+pub struct Expectations {
+    program: Vec<u8>,
+    seed: Option<u64>,
+    input: InputScript,
+    /// Checks with the frame after which they're made.
+    checks: Vec<(u64, Check)>,
+    sound_by: Option<u64>,
+}
+
+/// Expectation which wasn't met.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub frame: u64,
+    pub message: String,
+}
+
+/// Outcome of [`Expectations::run`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub frames_run: u64,
+    pub failures: Vec<Failure>,
+}
+
+impl Expectations {
+    pub fn new(program: &[u8]) -> Self {
+        Self {
+            program: program.to_vec(),
+            seed: None,
+            input: InputScript::default(),
+            checks: Vec::new(),
+            sound_by: None,
+        }
+    }
+
+    /// Seed the random number generator, for programs using `Cxkk`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Press keys as `script` says, see [`crate::script`].
+    pub fn with_input(mut self, script: InputScript) -> Self {
+        self.input = script;
+        self
+    }
+
+    /// Expect pixel (`x`, `y`) to be lit, or not when `on` is false, after
+    /// `frame` frames.
+    pub fn expect_pixel(mut self, frame: u64, x: usize, y: usize, on: bool) -> Self {
+        assert!(x < DISPLAY_COLS);
+        assert!(y < DISPLAY_ROWS);
+        self.checks.push((frame, Check::Pixel { x, y, on }));
+        self
+    }
+
+    /// Expect register V`x` to hold `value` after `frame` frames.
+    pub fn expect_v(mut self, frame: u64, x: usize, value: u8) -> Self {
+        assert!(x < 16);
+        self.checks.push((frame, Check::V { x, value }));
+        self
+    }
+
+    /// Expect the sound to be playing after some frame up to `frame`.
+    pub fn expect_sound_by(mut self, frame: u64) -> Self {
+        self.sound_by = Some(self.sound_by.map_or(frame, |by| by.min(frame)));
+        self
+    }
+
+    /// Run the program for as many frames as the last check needs.
+    pub fn run(mut self) -> Report {
+        self.checks.sort_by_key(|&(frame, _)| frame);
+        let last_frame = self
+            .checks
+            .iter()
+            .map(|&(frame, _)| frame)
+            .chain(self.sound_by)
+            .max()
+            .unwrap_or(0);
+
+        let mut vm = VM::new();
+        if let Some(seed) = self.seed {
+            vm.set_rng_seed(seed);
+        }
+        if let Err(e) = vm.load_program(&self.program) {
+            return Report {
+                frames_run: 0,
+                failures: vec![Failure {
+                    frame: 0,
+                    message: e.to_string(),
+                }],
+            };
+        }
+
+        let mut failures = Vec::new();
+        let mut checks = self.checks.iter().peekable();
+        let mut sound_played = false;
+        let mut player = self.input.player();
+        let mut frame = 0;
+        loop {
+            while let Some((_, check)) = checks.next_if(|&&(at, _)| at == frame) {
+                if let Some(message) = check.failure(&vm) {
+                    failures.push(Failure { frame, message });
+                }
+            }
+            sound_played |= vm.is_sound_playing();
+            if frame == last_frame {
+                break;
+            }
+            if let Some(input) = player.input_for(frame) {
+                vm.set_input(input);
+            }
+            if let Err(e) = vm.exec_current_instruction() {
+                failures.push(Failure {
+                    frame,
+                    message: e.to_string(),
+                });
+                break;
+            }
+            frame += 1;
+        }
+
+        if let Some(by) = self.sound_by {
+            if !sound_played {
+                failures.push(Failure {
+                    frame: by,
+                    message: "expected sound to play, it never did".into(),
+                });
+            }
+        }
+        failures.sort_by_key(|failure| failure.frame);
+        Report {
+            frames_run: frame,
+            failures,
+        }
+    }
+}
+
+impl Check {
+    fn failure(&self, vm: &VM) -> Option<String> {
+        let snapshot = vm.snapshot();
+        match *self {
+            Check::Pixel { x, y, on } => {
+                let graphics = snapshot.graphics();
+                (graphics.pixel(x, y) != on).then(|| {
+                    format!(
+                        "expected pixel ({}, {}) {}, was {}\n{}",
+                        x,
+                        y,
+                        on_off(on),
+                        on_off(!on),
+                        render(graphics, x, y)
+                    )
+                })
+            }
+            Check::V { x, value } => {
+                let actual = snapshot.registers().v[x];
+                (actual != value)
+                    .then(|| format!("expected V{:X} == {:#04X}, was {:#04X}", x, value, actual))
+            }
+        }
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Display as text, `#` for lit pixels, with the checked pixel marked by `X`
+/// when lit and `o` when not.
+fn render(graphics: &Graphics, marked_x: usize, marked_y: usize) -> String {
+    let mut text = String::with_capacity((DISPLAY_COLS + 1) * DISPLAY_ROWS);
+    for y in 0..DISPLAY_ROWS {
+        for x in 0..DISPLAY_COLS {
+            let lit = graphics.pixel(x, y);
+            text.push(match (x == marked_x && y == marked_y, lit) {
+                (true, true) => 'X',
+                (true, false) => 'o',
+                (false, true) => '#',
+                (false, false) => '.',
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Panic with the report if any expectation wasn't met.
+    #[track_caller]
+    pub fn assert_ok(&self) {
+        if !self.is_ok() {
+            panic!("{}", self);
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "all expectations met in {} frames", self.frames_run);
+        }
+        writeln!(
+            f,
+            "{} of the expectations failed in {} frames:",
+            self.failures.len(),
+            self.frames_run
+        )?;
+        for failure in &self.failures {
+            writeln!(f, "frame {}: {}", failure.frame, failure.message)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // V3 := 5; I := digit 0 sprite; draw it at (V4, V4) = (0, 0); jump to self.
+    const PROGRAM: [u8; 10] = [0x63, 0x05, 0x64, 0x00, 0xF4, 0x29, 0xD4, 0x45, 0x12, 0x08];
+
+    #[test]
+    fn test_expectations_met() {
+        let report = Expectations::new(&PROGRAM)
+            .expect_v(0, 3, 0)
+            .expect_v(1, 3, 5)
+            .expect_pixel(3, 0, 0, false)
+            .expect_pixel(4, 0, 0, true)
+            .expect_pixel(4, 1, 1, false)
+            .run();
+
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!(report.frames_run, 4);
+    }
+
+    #[test]
+    fn test_failures_reported_in_frame_order() {
+        let report = Expectations::new(&PROGRAM)
+            .expect_pixel(10, 1, 1, true)
+            .expect_v(2, 3, 6)
+            .run();
+
+        assert_eq!(report.frames_run, 10);
+        assert_eq!(report.failures.len(), 2);
+        assert_eq!(report.failures[0].frame, 2);
+        assert_eq!(report.failures[0].message, "expected V3 == 0x06, was 0x05");
+        assert_eq!(report.failures[1].frame, 10);
+        assert!(report.failures[1]
+            .message
+            .starts_with("expected pixel (1, 1) on, was off\n####."));
+        assert!(report.failures[1].message.contains("\n#o.#."));
+    }
+
+    #[test]
+    fn test_with_input() {
+        // V0 := key, jump to self.
+        let program = [0xF0, 0x0A, 0x12, 0x02];
+        let script = InputScript::parse("frame 3: press 7\nframe 4: release 7").unwrap();
+
+        Expectations::new(&program)
+            .with_input(script)
+            .expect_v(4, 0, 0)
+            .expect_v(5, 0, 7)
+            .run()
+            .assert_ok();
+    }
+
+    #[test]
+    fn test_expect_sound_by() {
+        // ST := V0 = 2 at frame 2, sound plays after it.
+        let program = [0x60, 0x02, 0xF0, 0x18, 0x12, 0x04];
+
+        assert!(Expectations::new(&program).expect_sound_by(2).run().is_ok());
+        let report = Expectations::new(&program).expect_sound_by(1).run();
+        assert_eq!(
+            report.failures,
+            vec![Failure {
+                frame: 1,
+                message: "expected sound to play, it never did".into()
+            }]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "1 of the expectations failed in 1 frames:\nframe 1: expected V3")]
+    fn test_assert_ok_panics_with_report() {
+        Expectations::new(&PROGRAM)
+            .expect_v(1, 3, 1)
+            .run()
+            .assert_ok();
+    }
+}
@@ -0,0 +1,83 @@
+//! Last instructions executed, see
+//! [`VM::enable_trace`](super::vm::VM::enable_trace).
+
+use alloc::collections::VecDeque;
+
+/// Instruction word executed at `pc`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: u16,
+    pub instruction: u16,
+}
+
+/// Tail of the instructions executed, the failing one included, to see what
+/// led to a crash.
+#[derive(Clone, Debug)]
+pub struct Trace {
+    capacity: usize,
+    entries: VecDeque<TraceEntry>,
+}
+
+impl Trace {
+    /// Create trace keeping up to `capacity` last instructions.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn record(&mut self, pc: u16, instruction: u16) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(TraceEntry { pc, instruction });
+    }
+
+    /// Instructions kept, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Bytes allocated on the heap for the instructions.
+    pub fn heap_size(&self) -> usize {
+        self.entries.capacity() * size_of::<TraceEntry>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_last_instructions() {
+        let mut trace = Trace::new(2);
+        trace.record(0x200, 0x6001);
+        trace.record(0x202, 0x7001);
+        trace.record(0x204, 0x1204);
+
+        assert_eq!(trace.len(), 2);
+        assert_eq!(
+            trace.iter().copied().collect::<Vec<_>>(),
+            [
+                TraceEntry {
+                    pc: 0x202,
+                    instruction: 0x7001
+                },
+                TraceEntry {
+                    pc: 0x204,
+                    instruction: 0x1204
+                },
+            ]
+        );
+    }
+}
@@ -1,22 +1,406 @@
 use super::{
-    graphics::Graphics,
-    input::Input,
+    draws::{Collision, Draw, DrawLog},
+    effects::{Effects, StepInfo},
+    faults::FaultLog,
+    graphics::{Framebuffer, Graphics, Resolution, Row, SchipGraphics},
+    history::RegisterHistory,
+    input::{Input, Key},
+    instruction::Instruction,
     memory::{
-        Memory, INSTRUCTION_SIZE, PROGRAM_START_LOCATION, SPRITE_SIZE, SPRITE_START_LOCATION,
+        check_program, FontStyle, LoadError, Memory, OutOfRange, BIG_FONT_SIZE, BIG_SPRITE_SIZE,
+        BIG_SPRITE_START_LOCATION, FONT_SIZE, INSTRUCTION_SIZE, MEMORY_SIZE,
+        PROGRAM_START_LOCATION, SPRITE_SIZE, SPRITE_START_LOCATION,
     },
-    registers::Registers,
+    mmio::{IoMap, IoWrite},
+    observer::{ObserverId, VmObserver},
+    platform::{OpcodeFamily, Platform, Quirks, Variant},
+    registers::{Registers, V_REGISTERS_SIZE},
+    rng::{Generator, RngSource},
+    rng_log::RngLog,
+    rpl::{RplFlags, RPL_FLAGS},
+    snapshot::{Snapshots, StateSnapshot},
     stack::Stack,
+    trace::Trace,
 };
-use rand::rngs::SmallRng;
-use rand::{Rng, SeedableRng};
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+use core::num::{NonZeroU32, NonZeroU8};
+use core::ops::Range;
+use core::time::Duration;
+use rand::Rng;
+
+/// First word of the XO-CHIP `F000 nnnn` instruction.
+const LONG_LD_I: u16 = 0xF000;
+/// Columns moved by the SUPER-CHIP `00FB` and `00FC` scrolls.
+const SCROLL_COLUMNS: usize = 4;
+/// Rows and bytes of the SUPER-CHIP 16 x 16 sprites drawn by `Dxy0`.
+const LARGE_SPRITE_ROWS: u8 = 16;
+const LARGE_SPRITE_SIZE: usize = 32;
+
+/// Delay and sound timer ticks per second.
+pub const TIMER_FREQUENCY: u32 = 60;
+/// Time between timer ticks, the length of a frame.
+pub const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / TIMER_FREQUENCY as u64);
+/// Default instructions executed per second by [`VM::run_for`].
+pub const INSTRUCTIONS_PER_SECOND: u32 = 600;
+/// Default instructions executed by [`VM::run_frame`].
+pub const INSTRUCTIONS_PER_FRAME: u32 = INSTRUCTIONS_PER_SECOND / TIMER_FREQUENCY;
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+fn frequency() -> NonZeroU32 {
+    NonZeroU32::new(TIMER_FREQUENCY).unwrap()
+}
+
+/// Instructions run per 60 Hz frame at `per_second`, at least one.
+fn per_frame(per_second: NonZeroU32) -> NonZeroU32 {
+    NonZeroU32::new(per_second.get() / TIMER_FREQUENCY).unwrap_or(NonZeroU32::MIN)
+}
+
+/// Why an instruction couldn't be executed. The VM is left as it was before
+/// the instruction, so the caller can report it, skip it or stop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VmError {
+    /// The word at `pc` isn't an instruction.
+    UnknownInstruction { pc: u16, instruction: u16 },
+    /// `CALL` at `pc` with every stack level in use.
+    StackOverflow { pc: u16 },
+    /// `RET` at `pc` with no subroutine to return from.
+    StackUnderflow { pc: u16 },
+    /// The instruction at `pc` accesses memory past its end, starting at
+    /// `address`.
+    MemoryOutOfBounds { pc: u16, address: usize },
+    /// `Fx75` at `pc` couldn't store the RPL flags, see
+    /// [`VM::set_rpl_flags`].
+    FlagStorage { pc: u16 },
+    /// `instruction` passed to [`VM::execute`] at `pc` has an operand that
+    /// doesn't fit its field, see [`Instruction::is_valid`].
+    InvalidOperand { pc: u16, instruction: Instruction },
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VmError::UnknownInstruction { pc, instruction } => {
+                write!(f, "unknown instruction {:#06X} at {:#05X}", instruction, pc)
+            }
+            VmError::StackOverflow { pc } => write!(f, "stack overflow at {:#05X}", pc),
+            VmError::StackUnderflow { pc } => {
+                write!(f, "return without a subroutine at {:#05X}", pc)
+            }
+            VmError::MemoryOutOfBounds { pc, address } => write!(
+                f,
+                "memory access past the end at {:#05X}, from address {:#X}",
+                pc, address
+            ),
+            VmError::FlagStorage { pc } => write!(f, "can't store RPL flags at {:#05X}", pc),
+            VmError::InvalidOperand { pc, instruction } => {
+                write!(f, "operand out of range in {} at {:#05X}", instruction, pc)
+            }
+        }
+    }
+}
+
+impl core::error::Error for VmError {}
+
+/// When delay and sound timers tick.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimerPolicy {
+    /// At [`TIMER_FREQUENCY`]: once per [`VM::exec_current_instruction`]
+    /// frame, and by the time passed in [`VM::run_for`] and
+    /// [`VM::advance_timers`].
+    #[default]
+    RealTime,
+    /// Once every `n` executed instructions however long they take, like
+    /// some historical interpreters did. Time passed in doesn't tick timers.
+    Instructions(NonZeroU32),
+    /// At [`TIMER_FREQUENCY`] of the time passed in [`VM::run_for`] and
+    /// [`VM::advance_timers`] only, for frontends executing instructions at
+    /// their own pace and keeping timers on the wall clock.
+    Elapsed,
+}
+
+/// What a [`VM::run_frame`] did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameResult {
+    /// Instructions executed, fewer than configured if the program halted.
+    pub instructions: u32,
+    pub display_changed: bool,
+}
+
+/// Why [`VM::run_until_halted`] returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunStatus {
+    /// The program jumped to itself, see [`VM::is_halted`].
+    Halted,
+    /// The instruction budget ran out first.
+    BudgetExhausted,
+}
+
+/// What a [`VM::run_until_halted`] did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunResult {
+    pub status: RunStatus,
+    pub instructions: u64,
+}
+
+/// Rows of a `DRW` drawn so far by a sprite animated over several frames,
+/// see [`VM::set_sprite_rows_per_frame`].
+#[derive(Clone, Copy, Default)]
+struct DrawProgress {
+    rows: u8,
+    collision: bool,
+}
+
+/// Bytes used by each part of a [`VM`], see [`VM::memory_footprint`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryFootprint {
+    pub memory: usize,
+    pub registers: usize,
+    pub stack: usize,
+    pub graphics: usize,
+    pub input: usize,
+    pub rng: usize,
+    /// Heap allocated by the copy of the loaded program kept for
+    /// [`VM::reset`].
+    pub program: usize,
+    /// Heap allocated by register history; 0 unless the history is enabled.
+    pub register_history: usize,
+    /// Heap allocated by the sprite draw log, 0 unless it's enabled.
+    pub draw_log: usize,
+    /// Heap allocated by the RNG log, 0 unless it's enabled.
+    pub rng_log: usize,
+    /// Heap allocated by the fault log, 0 unless sandbox mode is enabled.
+    pub fault_log: usize,
+    /// Heap allocated by the trace, 0 unless it's enabled.
+    pub trace: usize,
+    /// Size of the `VM` itself, including padding.
+    pub vm: usize,
+}
+
+impl MemoryFootprint {
+    /// Bytes used in total, inline and on the heap.
+    pub fn total(&self) -> usize {
+        self.vm
+            + self.program
+            + self.register_history
+            + self.draw_log
+            + self.rng_log
+            + self.fault_log
+            + self.trace
+    }
+}
+
+/// Callback set by [`VM::on_collision`]. The VM is shared between threads,
+/// so the handler has to be too.
+type CollisionHandler = Box<dyn FnMut(&Collision) + Send + Sync>;
+/// Callback set by [`VM::on_sound_start`] or [`VM::on_sound_stop`].
+type SoundHandler = Box<dyn FnMut() + Send + Sync>;
 
 pub struct VM {
     memory: Memory,
     registers: Registers,
     stack: Stack,
+    /// Low resolution display, the one of CHIP-8.
     pub graphics: Graphics,
+    /// SUPER-CHIP high resolution display, in use instead of `graphics`
+    /// while present.
+    hires: Option<SchipGraphics>,
     input: Input,
-    rng: SmallRng,
+    rng: Generator,
+    /// SUPER-CHIP flags of `Fx75` and `Fx85`, kept across resets.
+    rpl_flags: RplFlags,
+    register_history: Option<RegisterHistory>,
+    draw_log: Option<DrawLog>,
+    rng_log: Option<RngLog>,
+    /// Faults skipped in sandbox mode, which is on when present.
+    fault_log: Option<FaultLog>,
+    trace: Option<Trace>,
+    collision_handler: Option<CollisionHandler>,
+    /// Memory mapped by [`VM::on_io_write`].
+    io_map: Option<IoMap>,
+    sound_start_handler: Option<SoundHandler>,
+    sound_stop_handler: Option<SoundHandler>,
+    observers: Vec<(ObserverId, Box<dyn VmObserver>)>,
+    /// Id of the next observer added.
+    next_observer_id: u64,
+    out_of_range: OutOfRange,
+    timer_policy: TimerPolicy,
+    /// Instructions executed since the last timer tick with
+    /// [`TimerPolicy::Instructions`].
+    instructions_since_tick: u32,
+    /// Nanoseconds times [`TIMER_FREQUENCY`] passed since the last timer
+    /// tick, always less than a second.
+    timer_remainder: u128,
+    /// Nanoseconds times `instructions_per_second` passed since the last
+    /// instruction run by [`VM::run_for`], always less than a second.
+    instruction_remainder: u128,
+    /// Rows drawn per frame, all of them at once when `None`.
+    sprite_rows_per_frame: Option<NonZeroU8>,
+    /// Rows which can still be drawn until the next timer tick.
+    sprite_rows_left: u8,
+    /// Progress of the `DRW` at the program counter, if it's half drawn.
+    draw_progress: Option<DrawProgress>,
+    /// Key pressed while `Fx0A` at the program counter waits for its
+    /// release.
+    key_latch: Option<u8>,
+    instructions_per_second: NonZeroU32,
+    instructions_per_frame: NonZeroU32,
+    quirks: Quirks,
+    /// Most recent family of instructions executed, later ones being
+    /// unknown instructions.
+    opcode_family: OpcodeFamily,
+    /// Whether timers ticked since the last instruction, the display
+    /// refreshing along with them.
+    refreshed: bool,
+    /// Address programs are loaded at and start from.
+    program_start: usize,
+    /// Program last loaded, put back by [`VM::reset`].
+    program: Vec<u8>,
+}
+
+/// Settings of a [`VM`] to create, see [`VM::builder`]. Anything not set is
+/// as in [`VM::new`].
+#[derive(Clone)]
+pub struct VmBuilder {
+    rng: Generator,
+    instructions_per_second: NonZeroU32,
+    program_start: usize,
+    quirks: Quirks,
+    opcode_family: OpcodeFamily,
+    font: Option<[u8; FONT_SIZE]>,
+}
+
+impl Default for VmBuilder {
+    fn default() -> Self {
+        Self {
+            rng: Generator::seeded(0),
+            instructions_per_second: NonZeroU32::new(INSTRUCTIONS_PER_SECOND).unwrap(),
+            program_start: PROGRAM_START_LOCATION,
+            quirks: Quirks::default(),
+            opcode_family: OpcodeFamily::XoChip,
+            font: None,
+        }
+    }
+}
+
+impl VmBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Seed of the random number generator used by `Cxkk`.
+    pub fn rng_seed(mut self, seed: u64) -> Self {
+        self.rng = Generator::seeded(seed);
+        self
+    }
+
+    /// Random number generator used by `Cxkk`, see [`VM::set_rng`].
+    pub fn rng(mut self, rng: impl RngSource + 'static) -> Self {
+        self.rng = Generator::Custom(Box::new(rng));
+        self
+    }
+
+    /// CPU speed, as instructions run by [`VM::run_frame`].
+    pub fn instructions_per_frame(mut self, instructions: NonZeroU32) -> Self {
+        self.instructions_per_second = instructions.saturating_mul(frequency());
+        self
+    }
+
+    /// CPU speed, as instructions run per second by [`VM::run_for`], see
+    /// [`VM::set_instructions_per_second`].
+    pub fn instructions_per_second(mut self, instructions: NonZeroU32) -> Self {
+        self.instructions_per_second = instructions;
+        self
+    }
+
+    /// Address programs are loaded at and start from, 0x200 by default.
+    pub fn program_start(mut self, address: usize) -> Self {
+        assert!(address < MEMORY_SIZE);
+        self.program_start = address;
+        self
+    }
+
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Most recent family of instructions executed, see
+    /// [`VM::set_opcode_family`].
+    pub fn opcode_family(mut self, family: OpcodeFamily) -> Self {
+        self.opcode_family = family;
+        self
+    }
+
+    /// Built-in hexadecimal font, see [`VM::set_font_style`].
+    pub fn font_style(mut self, style: FontStyle) -> Self {
+        self.font = Some(*style.sprites());
+        self
+    }
+
+    /// Hexadecimal font replacing the built-in one, see [`VM::load_font`].
+    pub fn font(mut self, font: &[u8; FONT_SIZE]) -> Self {
+        self.font = Some(*font);
+        self
+    }
+
+    /// Behave like `platform`: its quirks, font and instructions.
+    pub fn platform(mut self, platform: &Platform) -> Self {
+        self.quirks = platform.quirks;
+        self.opcode_family = platform.opcode_family();
+        self.font = Some(*platform.font_style.sprites());
+        self
+    }
+
+    /// Behave like the platform of `variant`.
+    pub fn variant(self, variant: Variant) -> Self {
+        self.platform(&variant.platform())
+    }
+
+    pub fn build(self) -> VM {
+        let mut vm = VM {
+            memory: Memory::new_with_initial_sprites(),
+            registers: Registers::new(),
+            stack: Stack::new(),
+            graphics: Graphics::new(),
+            hires: None,
+            input: Input::new(),
+            rng: self.rng,
+            rpl_flags: RplFlags::new(),
+            register_history: None,
+            draw_log: None,
+            rng_log: None,
+            fault_log: None,
+            trace: None,
+            collision_handler: None,
+            io_map: None,
+            sound_start_handler: None,
+            sound_stop_handler: None,
+            observers: Vec::new(),
+            next_observer_id: 0,
+            out_of_range: OutOfRange::default(),
+            timer_policy: TimerPolicy::default(),
+            instructions_since_tick: 0,
+            timer_remainder: 0,
+            instruction_remainder: 0,
+            sprite_rows_per_frame: None,
+            sprite_rows_left: 0,
+            draw_progress: None,
+            key_latch: None,
+            instructions_per_second: self.instructions_per_second,
+            instructions_per_frame: per_frame(self.instructions_per_second),
+            quirks: self.quirks,
+            opcode_family: self.opcode_family,
+            refreshed: false,
+            program_start: self.program_start,
+            program: Vec::new(),
+        };
+        if let Some(font) = &self.font {
+            vm.load_font(font);
+        }
+        vm
+    }
 }
 
 #[allow(clippy::only_used_in_recursion)]
@@ -25,15 +409,30 @@ impl VM {
         Default::default()
     }
 
+    pub fn builder() -> VmBuilder {
+        VmBuilder::new()
+    }
+
+    /// VM running programs written for `variant`, with its quirks, font and
+    /// instructions. [`VM::new`] has every instruction and no quirks.
+    pub fn with_variant(variant: Variant) -> VM {
+        Self::builder().variant(variant).build()
+    }
+
     /// Return from a subroutine.
     ///
     /// Code: `00EE`
     ///
     /// The interpreter sets the program counter to the address at the top of
     /// the stack, then subtracts 1 from the stack pointer.
-    fn ret(&mut self) {
-        self.registers.program_counter = self.stack.pop();
+    fn ret(&mut self) -> Result<(), VmError> {
+        let pc = self.registers.program_counter;
+        self.registers.program_counter = self
+            .stack
+            .pop()
+            .map_err(|_| VmError::StackUnderflow { pc })?;
         self.next_instruction(1);
+        Ok(())
     }
 
     /// Jump to location `addr`.
@@ -50,7 +449,11 @@ impl VM {
     ///
     /// Code: `00E0`
     fn cls(&mut self) {
-        self.graphics.clear();
+        match &mut self.hires {
+            Some(hires) => hires.clear(),
+            None => self.graphics.clear(),
+        }
+        self.notify_observers(|observer, vm| observer.on_clear(vm));
         self.next_instruction(1);
     }
 
@@ -61,11 +464,15 @@ impl VM {
     /// The interpreter increments the stack pointer, then puts the current
     /// program counter on the top of the stack. The program counter is then
     /// set to `addr`.
-    fn call(&mut self, addr: u16) {
+    fn call(&mut self, addr: u16) -> Result<(), VmError> {
         assert!((addr & 0xF000) == 0);
 
-        self.stack.push(self.registers.program_counter);
+        let pc = self.registers.program_counter;
+        self.stack
+            .push(pc)
+            .map_err(|_| VmError::StackOverflow { pc })?;
         self.registers.program_counter = addr;
+        Ok(())
     }
 
     /// Skip next instruction if `Vx` = `value`.
@@ -76,7 +483,7 @@ impl VM {
     /// equal, increments the program counter by 2.
     fn se(&mut self, x: u8, value: u8) {
         if self.registers.v[x as usize] == value {
-            self.next_instruction(2);
+            self.skip_next_instruction();
         } else {
             self.next_instruction(1);
         }
@@ -90,7 +497,7 @@ impl VM {
     /// equal, increments the program counter by 2.
     fn sne(&mut self, x: u8, value: u8) {
         if self.registers.v[x as usize] != value {
-            self.next_instruction(2);
+            self.skip_next_instruction();
         } else {
             self.next_instruction(1);
         }
@@ -104,7 +511,7 @@ impl VM {
     /// are equal, increments the program counter by 2.
     fn se_v(&mut self, x: u8, y: u8) {
         if self.registers.v[x as usize] == self.registers.v[y as usize] {
-            self.next_instruction(2);
+            self.skip_next_instruction();
         } else {
             self.next_instruction(1);
         }
@@ -152,6 +559,7 @@ impl VM {
     /// 1. Otherwise, it is 0.
     fn or(&mut self, vx: u8, vy: u8) {
         self.registers.v[vx as usize] |= self.registers.v[vy as usize];
+        self.logic_quirk();
         self.next_instruction(1);
     }
 
@@ -165,6 +573,7 @@ impl VM {
     /// 1. Otherwise, it is 0.
     fn and(&mut self, x: u8, y: u8) {
         self.registers.v[x as usize] &= self.registers.v[y as usize];
+        self.logic_quirk();
         self.next_instruction(1);
     }
 
@@ -178,6 +587,7 @@ impl VM {
     /// corresponding bit in the result is set to 1. Otherwise, it is 0.
     fn xor(&mut self, vx: u8, vy: u8) {
         self.registers.v[vx as usize] ^= self.registers.v[vy as usize];
+        self.logic_quirk();
         self.next_instruction(1);
     }
 
@@ -191,8 +601,7 @@ impl VM {
     fn add_vx_vy(&mut self, x: u8, y: u8) {
         let (result, is_overflow) =
             self.registers.v[x as usize].overflowing_add(self.registers.v[y as usize]);
-        self.registers.v[x as usize] = result;
-        self.registers.v[0xF] = if is_overflow { 1 } else { 0 };
+        self.set_with_flag(x, result, is_overflow);
         self.next_instruction(1);
     }
 
@@ -205,8 +614,7 @@ impl VM {
     fn sub(&mut self, x: u8, y: u8) {
         let (result, is_overflow) =
             self.registers.v[x as usize].overflowing_sub(self.registers.v[y as usize]);
-        self.registers.v[x as usize] = result;
-        self.registers.v[0xF] = if is_overflow { 0 } else { 1 };
+        self.set_with_flag(x, result, !is_overflow);
         self.next_instruction(1);
     }
 
@@ -215,10 +623,11 @@ impl VM {
     /// Code: `8xy6`
     ///
     /// If the least-significant bit of `Vx` is 1, then `VF` is set to 1,
-    /// otherwise 0. Then `Vx` is divided by 2.
-    fn shr(&mut self, x: u8) {
-        self.registers.v[0xF] = self.registers.v[x as usize] % 2;
-        self.registers.v[x as usize] >>= 1;
+    /// otherwise 0. Then `Vx` is divided by 2. With the `shift_uses_vy`
+    /// quirk `Vy` is shifted instead.
+    fn shr(&mut self, x: u8, y: u8) {
+        let value = self.shift_source(x, y);
+        self.set_with_flag(x, value >> 1, value % 2 == 1);
         self.next_instruction(1);
     }
 
@@ -231,8 +640,7 @@ impl VM {
     fn subn(&mut self, x: u8, y: u8) {
         let (result, is_overflow) =
             self.registers.v[y as usize].overflowing_sub(self.registers.v[x as usize]);
-        self.registers.v[x as usize] = result;
-        self.registers.v[0xF] = if is_overflow { 0 } else { 1 };
+        self.set_with_flag(x, result, !is_overflow);
         self.next_instruction(1);
     }
 
@@ -241,14 +649,35 @@ impl VM {
     /// Code: `8xyE`
     ///
     /// If the most-significant bit of `Vx` is 1, then `VF` is set to 1,
-    /// otherwise to 0. Then `Vx` is multiplied by 2.
-    fn shl(&mut self, x: u8) {
-        let significant_bit = self.registers.v[x as usize] >= 0b1000_0000;
-        self.registers.v[0xF] = if significant_bit { 1 } else { 0 };
-        self.registers.v[x as usize] <<= 1;
+    /// otherwise to 0. Then `Vx` is multiplied by 2. With the
+    /// `shift_uses_vy` quirk `Vy` is shifted instead.
+    fn shl(&mut self, x: u8, y: u8) {
+        let value = self.shift_source(x, y);
+        self.set_with_flag(x, value << 1, value >= 0b1000_0000);
         self.next_instruction(1);
     }
 
+    /// Store `result` of an `8xy*` instruction in `Vx`, then `flag` in `VF`.
+    /// Both operands are read before either is written, and `VF` is written
+    /// last so it holds the flag even when it's `Vx`.
+    fn set_with_flag(&mut self, x: u8, result: u8, flag: bool) {
+        self.registers.v[x as usize] = result;
+        self.registers.v[0xF] = flag as u8;
+    }
+
+    /// Value shifted by `8xy6` and `8xyE`.
+    fn shift_source(&self, x: u8, y: u8) -> u8 {
+        let source = if self.quirks.shift_uses_vy { y } else { x };
+        self.registers.v[source as usize]
+    }
+
+    /// Reset `VF` after `8xy1`, `8xy2` and `8xy3` if the quirk says so.
+    fn logic_quirk(&mut self) {
+        if self.quirks.logic_resets_vf {
+            self.registers.v[0xF] = 0;
+        }
+    }
+
     /// Skip next instruction if `Vx` != `Vy`.
     ///
     /// Code: `9xy0`
@@ -257,12 +686,83 @@ impl VM {
     /// the program counter is increased by 2.
     fn sne_vx_vy(&mut self, x: u8, y: u8) {
         if self.registers.v[x as usize] != self.registers.v[y as usize] {
-            self.next_instruction(2);
+            self.skip_next_instruction();
         } else {
             self.next_instruction(1);
         }
     }
 
+    /// Switch to low resolution, 64 x 32.
+    ///
+    /// Code: `00FE`
+    ///
+    /// SUPER-CHIP extension. Like Octo, switching resolution clears the
+    /// display.
+    fn low(&mut self) {
+        self.hires = None;
+        self.graphics.clear();
+        self.next_instruction(1);
+    }
+
+    /// Switch to high resolution, 128 x 64.
+    ///
+    /// Code: `00FF`
+    ///
+    /// SUPER-CHIP extension. Sprites are then drawn, and their coordinates
+    /// wrap, on the larger display.
+    fn high(&mut self) {
+        self.hires = Some(SchipGraphics::default());
+        self.next_instruction(1);
+    }
+
+    /// Scroll the display down `n` rows.
+    ///
+    /// Code: `00Cn`
+    ///
+    /// SUPER-CHIP extension. Like the other scrolls, it moves the display in
+    /// use by pixels of its own resolution, as Octo and XO-CHIP do, rather
+    /// than by half pixels in low resolution as on the HP48.
+    fn scd(&mut self, n: u8) {
+        match &mut self.hires {
+            Some(hires) => hires.scroll_down(n as usize),
+            None => self.graphics.scroll_down(n as usize),
+        }
+        self.next_instruction(1);
+    }
+
+    /// Scroll the display right 4 columns.
+    ///
+    /// Code: `00FB`
+    fn scr(&mut self) {
+        match &mut self.hires {
+            Some(hires) => hires.scroll_right(SCROLL_COLUMNS),
+            None => self.graphics.scroll_right(SCROLL_COLUMNS),
+        }
+        self.next_instruction(1);
+    }
+
+    /// Scroll the display left 4 columns.
+    ///
+    /// Code: `00FC`
+    fn scl(&mut self) {
+        match &mut self.hires {
+            Some(hires) => hires.scroll_left(SCROLL_COLUMNS),
+            None => self.graphics.scroll_left(SCROLL_COLUMNS),
+        }
+        self.next_instruction(1);
+    }
+
+    /// Set `I` = `nnnn`, a 16-bit address following the instruction.
+    ///
+    /// Code: `F000 nnnn`
+    ///
+    /// XO-CHIP extension, the only instruction taking 4 bytes. Skip
+    /// instructions account for its width.
+    fn ld_i_long(&mut self, address: u16) {
+        self.registers.i = address;
+        self.next_instruction(2);
+    }
+
     /// Set `I` = `value`.
     ///
     /// Code: `Annn`
@@ -278,10 +778,17 @@ impl VM {
     ///
     /// Code: `Bnnn`
     ///
-    /// The program counter is set to `addr` plus the value of `V0`.
+    /// The program counter is set to `addr` plus the value of `V0`. With the
+    /// `jump_uses_vx` quirk it's `Vx` instead, `x` being the top digit of
+    /// `addr`.
     fn jp_v0(&mut self, addr: u16) {
         assert!((addr & 0xF000) == 0);
-        self.registers.program_counter = addr + (self.registers.v[0] as u16);
+        let x = if self.quirks.jump_uses_vx {
+            addr >> 8
+        } else {
+            0
+        };
+        self.registers.program_counter = addr + (self.registers.v[x as usize] as u16);
     }
 
     /// Set `Vx` = random byte AND `mask`.
@@ -294,6 +801,9 @@ impl VM {
     fn rnd(&mut self, x: u8, mask: u8) {
         let value = self.rng.gen::<u8>() & mask;
         self.registers.v[x as usize] = value;
+        if let Some(rng_log) = &mut self.rng_log {
+            rng_log.record(self.registers.program_counter, mask, value);
+        }
         self.next_instruction(1);
     }
 
@@ -308,19 +818,126 @@ impl VM {
     /// If this causes any pixels to be erased, `VF` is set to 1, otherwise it
     /// is set to 0. If the sprite is positioned so part of it is outside the
     /// coordinates of the display, it wraps around to the opposite side of the
-    /// screen. See instruction `8xy3` for more information on XOR, and section
-    /// Display for more information on the Chip-8 screen and sprites.
-    fn drw(&mut self, x: u8, y: u8, n: u8) {
+    /// screen. The starting coordinates themselves wrap too, so `Vx` = 70
+    /// draws at column 6. See instruction `8xy3` for more information on
+    /// XOR, and section Display for more information on the Chip-8 screen
+    /// and sprites.
+    ///
+    /// Sprite bytes past the end of memory are read according to the
+    /// [`OutOfRange`] policy, see [`VM::set_out_of_range`]. With
+    /// [`OutOfRange::Error`] it fails with [`VmError::MemoryOutOfBounds`].
+    ///
+    /// With [`VM::set_sprite_rows_per_frame`] the sprite is drawn a few rows
+    /// a frame and the instruction repeats until it's done.
+    ///
+    /// With the `clip_sprites` quirk the sprite is cut at the display edges
+    /// instead of wrapping, and with `display_wait` the instruction repeats
+    /// until timers tick, drawing right after they do.
+    ///
+    /// In SUPER-CHIP high resolution `Dxy0` draws a 16 x 16 sprite of 32
+    /// bytes, 2 a row, all at once, and sets `VF` to the number of rows
+    /// which erased a pixel like SUPER-CHIP 1.1.
+    fn drw(&mut self, x: u8, y: u8, n: u8) -> Result<(), VmError> {
+        if self.quirks.display_wait && !self.refreshed && self.draw_progress.is_none() {
+            return Ok(());
+        }
         let sprite_start = self.registers.i as usize;
-        let sprite_end = sprite_start + n as usize;
-        let sprite = self.memory.get_slice(sprite_start, sprite_end);
-
-        let x_coord = self.registers.v[x as usize] as usize;
-        let y_coord = self.registers.v[y as usize] as usize;
-        let is_collision = self.graphics.draw_sprite(x_coord, y_coord, sprite);
+        let sprite = self
+            .memory
+            .read(sprite_start, self.sprite_size(n), self.out_of_range)
+            .ok_or(VmError::MemoryOutOfBounds {
+                pc: self.registers.program_counter,
+                address: sprite_start,
+            })?;
+
+        let (cols, rows) = self.resolution().size();
+        let x_coord = self.registers.v[x as usize] as usize % cols;
+        let y_coord = self.registers.v[y as usize] as usize % rows;
+        let clip = self.quirks.clip_sprites;
+        let collisions = match (&mut self.hires, n) {
+            (Some(hires), 0) if clip => hires.draw_large_sprite_clipped(x_coord, y_coord, &sprite),
+            (Some(hires), 0) => hires.draw_large_sprite(x_coord, y_coord, &sprite),
+            _ => {
+                let is_collision = match self.sprite_rows_per_frame {
+                    None => draw_on_screen(
+                        &mut self.graphics,
+                        &mut self.hires,
+                        clip,
+                        x_coord,
+                        y_coord,
+                        &sprite,
+                    ),
+                    Some(_) => {
+                        let mut progress = self.draw_progress.take().unwrap_or_default();
+                        let start = progress.rows as usize;
+                        let end = sprite.len().min(start + self.sprite_rows_left as usize);
+                        progress.collision |= draw_on_screen(
+                            &mut self.graphics,
+                            &mut self.hires,
+                            clip,
+                            x_coord,
+                            y_coord + start,
+                            &sprite[start..end],
+                        );
+                        self.sprite_rows_left -= (end - start) as u8;
+                        progress.rows = end as u8;
+                        if end < sprite.len() {
+                            // Wait for the next frame to draw the rest.
+                            self.draw_progress = Some(progress);
+                            return Ok(());
+                        }
+                        progress.collision
+                    }
+                };
+                is_collision as usize
+            }
+        };
+        let is_collision = collisions > 0;
+        let height = match (&self.hires, n) {
+            (Some(_), 0) => LARGE_SPRITE_ROWS,
+            _ => n,
+        };
+        if let Some(draw_log) = &mut self.draw_log {
+            draw_log.record(
+                self.registers.i,
+                x_coord as u8,
+                y_coord as u8,
+                height,
+                is_collision,
+            );
+        }
+        let draw = Draw {
+            pc: self.registers.program_counter,
+            address: self.registers.i,
+            x: x_coord as u8,
+            y: y_coord as u8,
+            height,
+            collision: is_collision,
+        };
+        self.notify_observers(|observer, vm| observer.on_draw(vm, &draw));
+        if is_collision {
+            if let Some(handler) = &mut self.collision_handler {
+                handler(&Collision {
+                    pc: self.registers.program_counter,
+                    address: self.registers.i,
+                    x: x_coord as u8,
+                    y: y_coord as u8,
+                    height,
+                });
+            }
+        }
 
-        self.registers.v[0xF] = if is_collision { 1 } else { 0 };
+        self.registers.v[0xF] = collisions as u8;
         self.next_instruction(1);
+        Ok(())
+    }
+
+    /// Bytes of the sprite drawn by `Dxyn`.
+    fn sprite_size(&self, n: u8) -> usize {
+        match (&self.hires, n) {
+            (Some(_), 0) => LARGE_SPRITE_SIZE,
+            _ => n as usize,
+        }
     }
 
     /// Skip next instruction if key with the value of `Vx` is pressed.
@@ -331,8 +948,8 @@ impl VM {
     /// is currently in the down position, program counter is increased by 2.
     fn skp(&mut self, x: u8) {
         let key = self.registers.v[x as usize];
-        if self.input.get_pressed_key() == Some(key) {
-            self.next_instruction(2);
+        if self.input.is_pressed(key) {
+            self.skip_next_instruction();
         } else {
             self.next_instruction(1);
         }
@@ -346,8 +963,8 @@ impl VM {
     /// is currently in the up position, program counter is increased by 2.
     fn sknp(&mut self, x: u8) {
         let key = self.registers.v[x as usize];
-        if self.input.get_pressed_key() != Some(key) {
-            self.next_instruction(2);
+        if !self.input.is_pressed(key) {
+            self.skip_next_instruction();
         } else {
             self.next_instruction(1);
         }
@@ -377,10 +994,24 @@ impl VM {
     ///
     /// Code: `Fx0A`
     ///
-    /// All execution stops until a key is pressed, then the value of that key
-    /// is stored in `Vx`.
+    /// All execution stops until a key is pressed and released, like on the
+    /// COSMAC VIP, then the value of that key is stored in `Vx`. With the
+    /// `get_key_on_press` quirk execution resumes on the press.
     fn ld_vx_k(&mut self, x: u8) {
-        if let Some(key) = self.input.get_pressed_key() {
+        let pressed = self.input.first_pressed();
+        let key = if self.quirks.get_key_on_press {
+            pressed
+        } else {
+            match self.key_latch {
+                Some(key) if !self.input.is_pressed(key) => self.key_latch.take(),
+                Some(_) => None,
+                None => {
+                    self.key_latch = pressed;
+                    None
+                }
+            }
+        };
+        if let Some(key) = key {
             self.registers.v[x as usize] = key;
             self.next_instruction(1);
         }
@@ -392,7 +1023,9 @@ impl VM {
     ///
     /// Sound timer is set equal to the value of `Vx`.
     fn ld_st(&mut self, x: u8) {
+        let was_playing = self.is_sound_playing();
         self.registers.sound_timer = self.registers.v[x as usize];
+        self.notify_sound(was_playing);
         self.next_instruction(1);
     }
 
@@ -420,6 +1053,19 @@ impl VM {
         self.next_instruction(1);
     }
 
+    /// Set `I` = location of the big sprite for digit `Vx`.
+    ///
+    /// Code: `Fx30`
+    ///
+    /// SUPER-CHIP extension. Big digits are 8 x 10 pixels, drawn with
+    /// `DxyA`, see [`VM::load_big_font`].
+    fn ld_hf(&mut self, x: u8) {
+        let digit = self.registers.v[x as usize] as usize;
+        let sprite_location = BIG_SPRITE_START_LOCATION + (digit * BIG_SPRITE_SIZE);
+        self.registers.i = sprite_location as u16;
+        self.next_instruction(1);
+    }
+
     /// Store BCD representation of `Vx` in memory locations `I`, `I+1`, and
     /// `I+2`.
     ///
@@ -439,6 +1085,7 @@ impl VM {
         slice[0] = hundreds;
         slice[1] = tens;
         slice[2] = ones;
+        self.notify_io(start_pos, 3);
         self.next_instruction(1);
     }
 
@@ -447,7 +1094,8 @@ impl VM {
     /// Code: `Fx55`
     ///
     /// The interpreter copies the values of registers `V0` through `Vx` into
-    /// memory, starting at the address in `I`.
+    /// memory, starting at the address in `I`. With the
+    /// `load_store_increments_i` quirk `I` is left past the last address.
     fn ld_i_vx(&mut self, x: u8) {
         let registers = &self.registers.v[0..=x as usize];
         let start = self.registers.i as usize;
@@ -455,7 +1103,9 @@ impl VM {
         let memory = self.memory.get_slice_mut(start, finish);
 
         memory.copy_from_slice(registers);
+        self.notify_io(start, finish - start);
 
+        self.load_store_quirk(x);
         self.next_instruction(1);
     }
 
@@ -464,7 +1114,8 @@ impl VM {
     /// Code: `Fx65`
     ///
     /// The interpreter reads values from memory starting at location `I` into
-    /// registers `V0` through `Vx`.
+    /// registers `V0` through `Vx`. With the `load_store_increments_i` quirk
+    /// `I` is left past the last address.
     fn ld_vx_i(&mut self, x: u8) {
         let registers = &mut self.registers.v[0..=x as usize];
         let start_memory_pos = self.registers.i as usize;
@@ -473,962 +1124,3282 @@ impl VM {
 
         registers.copy_from_slice(memory);
 
+        self.load_store_quirk(x);
+        self.next_instruction(1);
+    }
+
+    /// Store registers `V0` through `Vx` in the RPL flags.
+    ///
+    /// Code: `Fx75`
+    ///
+    /// SUPER-CHIP extension. There are [`RPL_FLAGS`] flags, registers past
+    /// them are left out. Fails, with flags left as they were, if they
+    /// can't be stored, see [`VM::set_rpl_flags`].
+    fn ld_r_vx(&mut self, x: u8) -> Result<(), VmError> {
+        let count = (x as usize + 1).min(RPL_FLAGS);
+        self.rpl_flags
+            .set(&self.registers.v[..count])
+            .map_err(|_| VmError::FlagStorage {
+                pc: self.registers.program_counter,
+            })?;
+        self.next_instruction(1);
+        Ok(())
+    }
+
+    /// Read registers `V0` through `Vx` from the RPL flags.
+    ///
+    /// Code: `Fx85`
+    ///
+    /// SUPER-CHIP extension. There are [`RPL_FLAGS`] flags, registers past
+    /// them are left as they were.
+    fn ld_vx_r(&mut self, x: u8) {
+        let count = (x as usize + 1).min(RPL_FLAGS);
+        self.registers.v[..count].copy_from_slice(&self.rpl_flags.flags()[..count]);
         self.next_instruction(1);
     }
 
+    /// Move `I` past `V0` through `Vx` after `Fx55` and `Fx65` if the quirk
+    /// says so.
+    fn load_store_quirk(&mut self, x: u8) {
+        if self.quirks.load_store_increments_i {
+            self.registers.i += x as u16 + 1;
+        }
+    }
+
     /// Execute instruction `inst`
     ///
     /// `inst` integer should be in navite endian order.
-    #[allow(clippy::cognitive_complexity)]
-    pub fn exec_instruction(&mut self, inst: u16) {
-        match inst {
-            0x00E0 => self.cls(),
-            0x00EE => self.ret(),
-            inst if inst & 0xF000 == 0x1000 => {
-                let addr = inst & 0x0FFF;
-                self.jp(addr);
-            }
-            inst if inst & 0xF000 == 0x2000 => {
-                let addr = inst & 0x0FFF;
-                self.call(addr);
-            }
-            inst if inst & 0xF000 == 0x3000 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let value = (inst & 0x00FF) as u8;
-                self.se(x, value);
-            }
-            inst if inst & 0xF000 == 0x4000 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let value = (inst & 0x00FF) as u8;
-                self.sne(x, value);
-            }
-            inst if inst & 0xF00F == 0x5000 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                self.se_v(x, y);
-            }
-            inst if inst & 0xF000 == 0x6000 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let value = (inst & 0x00FF) as u8;
-                self.ld_vx(x, value);
-            }
-            inst if inst & 0xF000 == 0x7000 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let value = (inst & 0x00FF) as u8;
-                self.add_vx(x, value);
-            }
-            inst if inst & 0xF00F == 0x8000 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                self.ld_vx_vy(x, y);
-            }
-            inst if inst & 0xF00F == 0x8001 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                self.or(x, y);
-            }
-            inst if inst & 0xF00F == 0x8002 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                self.and(x, y);
-            }
-            inst if inst & 0xF00F == 0x8003 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                self.xor(x, y);
-            }
-            inst if inst & 0xF00F == 0x8004 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                self.add_vx_vy(x, y);
-            }
-            inst if inst & 0xF00F == 0x8005 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                self.sub(x, y);
-            }
-            inst if inst & 0xF00F == 0x8006 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.shr(x);
-            }
-            inst if inst & 0xF00F == 0x8007 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                self.subn(x, y);
-            }
-            inst if inst & 0xF00F == 0x800E => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.shl(x);
-            }
-            inst if inst & 0xF00F == 0x9000 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                self.sne_vx_vy(x, y);
-            }
-            inst if inst & 0xF000 == 0xA000 => {
-                let value = inst & 0x0FFF;
-                self.ld_i(value);
-            }
-            inst if inst & 0xF000 == 0xB000 => {
-                let addr = inst & 0x0FFF;
-                self.jp_v0(addr);
-            }
-            inst if inst & 0xF000 == 0xC000 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let mask = (inst & 0x00FF) as u8;
-                self.rnd(x, mask);
-            }
-            inst if inst & 0xF000 == 0xD000 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                let y = ((inst & 0x00F0) >> 4) as u8;
-                let n = (inst & 0x000F) as u8;
-                self.drw(x, y, n);
-            }
-            inst if inst & 0xF0FF == 0xE09E => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.skp(x);
-            }
-            inst if inst & 0xF0FF == 0xE0A1 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.sknp(x);
-            }
-            inst if inst & 0xF0FF == 0xF007 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.ld_vx_dt(x);
-            }
-            inst if inst & 0xF0FF == 0xF00A => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.ld_vx_k(x);
-            }
-            inst if inst & 0xF0FF == 0xF015 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.ld_dt_vx(x);
-            }
-            inst if inst & 0xF0FF == 0xF018 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.ld_st(x);
+    pub fn exec_instruction(&mut self, inst: u16) -> Result<(), VmError> {
+        let pc = self.registers.program_counter;
+        if let Some(trace) = &mut self.trace {
+            trace.record(pc, inst);
+        }
+        self.notify_observers(|observer, vm| observer.before_instruction(vm, pc, inst));
+        let instruction = self.decode_at(pc, inst)?;
+        self.execute(instruction)?;
+        self.notify_observers(|observer, vm| observer.after_instruction(vm, pc, inst));
+        Ok(())
+    }
+
+    /// Decode word `inst` at `pc`, reading the address after it for
+    /// `LDL I`.
+    fn decode_at(&self, pc: u16, inst: u16) -> Result<Instruction, VmError> {
+        if inst == LONG_LD_I {
+            let address =
+                self.word_at(pc as usize + INSTRUCTION_SIZE)
+                    .ok_or(VmError::MemoryOutOfBounds {
+                        pc,
+                        address: pc as usize,
+                    })?;
+            Ok(Instruction::LdILong(address))
+        } else {
+            Instruction::decode(inst).map_err(|e| VmError::UnknownInstruction {
+                pc,
+                instruction: e.word,
+            })
+        }
+    }
+
+    /// Execute decoded `instruction` as if it was at the program counter.
+    /// Instructions built by hand are checked like decoded ones, failing
+    /// with [`VmError::InvalidOperand`] if they couldn't have been decoded.
+    pub fn execute(&mut self, instruction: Instruction) -> Result<(), VmError> {
+        self.check(instruction)?;
+        match self.register_history {
+            Some(_) => {
+                let pc = self.registers.program_counter;
+                let before = self.registers.clone();
+                self.dispatch(instruction)?;
+                if let Some(history) = &mut self.register_history {
+                    // Instructions leaving the program counter where it was
+                    // are waiting, for a key or the rest of a sprite, and
+                    // haven't written anything yet.
+                    let (written_v, writes_i) = if self.registers.program_counter == pc {
+                        (0, false)
+                    } else {
+                        (instruction.written_v(), instruction.writes_i())
+                    };
+                    history.record_writes(pc, written_v, writes_i, &before, &self.registers);
+                }
             }
-            inst if inst & 0xF0FF == 0xF01E => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.add_i(x);
+            None => self.dispatch(instruction)?,
+        }
+        self.refreshed = false;
+        Ok(())
+    }
+
+    /// Fail if `instruction` can't be executed in the current state, before
+    /// any of it is.
+    fn check(&self, instruction: Instruction) -> Result<(), VmError> {
+        let pc = self.registers.program_counter;
+        let i = self.registers.i as usize;
+        if !instruction.is_valid() {
+            return Err(VmError::InvalidOperand { pc, instruction });
+        }
+        if instruction.family() > self.opcode_family {
+            return Err(VmError::UnknownInstruction {
+                pc,
+                instruction: instruction.encode(),
+            });
+        }
+        let accessed = match instruction {
+            Instruction::Sys(_) => {
+                return Err(VmError::UnknownInstruction {
+                    pc,
+                    instruction: instruction.encode(),
+                })
             }
-            inst if inst & 0xF0FF == 0xF029 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.ld_f(x);
+            Instruction::Ret if self.stack.is_empty() => {
+                return Err(VmError::StackUnderflow { pc })
             }
-            inst if inst & 0xF0FF == 0xF033 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.ld_b(x);
+            Instruction::Call(_) if self.stack.is_full() => {
+                return Err(VmError::StackOverflow { pc })
             }
-            inst if inst & 0xF0FF == 0xF055 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.ld_i_vx(x);
+            Instruction::Drw(_, _, n) if self.out_of_range == OutOfRange::Error => {
+                self.sprite_size(n)
             }
-            inst if inst & 0xF0FF == 0xF065 => {
-                let x = ((inst & 0x0F00) >> 8) as u8;
-                self.ld_vx_i(x);
+            Instruction::LdBVx(_) => 3,
+            Instruction::LdMemVx(x) | Instruction::LdVxMem(x) => x as usize + 1,
+            _ => 0,
+        };
+        if accessed > 0 && i + accessed > MEMORY_SIZE {
+            return Err(VmError::MemoryOutOfBounds { pc, address: i });
+        }
+        Ok(())
+    }
+
+    fn dispatch(&mut self, instruction: Instruction) -> Result<(), VmError> {
+        use Instruction::*;
+        match instruction {
+            Sys(_) => {
+                return Err(VmError::UnknownInstruction {
+                    pc: self.registers.program_counter,
+                    instruction: instruction.encode(),
+                })
             }
-            _ => panic!("unexpected instruction: {:#06X}", inst),
+            Cls => self.cls(),
+            Ret => self.ret()?,
+            Low => self.low(),
+            High => self.high(),
+            Scd(n) => self.scd(n),
+            Scr => self.scr(),
+            Scl => self.scl(),
+            Jp(addr) => self.jp(addr),
+            Call(addr) => self.call(addr)?,
+            SeByte(x, value) => self.se(x, value),
+            SneByte(x, value) => self.sne(x, value),
+            SeReg(x, y) => self.se_v(x, y),
+            LdByte(x, value) => self.ld_vx(x, value),
+            AddByte(x, value) => self.add_vx(x, value),
+            LdReg(x, y) => self.ld_vx_vy(x, y),
+            Or(x, y) => self.or(x, y),
+            And(x, y) => self.and(x, y),
+            Xor(x, y) => self.xor(x, y),
+            AddReg(x, y) => self.add_vx_vy(x, y),
+            Sub(x, y) => self.sub(x, y),
+            Shr(x, y) => self.shr(x, y),
+            Subn(x, y) => self.subn(x, y),
+            Shl(x, y) => self.shl(x, y),
+            SneReg(x, y) => self.sne_vx_vy(x, y),
+            LdI(value) => self.ld_i(value),
+            JpV0(addr) => self.jp_v0(addr),
+            Rnd(x, mask) => self.rnd(x, mask),
+            Drw(x, y, n) => self.drw(x, y, n)?,
+            Skp(x) => self.skp(x),
+            Sknp(x) => self.sknp(x),
+            LdVxDt(x) => self.ld_vx_dt(x),
+            LdVxK(x) => self.ld_vx_k(x),
+            LdDtVx(x) => self.ld_dt_vx(x),
+            LdStVx(x) => self.ld_st(x),
+            AddIVx(x) => self.add_i(x),
+            LdFVx(x) => self.ld_f(x),
+            LdHfVx(x) => self.ld_hf(x),
+            LdBVx(x) => self.ld_b(x),
+            LdMemVx(x) => self.ld_i_vx(x),
+            LdVxMem(x) => self.ld_vx_i(x),
+            LdRVx(x) => self.ld_r_vx(x)?,
+            LdVxR(x) => self.ld_vx_r(x),
+            LdILong(address) => self.ld_i_long(address),
         }
+        Ok(())
+    }
+
+    /// Load program `program`, replacing previously loaded one. Fails, with
+    /// the VM left as it was, if the program is empty or doesn't fit in
+    /// memory from the program start address.
+    pub fn load_program(&mut self, program: &[u8]) -> Result<(), LoadError> {
+        check_program(self.program_start, program)?;
+        self.memory.clear_program_area();
+        self.memory.load_program_at(self.program_start, program)?;
+        self.registers.program_counter = self.program_start as u16;
+        self.draw_progress = None;
+        self.key_latch = None;
+        self.program = program.to_vec();
+        Ok(())
+    }
+
+    /// Address programs are loaded at and start from by the next
+    /// [`VM::load_program`], e.g.
+    /// [`ETI_660_PROGRAM_START_LOCATION`](super::memory::ETI_660_PROGRAM_START_LOCATION).
+    pub fn set_program_start(&mut self, address: usize) {
+        assert!(address < MEMORY_SIZE);
+        self.program_start = address;
+    }
+
+    pub fn program_start(&self) -> usize {
+        self.program_start
+    }
+
+    /// Restart the loaded program: registers, stack, display and timers are
+    /// cleared and program memory is put back as it was loaded, undoing
+    /// whatever the program stored. Fonts, quirks, settings and keys held
+    /// are kept.
+    pub fn reset(&mut self) {
+        let was_playing = self.is_sound_playing();
+        self.registers = Registers::new();
+        self.registers.program_counter = self.program_start as u16;
+        self.stack = Stack::new();
+        self.graphics = Graphics::new();
+        self.hires = None;
+        self.memory.clear_program_area();
+        // Nothing is loaded after a hard reset, or if the program start moved
+        // so that the program doesn't fit anymore.
+        let _ = self
+            .memory
+            .load_program_at(self.program_start, &self.program);
+        self.draw_progress = None;
+        self.key_latch = None;
+        self.sprite_rows_left = self.sprite_rows_per_frame.map_or(0, NonZeroU8::get);
+        self.instructions_since_tick = 0;
+        self.timer_remainder = 0;
+        self.instruction_remainder = 0;
+        self.refreshed = false;
+        self.notify_sound(was_playing);
+    }
+
+    /// Program last loaded, as it was before running.
+    pub fn program(&self) -> &[u8] {
+        &self.program
+    }
+
+    /// [`VM::reset`] and forget the loaded program, leaving program memory
+    /// empty until the next [`VM::load_program`].
+    pub fn hard_reset(&mut self) {
+        self.program = Vec::new();
+        self.reset();
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// SUPER-CHIP RPL flags `Fx75` stores registers in.
+    pub fn rpl_flags(&self) -> &[u8; RPL_FLAGS] {
+        self.rpl_flags.flags()
+    }
+
+    /// Keep RPL flags in `flags`, e.g. [`RplFlags::with_storage`] for games
+    /// to find their saved progress on the next run. They're only kept in
+    /// memory otherwise.
+    pub fn set_rpl_flags(&mut self, flags: RplFlags) {
+        self.rpl_flags = flags;
+    }
+
+    /// Set `Vx` to `value`.
+    pub fn set_register(&mut self, x: u8, value: u8) {
+        assert!((x as usize) < V_REGISTERS_SIZE);
+        self.registers.v[x as usize] = value;
     }
 
-    /// Load program `program`.
-    pub fn load_program(&mut self, program: &[u8]) {
-        self.memory.load_program(program);
-        self.registers.program_counter = PROGRAM_START_LOCATION as u16;
+    /// Set `I` to `value`, which instructions accessing memory through it
+    /// check like any other address.
+    pub fn set_i(&mut self, value: u16) {
+        self.registers.i = value;
     }
 
-    pub fn exec_current_instruction(&mut self) {
-        let instruction = self.read_current_instruction();
-        self.exec_instruction(instruction);
-        self.decrement_timers();
+    pub fn display(&self) -> &Graphics {
+        &self.graphics
     }
 
-    fn read_current_instruction(&self) -> u16 {
-        self.memory
-            .fetch_instruction(self.registers.program_counter as usize)
+    pub fn resolution(&self) -> Resolution {
+        match self.hires {
+            Some(_) => Resolution::High,
+            None => Resolution::Low,
+        }
     }
 
-    /// Advance program counter by `n` instructions.
-    fn next_instruction(&mut self, n: usize) {
-        self.registers.program_counter += (n * INSTRUCTION_SIZE) as u16;
+    /// High resolution display, while it's in use.
+    pub fn hires_graphics(&self) -> Option<&SchipGraphics> {
+        self.hires.as_ref()
     }
 
-    fn decrement_timers(&mut self) {
-        if self.registers.delay_timer > 0 {
-            self.registers.delay_timer -= 1;
-        }
-        if self.registers.sound_timer > 0 {
-            self.registers.sound_timer -= 1;
+    /// Display in use, low or high resolution.
+    pub fn screen(&self) -> &dyn Framebuffer {
+        match &self.hires {
+            Some(hires) => hires,
+            None => &self.graphics,
         }
     }
-}
 
-impl Default for VM {
-    fn default() -> Self {
-        Self {
-            memory: Memory::new_with_initial_sprites(),
-            registers: Registers::new(),
-            stack: Stack::new(),
-            graphics: Graphics::new(),
-            input: Input::new(),
-            rng: SmallRng::seed_from_u64(0),
+    /// Hash of the display in use, see [`Graphics::hash`].
+    pub fn display_hash(&self) -> u64 {
+        match &self.hires {
+            Some(hires) => hires.hash(),
+            None => self.graphics.hash(),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::super::graphics::DISPLAY_ROWS;
-    use super::*;
-    use std::u64;
+    /// Addresses of the `CALL`s of the subroutines being run, outermost
+    /// first.
+    pub fn stack_frames(&self) -> &[u16] {
+        self.stack.frames()
+    }
 
-    #[test]
-    fn test_jp() {
-        let mut vm = VM::new();
-        let addr = 16u16;
+    /// Bytes of memory at addresses in `range`, `None` if it runs past the
+    /// end of memory.
+    pub fn memory(&self, range: Range<usize>) -> Option<&[u8]> {
+        self.memory.get(range)
+    }
 
-        vm.jp(addr);
+    /// Overwrite memory starting at `address` with `bytes`. If they run
+    /// past the end of memory nothing is written and it fails with
+    /// [`VmError::MemoryOutOfBounds`] at the program counter.
+    pub fn write_memory(&mut self, address: usize, bytes: &[u8]) -> Result<(), VmError> {
+        let pc = self.registers.program_counter;
+        self.memory
+            .get_mut(address..address.saturating_add(bytes.len()))
+            .ok_or(VmError::MemoryOutOfBounds { pc, address })?
+            .copy_from_slice(bytes);
+        Ok(())
+    }
 
-        assert_eq!(vm.registers.program_counter, addr);
+    /// Replace built-in hexadecimal font with `font`, 5 bytes per digit.
+    pub fn load_font(&mut self, font: &[u8; FONT_SIZE]) {
+        self.memory.load_font(font);
     }
 
-    #[test]
-    fn test_jp_edge_case() {
-        let mut vm = VM::new();
-        let addr = 0x0FFF;
+    /// Replace hexadecimal font with built-in `style`.
+    pub fn set_font_style(&mut self, style: FontStyle) {
+        self.memory.load_font(style.sprites());
+    }
 
-        vm.jp(addr);
+    /// Replace built-in high-resolution font with `font`, 10 bytes per digit.
+    pub fn load_big_font(&mut self, font: &[u8; BIG_FONT_SIZE]) {
+        self.memory.load_big_font(font);
+    }
 
-        assert_eq!(vm.registers.program_counter, addr);
+    /// Copy complete VM state.
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            memory: self.memory.clone(),
+            registers: self.registers.clone(),
+            stack: self.stack.clone(),
+            graphics: self.graphics.clone(),
+            hires: self.hires.clone(),
+            input: self.input,
+            rng: self.rng.clone(),
+        }
     }
 
-    #[test]
-    #[should_panic]
-    fn test_jp_incorrect_addr() {
-        let mut vm = VM::new();
-        vm.jp(0x1000);
+    /// Return VM to the state of `snapshot`.
+    pub fn restore(&mut self, snapshot: &StateSnapshot) {
+        let was_playing = self.is_sound_playing();
+        self.memory = snapshot.memory.clone();
+        self.registers = snapshot.registers.clone();
+        self.stack = snapshot.stack.clone();
+        self.graphics = snapshot.graphics.clone();
+        self.hires = snapshot.hires.clone();
+        self.input = snapshot.input;
+        self.rng = snapshot.rng.clone();
+        self.draw_progress = None;
+        self.key_latch = None;
+        self.notify_sound(was_playing);
     }
 
-    #[test]
-    fn test_cls() {
-        let mut vm = VM::new();
-        vm.graphics.display = [u64::MAX; DISPLAY_ROWS];
-        assert_eq!(vm.registers.program_counter, 0);
+    /// Run the VM, yielding a snapshot every `stride` frames.
+    pub fn snapshots(&mut self, stride: u64) -> Snapshots<'_> {
+        Snapshots::new(self, stride)
+    }
 
-        vm.cls();
+    /// Start recording up to `capacity` last writes to every `V` register
+    /// and `I`, with the instruction which wrote them, discarding previously
+    /// recorded history.
+    pub fn enable_register_history(&mut self, capacity: usize) {
+        self.register_history = Some(RegisterHistory::new(capacity));
+    }
+
+    pub fn disable_register_history(&mut self) {
+        self.register_history = None;
+    }
+
+    /// Recorded register changes, if enabled.
+    pub fn register_history(&self) -> Option<&RegisterHistory> {
+        self.register_history.as_ref()
+    }
+
+    /// Start recording sprite draws of `frames` last frames, counted in timer
+    /// ticks, discarding previously recorded ones.
+    pub fn enable_draw_log(&mut self, frames: u64) {
+        self.draw_log = Some(DrawLog::new(frames));
+    }
+
+    pub fn disable_draw_log(&mut self) {
+        self.draw_log = None;
+    }
+
+    /// Recorded sprite draws, if enabled.
+    pub fn draw_log(&self) -> Option<&DrawLog> {
+        self.draw_log.as_ref()
+    }
+
+    /// Start recording up to `capacity` last `RND` calls, discarding
+    /// previously recorded ones.
+    pub fn enable_rng_log(&mut self, capacity: usize) {
+        self.rng_log = Some(RngLog::new(capacity));
+    }
+
+    pub fn disable_rng_log(&mut self) {
+        self.rng_log = None;
+    }
+
+    /// Recorded `RND` calls, if enabled.
+    pub fn rng_log(&self) -> Option<&RngLog> {
+        self.rng_log.as_ref()
+    }
+
+    /// Turn on sandbox mode, for running untrusted programs: instructions
+    /// which would fail with a [`VmError`] are skipped instead, and up to
+    /// `capacity` last of them recorded. Running never fails then.
+    ///
+    /// Only applies to instructions run at the program counter, not to
+    /// [`VM::exec_instruction`] and [`VM::execute`].
+    pub fn enable_sandbox(&mut self, capacity: usize) {
+        self.fault_log = Some(FaultLog::new(capacity));
+    }
+
+    pub fn disable_sandbox(&mut self) {
+        self.fault_log = None;
+    }
+
+    /// Faults skipped in sandbox mode, if enabled.
+    pub fn fault_log(&self) -> Option<&FaultLog> {
+        self.fault_log.as_ref()
+    }
+
+    /// Start recording up to `capacity` last instructions executed,
+    /// discarding previously recorded ones.
+    pub fn enable_trace(&mut self, capacity: usize) {
+        self.trace = Some(Trace::new(capacity));
+    }
+
+    pub fn disable_trace(&mut self) {
+        self.trace = None;
+    }
+
+    /// Last instructions executed, if the trace is enabled.
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// Call `handler` every time `DRW` erases pixels and sets `VF` to 1,
+    /// replacing the previous handler.
+    pub fn on_collision(&mut self, handler: impl FnMut(&Collision) + Send + Sync + 'static) {
+        self.collision_handler = Some(Box::new(handler));
+    }
+
+    /// Call `handler` for every byte the program stores at addresses in
+    /// `range`, after storing it, replacing the previous mapping. Only `LD B`
+    /// and `LD [I]` store bytes.
+    pub fn on_io_write(
+        &mut self,
+        range: Range<usize>,
+        handler: impl FnMut(&IoWrite) + Send + Sync + 'static,
+    ) {
+        self.io_map = Some(IoMap {
+            range,
+            handler: Box::new(handler),
+        });
+    }
+
+    /// Stop calling the handler set by [`VM::on_io_write`].
+    pub fn unmap_io(&mut self) {
+        self.io_map = None;
+    }
+
+    /// Pass `len` bytes just stored at `address` to the I/O handler.
+    fn notify_io(&mut self, address: usize, len: usize) {
+        if let Some(io_map) = &mut self.io_map {
+            io_map.written(address, self.memory.get_slice(address, address + len));
+        }
+    }
+
+    /// Call `handler` when the beeper starts sounding, i.e. the sound timer
+    /// is set from 0, replacing the previous handler.
+    pub fn on_sound_start(&mut self, handler: impl FnMut() + Send + Sync + 'static) {
+        self.sound_start_handler = Some(Box::new(handler));
+    }
+
+    /// Call `handler` when the beeper stops sounding, i.e. the sound timer
+    /// reaches 0, replacing the previous handler.
+    pub fn on_sound_stop(&mut self, handler: impl FnMut() + Send + Sync + 'static) {
+        self.sound_stop_handler = Some(Box::new(handler));
+    }
+
+    /// Pass events to `observer` as the VM runs, until it's removed with
+    /// [`VM::remove_observer`]. Observers are called in the order they were
+    /// added.
+    pub fn add_observer(&mut self, observer: impl VmObserver + 'static) -> ObserverId {
+        let id = ObserverId(self.next_observer_id);
+        self.next_observer_id += 1;
+        self.observers.push((id, Box::new(observer)));
+        id
+    }
+
+    /// Stop passing events to observer `id`, false if it was removed
+    /// already.
+    pub fn remove_observer(&mut self, id: ObserverId) -> bool {
+        let len = self.observers.len();
+        self.observers.retain(|(observer_id, _)| *observer_id != id);
+        self.observers.len() < len
+    }
+
+    /// Call `event` on every observer with the VM as it is now.
+    fn notify_observers(&mut self, mut event: impl FnMut(&mut dyn VmObserver, &VM)) {
+        if self.observers.is_empty() {
+            return;
+        }
+        // Taken out for the time of the calls so they can see the VM.
+        let mut observers = core::mem::take(&mut self.observers);
+        for (_, observer) in &mut observers {
+            event(observer.as_mut(), self);
+        }
+        self.observers = observers;
+    }
+
+    /// Call the sound handler for the change from `was_playing`, if any.
+    fn notify_sound(&mut self, was_playing: bool) {
+        let handler = match (was_playing, self.is_sound_playing()) {
+            (false, true) => &mut self.sound_start_handler,
+            (true, false) => &mut self.sound_stop_handler,
+            _ => return,
+        };
+        if let Some(handler) = handler {
+            handler();
+        }
+    }
+
+    /// Whether the beeper should sound, i.e. the sound timer is active.
+    pub fn is_sound_playing(&self) -> bool {
+        self.registers.sound_timer > 0
+    }
+
+    /// Whether the program is spinning in the common idle loop waiting for
+    /// the delay timer to expire:
+    ///
+    /// ```text
+    /// loop: Fx07    ; Vx = DT
+    ///       3x00    ; skip next if Vx == 0
+    ///       1loop   ; jump to loop
+    /// ```
+    ///
+    /// Until the next timer tick executing it changes nothing, so a runner
+    /// can sleep instead of burning cycles.
+    pub fn is_waiting_for_timer(&self) -> bool {
+        if self.registers.delay_timer == 0 {
+            return false;
+        }
+        let pc = self.registers.program_counter as usize;
+        let read = match self.read_current_instruction() {
+            Ok(read) => read,
+            Err(_) => return false,
+        };
+        let x = (read & 0x0F00) >> 8;
+        read & 0xF0FF == 0xF007
+            && self.word_at(pc + INSTRUCTION_SIZE) == Some(0x3000 | (x << 8))
+            && self.word_at(pc + 2 * INSTRUCTION_SIZE) == Some(0x1000 | pc as u16)
+    }
+
+    /// Size of each subsystem, inline and on the heap. The heap holds the
+    /// copy of the loaded program and whichever of the register history,
+    /// draw log, RNG log, fault log and trace are enabled, so a new VM
+    /// doesn't allocate. Callbacks, observers and a custom RNG aren't
+    /// counted.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        MemoryFootprint {
+            memory: size_of::<Memory>(),
+            registers: size_of::<Registers>(),
+            stack: size_of::<Stack>(),
+            graphics: size_of::<Graphics>(),
+            input: size_of::<Input>(),
+            rng: size_of::<Generator>(),
+            program: self.program.capacity(),
+            register_history: self
+                .register_history
+                .as_ref()
+                .map_or(0, RegisterHistory::heap_size),
+            draw_log: self.draw_log.as_ref().map_or(0, DrawLog::heap_size),
+            rng_log: self.rng_log.as_ref().map_or(0, RngLog::heap_size),
+            fault_log: self.fault_log.as_ref().map_or(0, FaultLog::heap_size),
+            trace: self.trace.as_ref().map_or(0, Trace::heap_size),
+            vm: size_of::<VM>(),
+        }
+    }
+
+    /// Reseed the random number generator used by `Cxkk`.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng = Generator::seeded(seed);
+    }
+
+    /// Replace the random number generator used by `Cxkk` with `rng`, e.g.
+    /// one seeded from OS entropy. It's kept on the heap.
+    pub fn set_rng(&mut self, rng: impl RngSource + 'static) {
+        self.rng = Generator::Custom(Box::new(rng));
+    }
+
+    /// Choose what drawing a sprite running past the end of memory does.
+    pub fn set_out_of_range(&mut self, policy: OutOfRange) {
+        self.out_of_range = policy;
+    }
+
+    /// Draw at most `rows` sprite rows a frame like the COSMAC VIP, where
+    /// drawing large sprites took long enough for the display to show them
+    /// half drawn, or all at once with `None`, the default. `DRW` keeps the
+    /// program waiting until the whole sprite is drawn.
+    pub fn set_sprite_rows_per_frame(&mut self, rows: Option<NonZeroU8>) {
+        self.sprite_rows_per_frame = rows;
+        self.sprite_rows_left = rows.map_or(0, NonZeroU8::get);
+        self.draw_progress = None;
+    }
+
+    /// Set instructions executed by [`VM::run_frame`],
+    /// [`INSTRUCTIONS_PER_FRAME`] by default. [`VM::run_for`] runs as many
+    /// per 60th of a second.
+    pub fn set_instructions_per_frame(&mut self, instructions: NonZeroU32) {
+        self.set_instructions_per_second(instructions.saturating_mul(frequency()));
+    }
+
+    /// Set instructions executed per second of [`VM::run_for`],
+    /// [`INSTRUCTIONS_PER_SECOND`] by default, typically 500 to 1000.
+    /// [`VM::run_frame`] runs a 60th of them, at least one.
+    pub fn set_instructions_per_second(&mut self, instructions: NonZeroU32) {
+        self.instructions_per_second = instructions;
+        self.instructions_per_frame = per_frame(instructions);
+        self.instruction_remainder = 0;
+    }
+
+    pub fn instructions_per_second(&self) -> NonZeroU32 {
+        self.instructions_per_second
+    }
+
+    /// Choose when timers tick, see [`TimerPolicy`].
+    pub fn set_timer_policy(&mut self, policy: TimerPolicy) {
+        self.timer_policy = policy;
+        self.instructions_since_tick = 0;
+    }
+
+    /// Whether the current instruction is a jump to itself, which many
+    /// programs use to stop.
+    pub fn is_halted(&self) -> bool {
+        matches!(
+            self.read_current_instruction(),
+            Ok(inst) if inst & 0xF000 == 0x1000 && inst & 0x0FFF == self.registers.program_counter
+        )
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn opcode_family(&self) -> OpcodeFamily {
+        self.opcode_family
+    }
+
+    /// Run instructions of `family` and the ones before it, later ones
+    /// failing as unknown instructions. Every family is run by default.
+    pub fn set_opcode_family(&mut self, family: OpcodeFamily) {
+        self.opcode_family = family;
+    }
+
+    /// Set current keypad state.
+    pub fn set_input(&mut self, input: Input) {
+        self.input = input;
+    }
+
+    /// Current keypad state.
+    pub fn input(&self) -> Input {
+        self.input
+    }
+
+    /// Press `key`, keeping other keys as they are.
+    pub fn key_down(&mut self, key: Key) {
+        self.input.press(key.value());
+    }
+
+    /// Release `key`, keeping other keys as they are.
+    pub fn key_up(&mut self, key: Key) {
+        self.input.release(key.value());
+    }
+
+    /// Execute the instruction at the program counter and tick timers as
+    /// the [`TimerPolicy`] says. Timers don't tick if the instruction fails.
+    pub fn exec_current_instruction(&mut self) -> Result<(), VmError> {
+        self.exec_at_pc()?;
+        match self.timer_policy {
+            TimerPolicy::RealTime => self.tick_timers(),
+            TimerPolicy::Instructions(n) => self.count_instruction(n),
+            TimerPolicy::Elapsed => {}
+        }
+        Ok(())
+    }
+
+    /// Execute the current instruction like
+    /// [`VM::exec_current_instruction`] and tell what it was and what it
+    /// did.
+    pub fn step(&mut self) -> Result<StepInfo, VmError> {
+        let pc = self.registers.program_counter;
+        let instruction = self
+            .read_current_instruction()
+            .and_then(|word| self.decode_at(pc, word))
+            .ok();
+        let timers = (self.registers.delay_timer, self.registers.sound_timer);
+        let display = self.graphics.display;
+        let hires = self.hires.as_ref().map(|hires| hires.display);
+        let sound_playing = self.is_sound_playing();
+        let depth = self.stack.depth();
+
+        self.exec_at_pc()?;
+        // A timer tick may stop the sound the instruction started.
+        let sound_started = !sound_playing && self.is_sound_playing();
+        match self.timer_policy {
+            TimerPolicy::RealTime => self.tick_timers(),
+            TimerPolicy::Instructions(n) => self.count_instruction(n),
+            TimerPolicy::Elapsed => {}
+        }
+
+        let mut effects = Effects::NONE;
+        if self.graphics.display != display
+            || self.hires.as_ref().map(|hires| hires.display) != hires
+        {
+            effects |= Effects::DISPLAY_CHANGED;
+        }
+        if sound_started {
+            effects |= Effects::SOUND_STARTED;
+        }
+        if (sound_playing || sound_started) && !self.is_sound_playing() {
+            effects |= Effects::SOUND_STOPPED;
+        }
+        let waiting = matches!(instruction, Some(Instruction::LdVxK(_)));
+        if waiting && self.registers.program_counter == pc {
+            effects |= Effects::WAITING_FOR_KEY;
+        }
+        if self.is_halted() {
+            effects |= Effects::HALTED;
+        }
+        if self.stack.depth() > depth {
+            effects |= Effects::STACK_PUSHED;
+        } else if self.stack.depth() < depth {
+            effects |= Effects::STACK_POPPED;
+        }
+        Ok(StepInfo {
+            instruction,
+            pc,
+            next_pc: self.registers.program_counter,
+            effects,
+            timers_changed: timers != (self.registers.delay_timer, self.registers.sound_timer),
+        })
+    }
+
+    /// Execute the instruction at the program counter. In sandbox mode one
+    /// which fails is recorded and skipped instead.
+    fn exec_at_pc(&mut self) -> Result<(), VmError> {
+        let result = self
+            .read_current_instruction()
+            .and_then(|instruction| self.exec_instruction(instruction));
+        match (result, &mut self.fault_log) {
+            (Err(fault), Some(fault_log)) => {
+                fault_log.record(fault);
+                let next = self.registers.program_counter as usize + INSTRUCTION_SIZE;
+                self.registers.program_counter = (next % MEMORY_SIZE) as u16;
+                Ok(())
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Tick timers if `n` instructions were executed since the last tick.
+    fn count_instruction(&mut self, n: NonZeroU32) {
+        self.instructions_since_tick += 1;
+        if self.instructions_since_tick >= n.get() {
+            self.instructions_since_tick = 0;
+            self.tick_timers();
+        }
+    }
+
+    fn read_current_instruction(&self) -> Result<u16, VmError> {
+        let pc = self.registers.program_counter;
+        self.word_at(pc as usize).ok_or(VmError::MemoryOutOfBounds {
+            pc,
+            address: pc as usize,
+        })
+    }
+
+    /// Word at `address`, if it's inside memory.
+    fn word_at(&self, address: usize) -> Option<u16> {
+        (address + INSTRUCTION_SIZE <= MEMORY_SIZE).then(|| self.memory.fetch_instruction(address))
+    }
+
+    /// Advance program counter by `n` instructions.
+    fn next_instruction(&mut self, n: usize) {
+        self.registers.program_counter += (n * INSTRUCTION_SIZE) as u16;
+    }
+
+    /// Move past the current instruction and the one after it, which is
+    /// twice as long if it's the XO-CHIP `F000 nnnn`.
+    fn skip_next_instruction(&mut self) {
+        self.next_instruction(1);
+        let next = self.registers.program_counter as usize;
+        if self.word_at(next) == Some(LONG_LD_I) {
+            self.next_instruction(2);
+        } else {
+            self.next_instruction(1);
+        }
+    }
+
+    /// Run one 60 Hz frame: the configured number of instructions, see
+    /// [`VM::set_instructions_per_frame`], then one timer tick. Stops early
+    /// when the program halts. With [`TimerPolicy::Instructions`] timers
+    /// tick by instructions instead, and with [`TimerPolicy::Elapsed`] not
+    /// at all.
+    pub fn run_frame(&mut self) -> Result<FrameResult, VmError> {
+        let display = self.graphics.display;
+        let hires = self.hires.as_ref().map(|hires| hires.display);
+        let mut instructions = 0;
+        while instructions < self.instructions_per_frame.get() && !self.is_halted() {
+            self.exec_at_pc()?;
+            if let TimerPolicy::Instructions(per_tick) = self.timer_policy {
+                self.count_instruction(per_tick);
+            }
+            instructions += 1;
+        }
+        if self.timer_policy == TimerPolicy::RealTime {
+            self.tick_timers();
+        }
+        Ok(FrameResult {
+            instructions,
+            display_changed: self.graphics.display != display
+                || self.hires.as_ref().map(|hires| hires.display) != hires,
+        })
+    }
+
+    /// Run as fast as possible until the program halts, or after `budget`
+    /// instructions if it's given, for headless runs which shouldn't spin
+    /// forever. Timers tick as in [`VM::run_frame`].
+    ///
+    /// Stops at the first instruction which fails.
+    pub fn run_until_halted(&mut self, budget: Option<u64>) -> Result<RunResult, VmError> {
+        let mut instructions = 0;
+        let mut in_frame = 0;
+        while !self.is_halted() {
+            if budget.is_some_and(|budget| instructions >= budget) {
+                return Ok(RunResult {
+                    status: RunStatus::BudgetExhausted,
+                    instructions,
+                });
+            }
+            self.exec_at_pc()?;
+            instructions += 1;
+            match self.timer_policy {
+                TimerPolicy::RealTime => {
+                    in_frame += 1;
+                    if in_frame == self.instructions_per_frame.get() {
+                        in_frame = 0;
+                        self.tick_timers();
+                    }
+                }
+                TimerPolicy::Instructions(n) => self.count_instruction(n),
+                TimerPolicy::Elapsed => {}
+            }
+        }
+        Ok(RunResult {
+            status: RunStatus::Halted,
+            instructions,
+        })
+    }
+
+    /// Run instructions and timers for `duration` of real time, at
+    /// [`VM::set_instructions_per_second`] and [`TIMER_FREQUENCY`]. Frontends
+    /// call it with the time passed since the last frame. Time which isn't
+    /// enough for a whole instruction or tick is carried over to the next
+    /// call, so running in irregular slices runs the same as in one go.
+    ///
+    /// Stops at the first instruction which fails.
+    pub fn run_for(&mut self, duration: Duration) -> Result<(), VmError> {
+        let nanos = duration.as_nanos();
+        let total = self.instruction_remainder + nanos * self.instructions_per_second.get() as u128;
+        let instructions = total / NANOS_PER_SECOND;
+        self.instruction_remainder = total % NANOS_PER_SECOND;
+
+        // Spread timer ticks between instructions.
+        let mut elapsed = 0;
+        for n in 1..=instructions {
+            self.exec_at_pc()?;
+            if let TimerPolicy::Instructions(per_tick) = self.timer_policy {
+                self.count_instruction(per_tick);
+            }
+            let now = nanos * n / instructions;
+            self.advance_timers_nanos(now - elapsed);
+            elapsed = now;
+        }
+        self.advance_timers_nanos(nanos - elapsed);
+        Ok(())
+    }
+
+    /// Tick timers for `elapsed` time at [`TIMER_FREQUENCY`], carrying time
+    /// short of a whole tick over to the next call.
+    pub fn advance_timers(&mut self, elapsed: Duration) {
+        self.advance_timers_nanos(elapsed.as_nanos());
+    }
+
+    /// Fraction of the time between timer ticks passed since the last one
+    /// made by [`VM::advance_timers`] or [`VM::run_for`], from 0 to 1.
+    pub fn timer_phase(&self) -> f64 {
+        self.timer_remainder as f64 / NANOS_PER_SECOND as f64
+    }
+
+    fn advance_timers_nanos(&mut self, nanos: u128) {
+        if let TimerPolicy::Instructions(_) = self.timer_policy {
+            return;
+        }
+        let total = self.timer_remainder + nanos * TIMER_FREQUENCY as u128;
+        // Timers stop at 0, there is no point in ticking them more than
+        // their largest value.
+        let ticks = (total / NANOS_PER_SECOND).min(u8::MAX as u128);
+        for _ in 0..ticks {
+            self.tick_timers();
+        }
+        self.timer_remainder = total % NANOS_PER_SECOND;
+    }
+
+    /// Decrement delay and sound timers by one tick if they are active.
+    pub fn tick_timers(&mut self) {
+        self.refreshed = true;
+        if let Some(draw_log) = &mut self.draw_log {
+            draw_log.next_frame();
+        }
+        if let Some(rng_log) = &mut self.rng_log {
+            rng_log.next_frame();
+        }
+        if let Some(rows) = self.sprite_rows_per_frame {
+            self.sprite_rows_left = rows.get();
+        }
+        if self.registers.delay_timer > 0 {
+            self.registers.delay_timer -= 1;
+        }
+        if self.registers.sound_timer > 0 {
+            self.registers.sound_timer -= 1;
+            self.notify_sound(true);
+        }
+        self.notify_observers(|observer, vm| observer.on_timer_tick(vm));
+    }
+}
+
+/// Draw `rows` of a sprite on `hires` if it's in use, on `graphics`
+/// otherwise.
+fn draw_on_screen(
+    graphics: &mut Graphics,
+    hires: &mut Option<SchipGraphics>,
+    clip: bool,
+    x: usize,
+    y: usize,
+    rows: &[u8],
+) -> bool {
+    match hires {
+        Some(hires) => draw_rows(hires, clip, x, y, rows),
+        None => draw_rows(graphics, clip, x, y, rows),
+    }
+}
+
+/// Draw sprite `rows` with the top one at row `y`, which may be past the
+/// bottom of the display, wrapping or clipping as `clip` says.
+fn draw_rows<R: Row, const H: usize>(
+    graphics: &mut Graphics<R, H>,
+    clip: bool,
+    x: usize,
+    y: usize,
+    rows: &[u8],
+) -> bool {
+    if !clip {
+        graphics.draw_sprite(x, y % H, rows)
+    } else if y < H {
+        graphics.draw_sprite_clipped(x, y, rows)
+    } else {
+        false
+    }
+}
+
+impl Default for VM {
+    fn default() -> Self {
+        VmBuilder::new().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::graphics::DISPLAY_ROWS;
+    use super::super::memory::{ETI_660_PROGRAM_START_LOCATION, MAX_PROGRAM_SIZE, MEMORY_SIZE};
+    use super::super::platform;
+    use super::super::storage::{MemoryStorage, Storage};
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_jp() {
+        let mut vm = VM::new();
+        let addr = 16u16;
+
+        vm.jp(addr);
+
+        assert_eq!(vm.registers.program_counter, addr);
+    }
+
+    #[test]
+    fn test_jp_edge_case() {
+        let mut vm = VM::new();
+        let addr = 0x0FFF;
+
+        vm.jp(addr);
+
+        assert_eq!(vm.registers.program_counter, addr);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_jp_incorrect_addr() {
+        let mut vm = VM::new();
+        vm.jp(0x1000);
+    }
+
+    /// Jump, call, return, `JP V0` and skip targets are byte addresses, so a
+    /// ROM's raw bytes run as assembled.
+    #[test]
+    fn test_byte_addressed_control_flow() {
+        let mut vm = VM::new();
+        vm.load_program(&[
+            0x22, 0x08, // 0x200: CALL 0x208
+            0x60, 0x04, // 0x202: LD V0, 4
+            0xB2, 0x0A, // 0x204: JP V0, 0x20A
+            0x12, 0x06, // 0x206: trap
+            0x61, 0x01, // 0x208: LD V1, 1
+            0x00, 0xEE, // 0x20A: RET
+            0x12, 0x0C, // 0x20C: trap
+            0x31, 0x01, // 0x20E: SE V1, 1
+            0x12, 0x10, // 0x210: trap
+            0x12, 0x12, // 0x212: halt
+        ])
+        .unwrap();
+
+        let mut trace = Vec::new();
+        for _ in 0..6 {
+            trace.push(vm.registers.program_counter);
+            vm.exec_current_instruction().unwrap();
+        }
+
+        assert_eq!(trace, [0x200, 0x208, 0x20A, 0x202, 0x204, 0x20E]);
+        assert_eq!(vm.registers.program_counter, 0x212);
+        assert!(vm.is_halted());
+    }
+
+    #[test]
+    fn test_load_program_errors() {
+        let mut vm = VM::new();
+        vm.load_program(&[0x12, 0x00]).unwrap();
+
+        assert_eq!(vm.load_program(&[]), Err(LoadError::Empty));
+        assert_eq!(
+            vm.load_program(&[0; MEMORY_SIZE]),
+            Err(LoadError::TooLarge {
+                size: MEMORY_SIZE,
+                available: MEMORY_SIZE - PROGRAM_START_LOCATION
+            })
+        );
+        // The program loaded before is kept.
+        assert_eq!(vm.program(), [0x12, 0x00]);
+        assert_eq!(vm.memory(0x200..0x202).unwrap(), [0x12, 0x00]);
+    }
+
+    #[test]
+    fn test_eti_660_program_start() {
+        let mut vm = VM::new();
+        vm.set_program_start(ETI_660_PROGRAM_START_LOCATION);
+        vm.load_program(&[0x70, 0x01, 0x16, 0x00]).unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x600);
+        assert_eq!(vm.memory(0x600..0x604).unwrap(), [0x70, 0x01, 0x16, 0x00]);
+        assert_eq!(
+            vm.load_program(&[0; MAX_PROGRAM_SIZE]),
+            Err(LoadError::TooLarge {
+                size: MAX_PROGRAM_SIZE,
+                available: MEMORY_SIZE - 0x600
+            })
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let program = [0xC0, 0xFF, 0x70, 0x01, 0x16, 0x00];
+        let mut vm = VM::builder()
+            .rng_seed(7)
+            .program_start(0x600)
+            .instructions_per_frame(NonZeroU32::new(3).unwrap())
+            .platform(&platform::CHIP8)
+            .build();
+        vm.load_program(&program).unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x600);
+        assert_eq!(vm.memory(0x600..0x606).unwrap(), program);
+        assert_eq!(vm.quirks(), platform::CHIP8.quirks);
+        assert_eq!(vm.memory(5..10).unwrap(), &FontStyle::Vip.sprites()[5..10]);
+        assert_eq!(vm.run_frame().unwrap().instructions, 3);
+
+        let mut seeded = VM::builder().rng_seed(7).build();
+        seeded.load_program(&program).unwrap();
+        seeded.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.v[0], seeded.registers.v[0].wrapping_add(1));
+    }
+
+    #[test]
+    fn test_builder_font_style() {
+        let vm = VM::builder()
+            .platform(&platform::CHIP8)
+            .font_style(FontStyle::Octo)
+            .build();
+
+        assert_eq!(vm.memory(5..10).unwrap(), &FontStyle::Octo.sprites()[5..10]);
+        assert_eq!(vm.quirks(), platform::CHIP8.quirks);
+    }
+
+    #[test]
+    fn test_builder_font() {
+        let font = [0xAA; FONT_SIZE];
+        let vm = VM::builder()
+            .font_style(FontStyle::Octo)
+            .font(&font)
+            .build();
+
+        assert_eq!(vm.memory(0..FONT_SIZE).unwrap(), font);
+    }
+
+    #[test]
+    fn test_with_variant() {
+        // LDL I, 0x1234
+        let program = [0xF0, 0x00, 0x12, 0x34];
+        let mut vm = VM::with_variant(Variant::Chip8);
+        vm.load_program(&program).unwrap();
+
+        assert_eq!(vm.quirks(), platform::CHIP8.quirks);
+        assert_eq!(vm.opcode_family(), OpcodeFamily::Chip8);
+        assert_eq!(
+            vm.exec_current_instruction(),
+            Err(VmError::UnknownInstruction {
+                pc: 0x200,
+                instruction: 0xF000
+            })
+        );
+
+        let mut vm = VM::with_variant(Variant::XoChip);
+        vm.load_program(&program).unwrap();
+        assert_eq!(vm.quirks(), platform::XOCHIP.quirks);
+        assert_eq!(vm.memory(5..10).unwrap(), &FontStyle::Octo.sprites()[5..10]);
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.i, 0x1234);
+    }
+
+    fn vm_with_quirks(quirks: Quirks, program: &[u8]) -> VM {
+        let mut vm = VM::builder().quirks(quirks).build();
+        vm.load_program(program).unwrap();
+        vm
+    }
+
+    #[test]
+    fn test_shift_quirk() {
+        let quirks = Quirks {
+            shift_uses_vy: true,
+            ..Quirks::default()
+        };
+        let mut vm = vm_with_quirks(quirks, &[0x80, 0x16, 0x82, 0x1E]);
+        vm.registers.v[1] = 0b1000_0011;
+
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.v[0], 0b0100_0001);
+        assert_eq!(vm.registers.v[0xF], 1);
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.v[2], 0b0000_0110);
+        assert_eq!(vm.registers.v[1], 0b1000_0011);
+        assert_eq!(vm.registers.v[0xF], 1);
+    }
+
+    #[test]
+    fn test_load_store_quirk() {
+        let quirks = Quirks {
+            load_store_increments_i: true,
+            ..Quirks::default()
+        };
+        let mut vm = vm_with_quirks(quirks, &[0xA3, 0x00, 0xF2, 0x55, 0xF1, 0x65]);
+
+        for _ in 0..3 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        assert_eq!(vm.registers.i, 0x305);
+    }
+
+    #[test]
+    fn test_jump_quirk() {
+        let quirks = Quirks {
+            jump_uses_vx: true,
+            ..Quirks::default()
+        };
+        let mut vm = vm_with_quirks(quirks, &[0xB3, 0x00]);
+        vm.registers.v[0] = 1;
+        vm.registers.v[3] = 4;
+
+        vm.exec_current_instruction().unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x304);
+    }
+
+    #[test]
+    fn test_logic_quirk() {
+        let quirks = Quirks {
+            logic_resets_vf: true,
+            ..Quirks::default()
+        };
+        for instruction in [0x01, 0x02, 0x03] {
+            let mut vm = vm_with_quirks(quirks, &[0x80, 0x10 | instruction]);
+            vm.registers.v[0xF] = 1;
+
+            vm.exec_current_instruction().unwrap();
+
+            assert_eq!(vm.registers.v[0xF], 0);
+        }
+    }
+
+    #[test]
+    fn test_clip_quirk() {
+        let quirks = Quirks {
+            clip_sprites: true,
+            ..Quirks::default()
+        };
+        // Draw the digit 0 at (62, 30).
+        let mut vm = vm_with_quirks(quirks, &[0x60, 0x3E, 0x61, 0x1E, 0xD0, 0x15]);
+
+        for _ in 0..3 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        assert_eq!(vm.graphics.display[30], 0b11 << 62);
+        assert_eq!(vm.graphics.display[31], 0b01 << 62);
+        assert_eq!(vm.graphics.display[0], 0);
+        assert_eq!(vm.graphics.display[30] & 1, 0);
+    }
+
+    #[test]
+    fn test_display_wait_quirk() {
+        let quirks = Quirks {
+            display_wait: true,
+            ..Quirks::default()
+        };
+        let mut vm = vm_with_quirks(quirks, &[0x60, 0x00, 0xD0, 0x05]);
+        vm.set_timer_policy(TimerPolicy::Elapsed);
+
+        vm.exec_current_instruction().unwrap();
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(vm.graphics.display[0], 0);
+
+        vm.tick_timers();
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.program_counter, 0x204);
+        assert_ne!(vm.graphics.display[0], 0);
+    }
+
+    #[test]
+    fn test_state_accessors() {
+        let mut vm = VM::new();
+        vm.load_program(&[
+            0x22, 0x04, // CALL 0x204
+            0x00, 0x00, // padding
+            0x22, 0x08, // CALL 0x208
+            0x00, 0x00, // padding
+            0xF1, 0x29, // LD F, V1
+            0xD0, 0x05, // DRW V0, V0, 5
+        ])
+        .unwrap();
+        vm.set_register(1, 0xA);
+        vm.set_i(0x300);
+        assert_eq!(vm.registers().v[1], 0xA);
+        assert_eq!(vm.registers().i, 0x300);
+
+        for _ in 0..4 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        assert_eq!(vm.stack_frames(), [0x200, 0x204]);
+        assert_eq!(vm.registers().program_counter, 0x20C);
+        assert!(vm.display().pixel(0, 0));
+        assert_eq!(vm.memory(0x200..0x202).unwrap(), [0x22, 0x04]);
+    }
+
+    #[test]
+    fn test_sandbox_skips_faults() {
+        let mut vm = VM::new();
+        vm.enable_sandbox(8);
+        vm.load_program(&[
+            0xFF, 0xFF, // unknown instruction
+            0x00, 0xEE, // RET without CALL
+            0x60, 0x07, // LD V0, 7
+            0x12, 0x06, // halt
+        ])
+        .unwrap();
+
+        for _ in 0..3 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        assert_eq!(vm.registers.v[0], 7);
+        assert!(vm.is_halted());
+        let faults: Vec<_> = vm.fault_log().unwrap().iter().copied().collect();
+        assert_eq!(
+            faults,
+            [
+                VmError::UnknownInstruction {
+                    pc: 0x200,
+                    instruction: 0xFFFF
+                },
+                VmError::StackUnderflow { pc: 0x202 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sandbox_wraps_past_memory_end() {
+        let mut vm = VM::new();
+        vm.enable_sandbox(1);
+        vm.execute(Instruction::Jp(0xFFF)).unwrap();
+
+        vm.step().unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x001);
+        assert_eq!(vm.fault_log().unwrap().total(), 1);
+    }
+
+    #[test]
+    fn test_execute_decoded_instruction() {
+        let mut vm = VM::new();
+        vm.load_program(&[0x00, 0xE0]).unwrap();
+
+        vm.execute(Instruction::LdByte(3, 0x2A)).unwrap();
+        vm.execute(Instruction::LdILong(0x1234)).unwrap();
+
+        assert_eq!(vm.registers.v[3], 0x2A);
+        assert_eq!(vm.registers.i, 0x1234);
+        assert_eq!(vm.registers.program_counter, 0x206);
+        assert_eq!(
+            vm.execute(Instruction::Ret),
+            Err(VmError::StackUnderflow { pc: 0x206 })
+        );
+    }
+
+    #[test]
+    fn test_execute_invalid_operand() {
+        let mut vm = VM::new();
+        vm.load_program(&[0x00, 0xE0]).unwrap();
+
+        for instruction in [Instruction::Jp(0x1234), Instruction::LdByte(0x20, 1)] {
+            assert_eq!(
+                vm.execute(instruction),
+                Err(VmError::InvalidOperand {
+                    pc: 0x200,
+                    instruction
+                })
+            );
+        }
+        assert_eq!(vm.registers.program_counter, 0x200);
+        assert_eq!(vm.registers.v, [0; V_REGISTERS_SIZE]);
+    }
+
+    #[test]
+    fn test_cls() {
+        let mut vm = VM::new();
+        vm.graphics.display = [u64::MAX; DISPLAY_ROWS];
+        assert_eq!(vm.registers.program_counter, 0);
+
+        vm.cls();
+
+        assert!(vm.graphics.display.iter().all(|&row| row == 0));
+        assert_eq!(vm.registers.program_counter, 2);
+    }
+
+    #[test]
+    fn test_high_low() {
+        // HIGH; LD V0, 120; LD V1, 60; LD I, 0x000; DRW V0, V1, 5; LOW
+        let program = [
+            0x00, 0xFF, 0x60, 0x78, 0x61, 0x3C, 0xA0, 0x00, 0xD0, 0x15, 0x00, 0xFE,
+        ];
+        let mut vm = VM::new();
+        vm.load_program(&program).unwrap();
+        assert_eq!(vm.resolution(), Resolution::Low);
+
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.resolution(), Resolution::High);
+        assert_eq!((vm.screen().width(), vm.screen().height()), (128, 64));
+        for _ in 0..3 {
+            vm.exec_current_instruction().unwrap();
+        }
+        let info = vm.step().unwrap();
+        assert!(info.display_changed());
+
+        // The digit wraps around the bottom of the larger display.
+        let hires = vm.hires_graphics().unwrap();
+        assert!(hires.pixel(120, 60));
+        assert!(hires.pixel(123, 0));
+        assert!(!hires.pixel(120, 28));
+        assert!(vm.graphics.display.iter().all(|&row| row == 0));
+        assert_eq!(vm.display_hash(), hires.hash());
+
+        let snapshot = vm.snapshot();
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.resolution(), Resolution::Low);
+        assert!(vm.hires_graphics().is_none());
+        assert_eq!(vm.display_hash(), Graphics::new().hash());
+
+        vm.restore(&snapshot);
+        assert_eq!(vm.resolution(), Resolution::High);
+        assert!(vm.screen().pixel(120, 60));
+        vm.reset();
+        assert_eq!(vm.resolution(), Resolution::Low);
+    }
+
+    #[test]
+    fn test_scroll() {
+        // SCD 3; SCR; SCL; SCL
+        let program = [0x00, 0xC3, 0x00, 0xFB, 0x00, 0xFC, 0x00, 0xFC];
+        let mut vm = VM::new();
+        vm.load_program(&program).unwrap();
+        vm.graphics.draw_sprite(8, 0, &[0x80]);
+
+        vm.exec_current_instruction().unwrap();
+        assert!(vm.graphics.pixel(8, 3));
+        assert!(!vm.graphics.pixel(8, 0));
+        vm.exec_current_instruction().unwrap();
+        assert!(vm.graphics.pixel(12, 3));
+        vm.exec_current_instruction().unwrap();
+        vm.exec_current_instruction().unwrap();
+        assert!(vm.graphics.pixel(4, 3));
+        assert_eq!(vm.registers.program_counter, 0x208);
+
+        vm.reset();
+        let mut hires = SchipGraphics::default();
+        hires.draw_sprite(120, 60, &[0xC2]);
+        vm.hires = Some(hires);
+        vm.graphics.draw_sprite(0, 0, &[0x80]);
+        vm.exec_current_instruction().unwrap();
+        vm.exec_current_instruction().unwrap();
+
+        // Pixels scrolled off the larger display are gone, and the low
+        // resolution one is left alone.
+        let hires = vm.hires_graphics().unwrap();
+        assert!(hires.pixel(124, 63) && hires.pixel(125, 63));
+        assert_eq!(hires.display[63].count_ones(), 2);
+        assert!(vm.graphics.pixel(0, 0));
+    }
+
+    #[test]
+    fn test_drw_large_sprite() {
+        // HIGH; LD I, 0x20C; DRW V0, V0, 0; DRW V0, V0, 0; JP 0x20A; sprite
+        let mut program = vec![
+            0x00, 0xFF, 0xA2, 0x0C, 0xD0, 0x00, 0xD0, 0x00, 0x00, 0xFE, 0x12, 0x0A,
+        ];
+        program.extend([0xFF; 32]);
+        let mut vm = VM::new();
+        vm.load_program(&program).unwrap();
+        for _ in 0..3 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        let hires = vm.hires_graphics().unwrap();
+        assert_eq!(hires.display[..16], [0xFFFF; 16]);
+        assert_eq!(hires.display[16], 0);
+        assert_eq!(vm.registers.v[0xF], 0);
+
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.v[0xF], 16);
+        assert_eq!(vm.hires_graphics().unwrap().display[0], 0);
+
+        // In low resolution nothing is drawn.
+        vm.exec_current_instruction().unwrap();
+        vm.registers.program_counter = 0x204;
+        vm.exec_current_instruction().unwrap();
+        assert!(vm.graphics.display.iter().all(|&row| row == 0));
+        assert_eq!(vm.registers.v[0xF], 0);
+    }
+
+    #[test]
+    fn test_high_cls() {
+        let mut vm = VM::new();
+        vm.graphics.display = [u64::MAX; DISPLAY_ROWS];
+        vm.high();
+        vm.hires.as_mut().unwrap().draw_sprite(0, 0, &[0xFF]);
+
+        vm.cls();
+
+        assert!(vm
+            .hires_graphics()
+            .unwrap()
+            .display
+            .iter()
+            .all(|&row| row == 0));
+        assert_eq!(vm.graphics.display, [u64::MAX; DISPLAY_ROWS]);
+    }
+
+    #[test]
+    fn test_ret() {
+        let mut vm = VM::new();
+        vm.stack.push(0x202).unwrap();
+        vm.stack.push(0x204).unwrap();
+        vm.registers.program_counter = 0x210;
+
+        vm.ret().unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x206);
+        assert_eq!(vm.stack.frames(), [0x202]);
+    }
+
+    #[test]
+    fn test_call() {
+        let mut vm = VM::new();
+        vm.stack.push(0x202).unwrap();
+        vm.stack.push(0x204).unwrap();
+        vm.registers.program_counter = 0x206;
+
+        vm.call(0x208).unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x208);
+        assert_eq!(vm.stack.frames(), [0x202, 0x204, 0x206]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_call_invalid_addr() {
+        let mut vm = VM::new();
+        vm.call(0x1111).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_call_invalid_addr_edge_case() {
+        let mut vm = VM::new();
+        vm.call(0x1000).unwrap();
+    }
+
+    #[test]
+    fn test_se_equal() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 1;
+        vm.registers.program_counter = 0x200;
+
+        vm.se(1, 1);
+
+        assert_eq!(vm.registers.v[1], 1);
+        assert_eq!(vm.registers.program_counter, 0x204);
+    }
+
+    #[test]
+    fn test_se_not_equal() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 1;
+        vm.registers.program_counter = 0x200;
+
+        vm.se(1, 2);
+
+        assert_eq!(vm.registers.v[1], 1);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_skips_over_long_instruction() {
+        // Every skip instruction with its condition met given V0 = V1 = 0,
+        // V2 = 1 and key 0 pressed.
+        for skip in [0x3000u16, 0x4001, 0x5010, 0x9020, 0xE09E, 0xE2A1] {
+            let mut vm = VM::new();
+            let [high, low] = skip.to_be_bytes();
+            vm.load_program(&[high, low, 0xF0, 0x00, 0x12, 0x34, 0x60, 0x01])
+                .unwrap();
+            vm.registers.v[2] = 1;
+            vm.input = Input::new_with_key_pressed(0x0);
+
+            vm.exec_current_instruction().unwrap();
+
+            assert_eq!(
+                vm.registers.program_counter, 0x206,
+                "skip instruction {:#06X}",
+                skip
+            );
+        }
+    }
+
+    #[test]
+    fn test_not_skipped_long_instruction() {
+        let mut vm = VM::new();
+        vm.load_program(&[0x30, 0x01, 0xF0, 0x00, 0x12, 0x34, 0x60, 0x01])
+            .unwrap();
+
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.program_counter, 0x202);
+        vm.exec_current_instruction().unwrap();
+
+        assert_eq!(vm.registers.i, 0x1234);
+        assert_eq!(vm.registers.program_counter, 0x206);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_se_invalid() {
+        let mut vm = VM::new();
+        vm.sne(16, 1);
+    }
+
+    #[test]
+    fn test_sne_equal() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 1;
+        vm.registers.program_counter = 0x200;
+
+        vm.sne(1, 1);
+
+        assert_eq!(vm.registers.v[1], 1);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_sne_not_equal() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 1;
+        vm.registers.program_counter = 0x200;
+
+        vm.sne(1, 2);
+
+        assert_eq!(vm.registers.v[1], 1);
+        assert_eq!(vm.registers.program_counter, 0x204);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sne_invalid() {
+        let mut vm = VM::new();
+        vm.sne(16, 1);
+    }
+
+    #[test]
+    fn test_se_v_equal() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 4;
+        vm.registers.v[2] = 4;
+        vm.registers.program_counter = 0x200;
+
+        vm.se_v(1, 2);
+
+        assert_eq!(vm.registers.v[1], 4);
+        assert_eq!(vm.registers.v[2], 4);
+        assert_eq!(vm.registers.program_counter, 0x204);
+    }
+
+    #[test]
+    fn test_se_v_not_equal() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 4;
+        vm.registers.v[2] = 5;
+        vm.registers.program_counter = 0x200;
+
+        vm.se_v(1, 2);
+
+        assert_eq!(vm.registers.v[1], 4);
+        assert_eq!(vm.registers.v[2], 5);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_se_v_invalid_first() {
+        let mut vm = VM::new();
+        vm.se_v(16, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_se_v_invalid_second() {
+        let mut vm = VM::new();
+        vm.se_v(0, 16);
+    }
+
+    #[test]
+    fn test_ld_vx() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 4;
+        vm.registers.program_counter = 0x200;
+
+        vm.ld_vx(1, 2);
+
+        assert_eq!(vm.registers.v[1], 2);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ld_vx_invalid() {
+        let mut vm = VM::new();
+        vm.ld_vx(16, 1);
+    }
+
+    #[test]
+    fn test_add_vx() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 200;
+        vm.registers.program_counter = 0x200;
+
+        vm.add_vx(1, 50);
+
+        assert_eq!(vm.registers.v[1], 250);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_add_vx_overflow() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 255;
+        vm.registers.program_counter = 0x200;
+
+        vm.add_vx(1, 1);
+
+        assert_eq!(vm.registers.v[1], 0);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_ld_vx_vy() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 4;
+        vm.registers.v[2] = 7;
+        vm.registers.program_counter = 0x200;
+
+        vm.ld_vx_vy(1, 2);
+
+        assert_eq!(vm.registers.v[1], 7);
+        assert_eq!(vm.registers.v[2], 7);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ld_vx_vy_invalid_x() {
+        let mut vm = VM::new();
+        vm.ld_vx_vy(16, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ld_vx_vy_invalid_y() {
+        let mut vm = VM::new();
+        vm.ld_vx_vy(1, 16);
+    }
+
+    #[test]
+    fn test_or() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 0xF0;
+        vm.registers.v[2] = 0x0F;
+        vm.registers.program_counter = 0x200;
+
+        vm.or(1, 2);
+
+        assert_eq!(vm.registers.v[1], 0xFF);
+        assert_eq!(vm.registers.v[2], 0x0F);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_or_invalid_first() {
+        let mut vm = VM::new();
+        vm.or(16, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_or_invalid_second() {
+        let mut vm = VM::new();
+        vm.or(0, 16);
+    }
+
+    #[test]
+    fn test_and() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 0b0101;
+        vm.registers.v[2] = 0b1110;
+        vm.registers.program_counter = 0x200;
+
+        vm.and(1, 2);
+
+        assert_eq!(vm.registers.v[1], 0b0100);
+        assert_eq!(vm.registers.v[2], 0b1110);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_and_invalid_first() {
+        let mut vm = VM::new();
+        vm.and(16, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_and_invalid_second() {
+        let mut vm = VM::new();
+        vm.and(0, 16);
+    }
+
+    #[test]
+    fn test_xor() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 0b0100;
+        vm.registers.v[2] = 0b1110;
+        vm.registers.program_counter = 0x200;
+
+        vm.xor(1, 2);
+
+        assert_eq!(vm.registers.v[1], 0b1010);
+        assert_eq!(vm.registers.v[2], 0b1110);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_xor_invalid_first() {
+        let mut vm = VM::new();
+        vm.xor(16, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_xor_invalid_second() {
+        let mut vm = VM::new();
+        vm.xor(0, 16);
+    }
+
+    #[test]
+    fn test_add_vx_vy_with_overflow() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 200;
+        vm.registers.v[2] = 100;
+        vm.registers.v[0xF] = 4;
+        vm.registers.program_counter = 0x200;
+
+        vm.add_vx_vy(1, 2);
+
+        assert_eq!(vm.registers.v[1], 44);
+        assert_eq!(vm.registers.v[2], 100);
+        assert_eq!(vm.registers.v[0xF], 1);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_add_vx_vy_without_overflow() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 50;
+        vm.registers.v[2] = 100;
+        vm.registers.v[0xF] = 4;
+        vm.registers.program_counter = 0x200;
+
+        vm.add_vx_vy(1, 2);
+
+        assert_eq!(vm.registers.v[1], 150);
+        assert_eq!(vm.registers.v[2], 100);
+        assert_eq!(vm.registers.v[0xF], 0);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_add_vx_vy_invalid_first() {
+        let mut vm = VM::new();
+        vm.add_vx_vy(16, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_add_vx_vy_invalid_second() {
+        let mut vm = VM::new();
+        vm.add_vx_vy(0, 16);
+    }
+
+    #[test]
+    fn test_sub_with_overflow() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 100;
+        vm.registers.v[2] = 200;
+        vm.registers.v[0xF] = 4;
+        vm.registers.program_counter = 0x200;
+
+        vm.sub(1, 2);
+
+        assert_eq!(vm.registers.v[1], 156);
+        assert_eq!(vm.registers.v[2], 200);
+        assert_eq!(vm.registers.v[0xF], 0);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_sub_without_overflow() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 150;
+        vm.registers.v[2] = 100;
+        vm.registers.v[0xF] = 4;
+        vm.registers.program_counter = 0x200;
+
+        vm.sub(1, 2);
+
+        assert_eq!(vm.registers.v[1], 50);
+        assert_eq!(vm.registers.v[2], 100);
+        assert_eq!(vm.registers.v[0xF], 1);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sub_invalid_first() {
+        let mut vm = VM::new();
+        vm.sub(16, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sub_invalid_second() {
+        let mut vm = VM::new();
+        vm.sub(0, 16);
+    }
+
+    #[test]
+    fn test_shr_with_overflow() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 0b0101;
+        vm.registers.v[0xF] = 4;
+        vm.registers.program_counter = 0x200;
 
-        assert!(vm.graphics.display.iter().all(|&row| row == 0));
-        assert_eq!(vm.registers.program_counter, 2);
+        vm.shr(1, 0);
+
+        assert_eq!(vm.registers.v[1], 0b0010);
+        assert_eq!(vm.registers.v[0xF], 1);
+        assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    fn test_ret() {
+    fn test_shr_without_overflow() {
         let mut vm = VM::new();
-        vm.stack.push(0x202);
-        vm.stack.push(0x204);
-        vm.registers.program_counter = 0x210;
+        vm.registers.v[1] = 0b1010;
+        vm.registers.v[0xF] = 4;
+        vm.registers.program_counter = 0x200;
 
-        vm.ret();
+        vm.shr(1, 0);
 
-        assert_eq!(vm.registers.program_counter, 0x206);
-        assert_eq!(vm.stack.pointer, 1);
-        assert_eq!(vm.stack.stack[0], 0x202);
+        assert_eq!(vm.registers.v[1], 0b0101);
+        assert_eq!(vm.registers.v[0xF], 0);
+        assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    fn test_call() {
+    #[should_panic]
+    fn test_shr_invalid() {
         let mut vm = VM::new();
-        vm.stack.push(0x202);
-        vm.stack.push(0x204);
-        vm.registers.program_counter = 0x206;
+        vm.shr(16, 0);
+    }
+
+    #[test]
+    fn test_subn_with_overflow() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 200;
+        vm.registers.v[2] = 100;
+        vm.registers.v[0xF] = 4;
+        vm.registers.program_counter = 0x200;
 
-        vm.call(0x208);
+        vm.subn(1, 2);
 
-        assert_eq!(vm.registers.program_counter, 0x208);
-        assert_eq!(vm.stack.pointer, 3);
-        assert_eq!(vm.stack.stack[0], 0x202);
-        assert_eq!(vm.stack.stack[1], 0x204);
-        assert_eq!(vm.stack.stack[2], 0x206);
+        assert_eq!(vm.registers.v[1], 156);
+        assert_eq!(vm.registers.v[2], 100);
+        assert_eq!(vm.registers.v[0xF], 0);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_subn_without_overflow() {
+        let mut vm = VM::new();
+        vm.registers.v[1] = 100;
+        vm.registers.v[2] = 150;
+        vm.registers.v[0xF] = 4;
+        vm.registers.program_counter = 0x200;
+
+        vm.subn(1, 2);
+
+        assert_eq!(vm.registers.v[1], 50);
+        assert_eq!(vm.registers.v[2], 150);
+        assert_eq!(vm.registers.v[0xF], 1);
+        assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
     #[should_panic]
-    fn test_call_invalid_addr() {
+    fn test_subn_invalid_first() {
         let mut vm = VM::new();
-        vm.call(0x1111);
+        vm.subn(16, 1);
     }
 
     #[test]
     #[should_panic]
-    fn test_call_invalid_addr_edge_case() {
+    fn test_subn_invalid_second() {
         let mut vm = VM::new();
-        vm.call(0x1000);
+        vm.subn(0, 16);
     }
 
     #[test]
-    fn test_se_equal() {
+    fn test_shl_with_overflow() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 1;
+        vm.registers.v[1] = 0b10101010;
+        vm.registers.v[0xF] = 4;
         vm.registers.program_counter = 0x200;
 
-        vm.se(1, 1);
+        vm.shl(1, 0);
 
-        assert_eq!(vm.registers.v[1], 1);
-        assert_eq!(vm.registers.program_counter, 0x204);
+        assert_eq!(vm.registers.v[1], 0b01010100);
+        assert_eq!(vm.registers.v[0xF], 1);
+        assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    fn test_se_not_equal() {
+    fn test_shl_without_overflow() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 1;
+        vm.registers.v[1] = 0b01101010;
+        vm.registers.v[0xF] = 4;
         vm.registers.program_counter = 0x200;
 
-        vm.se(1, 2);
+        vm.shl(1, 0);
 
-        assert_eq!(vm.registers.v[1], 1);
+        assert_eq!(vm.registers.v[1], 0b11010100);
+        assert_eq!(vm.registers.v[0xF], 0);
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
     #[should_panic]
-    fn test_se_invalid() {
+    fn test_shl_invalid() {
         let mut vm = VM::new();
-        vm.sne(16, 1);
+        vm.shr(16, 0);
     }
 
     #[test]
-    fn test_sne_equal() {
+    fn test_sne_vx_vy_equal() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 1;
         vm.registers.program_counter = 0x200;
+        vm.registers.v[0x1] = 5;
+        vm.registers.v[0x2] = 5;
 
-        vm.sne(1, 1);
+        vm.sne_vx_vy(0x1, 0x2);
 
-        assert_eq!(vm.registers.v[1], 1);
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    fn test_sne_not_equal() {
+    fn test_sne_vx_vy_unequal() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 1;
         vm.registers.program_counter = 0x200;
+        vm.registers.v[0x1] = 5;
+        vm.registers.v[0x1] = 6;
 
-        vm.sne(1, 2);
+        vm.sne_vx_vy(0x1, 0x2);
 
-        assert_eq!(vm.registers.v[1], 1);
         assert_eq!(vm.registers.program_counter, 0x204);
     }
 
+    #[test]
+    fn test_ld_i() {
+        let mut vm = VM::new();
+        vm.registers.i = 5;
+        vm.registers.program_counter = 0x200;
+
+        vm.ld_i(0x0111);
+
+        assert_eq!(vm.registers.i, 0x0111);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
     #[test]
     #[should_panic]
-    fn test_sne_invalid() {
+    fn test_ld_i_invalid() {
         let mut vm = VM::new();
-        vm.sne(16, 1);
+        vm.ld_i(0xF000);
     }
 
     #[test]
-    fn test_se_v_equal() {
+    fn test_jp_v0() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 4;
-        vm.registers.v[2] = 4;
+        vm.registers.program_counter = 100;
+        vm.registers.v[0] = 5;
+
+        vm.jp_v0(20);
+
+        assert_eq!(vm.registers.program_counter, 25);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_jp_v0_invalid() {
+        let mut vm = VM::new();
+        vm.jp_v0(0xF000);
+    }
+
+    #[test]
+    fn test_rnd() {
+        let mut vm = VM::new();
+        vm.set_rng_seed(0xFF);
         vm.registers.program_counter = 0x200;
+        vm.registers.v[1] = 0xAF;
 
-        vm.se_v(1, 2);
+        vm.rnd(1, 0xFF);
 
-        assert_eq!(vm.registers.v[1], 4);
-        assert_eq!(vm.registers.v[2], 4);
+        assert_eq!(vm.registers.v[1], 181);
+        assert_eq!(vm.registers.program_counter, 0x202);
+
+        vm.rnd(1, 0x0F);
+
+        assert_eq!(vm.registers.v[1], 5);
         assert_eq!(vm.registers.program_counter, 0x204);
     }
 
     #[test]
-    fn test_se_v_not_equal() {
+    #[should_panic]
+    fn test_rnd_invalid() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 4;
-        vm.registers.v[2] = 5;
+        vm.rnd(0xFF, 0);
+    }
+
+    #[test]
+    fn test_custom_rng() {
+        use rand::rngs::mock::StepRng;
+
+        let mut vm = VM::new();
+        vm.registers.program_counter = 0x200;
+        vm.set_rng(StepRng::new(0x42, 1));
+        vm.rnd(1, 0xFF);
+        assert_eq!(vm.registers.v[1], 0x42);
+
+        let snapshot = vm.snapshot();
+        vm.rnd(1, 0xFF);
+        assert_eq!(vm.registers.v[1], 0x43);
+        vm.restore(&snapshot);
+        vm.rnd(1, 0xFF);
+        assert_eq!(vm.registers.v[1], 0x43);
+
+        let mut vm = VM::builder().rng(StepRng::new(7, 0)).build();
+        vm.registers.program_counter = 0x200;
+        vm.rnd(2, 0x0F);
+        assert_eq!(vm.registers.v[2], 7);
+    }
+
+    #[test]
+    fn test_drw() {
+        let mut vm = VM::new();
+        vm.registers.program_counter = 0x200;
+        let location = 0x100;
+        vm.registers.i = location as u16;
+        vm.registers.v[0x1] = 0x4;
+        vm.registers.v[0x2] = 0x5;
+        vm.registers.v[0xF] = 2;
+        let sprite = [0x20, 0x60, 0x20, 0x20, 0x70];
+        vm.memory
+            .get_slice_mut(location, location + sprite.len())
+            .copy_from_slice(&sprite);
+
+        vm.drw(0x1, 0x2, 5).unwrap();
+
+        let screen = [0, 0, 0, 0, 0, 0x40, 0x60, 0x40, 0x40, 0xE0, 0];
+        assert_eq!(&vm.graphics.display[0..11], &screen);
+        assert_eq!(vm.registers.v[0xF], 0);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_drw_collision() {
+        let mut vm = VM::new();
+        vm.registers.program_counter = 0x200;
+        let location = 0x100;
+        vm.registers.i = location as u16;
+        vm.registers.v[0x0] = 0x0;
+        vm.registers.v[0xF] = 0x2;
+        let sprite = [0xFF];
+        vm.memory
+            .get_slice_mut(location, location + sprite.len())
+            .copy_from_slice(&sprite);
+        vm.graphics.display[0] = 0x1;
+
+        vm.drw(0, 0, 1).unwrap();
+
+        assert_eq!(vm.graphics.display[0], 0xFE);
+        assert_eq!(vm.registers.v[0xF], 1);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_drw_uses_register_values() {
+        let mut vm = VM::new();
+        vm.registers.i = SPRITE_START_LOCATION as u16;
+        vm.registers.v[0x3] = 10;
+        vm.registers.v[0x4] = 20;
+
+        vm.drw(0x3, 0x4, 1).unwrap();
+
+        // Top row of digit 0 is 0xF0.
+        assert_eq!(vm.graphics.display[20], 0xF << 10);
+        assert!(vm.graphics.display[4] == 0 && vm.graphics.display[3] == 0);
+    }
+
+    #[test]
+    fn test_drw_wraps_start_coordinates() {
+        let mut vm = VM::new();
+        vm.registers.i = SPRITE_START_LOCATION as u16;
+        vm.registers.v[0x0] = 70;
+        vm.registers.v[0x1] = 40;
+
+        vm.drw(0x0, 0x1, 1).unwrap();
+
+        assert_eq!(vm.graphics.display[8], 0xF << 6);
+        assert_eq!(vm.registers.v[0xF], 0);
+    }
+
+    #[test]
+    fn test_drw_sprite_rows_per_frame() {
+        let mut vm = VM::new();
+        vm.load_program(&[0xD0, 0x15, 0x12, 0x02]).unwrap();
+        vm.registers.v[1] = 30;
+        vm.set_sprite_rows_per_frame(NonZeroU8::new(2));
+
+        vm.exec_current_instruction().unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x200);
+        assert_eq!(vm.graphics.display[30..32], [0x0F, 0x09]);
+        assert_eq!(vm.graphics.display[0], 0);
+
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.program_counter, 0x200);
+        vm.exec_current_instruction().unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(vm.registers.v[0xF], 0);
+        assert_ne!(vm.graphics.display[2], 0);
+    }
+
+    #[test]
+    fn test_drw_waits_for_next_frame() {
+        let mut vm = VM::new();
+        vm.load_program(&[0xD0, 0x05]).unwrap();
+        vm.set_sprite_rows_per_frame(NonZeroU8::new(4));
+
+        vm.exec_instruction(vm.read_current_instruction().unwrap())
+            .unwrap();
+        vm.exec_instruction(vm.read_current_instruction().unwrap())
+            .unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x200);
+        assert_eq!(vm.graphics.display[4], 0);
+
+        vm.tick_timers();
+        vm.exec_instruction(vm.read_current_instruction().unwrap())
+            .unwrap();
+
+        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_ne!(vm.graphics.display[4], 0);
+    }
+
+    #[test]
+    fn test_drw_wraps_maximal_coordinates() {
+        let mut vm = VM::new();
+        vm.registers.i = SPRITE_START_LOCATION as u16;
+        vm.registers.v[0x0] = 0xFF;
+        vm.registers.v[0x1] = 0xFF;
+
+        vm.drw(0x0, 0x1, 2).unwrap();
+
+        // 255 % 64 = 63 and 255 % 32 = 31, so the sprite wraps around both
+        // edges from the bottom right corner.
+        assert_eq!(vm.graphics.display[31], 0x7 | 1 << 63);
+        assert_eq!(vm.graphics.display[0], 1 << 2 | 1 << 63);
+    }
+
+    fn vm_with_sprite_at_memory_end(policy: OutOfRange) -> VM {
+        let mut vm = VM::new();
+        vm.set_out_of_range(policy);
         vm.registers.program_counter = 0x200;
+        vm.registers.i = 0xFFE;
+        vm.memory
+            .get_slice_mut(0xFFE, 0x1000)
+            .copy_from_slice(&[0x80, 0xC0]);
+        vm
+    }
+
+    #[test]
+    fn test_drw_past_memory_end_wraps() {
+        let mut vm = vm_with_sprite_at_memory_end(OutOfRange::Wrap);
+
+        vm.drw(0, 0, 3).unwrap();
+
+        // The third row is the first byte of the "0" digit sprite.
+        assert_eq!(vm.graphics.display[0..4], [0x1, 0x3, 0xF, 0]);
+        assert_eq!(vm.registers.v[0xF], 0);
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
 
-        vm.se_v(1, 2);
+    #[test]
+    fn test_drw_past_memory_end_clamps() {
+        let mut vm = vm_with_sprite_at_memory_end(OutOfRange::Clamp);
 
-        assert_eq!(vm.registers.v[1], 4);
-        assert_eq!(vm.registers.v[2], 5);
+        vm.drw(0, 0, 3).unwrap();
+
+        assert_eq!(vm.graphics.display[0..3], [0x1, 0x3, 0]);
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    #[should_panic]
-    fn test_se_v_invalid_first() {
-        let mut vm = VM::new();
-        vm.se_v(16, 1);
+    fn test_drw_i_past_memory_end_clamps_to_nothing() {
+        let mut vm = vm_with_sprite_at_memory_end(OutOfRange::Clamp);
+        vm.registers.i = 0x1004;
+
+        vm.drw(0, 0, 3).unwrap();
+
+        assert!(vm.graphics.display.iter().all(|&row| row == 0));
+        assert_eq!(vm.registers.v[0xF], 0);
     }
 
     #[test]
-    #[should_panic]
-    fn test_se_v_invalid_second() {
-        let mut vm = VM::new();
-        vm.se_v(0, 16);
+    fn test_drw_past_memory_end_fails() {
+        let mut vm = vm_with_sprite_at_memory_end(OutOfRange::Error);
+
+        assert_eq!(
+            vm.drw(0, 0, 3),
+            Err(VmError::MemoryOutOfBounds {
+                pc: vm.registers.program_counter,
+                address: MEMORY_SIZE - 2
+            })
+        );
     }
 
     #[test]
-    fn test_ld_vx() {
+    fn test_skp_key_pressed() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 4;
+        vm.input = Input::new_with_key_pressed(0x5);
+        vm.registers.v[0x2] = 0x5;
         vm.registers.program_counter = 0x200;
 
-        vm.ld_vx(1, 2);
-
-        assert_eq!(vm.registers.v[1], 2);
-        assert_eq!(vm.registers.program_counter, 0x202);
-    }
+        vm.skp(0x2);
 
-    #[test]
-    #[should_panic]
-    fn test_ld_vx_invalid() {
-        let mut vm = VM::new();
-        vm.ld_vx(16, 1);
+        assert_eq!(vm.registers.program_counter, 0x204);
     }
 
     #[test]
-    fn test_add_vx() {
+    fn test_skp_key_unpressed() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 200;
+        vm.input = Input::new_with_key_pressed(0x3);
+        vm.registers.v[0x2] = 0x5;
         vm.registers.program_counter = 0x200;
 
-        vm.add_vx(1, 50);
+        vm.skp(0x2);
 
-        assert_eq!(vm.registers.v[1], 250);
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    fn test_add_vx_overflow() {
+    fn test_sknp_key_pressed() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 255;
+        vm.input = Input::new_with_key_pressed(0x5);
+        vm.registers.v[0x2] = 0x5;
         vm.registers.program_counter = 0x200;
 
-        vm.add_vx(1, 1);
+        vm.sknp(0x2);
 
-        assert_eq!(vm.registers.v[1], 0);
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    fn test_ld_vx_vy() {
+    fn test_sknp_key_unpressed() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 4;
-        vm.registers.v[2] = 7;
+        vm.input = Input::new_with_key_pressed(0x4);
+        vm.registers.v[0x2] = 0x5;
         vm.registers.program_counter = 0x200;
 
-        vm.ld_vx_vy(1, 2);
+        vm.sknp(0x2);
 
-        assert_eq!(vm.registers.v[1], 7);
-        assert_eq!(vm.registers.v[2], 7);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(vm.registers.program_counter, 0x204);
     }
 
     #[test]
-    #[should_panic]
-    fn test_ld_vx_vy_invalid_x() {
+    fn test_key_down_up() {
         let mut vm = VM::new();
-        vm.ld_vx_vy(16, 1);
+        // Wait for a key, then skip the jump to self while key 2 is held.
+        vm.load_program(&[0xF0, 0x0A, 0xE1, 0x9E, 0x12, 0x04, 0x12, 0x06])
+            .unwrap();
+        vm.registers.v[1] = 2;
+        let key = |value| Key::new(value).unwrap();
+
+        vm.key_down(key(7));
+        vm.exec_current_instruction().unwrap();
+        assert!(vm.input().is_pressed(7));
+        vm.key_down(key(2));
+        vm.key_up(key(7));
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(vm.registers.v[0], 7);
+
+        vm.exec_current_instruction().unwrap();
+        assert!(vm.is_halted());
+        assert_eq!(vm.registers.program_counter, 0x206);
+        assert_eq!(vm.input(), Input::new_with_key_pressed(2));
     }
 
     #[test]
-    #[should_panic]
-    fn test_ld_vx_vy_invalid_y() {
+    fn test_skp_chord() {
         let mut vm = VM::new();
-        vm.ld_vx_vy(1, 16);
+        vm.input.press(0x4);
+        vm.input.press(0x6);
+        vm.registers.v[0x1] = 0x4;
+        vm.registers.v[0x2] = 0x6;
+        vm.registers.program_counter = 0x200;
+
+        vm.skp(0x1);
+        vm.skp(0x2);
+
+        assert_eq!(vm.registers.program_counter, 0x208);
     }
 
     #[test]
-    fn test_or() {
+    fn test_load_program_replaces_previous() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 0xF0;
-        vm.registers.v[2] = 0x0F;
-        vm.registers.program_counter = 0x200;
+        vm.load_program(&[0x1, 0x2, 0x3, 0x4]).unwrap();
 
-        vm.or(1, 2);
+        vm.load_program(&[0x5, 0x6]).unwrap();
 
-        assert_eq!(vm.registers.v[1], 0xFF);
-        assert_eq!(vm.registers.v[2], 0x0F);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(vm.memory.get_slice(0x200, 0x204), &[0x5, 0x6, 0x0, 0x0]);
+        assert_eq!(vm.registers.program_counter, 0x200);
     }
 
     #[test]
-    #[should_panic]
-    fn test_or_invalid_first() {
+    fn test_register_history() {
         let mut vm = VM::new();
-        vm.or(16, 1);
+        vm.enable_register_history(8);
+        vm.load_program(&[0x67, 0x05, 0x77, 0xFB, 0xA3, 0x00])
+            .unwrap();
+
+        for _ in 0..3 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        let history = vm.register_history().unwrap();
+        let writes = history
+            .v(0x7)
+            .map(|write| (write.pc, write.value))
+            .collect::<Vec<_>>();
+        assert_eq!(writes, [(0x200, 0x05), (0x202, 0x00)]);
+        assert_eq!(history.last_write_of_v(0x7, 0).map(|w| w.pc), Some(0x202));
+        assert_eq!(
+            history.i().map(|w| (w.pc, w.value)).last(),
+            Some((0x204, 0x300))
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn test_or_invalid_second() {
+    fn test_register_history_same_value() {
         let mut vm = VM::new();
-        vm.or(0, 16);
+        vm.enable_register_history(8);
+        // LD V7, 0; LD V0, K; ADD V1, V2
+        vm.load_program(&[0x67, 0x00, 0xF0, 0x0A, 0x81, 0x24])
+            .unwrap();
+
+        vm.exec_current_instruction().unwrap();
+        // Waiting for a key writes nothing.
+        vm.exec_current_instruction().unwrap();
+        vm.set_input(Input::new_with_key_pressed(0));
+        vm.exec_current_instruction().unwrap();
+        vm.set_input(Input::new());
+        vm.exec_current_instruction().unwrap();
+        vm.exec_current_instruction().unwrap();
+
+        let history = vm.register_history().unwrap();
+        let pcs = |x| history.v(x).map(|write| write.pc).collect::<Vec<_>>();
+        assert_eq!(pcs(0x7), [0x200]);
+        assert_eq!(pcs(0x0), [0x202]);
+        assert_eq!(pcs(0x1), [0x204]);
+        assert_eq!(pcs(0xF), [0x204]);
+        assert_eq!(history.i().count(), 0);
     }
 
     #[test]
-    fn test_and() {
+    fn test_draw_log() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 0b0101;
-        vm.registers.v[2] = 0b1110;
-        vm.registers.program_counter = 0x200;
+        vm.enable_draw_log(3);
+        // V0 := 70, I := digit 0 sprite, draw it at (V0, V1) twice, then
+        // draw its first row.
+        vm.load_program(&[0x60, 0x46, 0xF2, 0x29, 0xD0, 0x15, 0xD0, 0x15, 0xD0, 0x11])
+            .unwrap();
+
+        for _ in 0..5 {
+            vm.exec_current_instruction().unwrap();
+        }
 
-        vm.and(1, 2);
+        let draws = vm
+            .draw_log()
+            .unwrap()
+            .iter()
+            .map(|draw| {
+                (
+                    draw.frame,
+                    draw.address,
+                    draw.x,
+                    draw.y,
+                    draw.height,
+                    draw.collision,
+                )
+            })
+            .collect::<Vec<_>>();
+        // The first draw happened 3 ticks ago and is forgotten.
+        assert_eq!(
+            draws,
+            [
+                (3, SPRITE_START_LOCATION as u16, 6, 0, 5, true),
+                (4, SPRITE_START_LOCATION as u16, 6, 0, 1, false),
+            ]
+        );
+    }
 
-        assert_eq!(vm.registers.v[1], 0b0100);
-        assert_eq!(vm.registers.v[2], 0b1110);
-        assert_eq!(vm.registers.program_counter, 0x202);
+    #[test]
+    fn test_reset() {
+        let mut vm = VM::new();
+        // V0 := 0xFF, I := 0x200, [I] := V0, CALL 0x20A, DRW; V1 := 1
+        let program = [
+            0x60, 0xFF, 0xA2, 0x00, 0xF0, 0x55, 0x22, 0x0A, 0xD0, 0x05, 0x61, 0x01,
+        ];
+        vm.load_program(&program).unwrap();
+        for _ in 0..4 {
+            vm.exec_current_instruction().unwrap();
+        }
+        vm.exec_instruction(0xD005).unwrap();
+        vm.registers.delay_timer = 10;
+        vm.key_down(Key::new(0x4).unwrap());
+
+        vm.reset();
+
+        assert_eq!(vm.registers.v, [0; V_REGISTERS_SIZE]);
+        assert_eq!(vm.registers.i, 0);
+        assert_eq!(vm.registers.delay_timer, 0);
+        assert_eq!(vm.registers.program_counter, PROGRAM_START_LOCATION as u16);
+        assert_eq!(vm.stack.depth(), 0);
+        assert_eq!(vm.graphics.hash(), Graphics::new().hash());
+        assert_eq!(vm.memory(0x200..0x20C).unwrap(), program);
+        assert!(vm.input.is_pressed(0x4));
+
+        vm.hard_reset();
+        assert!(vm
+            .memory(0x200..MEMORY_SIZE)
+            .unwrap()
+            .iter()
+            .all(|&byte| byte == 0));
+        assert_eq!(vm.registers.program_counter, PROGRAM_START_LOCATION as u16);
     }
 
     #[test]
-    #[should_panic]
-    fn test_and_invalid_first() {
+    fn test_on_sound_start_stop() {
+        use std::sync::{Arc, Mutex};
+
         let mut vm = VM::new();
-        vm.and(16, 1);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let start_events = Arc::clone(&events);
+        let stop_events = Arc::clone(&events);
+        vm.on_sound_start(move || start_events.lock().unwrap().push("start"));
+        vm.on_sound_stop(move || stop_events.lock().unwrap().push("stop"));
+        // V0 := 2, ST := V0, then ST := V0 again while sounding.
+        vm.load_program(&[0x60, 0x02, 0xF0, 0x18, 0xF0, 0x18, 0x12, 0x06])
+            .unwrap();
+
+        vm.exec_current_instruction().unwrap();
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(*events.lock().unwrap(), ["start"]);
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(*events.lock().unwrap(), ["start"]);
+        // One tick from 1 to 0.
+        vm.exec_current_instruction().unwrap();
+        assert_eq!(*events.lock().unwrap(), ["start", "stop"]);
+        assert!(!vm.is_sound_playing());
     }
 
     #[test]
-    #[should_panic]
-    fn test_and_invalid_second() {
+    fn test_on_collision() {
+        use std::sync::{Arc, Mutex};
+
         let mut vm = VM::new();
-        vm.and(0, 16);
+        let collisions = Arc::new(Mutex::new(Vec::new()));
+        let handler_collisions = Arc::clone(&collisions);
+        vm.on_collision(move |collision| handler_collisions.lock().unwrap().push(*collision));
+        // V0 := 70, I := digit 0 sprite, draw it at (V0, V1) twice.
+        vm.load_program(&[0x60, 0x46, 0xF2, 0x29, 0xD0, 0x15, 0xD0, 0x15])
+            .unwrap();
+
+        for _ in 0..4 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        assert_eq!(
+            *collisions.lock().unwrap(),
+            [Collision {
+                pc: 0x206,
+                address: SPRITE_START_LOCATION as u16,
+                x: 6,
+                y: 0,
+                height: 5,
+            }]
+        );
     }
 
     #[test]
-    fn test_xor() {
+    fn test_rng_log() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 0b0100;
-        vm.registers.v[2] = 0b1110;
-        vm.registers.program_counter = 0x200;
+        vm.set_rng_seed(0xFF);
+        vm.enable_rng_log(4);
+        vm.load_program(&[0xC1, 0xFF, 0x60, 0x00, 0xC2, 0x0F])
+            .unwrap();
 
-        vm.xor(1, 2);
+        for _ in 0..3 {
+            vm.exec_current_instruction().unwrap();
+        }
 
-        assert_eq!(vm.registers.v[1], 0b1010);
-        assert_eq!(vm.registers.v[2], 0b1110);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        let calls = vm
+            .rng_log()
+            .unwrap()
+            .iter()
+            .map(|call| (call.frame, call.pc, call.mask, call.value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calls,
+            [(0, 0x200, 0xFF, 181), (2, 0x204, 0x0F, vm.registers.v[2])]
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn test_xor_invalid_first() {
+    fn test_draw_log_disabled() {
         let mut vm = VM::new();
-        vm.xor(16, 1);
+
+        vm.exec_instruction(0xD005).unwrap();
+
+        assert!(vm.draw_log().is_none());
     }
 
     #[test]
-    #[should_panic]
-    fn test_xor_invalid_second() {
+    fn test_register_history_disabled() {
         let mut vm = VM::new();
-        vm.xor(0, 16);
+
+        vm.exec_instruction(0x6705).unwrap();
+
+        assert!(vm.register_history().is_none());
     }
 
     #[test]
-    fn test_add_vx_vy_with_overflow() {
+    fn test_is_halted() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 200;
-        vm.registers.v[2] = 100;
-        vm.registers.v[0xF] = 4;
-        vm.registers.program_counter = 0x200;
+        vm.load_program(&[0x12, 0x02, 0x12, 0x02]).unwrap();
+        assert!(!vm.is_halted());
 
-        vm.add_vx_vy(1, 2);
+        vm.exec_current_instruction().unwrap();
 
-        assert_eq!(vm.registers.v[1], 44);
-        assert_eq!(vm.registers.v[2], 100);
-        assert_eq!(vm.registers.v[0xF], 1);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert!(vm.is_halted());
     }
 
     #[test]
-    fn test_add_vx_vy_without_overflow() {
+    fn test_unknown_instruction() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 50;
-        vm.registers.v[2] = 100;
-        vm.registers.v[0xF] = 4;
-        vm.registers.program_counter = 0x200;
+        vm.load_program(&[0x60, 0x01, 0xFF, 0xFF]).unwrap();
+        vm.exec_current_instruction().unwrap();
 
-        vm.add_vx_vy(1, 2);
+        let result = vm.exec_current_instruction();
 
-        assert_eq!(vm.registers.v[1], 150);
-        assert_eq!(vm.registers.v[2], 100);
-        assert_eq!(vm.registers.v[0xF], 0);
+        assert_eq!(
+            result,
+            Err(VmError::UnknownInstruction {
+                pc: 0x202,
+                instruction: 0xFFFF
+            })
+        );
         assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unknown instruction 0xFFFF at 0x202"
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn test_add_vx_vy_invalid_first() {
+    fn test_stack_errors() {
         let mut vm = VM::new();
-        vm.add_vx_vy(16, 1);
-    }
+        vm.load_program(&[0x22, 0x00]).unwrap();
+        for _ in 0..16 {
+            vm.exec_current_instruction().unwrap();
+        }
+
+        assert_eq!(
+            vm.exec_current_instruction(),
+            Err(VmError::StackOverflow { pc: 0x200 })
+        );
 
-    #[test]
-    #[should_panic]
-    fn test_add_vx_vy_invalid_second() {
         let mut vm = VM::new();
-        vm.add_vx_vy(0, 16);
+        vm.load_program(&[0x00, 0xEE]).unwrap();
+        assert_eq!(
+            vm.exec_current_instruction(),
+            Err(VmError::StackUnderflow { pc: 0x200 })
+        );
     }
 
     #[test]
-    fn test_sub_with_overflow() {
+    fn test_memory_out_of_bounds() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 100;
-        vm.registers.v[2] = 200;
-        vm.registers.v[0xF] = 4;
         vm.registers.program_counter = 0x200;
+        vm.registers.i = 0xFFF;
+        vm.registers.v[1] = 0xAA;
+
+        assert_eq!(
+            vm.exec_instruction(0xF155),
+            Err(VmError::MemoryOutOfBounds {
+                pc: 0x200,
+                address: 0xFFF
+            })
+        );
+        assert_eq!(vm.memory(0xFFF..0x1000).unwrap(), [0]);
+        vm.exec_instruction(0xF055).unwrap();
 
-        vm.sub(1, 2);
+        vm.set_out_of_range(OutOfRange::Error);
+        assert!(vm.exec_instruction(0xD002).is_err());
 
-        assert_eq!(vm.registers.v[1], 156);
-        assert_eq!(vm.registers.v[2], 200);
-        assert_eq!(vm.registers.v[0xF], 0);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        vm.registers.program_counter = 0xFFF;
+        assert!(vm.exec_current_instruction().is_err());
+        assert!(!vm.is_halted());
     }
 
     #[test]
-    fn test_sub_without_overflow() {
+    fn test_i_past_memory_end_without_access() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 150;
-        vm.registers.v[2] = 100;
-        vm.registers.v[0xF] = 4;
-        vm.registers.program_counter = 0x200;
+        // LDL I, 0xFFFF; LD V0, 0xFF
+        vm.load_program(&[0xF0, 0x00, 0xFF, 0xFF, 0x60, 0xFF])
+            .unwrap();
 
-        vm.sub(1, 2);
+        vm.exec_current_instruction().unwrap();
+        vm.exec_current_instruction().unwrap();
 
-        assert_eq!(vm.registers.v[1], 50);
-        assert_eq!(vm.registers.v[2], 100);
-        assert_eq!(vm.registers.v[0xF], 1);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(vm.registers.i, 0xFFFF);
+        assert_eq!(vm.registers.v[0], 0xFF);
     }
 
     #[test]
-    #[should_panic]
-    fn test_sub_invalid_first() {
+    fn test_step_effects() {
         let mut vm = VM::new();
-        vm.sub(16, 1);
+        vm.load_program(&[
+            0x22, 0x08, // CALL 0x208
+            0xF0, 0x0A, // LD V0, K
+            0x12, 0x04, // JP 0x204
+            0x00, 0x00, //
+            0x60, 0x03, // LD V0, 3
+            0xF0, 0x18, // LD ST, V0
+            0xD0, 0x05, // DRW V0, V0, 5
+            0x00, 0xEE, // RET
+        ])
+        .unwrap();
+
+        let mut step = || vm.step().unwrap().effects;
+        assert_eq!(step(), Effects::STACK_PUSHED);
+        assert_eq!(step(), Effects::NONE);
+        assert_eq!(step(), Effects::SOUND_STARTED);
+        assert_eq!(step(), Effects::DISPLAY_CHANGED);
+        assert_eq!(step(), Effects::STACK_POPPED | Effects::SOUND_STOPPED);
+        assert_eq!(step(), Effects::WAITING_FOR_KEY);
+        vm.set_input(Input::new_with_key_pressed(5));
+        assert_eq!(vm.step().unwrap().effects, Effects::WAITING_FOR_KEY);
+        vm.set_input(Input::new());
+        assert_eq!(vm.step().unwrap().effects, Effects::HALTED);
     }
 
     #[test]
-    #[should_panic]
-    fn test_sub_invalid_second() {
+    fn test_step_info() {
         let mut vm = VM::new();
-        vm.sub(0, 16);
+        // LD V0, 2; LD DT, V0; DRW V0, V0, 5; LDL I, 0x1234; <unknown>
+        vm.load_program(&[
+            0x60, 0x02, 0xF0, 0x15, 0xD0, 0x05, 0xF0, 0x00, 0x12, 0x34, 0xFF, 0xFF,
+        ])
+        .unwrap();
+        vm.enable_sandbox(1);
+
+        let info = vm.step().unwrap();
+        assert_eq!(
+            info,
+            StepInfo {
+                instruction: Some(Instruction::LdByte(0, 2)),
+                pc: 0x200,
+                next_pc: 0x202,
+                effects: Effects::NONE,
+                timers_changed: false,
+            }
+        );
+        // Set to 2, then ticked to 1.
+        assert!(vm.step().unwrap().timers_changed);
+        // Ticked to 0.
+        let info = vm.step().unwrap();
+        assert!(info.display_changed());
+        assert!(info.timers_changed);
+        let info = vm.step().unwrap();
+        assert_eq!(info.instruction, Some(Instruction::LdILong(0x1234)));
+        assert_eq!(info.next_pc, 0x20A);
+        assert!(!info.timers_changed);
+        let info = vm.step().unwrap();
+        assert_eq!(info.instruction, None);
+        assert_eq!(info.next_pc, 0x20C);
     }
 
     #[test]
-    fn test_shr_with_overflow() {
+    fn test_advance_timers_carries_partial_ticks() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 0b0101;
-        vm.registers.v[0xF] = 4;
-        vm.registers.program_counter = 0x200;
+        vm.registers.delay_timer = 10;
+        vm.registers.sound_timer = 10;
 
-        vm.shr(1);
+        vm.advance_timers(Duration::from_millis(10));
+        assert_eq!(vm.registers.delay_timer, 10);
+        assert!((vm.timer_phase() - 0.6).abs() < 1e-9);
 
-        assert_eq!(vm.registers.v[1], 0b0010);
-        assert_eq!(vm.registers.v[0xF], 1);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        vm.advance_timers(Duration::from_millis(7));
+        assert_eq!(vm.registers.delay_timer, 9);
+        assert_eq!(vm.registers.sound_timer, 9);
+
+        vm.advance_timers(Duration::from_millis(50));
+        assert_eq!(vm.registers.delay_timer, 6);
     }
 
     #[test]
-    fn test_shr_without_overflow() {
+    fn test_advance_timers_irregular_intervals() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 0b1010;
-        vm.registers.v[0xF] = 4;
-        vm.registers.program_counter = 0x200;
-
-        vm.shr(1);
+        vm.registers.delay_timer = 200;
+
+        let intervals = [3, 29, 1, 16, 17, 40, 8, 86];
+        let mut total = 0;
+        while total < 1000 {
+            for millis in intervals {
+                let millis = millis.min(1000 - total);
+                vm.advance_timers(Duration::from_millis(millis));
+                total += millis;
+            }
+        }
 
-        assert_eq!(vm.registers.v[1], 0b0101);
-        assert_eq!(vm.registers.v[0xF], 0);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(vm.registers.delay_timer, 200 - 60);
+        assert_eq!(vm.timer_phase(), 0.0);
     }
 
     #[test]
-    #[should_panic]
-    fn test_shr_invalid() {
+    fn test_advance_timers_long_time() {
         let mut vm = VM::new();
-        vm.shr(16);
+        vm.registers.delay_timer = 255;
+
+        vm.advance_timers(Duration::from_secs(3600));
+
+        assert_eq!(vm.registers.delay_timer, 0);
     }
 
     #[test]
-    fn test_subn_with_overflow() {
+    fn test_run_for() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 200;
-        vm.registers.v[2] = 100;
-        vm.registers.v[0xF] = 4;
-        vm.registers.program_counter = 0x200;
+        // loop: V0 += 1, jump to loop
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        vm.registers.delay_timer = 100;
 
-        vm.subn(1, 2);
+        vm.run_for(Duration::from_secs(1)).unwrap();
 
-        assert_eq!(vm.registers.v[1], 156);
-        assert_eq!(vm.registers.v[2], 100);
-        assert_eq!(vm.registers.v[0xF], 0);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        let added = INSTRUCTIONS_PER_SECOND / 2;
+        assert_eq!(vm.registers.v[0], added as u8);
+        assert_eq!(vm.registers.delay_timer, 100 - TIMER_FREQUENCY as u8);
     }
 
     #[test]
-    fn test_subn_without_overflow() {
-        let mut vm = VM::new();
-        vm.registers.v[1] = 100;
-        vm.registers.v[2] = 150;
-        vm.registers.v[0xF] = 4;
-        vm.registers.program_counter = 0x200;
+    fn test_instructions_per_second() {
+        let mut vm = VM::builder()
+            .instructions_per_second(NonZeroU32::new(1200).unwrap())
+            .build();
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
 
-        vm.subn(1, 2);
+        vm.run_for(Duration::from_millis(100)).unwrap();
+        assert_eq!(vm.registers.v[0], 60);
+        assert_eq!(vm.instructions_per_frame.get(), 20);
 
-        assert_eq!(vm.registers.v[1], 50);
-        assert_eq!(vm.registers.v[2], 150);
-        assert_eq!(vm.registers.v[0xF], 1);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        vm.set_instructions_per_second(NonZeroU32::new(30).unwrap());
+        assert_eq!(vm.instructions_per_frame.get(), 1);
+        vm.set_instructions_per_frame(NonZeroU32::new(8).unwrap());
+        assert_eq!(vm.instructions_per_second().get(), 480);
     }
 
     #[test]
-    #[should_panic]
-    fn test_subn_invalid_first() {
+    fn test_run_for_in_slices() {
+        let mut whole = VM::new();
+        let mut sliced = VM::new();
+        let program = [0x70, 0x01, 0x12, 0x00];
+        whole.load_program(&program).unwrap();
+        sliced.load_program(&program).unwrap();
+        whole.registers.delay_timer = 100;
+        sliced.registers.delay_timer = 100;
+
+        whole.run_for(Duration::from_millis(500)).unwrap();
+        for micros in [700, 1300, 16_667, 2_000, 79_333, 400_000] {
+            sliced.run_for(Duration::from_micros(micros)).unwrap();
+        }
+
+        assert_eq!(sliced.registers.v[0], whole.registers.v[0]);
+        assert_eq!(sliced.registers.delay_timer, whole.registers.delay_timer);
+        assert_eq!(sliced.registers.delay_timer, 70);
+    }
+
+    #[test]
+    fn test_instructions_timer_policy() {
         let mut vm = VM::new();
-        vm.subn(16, 1);
+        vm.set_timer_policy(TimerPolicy::Instructions(NonZeroU32::new(3).unwrap()));
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        vm.registers.delay_timer = 10;
+
+        for _ in 0..8 {
+            vm.exec_current_instruction().unwrap();
+        }
+        assert_eq!(vm.registers.delay_timer, 8);
+
+        vm.advance_timers(Duration::from_secs(1));
+        assert_eq!(vm.registers.delay_timer, 8);
+
+        // 60 instructions of run_for, 20 ticks.
+        vm.run_for(Duration::from_millis(100)).unwrap();
+        assert_eq!(vm.registers.delay_timer, 0);
     }
 
     #[test]
-    #[should_panic]
-    fn test_subn_invalid_second() {
+    fn test_run_frame() {
         let mut vm = VM::new();
-        vm.subn(0, 16);
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        vm.registers.delay_timer = 10;
+
+        let result = vm.run_frame().unwrap();
+
+        assert_eq!(
+            result,
+            FrameResult {
+                instructions: INSTRUCTIONS_PER_FRAME,
+                display_changed: false,
+            }
+        );
+        assert_eq!(vm.registers.v[0], 5);
+        assert_eq!(vm.registers.delay_timer, 9);
+
+        vm.set_instructions_per_frame(NonZeroU32::new(4).unwrap());
+        vm.run_frame().unwrap();
+        assert_eq!(vm.registers.v[0], 7);
+        assert_eq!(vm.registers.delay_timer, 8);
     }
 
     #[test]
-    fn test_shl_with_overflow() {
+    fn test_run_frame_display_changed_and_halt() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 0b10101010;
-        vm.registers.v[0xF] = 4;
-        vm.registers.program_counter = 0x200;
+        // Draw digit 0, then halt.
+        vm.load_program(&[0xD0, 0x05, 0x12, 0x02]).unwrap();
 
-        vm.shl(1);
+        let result = vm.run_frame().unwrap();
 
-        assert_eq!(vm.registers.v[1], 0b01010100);
-        assert_eq!(vm.registers.v[0xF], 1);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(
+            result,
+            FrameResult {
+                instructions: 1,
+                display_changed: true,
+            }
+        );
+        assert!(!vm.run_frame().unwrap().display_changed);
     }
 
     #[test]
-    fn test_shl_without_overflow() {
+    fn test_run_frame_display_changed_hires() {
         let mut vm = VM::new();
-        vm.registers.v[1] = 0b01101010;
-        vm.registers.v[0xF] = 4;
-        vm.registers.program_counter = 0x200;
-
-        vm.shl(1);
+        // HIGH; draw digit 0; halt.
+        vm.load_program(&[0x00, 0xFF, 0xD0, 0x05, 0x12, 0x04])
+            .unwrap();
+        vm.set_instructions_per_frame(NonZeroU32::new(1).unwrap());
 
-        assert_eq!(vm.registers.v[1], 0b11010100);
-        assert_eq!(vm.registers.v[0xF], 0);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert!(vm.run_frame().unwrap().display_changed);
+        assert!(vm.run_frame().unwrap().display_changed);
+        assert!(vm.screen().pixel(0, 0));
+        assert!(!vm.run_frame().unwrap().display_changed);
     }
 
     #[test]
-    #[should_panic]
-    fn test_shl_invalid() {
+    fn test_run_until_halted() {
         let mut vm = VM::new();
-        vm.shr(16);
+        // Count to 100 with V0, then halt.
+        vm.load_program(&[0x70, 0x01, 0x30, 0x64, 0x12, 0x00, 0x12, 0x06])
+            .unwrap();
+        vm.registers.delay_timer = 50;
+
+        let result = vm.run_until_halted(None).unwrap();
+
+        assert_eq!(
+            result,
+            RunResult {
+                status: RunStatus::Halted,
+                instructions: 299,
+            }
+        );
+        assert_eq!(vm.registers.v[0], 100);
+        assert_eq!(
+            vm.registers.delay_timer,
+            50 - (299 / INSTRUCTIONS_PER_FRAME) as u8
+        );
+        assert!(vm.is_halted());
     }
 
     #[test]
-    fn test_sne_vx_vy_equal() {
+    fn test_run_until_halted_budget() {
         let mut vm = VM::new();
-        vm.registers.program_counter = 0x200;
-        vm.registers.v[0x1] = 5;
-        vm.registers.v[0x2] = 5;
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
 
-        vm.sne_vx_vy(0x1, 0x2);
+        let result = vm.run_until_halted(Some(1000)).unwrap();
 
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(
+            result,
+            RunResult {
+                status: RunStatus::BudgetExhausted,
+                instructions: 1000,
+            }
+        );
+        assert_eq!(vm.registers.v[0], (500 % 256) as u8);
+        assert_eq!(vm.run_until_halted(Some(0)).unwrap().instructions, 0);
     }
 
     #[test]
-    fn test_sne_vx_vy_unequal() {
+    fn test_run_frame_error() {
         let mut vm = VM::new();
-        vm.registers.program_counter = 0x200;
-        vm.registers.v[0x1] = 5;
-        vm.registers.v[0x1] = 6;
-
-        vm.sne_vx_vy(0x1, 0x2);
-
-        assert_eq!(vm.registers.program_counter, 0x204);
+        vm.load_program(&[0x70, 0x01, 0xFF, 0xFF]).unwrap();
+        vm.registers.delay_timer = 10;
+
+        assert_eq!(
+            vm.run_frame(),
+            Err(VmError::UnknownInstruction {
+                pc: 0x202,
+                instruction: 0xFFFF
+            })
+        );
+        assert_eq!(vm.registers.delay_timer, 10);
     }
 
     #[test]
-    fn test_ld_i() {
+    fn test_elapsed_timer_policy() {
         let mut vm = VM::new();
-        vm.registers.i = 5;
-        vm.registers.program_counter = 0x200;
+        vm.set_timer_policy(TimerPolicy::Elapsed);
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        vm.registers.delay_timer = 100;
 
-        vm.ld_i(0x0111);
+        for _ in 0..10 {
+            vm.exec_current_instruction().unwrap();
+        }
+        assert_eq!(vm.registers.delay_timer, 100);
 
-        assert_eq!(vm.registers.i, 0x0111);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        vm.advance_timers(Duration::from_millis(500));
+        assert_eq!(vm.registers.delay_timer, 70);
+
+        vm.run_for(Duration::from_millis(500)).unwrap();
+        assert_eq!(vm.registers.delay_timer, 40);
     }
 
     #[test]
-    #[should_panic]
-    fn test_ld_i_invalid() {
+    fn test_instructions_timer_policy_in_run_for() {
         let mut vm = VM::new();
-        vm.ld_i(0xF000);
+        vm.set_timer_policy(TimerPolicy::Instructions(NonZeroU32::new(20).unwrap()));
+        vm.load_program(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        vm.registers.delay_timer = 100;
+
+        vm.run_for(Duration::from_secs(1)).unwrap();
+
+        assert_eq!(
+            vm.registers.delay_timer,
+            100 - (INSTRUCTIONS_PER_SECOND / 20) as u8
+        );
     }
 
     #[test]
-    fn test_jp_v0() {
+    fn test_is_waiting_for_timer() {
         let mut vm = VM::new();
-        vm.registers.program_counter = 100;
-        vm.registers.v[0] = 5;
+        vm.load_program(&[0x65, 0x03, 0xF5, 0x15, 0xF5, 0x07, 0x35, 0x00, 0x12, 0x04])
+            .unwrap();
+        vm.exec_current_instruction().unwrap();
+        vm.exec_current_instruction().unwrap();
+        assert!(vm.is_waiting_for_timer());
 
-        vm.jp_v0(20);
+        vm.tick_timers();
+        vm.tick_timers();
 
-        assert_eq!(vm.registers.program_counter, 25);
+        assert!(!vm.is_waiting_for_timer());
     }
 
     #[test]
-    #[should_panic]
-    fn test_jp_v0_invalid() {
+    fn test_is_waiting_for_timer_other_loop() {
         let mut vm = VM::new();
-        vm.jp_v0(0xF000);
+        // Register in the skip differs from the one read.
+        vm.load_program(&[0xF5, 0x07, 0x36, 0x00, 0x12, 0x00])
+            .unwrap();
+        vm.registers.delay_timer = 10;
+
+        assert!(!vm.is_waiting_for_timer());
     }
 
     #[test]
-    fn test_rnd() {
+    fn test_write_and_read_memory() {
         let mut vm = VM::new();
-        vm.rng = SmallRng::seed_from_u64(0xFF);
-        vm.registers.program_counter = 0x200;
-        vm.registers.v[1] = 0xAF;
-
-        vm.rnd(1, 0xFF);
-
-        assert_eq!(vm.registers.v[1], 181);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        vm.load_program(&[0x12, 0x00]).unwrap();
 
-        vm.rnd(1, 0x0F);
+        vm.write_memory(0x3A0, &[1, 2, 3]).unwrap();
 
-        assert_eq!(vm.registers.v[1], 5);
-        assert_eq!(vm.registers.program_counter, 0x204);
+        assert_eq!(vm.memory(0x39F..0x3A4), Some(&[0, 1, 2, 3, 0][..]));
+        assert_eq!(vm.memory(0x200..0x202), Some(&[0x12, 0x00][..]));
     }
 
     #[test]
-    #[should_panic]
-    fn test_rnd_invalid() {
+    fn test_memory_past_end() {
         let mut vm = VM::new();
-        vm.rnd(0xFF, 0);
+        vm.load_program(&[0x12, 0x00]).unwrap();
+
+        assert_eq!(
+            vm.write_memory(MEMORY_SIZE - 1, &[1, 2]),
+            Err(VmError::MemoryOutOfBounds {
+                pc: 0x200,
+                address: MEMORY_SIZE - 1
+            })
+        );
+        assert_eq!(vm.memory(MEMORY_SIZE - 1..MEMORY_SIZE), Some(&[0][..]));
+        assert_eq!(vm.memory(MEMORY_SIZE - 1..MEMORY_SIZE + 1), None);
     }
 
     #[test]
-    fn test_drw() {
+    fn test_memory_footprint() {
         let mut vm = VM::new();
-        vm.registers.program_counter = 0x200;
-        let location = 0x100;
-        vm.registers.i = location as u16;
-        vm.registers.v[0x1] = 0x4;
-        vm.registers.v[0x2] = 0x5;
-        vm.registers.v[0xF] = 2;
-        let sprite = [0x20, 0x60, 0x20, 0x20, 0x70];
-        vm.memory
-            .get_slice_mut(location, location + sprite.len())
-            .copy_from_slice(&sprite);
+        let footprint = vm.memory_footprint();
+
+        assert_eq!(footprint.memory, MEMORY_SIZE);
+        assert_eq!(footprint.graphics, DISPLAY_ROWS * 8);
+        assert_eq!(footprint.register_history, 0);
+        assert!(
+            footprint.vm
+                >= footprint.memory
+                    + footprint.registers
+                    + footprint.stack
+                    + footprint.graphics
+                    + footprint.input
+                    + footprint.rng
+        );
+        assert_eq!(footprint.total(), footprint.vm);
 
-        vm.drw(0x1, 0x2, 5);
+        vm.enable_register_history(4);
+        vm.exec_instruction(0x6001).unwrap();
+        vm.exec_instruction(0xA123).unwrap();
 
-        let screen = [0, 0, 0, 0, 0, 0x40, 0x60, 0x40, 0x40, 0xE0, 0];
-        assert_eq!(&vm.graphics.display[0..11], &screen);
-        assert_eq!(vm.registers.v[0xF], 0);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert!(vm.memory_footprint().register_history > 0);
     }
 
     #[test]
-    fn test_drw_collision() {
+    fn test_set_rng_seed() {
         let mut vm = VM::new();
-        vm.registers.program_counter = 0x200;
-        let location = 0x100;
-        vm.registers.i = location as u16;
-        vm.registers.v[0x0] = 0x0;
-        vm.registers.v[0xF] = 0x2;
-        let sprite = [0xFF];
-        vm.memory
-            .get_slice_mut(location, location + sprite.len())
-            .copy_from_slice(&sprite);
-        vm.graphics.display[0] = 0x1;
+        vm.set_rng_seed(0xFF);
 
-        vm.drw(0, 0, 1);
+        vm.exec_instruction(0xC1FF).unwrap();
 
-        assert_eq!(vm.graphics.display[0], 0xFE);
-        assert_eq!(vm.registers.v[0xF], 1);
-        assert_eq!(vm.registers.program_counter, 0x202);
+        assert_eq!(vm.registers.v[1], 181);
     }
 
     #[test]
-    fn test_skp_key_pressed() {
+    fn test_set_input() {
         let mut vm = VM::new();
-        vm.input = Input::new_with_key_pressed(0x5);
         vm.registers.v[0x2] = 0x5;
         vm.registers.program_counter = 0x200;
 
+        vm.set_input(Input::new_with_key_pressed(0x5));
         vm.skp(0x2);
 
         assert_eq!(vm.registers.program_counter, 0x204);
     }
 
     #[test]
-    fn test_skp_key_unpressed() {
+    fn test_ld_vx_dt() {
         let mut vm = VM::new();
-        vm.input = Input::new_with_key_pressed(0x3);
-        vm.registers.v[0x2] = 0x5;
         vm.registers.program_counter = 0x200;
+        let value = 0xFA;
+        vm.registers.delay_timer = value;
 
-        vm.skp(0x2);
+        vm.ld_vx_dt(0x2);
 
+        assert_eq!(vm.registers.v[0x2], value);
+        assert_eq!(vm.registers.delay_timer, value);
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    fn test_sknp_key_pressed() {
+    fn test_ld_vx_k_pressed() {
         let mut vm = VM::new();
         vm.input = Input::new_with_key_pressed(0x5);
-        vm.registers.v[0x2] = 0x5;
+        vm.registers.v[0x2] = 0x1;
         vm.registers.program_counter = 0x200;
 
-        vm.sknp(0x2);
-
-        assert_eq!(vm.registers.program_counter, 0x202);
-    }
+        vm.ld_vx_k(0x2);
+        vm.ld_vx_k(0x2);
 
-    #[test]
-    fn test_sknp_key_unpressed() {
-        let mut vm = VM::new();
-        vm.input = Input::new_with_key_pressed(0x4);
-        vm.registers.v[0x2] = 0x5;
-        vm.registers.program_counter = 0x200;
+        assert_eq!(vm.registers.v[0x2], 0x1);
+        assert_eq!(vm.registers.program_counter, 0x200);
 
-        vm.sknp(0x2);
+        vm.input = Input::new();
+        vm.ld_vx_k(0x2);
 
-        assert_eq!(vm.registers.program_counter, 0x204);
+        assert_eq!(vm.registers.v[0x2], 0x5);
+        assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    fn test_ld_vx_dt() {
+    fn test_ld_vx_k_other_key() {
         let mut vm = VM::new();
         vm.registers.program_counter = 0x200;
-        let value = 0xFA;
-        vm.registers.delay_timer = value;
+        vm.input = Input::new_with_key_pressed(0x5);
+        vm.ld_vx_k(0x2);
 
-        vm.ld_vx_dt(0x2);
+        vm.input = Input::new_with_key_pressed(0x7);
+        vm.ld_vx_k(0x2);
 
-        assert_eq!(vm.registers.v[0x2], value);
-        assert_eq!(vm.registers.delay_timer, value);
+        assert_eq!(vm.registers.v[0x2], 0x5);
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
     #[test]
-    fn test_ld_vx_k_pressed() {
+    fn test_ld_vx_k_on_press_quirk() {
         let mut vm = VM::new();
+        vm.set_quirks(Quirks {
+            get_key_on_press: true,
+            ..Quirks::default()
+        });
         vm.input = Input::new_with_key_pressed(0x5);
-        vm.registers.v[0x2] = 0x1;
         vm.registers.program_counter = 0x200;
 
         vm.ld_vx_k(0x2);
@@ -1477,6 +4448,20 @@ mod tests {
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
+    #[test]
+    fn test_is_sound_playing() {
+        let mut vm = VM::new();
+        assert!(!vm.is_sound_playing());
+        vm.registers.v[0x2] = 2;
+
+        vm.ld_st(0x2);
+        assert!(vm.is_sound_playing());
+
+        vm.tick_timers();
+        vm.tick_timers();
+        assert!(!vm.is_sound_playing());
+    }
+
     #[test]
     fn test_add_i() {
         let mut vm = VM::new();
@@ -1509,6 +4494,58 @@ mod tests {
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
+    #[test]
+    fn test_ld_hf() {
+        let mut vm = VM::new();
+        vm.registers.program_counter = 0x200;
+        vm.registers.v[0x3] = 8;
+
+        vm.ld_hf(0x3);
+
+        assert_eq!(vm.registers.i, 0xA0);
+        let sprite = vm.memory.get_slice(
+            vm.registers.i as usize,
+            vm.registers.i as usize + BIG_SPRITE_SIZE,
+        );
+        assert_eq!(
+            sprite,
+            &[0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF]
+        );
+        assert_eq!(vm.registers.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_ld_f_custom_font() {
+        let mut vm = VM::new();
+        let mut font = [0; FONT_SIZE];
+        font[25..30].copy_from_slice(&[0x1, 0x2, 0x3, 0x4, 0x5]);
+        vm.load_font(&font);
+        vm.registers.v[0x2] = 5;
+
+        vm.ld_f(0x2);
+
+        let sprite = vm.memory.get_slice(
+            vm.registers.i as usize,
+            vm.registers.i as usize + SPRITE_SIZE,
+        );
+        assert_eq!(sprite, &[0x1, 0x2, 0x3, 0x4, 0x5]);
+    }
+
+    #[test]
+    fn test_ld_f_font_style() {
+        let mut vm = VM::new();
+        vm.set_font_style(FontStyle::Vip);
+        vm.registers.v[0x2] = 1;
+
+        vm.ld_f(0x2);
+
+        let sprite = vm.memory.get_slice(
+            vm.registers.i as usize,
+            vm.registers.i as usize + SPRITE_SIZE,
+        );
+        assert_eq!(sprite, &[0x60, 0x20, 0x20, 0x20, 0x70]);
+    }
+
     #[test]
     fn test_ld_b() {
         let mut vm = VM::new();
@@ -1553,11 +4590,77 @@ mod tests {
         assert_eq!(vm.registers.program_counter, 0x202);
     }
 
+    #[test]
+    fn test_rpl_flags() {
+        let storage = Arc::new(MemoryStorage::new());
+        let mut vm = VM::new();
+        vm.set_rpl_flags(RplFlags::with_storage(storage.clone(), "pong.rpl").unwrap());
+        vm.registers.program_counter = 0x200;
+        vm.registers.v = (0x0..=0xF).collect::<Vec<u8>>().try_into().unwrap();
+
+        vm.exec_instruction(0xF275).unwrap();
+        vm.exec_instruction(0xFF75).unwrap();
+
+        assert_eq!(vm.rpl_flags(), &[0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(
+            storage.load("pong.rpl").unwrap(),
+            Some(vec![0, 1, 2, 3, 4, 5, 6, 7])
+        );
+        vm.registers.v = [0xAA; V_REGISTERS_SIZE];
+        vm.exec_instruction(0xF185).unwrap();
+        assert_eq!(vm.registers.v[0..3], [0, 1, 0xAA]);
+        vm.exec_instruction(0xFF85).unwrap();
+        assert_eq!(vm.registers.v[7..9], [7, 0xAA]);
+        assert_eq!(vm.registers.program_counter, 0x208);
+
+        // Flags outlive the VM.
+        let mut vm = VM::new();
+        vm.set_rpl_flags(RplFlags::with_storage(storage, "pong.rpl").unwrap());
+        vm.reset();
+        assert_eq!(vm.rpl_flags(), &[0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_rpl_flags_errors() {
+        struct ReadOnly;
+        impl Storage for ReadOnly {
+            fn load(&self, _key: &str) -> std::io::Result<Option<Vec<u8>>> {
+                Ok(None)
+            }
+            fn store(&self, _key: &str, _data: &[u8]) -> std::io::Result<()> {
+                Err(std::io::ErrorKind::PermissionDenied.into())
+            }
+            fn remove(&self, _key: &str) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut vm = VM::new();
+        vm.set_rpl_flags(RplFlags::with_storage(Arc::new(ReadOnly), "flags").unwrap());
+        vm.registers.program_counter = 0x200;
+        vm.registers.v[0] = 1;
+
+        assert_eq!(
+            vm.exec_instruction(0xF075),
+            Err(VmError::FlagStorage { pc: 0x200 })
+        );
+        assert_eq!(vm.rpl_flags(), &[0; RPL_FLAGS]);
+        assert_eq!(vm.registers.program_counter, 0x200);
+
+        vm.set_opcode_family(OpcodeFamily::Chip8);
+        assert_eq!(
+            vm.exec_instruction(0xF085),
+            Err(VmError::UnknownInstruction {
+                pc: 0x200,
+                instruction: 0xF085
+            })
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_exec_instruction_invalid() {
         let mut vm = VM::new();
-        vm.exec_instruction(0xFFFF);
+        vm.exec_instruction(0xFFFF).unwrap();
     }
 
     #[test]
@@ -1565,7 +4668,7 @@ mod tests {
         let mut vm = VM::new();
         vm.graphics.display[0x1] = 0xFF;
 
-        vm.exec_instruction(0x00E0);
+        vm.exec_instruction(0x00E0).unwrap();
 
         assert!(vm.graphics.display.iter().all(|&x| x == 0u64));
     }
@@ -1573,12 +4676,12 @@ mod tests {
     #[test]
     fn test_exec_instruction_ret() {
         let mut vm = VM::new();
-        vm.stack.push(0x1);
-        assert_eq!(vm.stack.pointer, 1);
+        vm.stack.push(0x1).unwrap();
+        assert_eq!(vm.stack.depth(), 1);
 
-        vm.exec_instruction(0x00EE);
+        vm.exec_instruction(0x00EE).unwrap();
 
-        assert_eq!(vm.stack.pointer, 0);
+        assert_eq!(vm.stack.depth(), 0);
     }
 
     #[test]
@@ -1586,7 +4689,7 @@ mod tests {
         let mut vm = VM::new();
         assert_eq!(vm.registers.program_counter, 0x0);
 
-        vm.exec_instruction(0x1ABC);
+        vm.exec_instruction(0x1ABC).unwrap();
 
         assert_eq!(vm.registers.program_counter, 0x0ABC);
     }
@@ -1595,12 +4698,12 @@ mod tests {
     fn test_exec_instruction_call() {
         let mut vm = VM::new();
         vm.registers.program_counter = 0x200;
-        assert_eq!(vm.stack.pointer, 0);
+        assert_eq!(vm.stack.depth(), 0);
 
-        vm.exec_instruction(0x2ABC);
+        vm.exec_instruction(0x2ABC).unwrap();
 
         assert_eq!(vm.registers.program_counter, 0x0ABC);
-        assert_eq!(vm.stack.pointer, 1);
+        assert_eq!(vm.stack.depth(), 1);
     }
 
     #[test]
@@ -1609,7 +4712,7 @@ mod tests {
         vm.registers.program_counter = 0x202;
         vm.registers.v[0xA] = 0xBC;
 
-        vm.exec_instruction(0x3ABC);
+        vm.exec_instruction(0x3ABC).unwrap();
 
         assert_eq!(vm.registers.program_counter, 0x206);
     }
@@ -1620,7 +4723,7 @@ mod tests {
         vm.registers.program_counter = 0x202;
         vm.registers.v[0xA] = 0xBC;
 
-        vm.exec_instruction(0x4ABB);
+        vm.exec_instruction(0x4ABB).unwrap();
 
         assert_eq!(vm.registers.program_counter, 0x206);
     }
@@ -1632,7 +4735,7 @@ mod tests {
         vm.registers.v[0xA] = 0xBC;
         vm.registers.v[0xB] = 0xBC;
 
-        vm.exec_instruction(0x5AB0);
+        vm.exec_instruction(0x5AB0).unwrap();
 
         assert_eq!(vm.registers.program_counter, 0x206);
     }
@@ -1642,7 +4745,7 @@ mod tests {
         let mut vm = VM::new();
         vm.registers.v[0xA] = 0xBC;
 
-        vm.exec_instruction(0x6AFF);
+        vm.exec_instruction(0x6AFF).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0xFF);
     }
@@ -1652,7 +4755,7 @@ mod tests {
         let mut vm = VM::new();
         vm.registers.v[0xA] = 0xBC;
 
-        vm.exec_instruction(0x7A11);
+        vm.exec_instruction(0x7A11).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0xCD);
     }
@@ -1663,7 +4766,7 @@ mod tests {
         vm.registers.v[0xA] = 0xAA;
         vm.registers.v[0xB] = 0xBB;
 
-        vm.exec_instruction(0x8AB0);
+        vm.exec_instruction(0x8AB0).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0xBB);
         assert_eq!(vm.registers.v[0xB], 0xBB);
@@ -1675,7 +4778,7 @@ mod tests {
         vm.registers.v[0xA] = 0b1100_1100;
         vm.registers.v[0xB] = 0b0011_1100;
 
-        vm.exec_instruction(0x8AB1);
+        vm.exec_instruction(0x8AB1).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0b1111_1100);
         assert_eq!(vm.registers.v[0xB], 0b0011_1100);
@@ -1687,7 +4790,7 @@ mod tests {
         vm.registers.v[0xA] = 0b1100_1100;
         vm.registers.v[0xB] = 0b0011_1100;
 
-        vm.exec_instruction(0x8AB2);
+        vm.exec_instruction(0x8AB2).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0b0000_1100);
         assert_eq!(vm.registers.v[0xB], 0b0011_1100);
@@ -1699,7 +4802,7 @@ mod tests {
         vm.registers.v[0xA] = 0b1100_1100;
         vm.registers.v[0xB] = 0b0011_1100;
 
-        vm.exec_instruction(0x8AB3);
+        vm.exec_instruction(0x8AB3).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0b1111_0000);
         assert_eq!(vm.registers.v[0xB], 0b0011_1100);
@@ -1711,7 +4814,7 @@ mod tests {
         vm.registers.v[0xA] = 0b1100_1100;
         vm.registers.v[0xB] = 0b0010_0100;
 
-        vm.exec_instruction(0x8AB4);
+        vm.exec_instruction(0x8AB4).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0b1111_0000);
         assert_eq!(vm.registers.v[0xB], 0b0010_0100);
@@ -1723,7 +4826,7 @@ mod tests {
         vm.registers.v[0xA] = 0b1100_1100;
         vm.registers.v[0xB] = 0b0010_0100;
 
-        vm.exec_instruction(0x8AB5);
+        vm.exec_instruction(0x8AB5).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0b1010_1000);
         assert_eq!(vm.registers.v[0xB], 0b0010_0100);
@@ -1734,7 +4837,7 @@ mod tests {
         let mut vm = VM::new();
         vm.registers.v[0xA] = 0b0100_1100;
 
-        vm.exec_instruction(0x8AB6);
+        vm.exec_instruction(0x8AB6).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0b0010_0110);
     }
@@ -1745,7 +4848,7 @@ mod tests {
         vm.registers.v[0xA] = 0b1100_0000;
         vm.registers.v[0xB] = 0b1100_1100;
 
-        vm.exec_instruction(0x8AB7);
+        vm.exec_instruction(0x8AB7).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0b0000_1100);
         assert_eq!(vm.registers.v[0xB], 0b1100_1100);
@@ -1756,7 +4859,7 @@ mod tests {
         let mut vm = VM::new();
         vm.registers.v[0xA] = 0b0100_1100;
 
-        vm.exec_instruction(0x8ABE);
+        vm.exec_instruction(0x8ABE).unwrap();
 
         assert_eq!(vm.registers.v[0xA], 0b1001_1000);
     }
@@ -1768,7 +4871,7 @@ mod tests {
         vm.registers.v[0xB] = 0x2;
         vm.registers.program_counter = 0x200;
 
-        vm.exec_instruction(0x9AB0);
+        vm.exec_instruction(0x9AB0).unwrap();
 
         assert_eq!(vm.registers.program_counter, 0x204);
     }
@@ -1778,7 +4881,7 @@ mod tests {
         let mut vm = VM::new();
         vm.registers.i = 0x1;
 
-        vm.exec_instruction(0xA111);
+        vm.exec_instruction(0xA111).unwrap();
 
         assert_eq!(vm.registers.i, 0x0111);
     }
@@ -1789,7 +4892,7 @@ mod tests {
         vm.registers.program_counter = 0xF00;
         vm.registers.v[0x0] = 0xAA;
 
-        vm.exec_instruction(0xB100);
+        vm.exec_instruction(0xB100).unwrap();
 
         assert_eq!(vm.registers.program_counter, 0x1AA);
     }
@@ -1797,14 +4900,14 @@ mod tests {
     #[test]
     fn test_exec_instruction_rnd() {
         let mut vm = VM::new();
-        vm.rng = SmallRng::seed_from_u64(0xFF);
+        vm.set_rng_seed(0xFF);
         vm.registers.v[1] = 0xAF;
 
-        vm.exec_instruction(0xC1FF);
+        vm.exec_instruction(0xC1FF).unwrap();
 
         assert_eq!(vm.registers.v[1], 181);
 
-        vm.exec_instruction(0xC10F);
+        vm.exec_instruction(0xC10F).unwrap();
 
         assert_eq!(vm.registers.v[1], 5);
     }
@@ -1822,7 +4925,7 @@ mod tests {
             .get_slice_mut(location, location + sprite.len())
             .copy_from_slice(&sprite);
 
-        vm.exec_instruction(0xD125);
+        vm.exec_instruction(0xD125).unwrap();
 
         let screen = [0, 0, 0, 0, 0, 0x40, 0x60, 0x40, 0x40, 0xE0, 0];
         assert_eq!(&vm.graphics.display[0..11], &screen);
@@ -1835,7 +4938,7 @@ mod tests {
         vm.registers.v[0x2] = 0x5;
         vm.registers.program_counter = 0x200;
 
-        vm.exec_instruction(0xE29E);
+        vm.exec_instruction(0xE29E).unwrap();
 
         assert_eq!(vm.registers.program_counter, 0x204);
     }
@@ -1847,7 +4950,7 @@ mod tests {
         vm.registers.v[0x2] = 0x5;
         vm.registers.program_counter = 0x200;
 
-        vm.exec_instruction(0xE2A1);
+        vm.exec_instruction(0xE2A1).unwrap();
 
         assert_eq!(vm.registers.program_counter, 0x204);
     }
@@ -1858,7 +4961,7 @@ mod tests {
         vm.registers.v[0x2] = 0x5;
         vm.registers.delay_timer = 0xFF;
 
-        vm.exec_instruction(0xF207);
+        vm.exec_instruction(0xF207).unwrap();
 
         assert_eq!(vm.registers.v[0x2], 0xFF);
     }
@@ -1869,7 +4972,9 @@ mod tests {
         vm.input = Input::new_with_key_pressed(0x3);
         vm.registers.v[0x2] = 0xFF;
 
-        vm.exec_instruction(0xF20A);
+        vm.exec_instruction(0xF20A).unwrap();
+        vm.input = Input::new();
+        vm.exec_instruction(0xF20A).unwrap();
 
         assert_eq!(vm.registers.v[0x2], 0x3);
     }
@@ -1880,7 +4985,7 @@ mod tests {
         vm.registers.delay_timer = 0x5;
         vm.registers.v[0x2] = 0xFF;
 
-        vm.exec_instruction(0xF215);
+        vm.exec_instruction(0xF215).unwrap();
 
         assert_eq!(vm.registers.delay_timer, 0xFF);
     }
@@ -1891,7 +4996,7 @@ mod tests {
         vm.registers.sound_timer = 0x5;
         vm.registers.v[0x2] = 0xFF;
 
-        vm.exec_instruction(0xF218);
+        vm.exec_instruction(0xF218).unwrap();
 
         assert_eq!(vm.registers.sound_timer, 0xFF);
     }
@@ -1902,7 +5007,7 @@ mod tests {
         vm.registers.i = 0x5;
         vm.registers.v[0x2] = 0xA0;
 
-        vm.exec_instruction(0xF21E);
+        vm.exec_instruction(0xF21E).unwrap();
 
         assert_eq!(vm.registers.i, 0xA5);
     }
@@ -1913,7 +5018,7 @@ mod tests {
         vm.registers.i = 0x5;
         vm.registers.v[0x2] = 0x5;
 
-        vm.exec_instruction(0xF229);
+        vm.exec_instruction(0xF229).unwrap();
 
         assert_eq!(vm.registers.i, 25);
     }
@@ -1924,7 +5029,7 @@ mod tests {
         vm.registers.v[0x5] = 123;
         vm.registers.i = 100;
 
-        vm.exec_instruction(0xF533);
+        vm.exec_instruction(0xF533).unwrap();
 
         assert_eq!(vm.memory.get_slice(100, 103), &[1, 2, 3]);
         assert_eq!(vm.registers.i, 100);
@@ -1937,7 +5042,7 @@ mod tests {
         let registers = (0x0..=0xF).collect::<Vec<u8>>();
         vm.registers.v.copy_from_slice(&registers);
 
-        vm.exec_instruction(0xFF55);
+        vm.exec_instruction(0xFF55).unwrap();
 
         assert_eq!(vm.memory.get_slice(0x100, 0x110), registers.as_slice());
     }
@@ -1951,8 +5056,95 @@ mod tests {
             .get_slice_mut(0x100, 0x110)
             .copy_from_slice(&memory);
 
-        vm.exec_instruction(0xFF65);
+        vm.exec_instruction(0xFF65).unwrap();
 
         assert_eq!(vm.registers.v, memory.as_slice());
     }
+
+    /// `8xy*` instructions with `VF` as an operand: the flag always wins.
+    mod flags {
+        use super::*;
+
+        /// Run `instruction` with registers set to `values`, return `Vx`
+        /// where `x` is the second digit and `VF`.
+        fn run(instruction: u16, values: &[(usize, u8)]) -> (u8, u8) {
+            let mut vm = VM::new();
+            for &(register, value) in values {
+                vm.registers.v[register] = value;
+            }
+            vm.exec_instruction(instruction).unwrap();
+            let x = (instruction >> 8 & 0xF) as usize;
+            (vm.registers.v[x], vm.registers.v[0xF])
+        }
+
+        #[test]
+        fn test_add() {
+            assert_eq!(run(0x8014, &[(0, 0xFF), (1, 2)]), (1, 1));
+            assert_eq!(run(0x8F14, &[(0xF, 0xFF), (1, 2)]), (1, 1));
+            assert_eq!(run(0x8F14, &[(0xF, 0x10), (1, 2)]), (0, 0));
+            assert_eq!(run(0x80F4, &[(0, 0x10), (0xF, 0x20)]), (0x30, 0));
+            assert_eq!(run(0x80F4, &[(0, 0xF0), (0xF, 0x20)]), (0x10, 1));
+        }
+
+        #[test]
+        fn test_sub() {
+            assert_eq!(run(0x8015, &[(0, 1), (1, 2)]), (0xFF, 0));
+            assert_eq!(run(0x8F15, &[(0xF, 5), (1, 3)]), (1, 1));
+            assert_eq!(run(0x8F15, &[(0xF, 3), (1, 5)]), (0, 0));
+            assert_eq!(run(0x80F5, &[(0, 1), (0xF, 2)]), (0xFF, 0));
+            assert_eq!(run(0x80F5, &[(0, 2), (0xF, 2)]), (0, 1));
+        }
+
+        #[test]
+        fn test_subn() {
+            assert_eq!(run(0x8017, &[(0, 2), (1, 1)]), (0xFF, 0));
+            assert_eq!(run(0x8F17, &[(0xF, 3), (1, 5)]), (1, 1));
+            assert_eq!(run(0x8F17, &[(0xF, 5), (1, 3)]), (0, 0));
+            assert_eq!(run(0x80F7, &[(0, 1), (0xF, 2)]), (1, 1));
+        }
+
+        #[test]
+        fn test_shr() {
+            assert_eq!(run(0x8006, &[(0, 0b11)]), (1, 1));
+            assert_eq!(run(0x8F06, &[(0xF, 0b11)]), (1, 1));
+            assert_eq!(run(0x8F06, &[(0xF, 0b10)]), (0, 0));
+        }
+
+        #[test]
+        fn test_shl() {
+            assert_eq!(run(0x800E, &[(0, 0x81)]), (0x02, 1));
+            assert_eq!(run(0x8F0E, &[(0xF, 0x81)]), (1, 1));
+            assert_eq!(run(0x8F0E, &[(0xF, 0x40)]), (0, 0));
+        }
+
+        #[test]
+        fn test_shift_from_vf() {
+            let mut vm = VM::new();
+            vm.set_quirks(Quirks {
+                shift_uses_vy: true,
+                ..Quirks::default()
+            });
+            vm.registers.v[0xF] = 0b101;
+
+            vm.exec_instruction(0x80F6).unwrap();
+
+            assert_eq!(vm.registers.v[0], 0b10);
+            assert_eq!(vm.registers.v[0xF], 1);
+        }
+
+        #[test]
+        fn test_logic_reset_vf() {
+            let mut vm = VM::new();
+            vm.set_quirks(Quirks {
+                logic_resets_vf: true,
+                ..Quirks::default()
+            });
+            vm.registers.v[0xF] = 0x0F;
+            vm.registers.v[1] = 0xF0;
+
+            vm.exec_instruction(0x8F11).unwrap();
+
+            assert_eq!(vm.registers.v[0xF], 0);
+        }
+    }
 }
@@ -0,0 +1,66 @@
+//! Counts heap allocations made by the current thread, so that tests running
+//! in parallel don't disturb each other.
+
+use chip_8_emulator::VM;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn test_new_vm_does_not_allocate() {
+    let allocations = allocations_during(|| {
+        let vm = VM::new();
+        std::hint::black_box(&vm);
+    });
+
+    assert_eq!(allocations, 0);
+}
+
+#[test]
+fn test_running_does_not_allocate() {
+    let mut vm = VM::new();
+    vm.load_program(&[0x60, 0x05, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x00])
+        .unwrap();
+
+    let allocations = allocations_during(|| {
+        for _ in 0..100 {
+            vm.exec_current_instruction().unwrap();
+        }
+    });
+
+    assert_eq!(allocations, 0);
+}
+
+#[test]
+fn test_counter_sees_allocations() {
+    let allocations = allocations_during(|| {
+        std::hint::black_box(vec![0u8; 16]);
+    });
+
+    assert_eq!(allocations, 1);
+}
@@ -0,0 +1,96 @@
+use chip_8_emulator::demos::{self, KEY_X, LOGO_X, LOGO_Y};
+use chip_8_emulator::input::Input;
+use chip_8_emulator::testing::Expectations;
+use chip_8_emulator::VM;
+
+#[test]
+fn test_logo() {
+    let x = LOGO_X as usize;
+    let y = LOGO_Y as usize;
+    Expectations::new(&demos::logo())
+        // C
+        .expect_pixel(17, x, y, true)
+        .expect_pixel(17, x + 1, y + 1, false)
+        .expect_pixel(17, x, y + 4, true)
+        // H
+        .expect_pixel(17, x + 5, y + 2, true)
+        .expect_pixel(17, x + 6, y, false)
+        // 8
+        .expect_pixel(17, x + 20, y + 2, true)
+        .expect_pixel(17, x + 21, y + 3, false)
+        .run()
+        .assert_ok();
+
+    let mut vm = VM::new();
+    vm.load_program(&demos::logo()).unwrap();
+    for _ in 0..17 {
+        vm.exec_current_instruction().unwrap();
+    }
+    assert!(vm.is_halted());
+}
+
+#[test]
+fn test_keypad_echo() {
+    let mut vm = VM::new();
+    vm.load_program(&demos::keypad_echo()).unwrap();
+    for _ in 0..10 {
+        vm.exec_current_instruction().unwrap();
+    }
+    assert!(!vm
+        .snapshot()
+        .graphics()
+        .pixel(KEY_X as usize, LOGO_Y as usize));
+
+    // The key is read when it's released.
+    vm.set_input(Input::new_with_key_pressed(0xA));
+    vm.exec_current_instruction().unwrap();
+    vm.set_input(Input::new());
+    for _ in 0..4 {
+        vm.exec_current_instruction().unwrap();
+    }
+
+    let snapshot = vm.snapshot();
+    assert_eq!(snapshot.registers().v[2], 0xA);
+    // Top row of digit A is 0xF0.
+    for dx in 0..4 {
+        assert!(snapshot
+            .graphics()
+            .pixel(KEY_X as usize + dx, LOGO_Y as usize));
+    }
+    assert!(!snapshot
+        .graphics()
+        .pixel(KEY_X as usize + 4, LOGO_Y as usize));
+}
+
+#[test]
+fn test_timer_beep() {
+    Expectations::new(&demos::timer_beep())
+        .expect_sound_by(200)
+        .run()
+        .assert_ok();
+}
+
+#[test]
+fn test_all_demos_build() {
+    for demo in demos::ALL {
+        assert!(!(demo.build)().is_empty(), "{} is empty", demo.name);
+    }
+}
+
+#[test]
+fn test_selftest() {
+    let (x, y) = demos::key_position(0x5);
+    let mut vm = VM::new();
+    vm.load_program(&demos::selftest()).unwrap();
+    vm.set_input(Input::new_with_key_pressed(0x5));
+    for _ in 0..100 {
+        vm.exec_current_instruction().unwrap();
+    }
+
+    let snapshot = vm.snapshot();
+    assert!(snapshot.graphics().pixel(0, 0));
+    assert!(snapshot.graphics().pixel(63, 31));
+    // Top row of digit 5 is 0xF0.
+    assert!(snapshot.graphics().pixel(x as usize, y as usize));
+    assert!(vm.is_sound_playing());
+}
@@ -0,0 +1,51 @@
+//! The supported surface, used the way a frontend would, so exports leaving
+//! the prelude break the build rather than downstream users.
+
+use chip_8_emulator::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct Draws(Arc<AtomicUsize>);
+
+impl VmObserver for Draws {
+    fn before_instruction(&mut self, _vm: &VM, _pc: u16, opcode: u16) {
+        if opcode & 0xF000 == 0xD000 {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[test]
+fn test_prelude() {
+    let mut vm: VM = VmBuilder::new()
+        .quirks(Quirks::default())
+        .opcode_family(OpcodeFamily::SuperChip)
+        .build();
+    let draws = Arc::default();
+    let _: ObserverId = vm.add_observer(Draws(Arc::clone(&draws)));
+    // LD F, V0; DRW V0, V0, 5; JP 0x204
+    let program = [
+        Instruction::LdFVx(0),
+        Instruction::Drw(0, 0, 5),
+        Instruction::Jp(0x204),
+    ];
+    let program: Vec<u8> = program
+        .iter()
+        .flat_map(|instruction| instruction.encode().to_be_bytes())
+        .collect();
+    vm.load_program(&program).unwrap();
+
+    let result: FrameResult = vm.run_frame().unwrap();
+    assert!(result.display_changed);
+    assert_eq!(draws.load(Ordering::Relaxed), 1);
+
+    let screen: &dyn Framebuffer = vm.screen();
+    assert!(screen.pixel(0, 0));
+    assert_eq!(vm.resolution(), Resolution::Low);
+    let registers: &Registers = vm.registers();
+    assert_eq!(registers.i, 0);
+
+    assert_eq!(vm.load_program(&[]), Err(LoadError::Empty));
+    let error: DecodeError = Instruction::decode(0xFFFF).unwrap_err();
+    let _: &dyn std::error::Error = &error;
+}
@@ -0,0 +1,125 @@
+//! Running the emulator inside a game engine. [`Chip8`] is what any engine
+//! needs: it steps the VM by the frame time and renders the display into a
+//! texture's pixels. With the `bevy` feature, [`Chip8Plugin`] wires it into
+//! Bevy; other engines, e.g. Godot through `gdext`, do the same with their
+//! own input and image types.
+
+use chip_8_emulator::input::Input;
+use chip_8_emulator::memory::LoadError;
+use chip_8_emulator::palette::Palette;
+use chip_8_emulator::vm::{VmError, VM};
+use std::time::Duration;
+
+#[cfg(feature = "bevy")]
+mod plugin;
+
+#[cfg(feature = "bevy")]
+pub use plugin::{Chip8Plugin, SCALE};
+
+/// Width of the display texture, in pixels.
+pub const WIDTH: u32 = 64;
+/// Height of the display texture, in pixels.
+pub const HEIGHT: u32 = 32;
+
+/// VM run by the frame time of an engine, stopped at its first error.
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
+pub struct Chip8 {
+    vm: VM,
+    palette: Palette,
+    error: Option<VmError>,
+}
+
+impl Chip8 {
+    pub fn new(program: &[u8]) -> Result<Self, LoadError> {
+        let mut vm = VM::new();
+        vm.load_program(program)?;
+        Ok(Self {
+            vm,
+            palette: Palette::default(),
+            error: None,
+        })
+    }
+
+    pub fn vm(&self) -> &VM {
+        &self.vm
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    /// Error the VM stopped at, if it did.
+    pub fn error(&self) -> Option<VmError> {
+        self.error
+    }
+
+    /// Run for `delta`, the time since the last frame, with `input` held.
+    /// Once the VM has failed it isn't run again, and only the call it
+    /// failed in returns the error.
+    pub fn update(&mut self, input: Input, delta: Duration) -> Result<(), VmError> {
+        if self.error.is_some() {
+            return Ok(());
+        }
+        self.vm.set_input(input);
+        self.vm.run_for(delta).inspect_err(|&error| {
+            self.error = Some(error);
+        })
+    }
+
+    /// Render the display into `rgba`, the pixels of a [`WIDTH`] x
+    /// [`HEIGHT`] RGBA texture.
+    ///
+    /// # Panics
+    ///
+    /// If `rgba` isn't [`RGBA_SIZE`](chip_8_emulator::palette::RGBA_SIZE)
+    /// bytes long.
+    pub fn render(&self, rgba: &mut [u8]) {
+        self.palette.render_rgba_into(self.vm.display(), None, rgba);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chip_8_emulator::palette::RGBA_SIZE;
+
+    #[test]
+    fn test_update_and_render() {
+        // CLS; LD V0, 0; LD F, V0; DRW V0, V0, 5; JP 0x208
+        let program = [0x00, 0xE0, 0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x08];
+        let mut chip8 = Chip8::new(&program).unwrap();
+
+        chip8
+            .update(Input::new(), Duration::from_millis(100))
+            .unwrap();
+        let mut rgba = vec![0; RGBA_SIZE];
+        chip8.render(&mut rgba);
+
+        // Top row of the 0 glyph, 0xF0: four lit pixels, then a dark one.
+        let lit = &rgba[..4];
+        let dark = &rgba[4 * 4..4 * 5];
+        assert_ne!(lit, dark);
+        assert!(rgba[..4 * 4].chunks(4).all(|pixel| pixel == lit));
+        assert_eq!(lit[3], 0xFF);
+        assert_eq!(chip8.error(), None);
+    }
+
+    #[test]
+    fn test_update_stops_at_error() {
+        // RET with nothing on the stack.
+        let mut chip8 = Chip8::new(&[0x00, 0xEE]).unwrap();
+
+        assert!(chip8
+            .update(Input::new(), Duration::from_millis(100))
+            .is_err());
+        let error = chip8.error();
+
+        assert!(error.is_some());
+        assert_eq!(
+            chip8.update(Input::new(), Duration::from_millis(100)),
+            Ok(())
+        );
+        assert_eq!(chip8.error(), error);
+        assert_eq!(chip8.vm().registers().program_counter, 0x200);
+    }
+}
@@ -0,0 +1,32 @@
+//! Runs a program in a Bevy window, or the self-test demo without one:
+//!
+//! ```text
+//! cargo run --release -- path/to/program.ch8
+//! ```
+
+use bevy::prelude::*;
+use chip_8_bevy::{Chip8, Chip8Plugin, HEIGHT, SCALE, WIDTH};
+use chip_8_emulator::demos;
+use std::error::Error;
+use std::{env, fs};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let program = match env::args_os().nth(1) {
+        Some(path) => fs::read(path)?,
+        None => demos::selftest(),
+    };
+    let window = Window {
+        title: "CHIP-8".into(),
+        resolution: (WIDTH as f32 * SCALE, HEIGHT as f32 * SCALE).into(),
+        ..default()
+    };
+    App::new()
+        .add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(window),
+            ..default()
+        }))
+        .insert_resource(Chip8::new(&program)?)
+        .add_plugins(Chip8Plugin)
+        .run();
+    Ok(())
+}
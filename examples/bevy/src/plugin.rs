@@ -0,0 +1,98 @@
+//! Bevy side of [`Chip8`]: keyboard to keypad, frame time to the VM and the
+//! display to a sprite's texture.
+
+use crate::{Chip8, HEIGHT, WIDTH};
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use chip_8_emulator::input::Input;
+
+/// The 4x4 block under `1` mirrors the COSMAC VIP keypad layout:
+///
+/// ```text
+/// 1 2 3 4      1 2 3 C
+/// Q W E R  ->  4 5 6 D
+/// A S D F      7 8 9 E
+/// Z X C V      A 0 B F
+/// ```
+const LAYOUT: [(KeyCode, u8); 16] = [
+    (KeyCode::Digit1, 0x1),
+    (KeyCode::Digit2, 0x2),
+    (KeyCode::Digit3, 0x3),
+    (KeyCode::Digit4, 0xC),
+    (KeyCode::KeyQ, 0x4),
+    (KeyCode::KeyW, 0x5),
+    (KeyCode::KeyE, 0x6),
+    (KeyCode::KeyR, 0xD),
+    (KeyCode::KeyA, 0x7),
+    (KeyCode::KeyS, 0x8),
+    (KeyCode::KeyD, 0x9),
+    (KeyCode::KeyF, 0xE),
+    (KeyCode::KeyZ, 0xA),
+    (KeyCode::KeyX, 0x0),
+    (KeyCode::KeyC, 0xB),
+    (KeyCode::KeyV, 0xF),
+];
+
+/// Window pixels a display pixel takes.
+pub const SCALE: f32 = 10.0;
+
+/// Runs the [`Chip8`] resource, which has to be inserted by the app, and
+/// shows its display on a sprite [`SCALE`] times its size.
+pub struct Chip8Plugin;
+
+/// Texture showing the display.
+#[derive(Resource)]
+struct Screen(Handle<Image>);
+
+impl Plugin for Chip8Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_screen)
+            .add_systems(Update, (step, upload).chain());
+    }
+}
+
+fn spawn_screen(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = Extent3d {
+        width: WIDTH,
+        height: HEIGHT,
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, 0xFF],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    // Keep the pixels square when scaled up.
+    image.sampler = ImageSampler::nearest();
+    let image = images.add(image);
+
+    commands.spawn(Camera2d);
+    commands.spawn(Sprite {
+        image: image.clone(),
+        custom_size: Some(Vec2::new(WIDTH as f32, HEIGHT as f32) * SCALE),
+        ..default()
+    });
+    commands.insert_resource(Screen(image));
+}
+
+fn step(mut chip8: ResMut<Chip8>, keys: Res<ButtonInput<KeyCode>>, time: Res<Time>) {
+    let mut input = Input::new();
+    for (code, key) in LAYOUT {
+        if keys.pressed(code) {
+            input.press(key);
+        }
+    }
+    if let Err(error) = chip8.update(input, time.delta()) {
+        error!("stopped: {}", error);
+    }
+}
+
+fn upload(chip8: Res<Chip8>, screen: Res<Screen>, mut images: ResMut<Assets<Image>>) {
+    if let Some(image) = images.get_mut(&screen.0) {
+        chip8.render(&mut image.data);
+    }
+}
@@ -0,0 +1,87 @@
+//! Drawing the CHIP-8 display on a monochrome screen.
+
+use chip_8_emulator::graphics::Framebuffer;
+use embedded_graphics_core::pixelcolor::BinaryColor;
+use embedded_graphics_core::prelude::{DrawTarget, OriginDimensions, Point};
+use embedded_graphics_core::Pixel;
+
+/// Draw every pixel of `framebuffer` to `target`, scaled by the largest
+/// whole factor it fits in: a 64 x 32 display takes 2 x 2 blocks of a
+/// 128 x 64 SSD1306 and the SUPER-CHIP 128 x 64 one takes it 1:1.
+pub fn draw<F, D>(framebuffer: &F, target: &mut D) -> Result<(), D::Error>
+where
+    F: Framebuffer + ?Sized,
+    D: DrawTarget<Color = BinaryColor> + OriginDimensions,
+{
+    let size = target.size();
+    let scale = (size.width as usize / framebuffer.width())
+        .min(size.height as usize / framebuffer.height())
+        .max(1);
+    let pixels = (0..framebuffer.height() * scale).flat_map(|y| {
+        (0..framebuffer.width() * scale).map(move |x| {
+            let color = BinaryColor::from(framebuffer.pixel(x / scale, y / scale));
+            Pixel(Point::new(x as i32, y as i32), color)
+        })
+    });
+    target.draw_iter(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chip_8_emulator::graphics::{Graphics, SchipGraphics};
+    use core::convert::Infallible;
+    use embedded_graphics_core::prelude::Size;
+
+    /// 128 x 64 screen, a byte a pixel.
+    struct Screen([[bool; 128]; 64]);
+
+    impl OriginDimensions for Screen {
+        fn size(&self) -> Size {
+            Size::new(128, 64)
+        }
+    }
+
+    impl DrawTarget for Screen {
+        type Color = BinaryColor;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+        where
+            I: IntoIterator<Item = Pixel<BinaryColor>>,
+        {
+            for Pixel(point, color) in pixels {
+                self.0[point.y as usize][point.x as usize] = color.is_on();
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_draw_low_resolution() {
+        let mut graphics = Graphics::new();
+        graphics.draw_sprite(0, 0, &[0b1000_0001]);
+        let mut screen = Screen([[true; 128]; 64]);
+
+        draw(&graphics, &mut screen).unwrap();
+
+        let lit: usize = screen.0.iter().flatten().filter(|&&on| on).count();
+        assert_eq!(lit, 8);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1), (14, 0), (15, 1)] {
+            assert!(screen.0[y][x], "({}, {}) is off", x, y);
+        }
+    }
+
+    #[test]
+    fn test_draw_high_resolution() {
+        let mut graphics = SchipGraphics::default();
+        graphics.draw_sprite(127, 63, &[0b1000_0000]);
+        let mut screen = Screen([[false; 128]; 64]);
+
+        draw(&graphics, &mut screen).unwrap();
+
+        let lit: usize = screen.0.iter().flatten().filter(|&&on| on).count();
+        assert_eq!(lit, 1);
+        assert!(screen.0[63][127]);
+    }
+}
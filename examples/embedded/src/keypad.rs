@@ -0,0 +1,92 @@
+//! Reading the keypad from a 4 x 4 button matrix.
+
+use chip_8_emulator::input::Input;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+
+/// Keys of the matrix by row and column, laid out like the COSMAC VIP
+/// keypad.
+pub const KEYS: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+/// Time for the columns to settle after a row is driven low.
+const SETTLE_US: u32 = 10;
+
+/// Button matrix: rows are driven low one at a time and columns are read
+/// with pull-ups, so a held button pulls its column low while its row is.
+pub struct Keypad<R, C> {
+    rows: [R; 4],
+    columns: [C; 4],
+}
+
+impl<R, C, E> Keypad<R, C>
+where
+    R: OutputPin<Error = E>,
+    C: InputPin<Error = E>,
+{
+    /// Keypad on `rows` and `columns`, every row left high.
+    pub fn new(mut rows: [R; 4], columns: [C; 4]) -> Result<Self, E> {
+        for row in &mut rows {
+            row.set_high()?;
+        }
+        Ok(Self { rows, columns })
+    }
+
+    /// Keys held now, for [`VM::set_input`](chip_8_emulator::VM::set_input).
+    pub fn scan(&mut self, delay: &mut impl DelayNs) -> Result<Input, E> {
+        let mut input = Input::new();
+        for (row, keys) in self.rows.iter_mut().zip(KEYS) {
+            row.set_low()?;
+            delay.delay_us(SETTLE_US);
+            for (column, key) in self.columns.iter_mut().zip(keys) {
+                if column.is_low()? {
+                    input.press(key);
+                }
+            }
+            row.set_high()?;
+        }
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+    use embedded_hal_mock::eh1::digital::{Mock, State, Transaction};
+
+    #[test]
+    fn test_scan() {
+        let scan = [Transaction::set(State::Low), Transaction::set(State::High)];
+        let rows = [(); 4].map(|_| {
+            let mut expectations = vec![Transaction::set(State::High)];
+            expectations.extend(scan.clone());
+            Mock::new(&expectations)
+        });
+        // Buttons 5, at row 1 and column 1, and F, at row 3 and column 3.
+        let columns = [0, 1, 2, 3].map(|column| {
+            let held_in_row = |row| match (row, column) {
+                (1, 1) | (3, 3) => State::Low,
+                _ => State::High,
+            };
+            Mock::new(&[0, 1, 2, 3].map(|row| Transaction::get(held_in_row(row))))
+        });
+        let mut keypad = Keypad::new(rows, columns).unwrap();
+
+        let input = keypad.scan(&mut NoopDelay::new()).unwrap();
+
+        assert_eq!(input.first_pressed(), Some(0x5));
+        assert!(input.is_pressed(0xF));
+        assert_eq!((0..16).filter(|&key| input.is_pressed(key)).count(), 2);
+        for pin in &mut keypad.rows {
+            pin.done();
+        }
+        for pin in &mut keypad.columns {
+            pin.done();
+        }
+    }
+}
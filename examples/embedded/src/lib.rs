@@ -0,0 +1,13 @@
+//! Running the emulator on a microcontroller through `embedded-hal`: the
+//! display is drawn to any monochrome `embedded-graphics` target, e.g. an
+//! SSD1306 over I2C, and the keypad is read from a 4 x 4 button matrix.
+//!
+//! Nothing here is tied to a chip; `src/main.rs` wires it up on an RP2040.
+
+#![cfg_attr(not(test), no_std)]
+
+pub mod display;
+pub mod keypad;
+
+pub use display::draw;
+pub use keypad::Keypad;
@@ -0,0 +1,154 @@
+//! CHIP-8 on a Raspberry Pi Pico: an SSD1306 on I2C0 (GP4 SDA, GP5 SCL),
+//! a 4 x 4 button matrix with rows on GP6 to GP9 and columns on GP10 to
+//! GP13, and a buzzer on GP15. It runs the self-test demo, showing held
+//! keys and beeping.
+
+#![no_std]
+#![no_main]
+
+use chip_8_embedded::{draw, Keypad};
+use chip_8_emulator::clock::{Clock, FramePacer};
+use chip_8_emulator::{demos, VM};
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::RefCell;
+use core::mem::MaybeUninit;
+use core::ptr::{self, NonNull};
+use core::time::Duration;
+use critical_section::Mutex;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use panic_halt as _;
+use rp2040_hal::fugit::RateExtU32;
+use rp2040_hal::gpio::{FunctionI2C, Pin, PullUp};
+use rp2040_hal::{clocks, entry, pac, Clock as _, Sio, Timer, Watchdog, I2C};
+use ssd1306::prelude::*;
+use ssd1306::{I2CDisplayInterface, Ssd1306};
+
+#[link_section = ".boot2"]
+#[used]
+pub static BOOT2_FIRMWARE: [u8; 256] = rp2040_boot2::BOOT_LOADER_GENERIC_03H;
+
+/// Crystal on the Pico.
+const XTAL_FREQ_HZ: u32 = 12_000_000;
+
+/// Heap for the VM's `alloc` collections; the VM itself is a few KiB.
+const HEAP_SIZE: usize = 32 * 1024;
+
+#[global_allocator]
+static HEAP: Heap = Heap(Mutex::new(RefCell::new(
+    linked_list_allocator::Heap::empty(),
+)));
+
+/// `linked_list_allocator` behind a critical section, since the RP2040
+/// has no atomic compare-and-swap for a spin lock.
+struct Heap(Mutex<RefCell<linked_list_allocator::Heap>>);
+
+unsafe impl GlobalAlloc for Heap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        critical_section::with(|cs| {
+            self.0
+                .borrow_ref_mut(cs)
+                .allocate_first_fit(layout)
+                .map_or(ptr::null_mut(), NonNull::as_ptr)
+        })
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        critical_section::with(|cs| {
+            self.0
+                .borrow_ref_mut(cs)
+                .deallocate(NonNull::new_unchecked(ptr), layout)
+        })
+    }
+}
+
+/// Microseconds counted by the RP2040 timer since boot.
+struct TimerClock(Timer);
+
+impl Clock for TimerClock {
+    fn now(&self) -> Duration {
+        Duration::from_micros(self.0.get_counter().ticks())
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    static mut HEAP_MEMORY: [MaybeUninit<u8>; HEAP_SIZE] = [MaybeUninit::uninit(); HEAP_SIZE];
+    critical_section::with(|cs| HEAP.0.borrow_ref_mut(cs).init_from_slice(HEAP_MEMORY));
+
+    let mut pac = pac::Peripherals::take().unwrap();
+    let mut watchdog = Watchdog::new(pac.WATCHDOG);
+    let clocks = clocks::init_clocks_and_plls(
+        XTAL_FREQ_HZ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .ok()
+    .unwrap();
+    let mut timer = Timer::new(pac.TIMER, &mut pac.RESETS, &clocks);
+    let sio = Sio::new(pac.SIO);
+    let pins = rp2040_hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    let sda: Pin<_, FunctionI2C, PullUp> = pins.gpio4.reconfigure();
+    let scl: Pin<_, FunctionI2C, PullUp> = pins.gpio5.reconfigure();
+    let i2c = I2C::i2c0(
+        pac.I2C0,
+        sda,
+        scl,
+        400.kHz(),
+        &mut pac.RESETS,
+        clocks.system_clock.freq(),
+    );
+    let mut display = Ssd1306::new(
+        I2CDisplayInterface::new(i2c),
+        DisplaySize128x64,
+        DisplayRotation::Rotate0,
+    )
+    .into_buffered_graphics_mode();
+    display.init().unwrap();
+
+    let mut keypad = Keypad::new(
+        [
+            pins.gpio6.into_push_pull_output().into_dyn_pin(),
+            pins.gpio7.into_push_pull_output().into_dyn_pin(),
+            pins.gpio8.into_push_pull_output().into_dyn_pin(),
+            pins.gpio9.into_push_pull_output().into_dyn_pin(),
+        ],
+        [
+            pins.gpio10.into_pull_up_input().into_dyn_pin(),
+            pins.gpio11.into_pull_up_input().into_dyn_pin(),
+            pins.gpio12.into_pull_up_input().into_dyn_pin(),
+            pins.gpio13.into_pull_up_input().into_dyn_pin(),
+        ],
+    )
+    .unwrap();
+    let mut buzzer = pins.gpio15.into_push_pull_output();
+
+    let mut vm = VM::new();
+    vm.load_program(&demos::selftest()).unwrap();
+    let mut pacer = FramePacer::new(TimerClock(timer), Duration::from_micros(16_667));
+    loop {
+        vm.set_input(keypad.scan(&mut timer).unwrap());
+        let frame = match vm.run_frame() {
+            Ok(frame) => frame,
+            Err(_) => loop {
+                cortex_m::asm::wfi();
+            },
+        };
+        if frame.display_changed {
+            draw(vm.screen(), &mut display).unwrap();
+            display.flush().unwrap();
+        }
+        buzzer.set_state(vm.is_sound_playing().into()).unwrap();
+        timer.delay_us(pacer.next_frame().as_micros() as u32);
+    }
+}
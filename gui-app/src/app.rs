@@ -1,96 +1,1047 @@
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
 use sdl2::pixels::Color;
-use std::time::Duration;
+use std::time::Instant;
 
-use chip_8_emulator::VM;
-use sdl2::{rect::Rect, render::WindowCanvas, Sdl};
+use crate::audio::Audio;
+use crate::config::{self, Config, MAX_VOLUME};
+use crate::debugger::DebugWindow;
+use crate::display::{sdl_color, DisplayOptions, Scaling, Viewport};
+use crate::help::HelpScreen;
+use crate::hotkeys::{Action, Hotkeys};
+use crate::inspector;
+use crate::keymap::{Keymap, Preset};
+use crate::latency::LatencyProbe;
+use crate::library::{self, format_play_time, format_stats, Library};
+use crate::menu::{collect_programs, Entry, Menu, Source};
+use crate::options::Options;
+use crate::practice::Practice;
+use crate::stop::{panic_message, Reason, StopScreen};
+use crate::toast::Toasts;
+#[cfg(feature = "debug-server")]
+use chip_8_emulator::debug_server::DebugServer;
+use chip_8_emulator::{
+    blend::FrameBlender,
+    capture::encode_png,
+    clock::{FramePacer, SystemClock},
+    crash::{self, CrashDump},
+    demos,
+    graphics::{Resolution, DISPLAY_COLS, DISPLAY_ROWS},
+    input::Input,
+    memory::{LoadError, BIG_FONT_SIZE, FONT_SIZE},
+    patch::{Patch, PatchError},
+    rpl::RplFlags,
+    snapshot::{FrameSnapshot, SnapshotBuffer, StateSnapshot},
+    storage::Storage,
+    vm::{TimerPolicy, FRAME_TIME},
+    VM,
+};
+use sdl2::{render::WindowCanvas, Sdl, TimerSubsystem, VideoSubsystem};
 use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
 
-const BLACK: Color = Color::RGB(0, 0, 0);
-const WHITE: Color = Color::RGB(255, 255, 255);
+/// Outline of a recent sprite draw.
+const DRAW_COLOR: Color = Color::RGB(0, 200, 0);
+/// Outline of a recent sprite draw which erased pixels.
+const COLLISION_COLOR: Color = Color::RGB(230, 0, 0);
+/// Frames sprite draws stay highlighted for.
+const DRAW_LOG_FRAMES: u64 = 30;
+/// Instructions kept for crash dumps.
+const TRACE_LENGTH: usize = 64;
+/// Writes kept per register for the debugger.
+const REGISTER_HISTORY_LENGTH: usize = 16;
 
 const PIXEL_SIZE: usize = 10;
+const VOLUME_STEP: u8 = 10;
+/// Frames between states kept for rewinding.
+const REWIND_STRIDE: u64 = 3;
+/// States kept for rewinding, 30 seconds at 60 frames per second.
+const REWIND_STATES: usize = 600;
+/// Speed-up while turbo is held.
+const TURBO_SPEED: u32 = 4;
+
+const TITLE: &str = "CHIP-8 emulator";
 
 pub struct App {
     vm: VM,
     sdl_context: Sdl,
+    timer: TimerSubsystem,
+    video: VideoSubsystem,
     canvas: WindowCanvas,
+    /// Second window with debugger panels, when open.
+    debug_window: Option<DebugWindow>,
+    /// Mouse pointer position in the main window, in pixels of the canvas.
+    pointer: Option<(i32, i32)>,
+    audio: Option<Audio>,
+    toasts: Toasts,
+    options: Options,
+    config: Config,
+    library: Library,
+    /// Where the config, library and saved memory are kept.
+    storage: Arc<dyn Storage>,
+    /// Display options of the running program: command line options
+    /// overridden by the ones remembered for the program.
+    display: DisplayOptions,
+    /// Keymap preset of the running program.
+    keymap_preset: Preset,
+    program_name: String,
+    /// When the running program was started, or resumed after the menu.
+    play_started: Option<Instant>,
+    /// Frame timing, timers tick by the time between frames.
+    pacer: FramePacer<SystemClock>,
+    keymap: Keymap,
+    /// Keypad keys held.
+    pressed_keys: Input,
+    /// Last frames drawn, blended as the display options say.
+    blender: FrameBlender,
+    font: Option<[u8; FONT_SIZE]>,
+    big_font: Option<[u8; BIG_FONT_SIZE]>,
+    programs: Vec<Source>,
+    current_program: usize,
+    /// Start screen, shown instead of the running program when present.
+    menu: Option<Menu>,
+    /// Whether recent sprite draws are highlighted.
+    show_draws: bool,
+    /// Loop point of the running program, when practicing a section.
+    practice: Option<Practice>,
+    /// Shown over the program once it halted or crashed, when it isn't run
+    /// anymore.
+    stopped: Option<StopScreen>,
+    /// Shown over everything while present, nothing runs meanwhile.
+    help: Option<HelpScreen>,
+    hotkeys: Hotkeys,
+    paused: bool,
+    /// Frames run since the program was started.
+    frame: u64,
+    /// State saved with the save state hotkey.
+    saved_state: Option<StateSnapshot>,
+    /// Recent states, stepped back through while rewinding.
+    rewind: SnapshotBuffer,
+    /// Key held to rewind, when rewinding.
+    rewind_key: Option<Keycode>,
+    /// Key held for turbo, when running faster.
+    turbo_key: Option<Keycode>,
+    /// Present with `--measure-latency`.
+    latency: Option<LatencyProbe>,
+    #[cfg(feature = "debug-server")]
+    debug_server: Option<DebugServer>,
 }
 
 impl App {
-    pub fn init() -> Result<Self> {
+    pub fn init(options: Options) -> Result<Self> {
         let sdl_context = sdl2::init().map_err(Error::Initialization)?;
         let video_subsystem = sdl_context.video().unwrap();
         let window = video_subsystem
-            .window("rust-sdl2 demo", 800, 600)
+            .window(
+                TITLE,
+                (DISPLAY_COLS * PIXEL_SIZE) as u32,
+                (DISPLAY_ROWS * PIXEL_SIZE) as u32,
+            )
             .position_centered()
+            .resizable()
             .build()
             .unwrap();
         let canvas = window.into_canvas().build().unwrap();
-        let vm = VM::new();
+        let timer = sdl_context.timer().map_err(Error::Initialization)?;
+        let latency = options.measure_latency.then(LatencyProbe::new);
+        let audio = Audio::init(&sdl_context)
+            .map_err(|e| eprintln!("Sound is disabled: {}", e))
+            .ok();
+        let keymap = Keymap::new(options.keymap, options.display.rotation);
+        let storage = config::storage();
+        let config = Config::load(&*storage);
+        let (hotkeys, hotkey_warnings) = Hotkeys::new(&config.hotkeys);
+        #[cfg(feature = "debug-server")]
+        let debug_server = match &options.debug_server {
+            Some(addr) => Some(DebugServer::bind(addr.as_str()).map_err(|e| {
+                Error::Initialization(format!("can't start debug server on {}: {}", addr, e))
+            })?),
+            None => None,
+        };
 
-        Ok(Self {
-            vm,
+        let mut app = Self {
+            vm: VM::new(),
             sdl_context,
+            timer,
+            video: video_subsystem,
             canvas,
-        })
+            debug_window: None,
+            pointer: None,
+            audio,
+            toasts: Toasts::new(),
+            display: options.display,
+            keymap_preset: options.keymap,
+            options,
+            config,
+            library: Library::load(&*storage),
+            storage,
+            program_name: String::new(),
+            play_started: None,
+            pacer: FramePacer::new(SystemClock::new(), FRAME_TIME),
+            keymap,
+            pressed_keys: Input::new(),
+            blender: FrameBlender::default(),
+            font: None,
+            big_font: None,
+            programs: Vec::new(),
+            current_program: 0,
+            menu: None,
+            show_draws: false,
+            practice: None,
+            stopped: None,
+            help: None,
+            hotkeys,
+            paused: false,
+            frame: 0,
+            saved_state: None,
+            rewind: SnapshotBuffer::new(REWIND_STATES),
+            rewind_key: None,
+            turbo_key: None,
+            latency,
+            #[cfg(feature = "debug-server")]
+            debug_server,
+        };
+        app.vm = app.new_vm();
+        app.apply_volume();
+        if app.options.debugger {
+            app.toggle_debugger();
+        }
+        for warning in hotkey_warnings {
+            eprintln!("Hotkeys: {}", warning);
+            app.notify(warning);
+        }
+        if let Some(key) = app.hotkeys.key_name(Action::Help) {
+            app.notify(format!("{}: help", key));
+        }
+        Ok(app)
+    }
+
+    /// Programs in the library directory, none when there is no library.
+    pub fn library_programs(&self) -> Result<Vec<PathBuf>> {
+        match self.config.library_dir() {
+            Some(dir) if dir.is_dir() => collect_programs(&[&dir]).map_err(Error::ProgramLoading),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Set programs to choose from. A single program is started right away,
+    /// otherwise the start screen is shown. Without programs the built-in
+    /// demos are offered instead.
+    pub fn set_programs(&mut self, programs: Vec<PathBuf>) -> Result<()> {
+        self.programs = if programs.is_empty() {
+            demos::ALL.into_iter().map(Source::Demo).collect()
+        } else {
+            programs.into_iter().map(Source::File).collect()
+        };
+        match self.programs.len() {
+            1 => self.start_program(0),
+            _ => {
+                self.open_menu(0);
+                Ok(())
+            }
+        }
+    }
+
+    /// Show the start screen with program `selected` selected.
+    fn open_menu(&mut self, selected: usize) {
+        self.store_saved_memory();
+        self.stop_play_time();
+        let entries = self
+            .programs
+            .iter()
+            .map(|source| {
+                let name = source.name();
+                let settings = self.library.get(&name);
+                Entry {
+                    details: format_play_time(settings.play_time),
+                    description: source.description(),
+                    stats: format_stats(&settings),
+                    name,
+                }
+            })
+            .collect();
+        self.menu = Some(Menu::new(entries, selected));
+    }
+
+    /// Start program number `index` from scratch.
+    fn start_program(&mut self, index: usize) -> Result<()> {
+        self.store_saved_memory();
+        self.stop_play_time();
+        let source = &self.programs[index];
+        let mut program = source.read().map_err(Error::ProgramLoading)?;
+        for patch_path in &self.options.patch_paths {
+            let patch = fs::read(patch_path).map_err(Error::ProgramLoading)?;
+            Patch::parse(&patch)
+                .map_err(Error::Patch)?
+                .apply(&mut program);
+        }
+        self.vm = self.new_vm();
+        if let Some(latency) = &mut self.latency {
+            latency.reset();
+        }
+        self.vm
+            .load_program(&program)
+            .map_err(Error::InvalidProgram)?;
+        self.practice = None;
+        self.stopped = None;
+        self.paused = false;
+        self.frame = 0;
+        self.saved_state = None;
+        self.rewind = SnapshotBuffer::new(REWIND_STATES);
+        self.current_program = index;
+        self.menu = None;
+        self.program_name = source.name();
+        self.apply_program_settings();
+        self.restore_saved_memory();
+        self.restore_rpl_flags();
+        self.play_started = Some(Instant::now());
+        self.notify(format!("Loaded {}", self.program_name));
+        self.update_title();
+        Ok(())
+    }
+
+    /// Use display options remembered for the running program.
+    fn apply_program_settings(&mut self) {
+        let settings = self.library.get(&self.program_name);
+        self.display = self.options.display;
+        if let Some(scaling) = settings.scaling {
+            self.display.scaling = scaling;
+        }
+        if let Some(rotation) = settings.rotation {
+            self.display.rotation = rotation;
+        }
+        self.keymap_preset = settings.keymap.unwrap_or(self.options.keymap);
+        self.keymap = Keymap::new(self.keymap_preset, self.display.rotation);
+    }
+
+    /// Add time since the running program was started to its play time.
+    fn stop_play_time(&mut self) {
+        if let Some(started) = self.play_started.take() {
+            self.library.get_mut(&self.program_name).play_time += started.elapsed();
+            self.save_library();
+        }
+    }
+
+    /// Load memory kept from the last run of the program, if it opted in.
+    fn restore_saved_memory(&mut self) {
+        let range = match self.library.get(&self.program_name).save_range {
+            Some(range) => range,
+            None => return,
+        };
+        let key = library::saved_memory_key(&self.program_name);
+        if let Ok(Some(saved)) = self.storage.load(&key) {
+            let len = saved.len().min(range.len());
+            if let Err(e) = self.vm.write_memory(range.start, &saved[..len]) {
+                self.notify(format!("Can't load saved memory: {}", e));
+            }
+        }
+    }
+
+    /// Keep RPL flags of the running program in storage, for SUPER-CHIP
+    /// games saving progress with `Fx75` to find it on their next run.
+    fn restore_rpl_flags(&mut self) {
+        let key = library::rpl_flags_key(&self.program_name);
+        match RplFlags::with_storage(Arc::clone(&self.storage), key) {
+            Ok(flags) => self.vm.set_rpl_flags(flags),
+            Err(e) => self.notify(format!("Can't load RPL flags: {}", e)),
+        }
+    }
+
+    /// Keep memory of the running program for its next run, if it opted in.
+    fn store_saved_memory(&mut self) {
+        if self.program_name.is_empty() {
+            return;
+        }
+        let range = match self.library.get(&self.program_name).save_range {
+            Some(range) => range,
+            None => return,
+        };
+        let key = library::saved_memory_key(&self.program_name);
+        if let Err(e) = self.storage.store(&key, self.vm.memory(range).unwrap()) {
+            self.notify(format!("Can't save memory: {}", e));
+        }
+    }
+
+    fn new_vm(&self) -> VM {
+        let mut vm = VM::new();
+        vm.set_timer_policy(TimerPolicy::Elapsed);
+        vm.set_font_style(self.options.font_style);
+        if let Some(speed) = self.options.speed {
+            vm.set_instructions_per_second(speed);
+        }
+        if let Some(font) = &self.font {
+            vm.load_font(font);
+        }
+        if let Some(big_font) = &self.big_font {
+            vm.load_big_font(big_font);
+        }
+        if self.show_draws {
+            vm.enable_draw_log(DRAW_LOG_FRAMES);
+        }
+        if self.options.crash_dir.is_some() {
+            vm.enable_trace(TRACE_LENGTH);
+        }
+        if self.debug_window.is_some() {
+            vm.enable_register_history(REGISTER_HISTORY_LENGTH);
+        }
+        if let Some(latency) = &self.latency {
+            vm.add_observer(latency.observer());
+        }
+        vm
+    }
+
+    /// Replace built-in font with the one in `font_path`.
+    pub fn load_font(&mut self, font_path: &str) -> Result<()> {
+        let font = read_font(font_path)?;
+        self.vm.load_font(&font);
+        self.font = Some(font);
+        Ok(())
     }
 
-    pub fn load_program(&mut self, program_path: &str) -> Result<()> {
-        let program = fs::read(program_path).map_err(Error::ProgramLoading)?;
-        self.vm.load_program(&program);
+    /// Replace built-in high-resolution font with the one in `font_path`.
+    pub fn load_big_font(&mut self, font_path: &str) -> Result<()> {
+        let font = read_font(font_path)?;
+        self.vm.load_big_font(&font);
+        self.big_font = Some(font);
         Ok(())
     }
 
+    /// Show `message` on screen for a short time.
+    pub fn notify(&mut self, message: impl Into<String>) {
+        self.toasts.push(message);
+    }
+
     pub fn run(&mut self) -> Result<()> {
-        self.canvas.set_draw_color(BLACK);
-        self.canvas.clear();
         let mut event_pump = self.sdl_context.event_pump().map_err(Error::Runtime)?;
         'running: loop {
-            self.canvas.set_draw_color(BLACK);
-            self.canvas.clear();
+            let elapsed = self.pacer.elapsed();
+
             for event in event_pump.poll_iter() {
                 match event {
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } if self.help.is_some() => self.help = None,
+                    Event::Window {
+                        window_id,
+                        win_event: WindowEvent::Close,
+                        ..
+                    } if self.debug_window.as_ref().map(DebugWindow::id) == Some(window_id) => {
+                        self.debug_window = None;
+                        self.vm.disable_register_history();
+                    }
+                    // The main window closing only quits by itself while
+                    // it's the last one.
                     Event::Quit { .. }
+                    | Event::Window {
+                        win_event: WindowEvent::Close,
+                        ..
+                    }
                     | Event::KeyDown {
                         keycode: Some(Keycode::Escape),
                         ..
-                    } => break 'running,
+                    } => {
+                        self.store_saved_memory();
+                        self.stop_play_time();
+                        if let Some(summary) = self.latency.as_ref().and_then(|l| l.summary()) {
+                            println!("{}", summary);
+                        }
+                        break 'running;
+                    }
+                    Event::KeyDown {
+                        timestamp,
+                        keycode: Some(keycode),
+                        keymod,
+                        repeat,
+                        ..
+                    } => self.on_key_down(keycode, keymod, repeat, timestamp)?,
+                    Event::KeyUp {
+                        keycode: Some(keycode),
+                        ..
+                    } => self.key_up(keycode),
+                    Event::MouseMotion {
+                        window_id, x, y, ..
+                    } if window_id == self.canvas.window().id() => {
+                        self.pointer = Some(self.canvas_point(x, y));
+                    }
+                    Event::Window {
+                        window_id,
+                        win_event: WindowEvent::Leave,
+                        ..
+                    } if window_id == self.canvas.window().id() => self.pointer = None,
                     _ => {}
                 }
             }
 
-            self.vm.exec_current_instruction();
+            if self.help.is_some() {
+                match &self.menu {
+                    Some(menu) => menu.draw(&mut self.canvas).map_err(Error::Runtime)?,
+                    None => self.draw_screen()?,
+                }
+                if let Some(help) = &self.help {
+                    help.draw(&mut self.canvas).map_err(Error::Runtime)?;
+                }
+                self.draw_overlay()?;
+                continue;
+            }
+
+            if let Some(menu) = &self.menu {
+                menu.draw(&mut self.canvas).map_err(Error::Runtime)?;
+                self.draw_overlay()?;
+                continue;
+            }
+
+            if self.stopped.is_some() {
+                self.draw_screen()?;
+                if let Some(stopped) = &self.stopped {
+                    stopped.draw(&mut self.canvas).map_err(Error::Runtime)?;
+                }
+                self.draw_overlay()?;
+                continue;
+            }
+
+            if self.paused {
+                self.draw_screen()?;
+                self.draw_overlay()?;
+                continue;
+            }
+
+            if self.rewind_key.is_some() {
+                if let Some(snapshot) = self.rewind.pop() {
+                    self.vm.restore(&snapshot.state);
+                    self.frame = snapshot.frame;
+                    self.apply_pressed_key();
+                }
+                self.draw_screen()?;
+                self.draw_overlay()?;
+                continue;
+            }
+
+            let frames = if self.turbo_key.is_some() {
+                TURBO_SPEED
+            } else {
+                1
+            };
+            let idle_detection = self.config.idle_detection;
+            let vm = &mut self.vm;
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                if idle_detection && vm.is_waiting_for_timer() {
+                    vm.advance_timers(elapsed * frames);
+                    Ok(())
+                } else {
+                    vm.run_for(elapsed * frames)
+                }
+            }));
+            let error = match result {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(payload) => Some(panic_message(payload.as_ref())),
+            };
+            if let Some(error) = error {
+                self.write_crash_dump(&error);
+                self.stop(Reason::Crashed(error));
+                continue;
+            }
+            self.frame += 1;
+            self.measure_latency();
+            if self.frame.is_multiple_of(REWIND_STRIDE) {
+                self.rewind.push(FrameSnapshot {
+                    frame: self.frame,
+                    state: self.vm.snapshot(),
+                });
+            }
+            self.check_practice();
+            if self.vm.is_halted() {
+                self.stop(Reason::Halted);
+            }
+            if let Some(audio) = &mut self.audio {
+                audio.frame(self.vm.is_sound_playing());
+            }
+            #[cfg(feature = "debug-server")]
+            if let Some(debug_server) = &self.debug_server {
+                debug_server.update(&self.vm);
+            }
+
+            self.draw_screen()?;
+            self.draw_overlay()?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw toasts on top of the frame, present it and wait for the next one.
+    fn draw_overlay(&mut self) -> Result<()> {
+        self.toasts.expire(Instant::now());
+        self.toasts.draw(&mut self.canvas).map_err(Error::Runtime)?;
+
+        self.canvas.present();
+        if let Some(debug_window) = &mut self.debug_window {
+            debug_window.draw(&self.vm).map_err(Error::Runtime)?;
+        }
+        ::std::thread::sleep(self.pacer.next_frame());
+        Ok(())
+    }
 
-            self.canvas.set_draw_color(WHITE);
-            for row in 0..chip_8_emulator::graphics::DISPLAY_ROWS {
-                for col in 0..chip_8_emulator::graphics::DISPLAY_COLS {
-                    if (self.vm.graphics.display[row] & (1 << col)) != 0 {
-                        let pixel = Rect::new(
-                            (col * PIXEL_SIZE) as i32,
-                            (row * PIXEL_SIZE) as i32,
-                            PIXEL_SIZE as u32,
-                            PIXEL_SIZE as u32,
-                        );
-                        self.canvas.fill_rect(pixel).unwrap();
+    /// Key press at event timestamp `timestamp`.
+    fn on_key_down(
+        &mut self,
+        keycode: Keycode,
+        keymod: Mod,
+        repeat: bool,
+        timestamp: u32,
+    ) -> Result<()> {
+        let in_program = self.menu.is_none();
+        let running = in_program && self.stopped.is_none();
+        let action = self.hotkeys.action(keycode, keymod);
+        if self.help.is_some() {
+            if action == Some(Action::Help) {
+                self.help = None;
+            }
+            return Ok(());
+        }
+        match action {
+            Some(Action::Help) => {
+                self.help = Some(HelpScreen::new(
+                    &self.keymap,
+                    self.keymap_preset,
+                    &self.hotkeys,
+                ));
+            }
+            Some(Action::ToggleScaling) => self.toggle_scaling(),
+            Some(Action::Rotate) => self.rotate(),
+            Some(Action::NextKeymap) => self.next_keymap(),
+            Some(Action::ToggleDraws) => self.toggle_draws(),
+            Some(Action::TogglePractice) if in_program => self.toggle_practice(),
+            Some(Action::Debugger) => self.toggle_debugger(),
+            Some(Action::Mute) => self.toggle_mute(),
+            Some(Action::VolumeUp) => self.change_volume(VOLUME_STEP as i8),
+            Some(Action::VolumeDown) => self.change_volume(-(VOLUME_STEP as i8)),
+            Some(Action::Menu) if self.programs.len() > 1 && in_program => {
+                self.open_menu(self.current_program);
+            }
+            Some(Action::PreviousProgram) if in_program => self.cycle_program(-1)?,
+            Some(Action::NextProgram) if in_program => self.cycle_program(1)?,
+            Some(Action::Reset) if in_program => self.reset_program()?,
+            Some(Action::Pause) if running => {
+                self.paused = !self.paused;
+                self.notify(if self.paused { "Paused" } else { "Resumed" });
+            }
+            Some(Action::SaveState) if running => {
+                self.saved_state = Some(self.vm.snapshot());
+                self.library.get_mut(&self.program_name).states_saved += 1;
+                self.save_library();
+                self.notify("State saved");
+            }
+            Some(Action::LoadState) if running => self.load_state(),
+            Some(Action::Rewind) if running && !repeat => self.rewind_key = Some(keycode),
+            Some(Action::Turbo) if running && !repeat => self.turbo_key = Some(keycode),
+            Some(Action::Screenshot) if in_program => self.screenshot(),
+            _ if keycode == Keycode::R && self.stopped.is_some() && in_program => {
+                self.reset_program()?;
+            }
+            _ => {
+                if let Some(menu) = &mut self.menu {
+                    match keycode {
+                        Keycode::Up | Keycode::Left => menu.step(-1),
+                        Keycode::Down | Keycode::Right => menu.step(1),
+                        Keycode::PageUp => menu.page(-1),
+                        Keycode::PageDown => menu.page(1),
+                        Keycode::Home => menu.jump(0),
+                        Keycode::End => menu.jump(menu.len() as isize - 1),
+                        Keycode::Return | Keycode::KpEnter => {
+                            let selected = menu.selected();
+                            self.start_program(selected)?;
+                        }
+                        _ => {}
                     }
+                } else if !repeat {
+                    self.key_down(keycode, timestamp);
                 }
             }
+        }
+        Ok(())
+    }
+
+    /// Restart the running program, counting it in its statistics.
+    fn reset_program(&mut self) -> Result<()> {
+        self.library.get_mut(&self.program_name).resets += 1;
+        self.start_program(self.current_program)
+    }
+
+    fn load_state(&mut self) {
+        match &self.saved_state {
+            Some(state) => {
+                self.vm.restore(state);
+                self.apply_pressed_key();
+                self.library.get_mut(&self.program_name).states_loaded += 1;
+                self.save_library();
+                self.notify("State loaded");
+            }
+            None => self.notify("No saved state"),
+        }
+    }
+
+    /// Save the display as a PNG file in the current directory.
+    fn screenshot(&mut self) {
+        let seconds = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let name: String = self
+            .program_name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = format!("{}-{}.png", name, seconds);
+        let png = encode_png(self.vm.screen(), PIXEL_SIZE, &self.display.palette);
+        match fs::write(&path, png) {
+            Ok(()) => self.notify(format!("Saved {}", path)),
+            Err(e) => self.notify(format!("Can't save screenshot: {}", e)),
+        }
+    }
 
-            self.canvas.present();
-            ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    /// Write the state of the VM which failed with `error` to the crash
+    /// directory, if there's one, for `chip8 debug --crash`.
+    fn write_crash_dump(&mut self, error: &str) {
+        let dir = match &self.options.crash_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => return,
+        };
+        let mut dump = CrashDump::new(&self.vm, error);
+        dump.config = vec![
+            ("program".into(), self.program_name.clone()),
+            ("frame".into(), self.frame.to_string()),
+            (
+                "speed".into(),
+                self.vm.instructions_per_second().to_string(),
+            ),
+            ("font_style".into(), self.options.font_style.name().into()),
+        ];
+        let seconds = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let name: String = self
+            .program_name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = dir.join(format!("{}-{}.{}", name, seconds, crash::EXTENSION));
+        let written = fs::create_dir_all(&dir).and_then(|()| fs::write(&path, dump.to_text()));
+        match written {
+            Ok(()) => self.notify(format!("Saved {}", path.display())),
+            Err(e) => self.notify(format!("Can't save crash dump: {}", e)),
         }
+    }
 
+    /// Start program `delta` positions away from the current one.
+    fn cycle_program(&mut self, delta: isize) -> Result<()> {
+        let len = self.programs.len() as isize;
+        if len > 1 {
+            let index = (self.current_program as isize + delta).rem_euclid(len);
+            self.start_program(index as usize)?;
+        }
         Ok(())
     }
+
+    fn toggle_scaling(&mut self) {
+        let scaling = self.display.scaling.toggled();
+        self.display.scaling = scaling;
+        self.library.get_mut(&self.program_name).scaling = Some(scaling);
+        self.save_library();
+        self.notify(match scaling {
+            Scaling::Integer => "Integer scaling",
+            Scaling::Fill => "Fill window scaling",
+        });
+    }
+
+    fn rotate(&mut self) {
+        let rotation = self.display.rotation.next();
+        self.display.rotation = rotation;
+        self.library.get_mut(&self.program_name).rotation = Some(rotation);
+        self.save_library();
+        self.keymap = Keymap::new(self.keymap_preset, rotation);
+        self.notify(format!("Rotation {}", rotation.degrees()));
+    }
+
+    fn next_keymap(&mut self) {
+        let preset = self.keymap_preset.next();
+        self.keymap_preset = preset;
+        self.keymap = Keymap::new(preset, self.display.rotation);
+        self.library.get_mut(&self.program_name).keymap = Some(preset);
+        self.save_library();
+        self.notify(format!("Keymap {}", preset.name()));
+    }
+
+    fn toggle_draws(&mut self) {
+        self.show_draws = !self.show_draws;
+        if self.show_draws {
+            self.vm.enable_draw_log(DRAW_LOG_FRAMES);
+            self.notify("Highlighting sprite draws");
+        } else {
+            self.vm.disable_draw_log();
+            self.notify("Not highlighting sprite draws");
+        }
+    }
+
+    fn toggle_practice(&mut self) {
+        if self.practice.take().is_some() {
+            self.notify("Practice mode off");
+        } else {
+            self.practice = Some(Practice::new(&self.vm, self.options.practice_watch));
+            self.notify("Practice mode: loop point set");
+        }
+    }
+
+    fn toggle_debugger(&mut self) {
+        if self.debug_window.take().is_some() {
+            self.vm.disable_register_history();
+            return;
+        }
+        match DebugWindow::open(&self.video, &format!("Debugger - {}", TITLE)) {
+            Ok(debug_window) => {
+                self.debug_window = Some(debug_window);
+                self.vm.enable_register_history(REGISTER_HISTORY_LENGTH);
+            }
+            Err(e) => self.notify(format!("Can't open debugger: {}", e)),
+        }
+    }
+
+    /// Stop running the program and show why.
+    fn stop(&mut self, reason: Reason) {
+        let switch_key = if self.programs.len() > 1 {
+            self.hotkeys.key_name(Action::Menu)
+        } else {
+            None
+        };
+        self.stopped = Some(StopScreen::new(&self.vm, reason, switch_key));
+    }
+
+    /// Go back to the loop point once the attempt is over.
+    fn check_practice(&mut self) {
+        let practice = match &self.practice {
+            Some(practice) if practice.is_over(&self.vm) => practice,
+            _ => return,
+        };
+        practice.restart(&mut self.vm);
+        self.apply_pressed_key();
+        self.notify("Practice mode: restarted");
+    }
+
+    fn toggle_mute(&mut self) {
+        self.config.muted = !self.config.muted;
+        self.apply_volume();
+        self.notify(if self.config.muted {
+            "Muted"
+        } else {
+            "Unmuted"
+        });
+        self.save_config();
+    }
+
+    fn change_volume(&mut self, delta: i8) {
+        let volume = (self.config.volume as i16 + delta as i16).clamp(0, MAX_VOLUME as i16);
+        self.config.volume = volume as u8;
+        self.config.muted = false;
+        self.apply_volume();
+        self.notify(format!("Volume {}%", self.config.volume));
+        self.save_config();
+    }
+
+    fn apply_volume(&mut self) {
+        if let Some(audio) = &self.audio {
+            let volume = if self.config.muted {
+                0
+            } else {
+                self.config.volume
+            };
+            audio.set_volume(volume as f32 / MAX_VOLUME as f32);
+        }
+        self.update_title();
+    }
+
+    fn save_config(&mut self) {
+        if let Err(e) = self.config.save(&*self.storage) {
+            self.notify(format!("Can't save config: {}", e));
+        }
+    }
+
+    fn save_library(&mut self) {
+        if let Err(e) = self.library.save(&*self.storage) {
+            self.notify(format!("Can't save library: {}", e));
+        }
+    }
+
+    fn update_title(&mut self) {
+        let mut title = TITLE.to_string();
+        if !self.program_name.is_empty() {
+            title = format!("{} - {}", self.program_name, title);
+        }
+        if self.config.muted {
+            title.push_str(" [muted]");
+        }
+        // Title can only fail to be set if it contains a nul byte.
+        let _ = self.canvas.window_mut().set_title(&title);
+    }
+
+    fn key_down(&mut self, keycode: Keycode, timestamp: u32) {
+        if let Some(key) = self.keymap.keypad_key(keycode) {
+            self.pressed_keys.press(key);
+            self.vm.set_input(self.pressed_keys);
+            if let Some(latency) = &mut self.latency {
+                latency.key_pressed(key, timestamp);
+            }
+        }
+    }
+
+    /// Show the latency of key presses the program saw in the frame just
+    /// emulated.
+    fn measure_latency(&mut self) {
+        let Some(latency) = &mut self.latency else {
+            return;
+        };
+        for milliseconds in latency.frame_done(self.timer.ticks()) {
+            self.toasts
+                .push(format!("Input latency {} ms", milliseconds));
+        }
+    }
+
+    /// Press keys held now rather than the ones held in a restored state.
+    fn apply_pressed_key(&mut self) {
+        self.vm.set_input(self.pressed_keys);
+    }
+
+    fn key_up(&mut self, keycode: Keycode) {
+        if self.rewind_key == Some(keycode) {
+            self.rewind_key = None;
+        }
+        if self.turbo_key == Some(keycode) {
+            self.turbo_key = None;
+        }
+        if let Some(key) = self.keymap.keypad_key(keycode) {
+            self.pressed_keys.release(key);
+            self.vm.set_input(self.pressed_keys);
+            if let Some(latency) = &mut self.latency {
+                latency.key_released(key);
+            }
+        }
+    }
+
+    fn draw_screen(&mut self) -> Result<()> {
+        let window_size = self.canvas.output_size().map_err(Error::Runtime)?;
+        let rotation = self.display.rotation;
+        let screen = self.vm.screen();
+        let (width, height) = (screen.width(), screen.height());
+        let (cols, rows) = rotation.dimensions(width, height);
+        let viewport = Viewport::new(self.display.scaling, window_size, cols, rows);
+
+        self.canvas.set_draw_color(self.display.border_color);
+        self.canvas.clear();
+        let palette = self.display.palette;
+        self.canvas
+            .set_draw_color(sdl_color(palette.pixel_color(false)));
+        self.canvas
+            .fill_rect(viewport.screen())
+            .map_err(Error::Runtime)?;
+
+        // Frames are only blended in low resolution, high resolution
+        // programs being SUPER-CHIP ones which rarely flicker.
+        let blend = self.vm.resolution() == Resolution::Low;
+        if blend {
+            self.blender.set_mode(self.display.blend);
+            self.blender.push(&self.vm.graphics);
+        }
+        for row in 0..height {
+            for col in 0..width {
+                let color = if blend {
+                    (self.blender.intensity(col, row) > 0)
+                        .then(|| self.blender.color(&palette, col, row))
+                } else {
+                    (screen.color_index(col, row) > 0)
+                        .then(|| palette.screen_color(screen, col, row))
+                };
+                if let Some(color) = color {
+                    self.canvas.set_draw_color(sdl_color(color));
+                    let (col, row) = rotation.apply(col, row, width, height);
+                    self.canvas
+                        .fill_rect(viewport.cell(col, row))
+                        .map_err(Error::Runtime)?;
+                }
+            }
+        }
+
+        if let Some(draw_log) = self.vm.draw_log() {
+            for draw in draw_log.iter() {
+                self.canvas.set_draw_color(if draw.collision {
+                    COLLISION_COLOR
+                } else {
+                    DRAW_COLOR
+                });
+                // Outline the part before wrapping around the display edges.
+                let left = draw.x as usize;
+                let top = draw.y as usize;
+                let right = (left + 7).min(width - 1);
+                let bottom = (top + draw.height.max(1) as usize - 1).min(height - 1);
+                let corner = |col, row| {
+                    let (col, row) = rotation.apply(col, row, width, height);
+                    viewport.cell(col, row)
+                };
+                let outline = corner(left, top).union(corner(right, bottom));
+                self.canvas.draw_rect(outline).map_err(Error::Runtime)?;
+            }
+        }
+
+        if let (Some(_), Some(pointer)) = (&self.debug_window, self.pointer) {
+            inspector::draw(
+                &mut self.canvas,
+                &viewport,
+                rotation,
+                pointer,
+                screen,
+                &palette,
+            )
+            .map_err(Error::Runtime)?;
+        }
+        Ok(())
+    }
+
+    /// Canvas pixel at window coordinates (`x`, `y`), which differ on high
+    /// DPI displays.
+    fn canvas_point(&self, x: i32, y: i32) -> (i32, i32) {
+        let (window_width, window_height) = self.canvas.window().size();
+        let (width, height) = self
+            .canvas
+            .output_size()
+            .unwrap_or((window_width, window_height));
+        (
+            x * width as i32 / window_width.max(1) as i32,
+            y * height as i32 / window_height.max(1) as i32,
+        )
+    }
+}
+
+fn read_font<const N: usize>(font_path: &str) -> Result<[u8; N]> {
+    let font = fs::read(font_path).map_err(|e| Error::FontLoading(e.to_string()))?;
+    font.as_slice().try_into().map_err(|_| {
+        Error::FontLoading(format!(
+            "{}: expected {} bytes, got {}",
+            font_path,
+            N,
+            font.len()
+        ))
+    })
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
+    Arguments(String),
     Initialization(String),
     ProgramLoading(std::io::Error),
+    InvalidProgram(LoadError),
+    FontLoading(String),
+    Patch(PatchError),
     Runtime(String),
 }
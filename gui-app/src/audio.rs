@@ -0,0 +1,214 @@
+//! Beeper output.
+//!
+//! The emulation loop generates samples at [`SOURCE_RATE`] into a lock-free
+//! single producer single consumer ring buffer, the SDL audio callback drains
+//! it and linearly resamples to the device rate. A few frames of samples are
+//! buffered before playback starts, so jitter in frame timing doesn't make the
+//! beep crackle or cut off. Volume is applied when mixing in the callback, so
+//! changes are heard immediately rather than after the buffered samples.
+
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::Sdl;
+
+pub const SOURCE_RATE: u32 = 44_100;
+const FRAME_RATE: u32 = 60;
+const SAMPLES_PER_FRAME: usize = (SOURCE_RATE / FRAME_RATE) as usize;
+/// Samples buffered before playback starts or resumes after running dry.
+const PREBUFFER: usize = 3 * SAMPLES_PER_FRAME;
+const CAPACITY: usize = 8 * SAMPLES_PER_FRAME;
+
+const TONE_FREQUENCY: f32 = 440.0;
+const AMPLITUDE: f32 = 0.25;
+/// Per sample decay of the last output sample when the buffer runs dry.
+const UNDERRUN_DECAY: f32 = 0.995;
+
+/// Fixed size sample queue, safe to use from one producer and one consumer
+/// thread without locking.
+pub struct RingBuffer {
+    samples: Box<[AtomicU32]>,
+    read: AtomicUsize,
+    write: AtomicUsize,
+}
+
+impl RingBuffer {
+    /// Create buffer holding up to `capacity` samples, split into the
+    /// producing and the consuming halves.
+    pub fn split(capacity: usize) -> (Producer, Consumer) {
+        let buffer = Arc::new(Self {
+            samples: (0..capacity + 1).map(|_| AtomicU32::new(0)).collect(),
+            read: AtomicUsize::new(0),
+            write: AtomicUsize::new(0),
+        });
+        (Producer(buffer.clone()), Consumer(buffer))
+    }
+
+    fn next(&self, index: usize) -> usize {
+        (index + 1) % self.samples.len()
+    }
+
+    fn len(&self) -> usize {
+        let read = self.read.load(Ordering::Acquire);
+        let write = self.write.load(Ordering::Acquire);
+        (write + self.samples.len() - read) % self.samples.len()
+    }
+}
+
+pub struct Producer(Arc<RingBuffer>);
+
+impl Producer {
+    /// Add `sample`, returns `false` and drops it when the buffer is full.
+    pub fn push(&self, sample: f32) -> bool {
+        let buffer = &self.0;
+        let write = buffer.write.load(Ordering::Relaxed);
+        let next = buffer.next(write);
+        if next == buffer.read.load(Ordering::Acquire) {
+            return false;
+        }
+        buffer.samples[write].store(sample.to_bits(), Ordering::Relaxed);
+        buffer.write.store(next, Ordering::Release);
+        true
+    }
+}
+
+pub struct Consumer(Arc<RingBuffer>);
+
+impl Consumer {
+    pub fn pop(&self) -> Option<f32> {
+        let buffer = &self.0;
+        let read = buffer.read.load(Ordering::Relaxed);
+        if read == buffer.write.load(Ordering::Acquire) {
+            return None;
+        }
+        let sample = f32::from_bits(buffer.samples[read].load(Ordering::Relaxed));
+        buffer.read.store(buffer.next(read), Ordering::Release);
+        Some(sample)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// SDL callback converting source samples to the device rate with linear
+/// interpolation.
+pub struct Resampler {
+    consumer: Consumer,
+    volume: Arc<AtomicU32>,
+    /// Source samples per device sample.
+    step: f32,
+    /// Position between `previous` and `next` source samples.
+    position: f32,
+    previous: f32,
+    next: f32,
+    buffering: bool,
+}
+
+impl Resampler {
+    pub fn new(consumer: Consumer, volume: Arc<AtomicU32>, device_rate: u32) -> Self {
+        Self {
+            consumer,
+            volume,
+            step: SOURCE_RATE as f32 / device_rate as f32,
+            position: 0.0,
+            previous: 0.0,
+            next: 0.0,
+            buffering: true,
+        }
+    }
+
+    fn next_source_sample(&mut self) -> f32 {
+        if self.buffering && self.consumer.len() < PREBUFFER {
+            return self.next * UNDERRUN_DECAY;
+        }
+        self.buffering = false;
+        match self.consumer.pop() {
+            Some(sample) => sample,
+            None => {
+                self.buffering = true;
+                self.next * UNDERRUN_DECAY
+            }
+        }
+    }
+}
+
+impl AudioCallback for Resampler {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        let volume = f32::from_bits(self.volume.load(Ordering::Relaxed));
+        for sample in out.iter_mut() {
+            while self.position >= 1.0 {
+                self.position -= 1.0;
+                self.previous = self.next;
+                self.next = self.next_source_sample();
+            }
+            *sample = (self.previous + (self.next - self.previous) * self.position) * volume;
+            self.position += self.step;
+        }
+    }
+}
+
+/// Square wave beeper fed by the emulation loop once per frame.
+pub struct Audio {
+    _device: AudioDevice<Resampler>,
+    producer: Producer,
+    /// Output volume from 0 to 1 as `f32` bits, shared with the callback.
+    volume: Arc<AtomicU32>,
+    phase: f32,
+}
+
+impl Audio {
+    pub fn init(sdl_context: &Sdl) -> Result<Self, String> {
+        let audio_subsystem = sdl_context.audio()?;
+        let desired = AudioSpecDesired {
+            freq: Some(SOURCE_RATE as i32),
+            channels: Some(1),
+            samples: None,
+        };
+        let (producer, consumer) = RingBuffer::split(CAPACITY);
+        let volume = Arc::new(AtomicU32::new(1.0f32.to_bits()));
+        let device = audio_subsystem.open_playback(None, &desired, |spec| {
+            Resampler::new(consumer, volume.clone(), spec.freq as u32)
+        })?;
+        device.resume();
+        Ok(Self {
+            _device: device,
+            producer,
+            volume,
+            phase: 0.0,
+        })
+    }
+
+    /// Set output volume from 0 (silent) to 1 (full).
+    pub fn set_volume(&self, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        self.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Generate one frame of samples, a tone if `beeping` or silence.
+    pub fn frame(&mut self, beeping: bool) {
+        let phase_step = TONE_FREQUENCY / SOURCE_RATE as f32;
+        for _ in 0..SAMPLES_PER_FRAME {
+            let sample = if beeping {
+                if self.phase < 0.5 {
+                    AMPLITUDE
+                } else {
+                    -AMPLITUDE
+                }
+            } else {
+                0.0
+            };
+            self.phase = (self.phase + phase_step) % 1.0;
+            if !self.producer.push(sample) {
+                break;
+            }
+        }
+    }
+}
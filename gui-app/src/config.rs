@@ -0,0 +1,153 @@
+//! Persistent user settings.
+//!
+//! Stored as `key = value` lines under the `config` key of the app storage,
+//! `chip-8-emulator/config` under the user configuration directory. Unknown
+//! keys and invalid values are ignored, so an old or hand-edited file never
+//! prevents the app from starting.
+
+use chip_8_emulator::storage::{FileStorage, MemoryStorage, Storage};
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+pub const MAX_VOLUME: u8 = 100;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Volume in percent.
+    pub volume: u8,
+    pub muted: bool,
+    /// Sleep instead of running the program while it spins waiting for the
+    /// delay timer.
+    pub idle_detection: bool,
+    /// Directory with programs shown when none are given on the command
+    /// line.
+    pub library: Option<PathBuf>,
+    /// Action names and their keys as written in `hotkey.<action> = <keys>`
+    /// lines, see [`crate::hotkeys`].
+    pub hotkeys: Vec<(String, String)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            volume: 50,
+            muted: false,
+            idle_detection: true,
+            library: None,
+            hotkeys: Vec::new(),
+        }
+    }
+}
+
+/// Directory of the app files under the user configuration directory, if the
+/// platform has one.
+pub fn config_dir() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("chip-8-emulator"))
+}
+
+/// Storage of the app files in [`config_dir`], or in memory, lost on exit,
+/// if the platform has no configuration directory.
+pub fn storage() -> Arc<dyn Storage> {
+    match config_dir() {
+        Some(dir) => Arc::new(FileStorage::new(dir)),
+        None => {
+            eprintln!("Settings won't be kept: no config directory");
+            Arc::new(MemoryStorage::new())
+        }
+    }
+}
+
+impl Config {
+    /// Storage key of the config file.
+    pub const KEY: &'static str = "config";
+
+    /// Configured library directory, or the one `chip8 fetch` saves programs
+    /// to: `CHIP8_LIBRARY` or `chip-8-emulator/roms` under the user data
+    /// directory.
+    pub fn library_dir(&self) -> Option<PathBuf> {
+        if let Some(library) = self
+            .library
+            .clone()
+            .or_else(|| env::var_os("CHIP8_LIBRARY").map(PathBuf::from))
+        {
+            return Some(library);
+        }
+        let data_dir = env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+        Some(data_dir.join("chip-8-emulator").join("roms"))
+    }
+
+    /// Load config file, falling back to defaults when it doesn't exist.
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .load(Self::KEY)
+            .ok()
+            .flatten()
+            .map(|text| Self::parse(&String::from_utf8_lossy(&text)))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> io::Result<()> {
+        storage.store(Self::KEY, self.to_text().as_bytes())
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            match key {
+                "volume" => {
+                    if let Ok(volume) = value.parse::<u8>() {
+                        config.volume = volume.min(MAX_VOLUME);
+                    }
+                }
+                "muted" => {
+                    if let Ok(muted) = value.parse() {
+                        config.muted = muted;
+                    }
+                }
+                "idle_detection" => {
+                    if let Ok(idle_detection) = value.parse() {
+                        config.idle_detection = idle_detection;
+                    }
+                }
+                "library" if !value.is_empty() => config.library = Some(value.into()),
+                _ => {
+                    if let Some(action) = key.strip_prefix("hotkey.") {
+                        config.hotkeys.push((action.to_string(), value.to_string()));
+                    }
+                }
+            }
+        }
+        config
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "volume = {}\nmuted = {}\nidle_detection = {}\n",
+            self.volume, self.muted, self.idle_detection
+        );
+        if let Some(library) = &self.library {
+            text.push_str(&format!("library = {}\n", library.display()));
+        }
+        for (action, keys) in &self.hotkeys {
+            text.push_str(&format!("hotkey.{} = {}\n", action, keys));
+        }
+        text
+    }
+}
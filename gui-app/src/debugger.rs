@@ -0,0 +1,177 @@
+//! Debugger panels in a second window, so the program's display stays
+//! unobstructed at full size: disassembly around the program counter,
+//! registers, the stack, memory at I and where each register was last
+//! written. Both windows are driven by the same event loop and show the
+//! same VM.
+
+use chip_8_emulator::{memory::MEMORY_SIZE, VM};
+use sdl2::{pixels::Color, render::WindowCanvas, VideoSubsystem};
+
+use crate::stop::context;
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT};
+
+const WIDTH: u32 = 840;
+const HEIGHT: u32 = 480;
+const TEXT_SCALE: usize = 2;
+const LINE_SPACING: usize = 4;
+const PADDING: i32 = 10;
+/// Space between the disassembly and the other panels.
+const COLUMN_GAP: i32 = 30;
+/// Bytes of instructions shown before and after the program counter.
+const CONTEXT_BEFORE: usize = 16;
+const CONTEXT_AFTER: usize = 32;
+/// Rows of memory shown from I, 8 bytes each.
+const MEMORY_ROWS: usize = 8;
+const MEMORY_ROW_SIZE: usize = 8;
+
+const BACKGROUND: Color = Color::RGB(40, 40, 40);
+const FOREGROUND: Color = Color::RGB(160, 160, 160);
+const HIGHLIGHT: Color = Color::RGB(255, 255, 255);
+
+type Panel = Vec<(String, Color)>;
+
+pub struct DebugWindow {
+    canvas: WindowCanvas,
+}
+
+impl DebugWindow {
+    pub fn open(video: &VideoSubsystem, title: &str) -> Result<Self, String> {
+        let window = video
+            .window(title, WIDTH, HEIGHT)
+            .resizable()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        Ok(Self { canvas })
+    }
+
+    /// SDL id of the window, to tell its events apart.
+    pub fn id(&self) -> u32 {
+        self.canvas.window().id()
+    }
+
+    /// Draw the panels for the current state of `vm` and present them.
+    pub fn draw(&mut self, vm: &VM) -> Result<(), String> {
+        self.canvas.set_draw_color(BACKGROUND);
+        self.canvas.clear();
+
+        let pc = vm.registers().program_counter as usize;
+        let mut disassembly = vec![("Disassembly".to_string(), HIGHLIGHT)];
+        disassembly.extend(context(vm, pc, CONTEXT_BEFORE, CONTEXT_AFTER));
+        let mut state = registers(vm);
+        state.push((String::new(), FOREGROUND));
+        state.extend(stack(vm));
+        state.push((String::new(), FOREGROUND));
+        state.extend(memory(vm));
+        let writes = writes(vm);
+
+        let left = PADDING;
+        let middle = left + panel_width(&disassembly) + COLUMN_GAP;
+        let right = middle + panel_width(&state) + COLUMN_GAP;
+        for (x, panel) in [(left, &disassembly), (middle, &state), (right, &writes)] {
+            for (i, (line, color)) in panel.iter().enumerate() {
+                let y = PADDING + (i * (GLYPH_HEIGHT * TEXT_SCALE + LINE_SPACING)) as i32;
+                draw_text(&mut self.canvas, x, y, TEXT_SCALE, line, *color)?;
+            }
+        }
+        self.canvas.present();
+        Ok(())
+    }
+}
+
+fn registers(vm: &VM) -> Panel {
+    let registers = vm.registers();
+    let mut lines = vec![
+        ("Registers".to_string(), HIGHLIGHT),
+        (
+            format!(
+                "PC {:03X}  I {:03X}",
+                registers.program_counter, registers.i
+            ),
+            FOREGROUND,
+        ),
+        (
+            format!(
+                "DT {:02X}   ST {:02X}",
+                registers.delay_timer, registers.sound_timer
+            ),
+            FOREGROUND,
+        ),
+    ];
+    for (row, values) in registers.v.chunks(4).enumerate() {
+        let values: Vec<_> = values
+            .iter()
+            .enumerate()
+            .map(|(col, value)| format!("V{:X} {:02X}", row * 4 + col, value))
+            .collect();
+        lines.push((values.join("  "), FOREGROUND));
+    }
+    lines
+}
+
+/// Return addresses, innermost first.
+fn stack(vm: &VM) -> Panel {
+    let frames = vm.stack_frames();
+    let mut lines = vec![(format!("Stack ({})", frames.len()), HIGHLIGHT)];
+    if frames.is_empty() {
+        lines.push(("-".to_string(), FOREGROUND));
+    }
+    lines.extend(
+        frames
+            .iter()
+            .rev()
+            .map(|address| (format!("{:03X}", address), FOREGROUND)),
+    );
+    lines
+}
+
+/// Bytes from the row holding I on.
+fn memory(vm: &VM) -> Panel {
+    let start = vm.registers().i as usize / MEMORY_ROW_SIZE * MEMORY_ROW_SIZE;
+    let end = (start + MEMORY_ROWS * MEMORY_ROW_SIZE).min(MEMORY_SIZE);
+    let mut lines = vec![("Memory at I".to_string(), HIGHLIGHT)];
+    for (row, bytes) in vm
+        .memory(start..end)
+        .unwrap()
+        .chunks(MEMORY_ROW_SIZE)
+        .enumerate()
+    {
+        let bytes: Vec<_> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        lines.push((
+            format!("{:03X}  {}", start + row * MEMORY_ROW_SIZE, bytes.join(" ")),
+            FOREGROUND,
+        ));
+    }
+    lines
+}
+
+/// Latest value written to each register and the address of the
+/// instruction which wrote it, from [`VM::register_history`].
+fn writes(vm: &VM) -> Panel {
+    let mut lines = vec![("Last writes".to_string(), HIGHLIGHT)];
+    let history = match vm.register_history() {
+        Some(history) => history,
+        None => return lines,
+    };
+    for x in 0..=0xF {
+        let line = match history.v(x).last() {
+            Some(write) => format!("V{:X} {:02X} {:03X}", x, write.value, write.pc),
+            None => format!("V{:X} -", x),
+        };
+        lines.push((line, FOREGROUND));
+    }
+    let line = match history.i().last() {
+        Some(write) => format!("I  {:03X} {:03X}", write.value, write.pc),
+        None => "I  -".to_string(),
+    };
+    lines.push((line, FOREGROUND));
+    lines
+}
+
+fn panel_width(panel: &Panel) -> i32 {
+    panel
+        .iter()
+        .map(|(line, _)| text_width(line, TEXT_SCALE) as i32)
+        .max()
+        .unwrap_or(0)
+}
@@ -0,0 +1,213 @@
+use chip_8_emulator::blend::BlendMode;
+use chip_8_emulator::palette::{Palette, Rgb};
+use sdl2::{pixels::Color, rect::Rect};
+
+/// How the CHIP-8 screen is stretched to the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scaling {
+    /// Every CHIP-8 pixel is an integer number of window pixels, crisp but
+    /// possibly leaving a wider border.
+    Integer,
+    /// The screen fills as much of the window as the aspect ratio allows,
+    /// with possibly non-integer pixel sizes.
+    Fill,
+}
+
+impl Scaling {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "integer" => Some(Scaling::Integer),
+            "fill" => Some(Scaling::Fill),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scaling::Integer => "integer",
+            Scaling::Fill => "fill",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Scaling::Integer => Scaling::Fill,
+            Scaling::Fill => Scaling::Integer,
+        }
+    }
+}
+
+/// Clockwise rotation of the screen, for vertically oriented games and
+/// portrait monitors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+impl Rotation {
+    pub fn from_degrees(degrees: u32) -> Option<Self> {
+        match degrees {
+            0 => Some(Rotation::None),
+            90 => Some(Rotation::Cw90),
+            180 => Some(Rotation::Cw180),
+            270 => Some(Rotation::Cw270),
+            _ => None,
+        }
+    }
+
+    pub fn degrees(self) -> u32 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Cw90 => 90,
+            Rotation::Cw180 => 180,
+            Rotation::Cw270 => 270,
+        }
+    }
+
+    /// Next rotation by 90 degrees clockwise.
+    pub fn next(self) -> Self {
+        match self {
+            Rotation::None => Rotation::Cw90,
+            Rotation::Cw90 => Rotation::Cw180,
+            Rotation::Cw180 => Rotation::Cw270,
+            Rotation::Cw270 => Rotation::None,
+        }
+    }
+
+    /// Size of `cols`x`rows` screen after the rotation.
+    pub fn dimensions(self, cols: usize, rows: usize) -> (usize, usize) {
+        match self {
+            Rotation::None | Rotation::Cw180 => (cols, rows),
+            Rotation::Cw90 | Rotation::Cw270 => (rows, cols),
+        }
+    }
+
+    /// Position of pixel (`col`, `row`) of `cols`x`rows` screen after the
+    /// rotation.
+    pub fn apply(self, col: usize, row: usize, cols: usize, rows: usize) -> (usize, usize) {
+        match self {
+            Rotation::None => (col, row),
+            Rotation::Cw90 => (rows - 1 - row, col),
+            Rotation::Cw180 => (cols - 1 - col, rows - 1 - row),
+            Rotation::Cw270 => (row, cols - 1 - col),
+        }
+    }
+
+    /// Pixel of `cols`x`rows` screen which the rotation puts at (`col`,
+    /// `row`), undoing [`Rotation::apply`].
+    pub fn invert(self, col: usize, row: usize, cols: usize, rows: usize) -> (usize, usize) {
+        match self {
+            Rotation::None => (col, row),
+            Rotation::Cw90 => (row, rows - 1 - col),
+            Rotation::Cw180 => (cols - 1 - col, rows - 1 - row),
+            Rotation::Cw270 => (cols - 1 - row, col),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayOptions {
+    pub scaling: Scaling,
+    /// Color of the letterboxed area around the screen.
+    pub border_color: Color,
+    pub rotation: Rotation,
+    pub palette: Palette,
+    /// Blending of the last two frames against flicker.
+    pub blend: BlendMode,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            scaling: Scaling::Integer,
+            border_color: Color::RGB(0, 0, 0),
+            rotation: Rotation::None,
+            palette: Palette::default(),
+            blend: BlendMode::Off,
+        }
+    }
+}
+
+pub fn sdl_color([r, g, b]: Rgb) -> Color {
+    Color::RGB(r, g, b)
+}
+
+/// Parse color written as `RRGGBB` or `#RRGGBB`.
+pub fn parse_color(s: &str) -> Option<Color> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some(Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+/// Area of the window the CHIP-8 screen is drawn to.
+pub struct Viewport {
+    x: f64,
+    y: f64,
+    pixel_size: f64,
+    cols: usize,
+    rows: usize,
+}
+
+impl Viewport {
+    /// Fit `cols`x`rows` screen centered into a window of `window_size`.
+    pub fn new(scaling: Scaling, window_size: (u32, u32), cols: usize, rows: usize) -> Self {
+        let (width, height) = (window_size.0 as f64, window_size.1 as f64);
+        let fit = (width / cols as f64).min(height / rows as f64);
+        let pixel_size = match scaling {
+            Scaling::Integer => fit.floor().max(1.0),
+            Scaling::Fill => fit,
+        };
+        Self {
+            x: ((width - pixel_size * cols as f64) / 2.0).floor(),
+            y: ((height - pixel_size * rows as f64) / 2.0).floor(),
+            pixel_size,
+            cols,
+            rows,
+        }
+    }
+
+    /// Whole screen area.
+    pub fn screen(&self) -> Rect {
+        self.span(0, 0, self.cols, self.rows)
+    }
+
+    /// Window area of the CHIP-8 pixel at (`col`, `row`).
+    pub fn cell(&self, col: usize, row: usize) -> Rect {
+        self.span(col, row, 1, 1)
+    }
+
+    /// CHIP-8 pixel whose area contains window pixel (`x`, `y`), if any.
+    pub fn cell_at(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        let col = ((x as f64 - self.x) / self.pixel_size).floor();
+        let row = ((y as f64 - self.y) / self.pixel_size).floor();
+        if col < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (col, row) = (col as usize, row as usize);
+        // Cells are rounded to whole window pixels, find the one drawn there.
+        let candidates = (col.saturating_sub(1)..=col + 1)
+            .flat_map(|col| (row.saturating_sub(1)..=row + 1).map(move |row| (col, row)));
+        candidates
+            .filter(|&(col, row)| col < self.cols && row < self.rows)
+            .find(|&(col, row)| self.cell(col, row).contains_point((x, y)))
+    }
+
+    fn span(&self, col: usize, row: usize, cols: usize, rows: usize) -> Rect {
+        let left = (self.x + col as f64 * self.pixel_size).round() as i32;
+        let top = (self.y + row as f64 * self.pixel_size).round() as i32;
+        let right = (self.x + (col + cols) as f64 * self.pixel_size).round() as i32;
+        let bottom = (self.y + (row + rows) as f64 * self.pixel_size).round() as i32;
+        Rect::new(
+            left,
+            top,
+            (right - left).max(1) as u32,
+            (bottom - top).max(1) as u32,
+        )
+    }
+}
@@ -0,0 +1,117 @@
+//! Help screen listing what every key does, built from the keymap and
+//! hotkeys in effect so it follows presets, rotation and the config file.
+
+use sdl2::{pixels::Color, rect::Rect, render::WindowCanvas};
+
+use crate::hotkeys::{Action, Hotkeys};
+use crate::keymap::{Keymap, Preset};
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT};
+
+const LINE_SPACING: usize = 3;
+const PADDING: i32 = 10;
+const MARGIN: i32 = 10;
+/// Space between the keypad and the hotkeys columns.
+const COLUMN_GAP: i32 = 20;
+
+const BACKGROUND: Color = Color::RGB(40, 40, 40);
+const FOREGROUND: Color = Color::RGB(160, 160, 160);
+const HIGHLIGHT: Color = Color::RGB(255, 255, 255);
+
+type Column = Vec<(String, Color)>;
+
+pub struct HelpScreen {
+    keypad: Column,
+    hotkeys: Column,
+    footer: String,
+}
+
+impl HelpScreen {
+    pub fn new(keymap: &Keymap, preset: Preset, hotkeys: &Hotkeys) -> Self {
+        let mut keypad = vec![(format!("Keypad ({})", preset.name()), HIGHLIGHT)];
+        keypad.extend((0..=0xF).map(|key| {
+            let names = keymap.key_names(key);
+            let names = if names.is_empty() {
+                "-".to_string()
+            } else {
+                names.join(", ")
+            };
+            (format!("{:X}  {}", key, names), FOREGROUND)
+        }));
+
+        let mut actions = vec![("Hotkeys".to_string(), HIGHLIGHT)];
+        actions.extend(Action::ALL.iter().map(|&action| {
+            let keys = hotkeys.key_names(action);
+            let keys = if keys.is_empty() {
+                "unbound".to_string()
+            } else {
+                keys.join(", ")
+            };
+            (
+                format!("{:<16} {}", action.name().replace('_', " "), keys),
+                FOREGROUND,
+            )
+        }));
+
+        let footer = match hotkeys.key_name(Action::Help) {
+            Some(key) => format!("{} or Escape: close", key),
+            None => "Escape: close".to_string(),
+        };
+        Self {
+            keypad,
+            hotkeys: actions,
+            footer,
+        }
+    }
+
+    /// Draw the screen as a panel covering the window, text as large as
+    /// fits.
+    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let (width, height) = canvas.output_size()?;
+        let panel_width = width as i32 - 2 * MARGIN;
+        let panel_height = height as i32 - 2 * MARGIN;
+        canvas.set_draw_color(BACKGROUND);
+        canvas.fill_rect(Rect::new(
+            MARGIN,
+            MARGIN,
+            panel_width.max(0) as u32,
+            panel_height.max(0) as u32,
+        ))?;
+
+        let scale = self.scale(panel_width - 2 * PADDING, panel_height - 2 * PADDING);
+        let line_height = (GLYPH_HEIGHT * scale + LINE_SPACING) as i32;
+        let left = MARGIN + PADDING;
+        let top = MARGIN + PADDING;
+        let right = left + self.column_width(&self.keypad, scale) + COLUMN_GAP;
+        for (x, column) in [(left, &self.keypad), (right, &self.hotkeys)] {
+            for (i, (line, color)) in column.iter().enumerate() {
+                draw_text(canvas, x, top + i as i32 * line_height, scale, line, *color)?;
+            }
+        }
+        let footer_y = MARGIN + panel_height - PADDING - (GLYPH_HEIGHT * scale) as i32;
+        draw_text(canvas, left, footer_y, scale, &self.footer, HIGHLIGHT)
+    }
+
+    /// Largest text scale fitting both columns and the footer in `width` by
+    /// `height` pixels, at least 1.
+    fn scale(&self, width: i32, height: i32) -> usize {
+        let lines = self.keypad.len().max(self.hotkeys.len()) + 1;
+        (1..=4)
+            .rev()
+            .find(|&scale| {
+                let columns = self.column_width(&self.keypad, scale)
+                    + COLUMN_GAP
+                    + self.column_width(&self.hotkeys, scale);
+                let rows = (lines * (GLYPH_HEIGHT * scale + LINE_SPACING)) as i32;
+                columns <= width && rows <= height
+            })
+            .unwrap_or(1)
+    }
+
+    fn column_width(&self, column: &Column, scale: usize) -> i32 {
+        column
+            .iter()
+            .map(|(line, _)| text_width(line, scale) as i32)
+            .max()
+            .unwrap_or(0)
+    }
+}
@@ -0,0 +1,265 @@
+//! Keys of emulator actions, remappable in the config file since the
+//! defaults inevitably clash with someone's window manager.
+//!
+//! Bindings are `hotkey.<action> = <keys>` lines, keys separated by commas
+//! and written as SDL key names with an optional `Ctrl+` prefix, e.g.
+//! `hotkey.pause = P` or `hotkey.volume_up = =, Keypad +`. Actions not in
+//! the config keep their default keys.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use sdl2::keyboard::{Keycode, Mod};
+
+use crate::display::Rotation;
+use crate::keymap::{Keymap, Preset};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Show keys of the keypad and of every action.
+    Help,
+    Pause,
+    Reset,
+    SaveState,
+    LoadState,
+    /// Run backwards while held.
+    Rewind,
+    /// Run faster while held.
+    Turbo,
+    Screenshot,
+    ToggleScaling,
+    Rotate,
+    NextKeymap,
+    ToggleDraws,
+    TogglePractice,
+    /// Open or close the debugger window.
+    Debugger,
+    Mute,
+    VolumeUp,
+    VolumeDown,
+    Menu,
+    PreviousProgram,
+    NextProgram,
+}
+
+impl Action {
+    pub const ALL: [Action; 20] = [
+        Action::Help,
+        Action::Pause,
+        Action::Reset,
+        Action::SaveState,
+        Action::LoadState,
+        Action::Rewind,
+        Action::Turbo,
+        Action::Screenshot,
+        Action::ToggleScaling,
+        Action::Rotate,
+        Action::NextKeymap,
+        Action::ToggleDraws,
+        Action::TogglePractice,
+        Action::Debugger,
+        Action::Mute,
+        Action::VolumeUp,
+        Action::VolumeDown,
+        Action::Menu,
+        Action::PreviousProgram,
+        Action::NextProgram,
+    ];
+
+    /// Name in the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Help => "help",
+            Action::Pause => "pause",
+            Action::Reset => "reset",
+            Action::SaveState => "save_state",
+            Action::LoadState => "load_state",
+            Action::Rewind => "rewind",
+            Action::Turbo => "turbo",
+            Action::Screenshot => "screenshot",
+            Action::ToggleScaling => "toggle_scaling",
+            Action::Rotate => "rotate",
+            Action::NextKeymap => "next_keymap",
+            Action::ToggleDraws => "toggle_draws",
+            Action::TogglePractice => "toggle_practice",
+            Action::Debugger => "debugger",
+            Action::Mute => "mute",
+            Action::VolumeUp => "volume_up",
+            Action::VolumeDown => "volume_down",
+            Action::Menu => "menu",
+            Action::PreviousProgram => "previous_program",
+            Action::NextProgram => "next_program",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Help => &["F1"],
+            Action::Pause => &["P"],
+            Action::Reset => &["Ctrl+R"],
+            Action::SaveState => &["F2"],
+            Action::LoadState => &["F3"],
+            Action::Rewind => &["Backspace"],
+            Action::Turbo => &["Left Shift"],
+            Action::Screenshot => &["F12"],
+            Action::ToggleScaling => &["F4"],
+            Action::Rotate => &["F5"],
+            Action::NextKeymap => &["F6"],
+            Action::ToggleDraws => &["F8"],
+            Action::TogglePractice => &["F9"],
+            Action::Debugger => &["F7"],
+            Action::Mute => &["M"],
+            Action::VolumeUp => &["=", "Keypad +"],
+            Action::VolumeDown => &["-", "Keypad -"],
+            Action::Menu => &["Tab"],
+            Action::PreviousProgram => &["Ctrl+Left"],
+            Action::NextProgram => &["Ctrl+Right"],
+        }
+    }
+}
+
+/// Key, pressed with or without Ctrl.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    pub keycode: Keycode,
+    pub ctrl: bool,
+}
+
+impl Hotkey {
+    /// Parse `Ctrl+<key>` or `<key>`, case insensitive.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (ctrl, name) = match s.get(..5) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("ctrl+") && s.len() > 5 => (true, &s[5..]),
+            _ => (false, s),
+        };
+        let keycode = Keycode::from_name(name.trim())?;
+        Some(Self { keycode, ctrl })
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        write!(f, "{}", self.keycode.name())
+    }
+}
+
+/// Keys of every action.
+pub struct Hotkeys {
+    actions: HashMap<Hotkey, Action>,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self::new(&[]).0
+    }
+}
+
+impl Hotkeys {
+    /// Bind actions to keys configured in `bindings`, pairs of action name
+    /// and keys, and the rest to their defaults. Also returns problems
+    /// found: invalid bindings, which are skipped, keys bound to several
+    /// actions, which keep the configured one or the first one, and keys
+    /// hiding a keypad key.
+    pub fn new(bindings: &[(String, String)]) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut configured: HashMap<Action, Vec<Hotkey>> = HashMap::new();
+        for (name, keys) in bindings {
+            let action = match Action::from_name(name) {
+                Some(action) => action,
+                None => {
+                    warnings.push(format!("unknown hotkey action: {}", name));
+                    continue;
+                }
+            };
+            let hotkeys = configured.entry(action).or_default();
+            for key in keys.split(',').filter(|key| !key.trim().is_empty()) {
+                match Hotkey::parse(key) {
+                    Some(hotkey) => hotkeys.push(hotkey),
+                    None => warnings.push(format!("unknown key for {}: {}", name, key.trim())),
+                }
+            }
+        }
+
+        // Configured keys are bound first so they win over defaults.
+        let mut ordered = Vec::new();
+        for action in Action::ALL {
+            if let Some(hotkeys) = configured.get(&action) {
+                ordered.extend(hotkeys.iter().map(|&hotkey| (hotkey, action)));
+            }
+        }
+        for action in Action::ALL {
+            if !configured.contains_key(&action) {
+                let defaults = action.default_keys().iter();
+                ordered.extend(defaults.map(|key| (Hotkey::parse(key).unwrap(), action)));
+            }
+        }
+
+        let mut actions: HashMap<Hotkey, Action> = HashMap::new();
+        for (hotkey, action) in ordered {
+            match actions.get(&hotkey) {
+                Some(&bound) if bound != action => warnings.push(format!(
+                    "{} is bound to both {} and {}, using it for {}",
+                    hotkey,
+                    bound.name(),
+                    action.name(),
+                    bound.name()
+                )),
+                Some(_) => {}
+                None => {
+                    actions.insert(hotkey, action);
+                }
+            }
+        }
+
+        let mut shadowed: Vec<_> = actions
+            .iter()
+            .filter(|(hotkey, _)| {
+                !hotkey.ctrl
+                    && Preset::ALL.iter().any(|&preset| {
+                        Keymap::new(preset, Rotation::None)
+                            .keypad_key(hotkey.keycode)
+                            .is_some()
+                    })
+            })
+            .map(|(hotkey, action)| format!("{} of {} hides a keypad key", hotkey, action.name()))
+            .collect();
+        shadowed.sort();
+        warnings.extend(shadowed);
+
+        (Self { actions }, warnings)
+    }
+
+    /// Action of `keycode` pressed with modifiers `keymod`.
+    pub fn action(&self, keycode: Keycode, keymod: Mod) -> Option<Action> {
+        let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+        self.actions.get(&Hotkey { keycode, ctrl }).copied()
+    }
+
+    /// First key of `action` as shown to the user, if it has any.
+    pub fn key_name(&self, action: Action) -> Option<String> {
+        self.key_names(action).into_iter().next()
+    }
+
+    /// Keys of `action` as shown to the user, sorted.
+    pub fn key_names(&self, action: Action) -> Vec<String> {
+        let mut keys: Vec<_> = self
+            .actions
+            .iter()
+            .filter(|(_, &bound)| bound == action)
+            .map(|(hotkey, _)| hotkey.to_string())
+            .collect();
+        keys.sort();
+        keys
+    }
+}
@@ -0,0 +1,72 @@
+//! Pointer inspector, shown with the debugger: the CHIP-8 pixel under the
+//! mouse pointer outlined, with its coordinates, whether it's lit and its
+//! color, for placing sprites precisely.
+
+use chip_8_emulator::{graphics::Framebuffer, palette::Palette};
+use sdl2::{pixels::Color, rect::Rect, render::WindowCanvas};
+
+use crate::display::{Rotation, Viewport};
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT};
+
+const TEXT_SCALE: usize = 2;
+const PADDING: i32 = 4;
+/// Distance between the pointer and the label.
+const OFFSET: i32 = 16;
+
+const OUTLINE: Color = Color::RGB(255, 200, 0);
+const BACKGROUND: Color = Color::RGB(40, 40, 40);
+const FOREGROUND: Color = Color::RGB(255, 255, 255);
+
+/// Outline the pixel under `pointer`, in window pixels, and label it. Does
+/// nothing when the pointer is off the screen.
+pub fn draw(
+    canvas: &mut WindowCanvas,
+    viewport: &Viewport,
+    rotation: Rotation,
+    pointer: (i32, i32),
+    screen: &dyn Framebuffer,
+    palette: &Palette,
+) -> Result<(), String> {
+    let Some((col, row)) = viewport.cell_at(pointer.0, pointer.1) else {
+        return Ok(());
+    };
+    let (x, y) = rotation.invert(col, row, screen.width(), screen.height());
+    let on = screen.pixel(x, y);
+    let [r, g, b] = palette.screen_color(screen, x, y);
+    let label = format!(
+        "{},{} {} #{:02X}{:02X}{:02X}",
+        x,
+        y,
+        if on { "on" } else { "off" },
+        r,
+        g,
+        b
+    );
+
+    canvas.set_draw_color(OUTLINE);
+    canvas.draw_rect(viewport.cell(col, row))?;
+
+    // Below right of the pointer, moved inside the window near its edges.
+    let (width, height) = canvas.output_size()?;
+    let label_width = text_width(&label, TEXT_SCALE) as i32 + 2 * PADDING;
+    let label_height = (GLYPH_HEIGHT * TEXT_SCALE) as i32 + 2 * PADDING;
+    let left = (pointer.0 + OFFSET).min(width as i32 - label_width).max(0);
+    let top = (pointer.1 + OFFSET)
+        .min(height as i32 - label_height)
+        .max(0);
+    canvas.set_draw_color(BACKGROUND);
+    canvas.fill_rect(Rect::new(
+        left,
+        top,
+        label_width as u32,
+        label_height as u32,
+    ))?;
+    draw_text(
+        canvas,
+        left + PADDING,
+        top + PADDING,
+        TEXT_SCALE,
+        &label,
+        FOREGROUND,
+    )
+}
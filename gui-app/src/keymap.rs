@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use sdl2::keyboard::Keycode;
+
+use crate::display::Rotation;
+
+/// Mapping of host keys to keypad keys, selectable per program since games
+/// use very different keys for the same actions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Preset {
+    /// Arrow keys press 2, 6, 8 and 4, the most common direction keys.
+    #[default]
+    Arrows2468,
+    /// Arrow keys press 5, 9, 8 and 7, an inverted T on the keypad.
+    Arrows5789,
+    /// W, D, S, A and arrow keys press 2, 6, 8 and 4, Space presses 5.
+    WasdSpace,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Arrows2468, Preset::Arrows5789, Preset::WasdSpace];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Arrows2468 => "arrows-2468",
+            Preset::Arrows5789 => "arrows-5789",
+            Preset::WasdSpace => "wasd-space",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|preset| preset.name() == name)
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&preset| preset == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Keypad keys pressed for up, right, down and left.
+    fn direction_keys(self) -> [u8; 4] {
+        match self {
+            Preset::Arrows2468 | Preset::WasdSpace => [0x2, 0x6, 0x8, 0x4],
+            Preset::Arrows5789 => [0x5, 0x9, 0x8, 0x7],
+        }
+    }
+}
+
+/// Mapping of host keyboard keys to CHIP-8 keypad keys.
+pub struct Keymap {
+    keys: HashMap<Keycode, u8>,
+}
+
+impl Keymap {
+    /// The 4x4 block under `1` mirrors the COSMAC VIP keypad layout:
+    ///
+    /// ```text
+    /// 1 2 3 4      1 2 3 C
+    /// Q W E R  ->  4 5 6 D
+    /// A S D F      7 8 9 E
+    /// Z X C V      A 0 B F
+    /// ```
+    ///
+    /// Direction keys of `preset` take precedence over the block and are
+    /// rotated along with the screen so that "up" stays up on a rotated
+    /// display.
+    pub fn new(preset: Preset, rotation: Rotation) -> Self {
+        let layout = [
+            (Keycode::Num1, 0x1),
+            (Keycode::Num2, 0x2),
+            (Keycode::Num3, 0x3),
+            (Keycode::Num4, 0xC),
+            (Keycode::Q, 0x4),
+            (Keycode::W, 0x5),
+            (Keycode::E, 0x6),
+            (Keycode::R, 0xD),
+            (Keycode::A, 0x7),
+            (Keycode::S, 0x8),
+            (Keycode::D, 0x9),
+            (Keycode::F, 0xE),
+            (Keycode::Z, 0xA),
+            (Keycode::X, 0x0),
+            (Keycode::C, 0xB),
+            (Keycode::V, 0xF),
+        ];
+        let mut keys: HashMap<Keycode, u8> = layout.iter().cloned().collect();
+        let arrows = [Keycode::Up, Keycode::Right, Keycode::Down, Keycode::Left];
+        keys.extend(directions(arrows, preset, rotation));
+        if preset == Preset::WasdSpace {
+            let wasd = [Keycode::W, Keycode::D, Keycode::S, Keycode::A];
+            keys.extend(directions(wasd, preset, rotation));
+            keys.insert(Keycode::Space, 0x5);
+        }
+        Self { keys }
+    }
+
+    pub fn keypad_key(&self, keycode: Keycode) -> Option<u8> {
+        self.keys.get(&keycode).copied()
+    }
+
+    /// Names of host keys pressing keypad key `key`, sorted.
+    pub fn key_names(&self, key: u8) -> Vec<String> {
+        let mut names: Vec<_> = self
+            .keys
+            .iter()
+            .filter(|(_, &keypad_key)| keypad_key == key)
+            .map(|(keycode, _)| keycode.name())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+/// Host keys in screen order up, right, down, left mapped to the direction
+/// keys of the game as seen through `rotation`.
+fn directions(
+    host_keys: [Keycode; 4],
+    preset: Preset,
+    rotation: Rotation,
+) -> impl Iterator<Item = (Keycode, u8)> {
+    let direction_keys = preset.direction_keys();
+    let quarter_turns = (rotation.degrees() / 90) as usize;
+    host_keys
+        .into_iter()
+        .enumerate()
+        .map(move |(i, keycode)| (keycode, direction_keys[(i + 4 - quarter_turns) % 4]))
+}
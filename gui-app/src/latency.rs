@@ -0,0 +1,103 @@
+//! Input latency measurement: time from a key press, as stamped by the SDL
+//! event queue, to the end of the frame in which the program checks the key
+//! with `SKP` and finds it held. Used to tune the order of event polling,
+//! emulation and frame pacing.
+
+use chip_8_emulator::{observer::VmObserver, VM};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+
+const KEYS: usize = 16;
+
+#[derive(Default)]
+pub struct LatencyProbe {
+    /// Event timestamp of keys pressed and not yet seen by the program, in
+    /// milliseconds since SDL started.
+    pressed_at: [Option<u32>; KEYS],
+    /// Keys `SKP` found held since the last frame, one bit each.
+    seen: Arc<AtomicU16>,
+    /// Latencies measured so far, in milliseconds.
+    samples: Vec<u32>,
+    /// Presses released before the program checked them.
+    missed: usize,
+}
+
+impl LatencyProbe {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Observer to add to the VM for key checks to be seen.
+    pub fn observer(&self) -> impl VmObserver + 'static {
+        KeyChecks(Arc::clone(&self.seen))
+    }
+
+    /// `key` went down at event timestamp `timestamp`.
+    pub fn key_pressed(&mut self, key: u8, timestamp: u32) {
+        let pressed_at = &mut self.pressed_at[key as usize];
+        if pressed_at.is_none() {
+            *pressed_at = Some(timestamp);
+        }
+    }
+
+    pub fn key_released(&mut self, key: u8) {
+        if self.pressed_at[key as usize].take().is_some() {
+            self.missed += 1;
+        }
+    }
+
+    /// Forget presses not seen yet, e.g. when another program starts.
+    pub fn reset(&mut self) {
+        self.pressed_at = [None; KEYS];
+        self.seen.store(0, Ordering::Relaxed);
+    }
+
+    /// A frame was emulated, `now` being the SDL tick count after it.
+    /// Returns the latencies of the presses the program saw in it.
+    pub fn frame_done(&mut self, now: u32) -> Vec<u32> {
+        let seen = self.seen.swap(0, Ordering::Relaxed);
+        let mut latencies = Vec::new();
+        for (key, pressed_at) in self.pressed_at.iter_mut().enumerate() {
+            if seen & (1 << key) == 0 {
+                continue;
+            }
+            if let Some(timestamp) = pressed_at.take() {
+                latencies.push(now.wrapping_sub(timestamp));
+            }
+        }
+        self.samples.extend(&latencies);
+        latencies
+    }
+
+    /// Summary of the measured latencies, `None` before the first one.
+    pub fn summary(&self) -> Option<String> {
+        let mut samples = self.samples.clone();
+        samples.sort_unstable();
+        let (&min, &max) = (samples.first()?, samples.last()?);
+        let mean = samples.iter().map(|&s| s as u64).sum::<u64>() / samples.len() as u64;
+        Some(format!(
+            "Input latency over {} presses: min {} ms, median {} ms, mean {} ms, max {} ms, {} released unseen",
+            samples.len(),
+            min,
+            samples[samples.len() / 2],
+            mean,
+            max,
+            self.missed
+        ))
+    }
+}
+
+/// Marks keys found held by `SKP Vx`.
+struct KeyChecks(Arc<AtomicU16>);
+
+impl VmObserver for KeyChecks {
+    fn before_instruction(&mut self, vm: &VM, _pc: u16, opcode: u16) {
+        if opcode & 0xF0FF != 0xE09E {
+            return;
+        }
+        let key = vm.registers().v[(opcode >> 8 & 0xF) as usize];
+        if vm.input().is_pressed(key) {
+            self.0.fetch_or(1 << key, Ordering::Relaxed);
+        }
+    }
+}
@@ -1,5 +1,22 @@
 extern crate sdl2;
 
 pub mod app;
+pub mod audio;
+pub mod config;
+pub mod debugger;
+pub mod display;
+pub mod help;
+pub mod hotkeys;
+pub mod inspector;
+pub mod keymap;
+pub mod latency;
+pub mod library;
+pub mod menu;
+pub mod options;
+pub mod practice;
+pub mod stop;
+pub mod text;
+pub mod toast;
 
 pub use app::{App, Error};
+pub use options::Options;
@@ -0,0 +1,210 @@
+//! Per-program settings and play statistics, remembered between runs.
+//!
+//! Stored under the `library` key of the app storage, next to the config
+//! file, as a `[program name]` line followed by `key = value` lines for
+//! every program.
+//! Like in the config file, unknown keys and invalid values are ignored.
+//!
+//! `save = 0x3A0..0x3B0` is only ever written by hand: it opts the program
+//! into keeping that memory range, e.g. high scores, between runs.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::display::{Rotation, Scaling};
+use crate::keymap::Preset;
+use chip_8_emulator::memory::MEMORY_SIZE;
+use chip_8_emulator::storage::Storage;
+
+/// Settings changed while the program was running.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProgramSettings {
+    pub scaling: Option<Scaling>,
+    pub rotation: Option<Rotation>,
+    pub keymap: Option<Preset>,
+    pub play_time: Duration,
+    /// Times the program was restarted with the reset hotkey.
+    pub resets: u32,
+    pub states_saved: u32,
+    pub states_loaded: u32,
+    /// Memory restored when the program starts and stored when it stops.
+    pub save_range: Option<Range<usize>>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Library {
+    programs: BTreeMap<String, ProgramSettings>,
+}
+
+impl Library {
+    /// Storage key of the library file.
+    pub const KEY: &'static str = "library";
+
+    /// Load library file, falling back to an empty library when it doesn't
+    /// exist.
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .load(Self::KEY)
+            .ok()
+            .flatten()
+            .map(|text| Self::parse(&String::from_utf8_lossy(&text)))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> io::Result<()> {
+        storage.store(Self::KEY, self.to_text().as_bytes())
+    }
+
+    /// Settings of program `name`, defaults if it was never run.
+    pub fn get(&self, name: &str) -> ProgramSettings {
+        self.programs.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> &mut ProgramSettings {
+        self.programs.entry(name.to_string()).or_default()
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut library = Self::default();
+        let mut current = None;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = Some(library.get_mut(name));
+                continue;
+            }
+            let (settings, (key, value)) = match (current.as_mut(), line.split_once('=')) {
+                (Some(settings), Some((key, value))) => (settings, (key.trim(), value.trim())),
+                _ => continue,
+            };
+            match key {
+                "scaling" => settings.scaling = Scaling::from_name(value).or(settings.scaling),
+                "rotation" => {
+                    settings.rotation = value
+                        .parse()
+                        .ok()
+                        .and_then(Rotation::from_degrees)
+                        .or(settings.rotation)
+                }
+                "keymap" => settings.keymap = Preset::from_name(value).or(settings.keymap),
+                "save" => {
+                    if let Some(range) = parse_range(value) {
+                        settings.save_range = Some(range);
+                    }
+                }
+                "play_time" => {
+                    if let Ok(seconds) = value.parse() {
+                        settings.play_time = Duration::from_secs(seconds);
+                    }
+                }
+                "resets" => settings.resets = value.parse().unwrap_or(settings.resets),
+                "states_saved" => {
+                    settings.states_saved = value.parse().unwrap_or(settings.states_saved)
+                }
+                "states_loaded" => {
+                    settings.states_loaded = value.parse().unwrap_or(settings.states_loaded)
+                }
+                _ => {}
+            }
+        }
+        library
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (name, settings) in &self.programs {
+            text.push_str(&format!("[{}]\n", name));
+            if let Some(scaling) = settings.scaling {
+                text.push_str(&format!("scaling = {}\n", scaling.name()));
+            }
+            if let Some(rotation) = settings.rotation {
+                text.push_str(&format!("rotation = {}\n", rotation.degrees()));
+            }
+            if let Some(keymap) = settings.keymap {
+                text.push_str(&format!("keymap = {}\n", keymap.name()));
+            }
+            if let Some(range) = &settings.save_range {
+                text.push_str(&format!("save = {:#X}..{:#X}\n", range.start, range.end));
+            }
+            let counts = [
+                ("resets", settings.resets),
+                ("states_saved", settings.states_saved),
+                ("states_loaded", settings.states_loaded),
+            ];
+            for (key, count) in counts {
+                if count > 0 {
+                    text.push_str(&format!("{} = {}\n", key, count));
+                }
+            }
+            text.push_str(&format!("play_time = {}\n\n", settings.play_time.as_secs()));
+        }
+        text
+    }
+}
+
+/// Memory range written as `start..end`, addresses in hex with `0x` prefix
+/// or decimal.
+fn parse_range(value: &str) -> Option<Range<usize>> {
+    let parse = |address: &str| match address.trim().strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => address.trim().parse().ok(),
+    };
+    let (start, end) = value.split_once("..")?;
+    let range = parse(start)?..parse(end)?;
+    (range.start < range.end && range.end <= MEMORY_SIZE).then_some(range)
+}
+
+/// Storage key of memory kept between runs of program `name`, see
+/// [`ProgramSettings::save_range`].
+pub fn saved_memory_key(name: &str) -> String {
+    format!("saves/{}.sav", name)
+}
+
+/// Storage key of the SUPER-CHIP RPL flags of program `name`.
+pub fn rpl_flags_key(name: &str) -> String {
+    format!("flags/{}.rpl", name)
+}
+
+/// Description of `program` from a text file next to it with the same name
+/// and `txt` extension: its first non-empty line.
+pub fn description(program: &Path) -> Option<String> {
+    let text = fs::read_to_string(program.with_extension("txt")).ok()?;
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+/// Statistics of a program for the start screen, `None` if it was never
+/// played.
+pub fn format_stats(settings: &ProgramSettings) -> Option<String> {
+    if settings.play_time.is_zero() {
+        return None;
+    }
+    let count =
+        |count: u32, what: &str| format!("{} {}{}", count, what, if count == 1 { "" } else { "s" });
+    Some(format!(
+        "Played {}, {}, {} saved, {} loaded",
+        format_play_time(settings.play_time),
+        count(settings.resets, "reset"),
+        count(settings.states_saved, "state"),
+        settings.states_loaded
+    ))
+}
+
+/// Play time as `1h 05m` or `12m`.
+pub fn format_play_time(time: Duration) -> String {
+    let minutes = time.as_secs() / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
@@ -1,12 +1,26 @@
-use chip_8_emulator_gui_app::{App, Error};
+use chip_8_emulator_gui_app::{menu::collect_programs, App, Error, Options};
 use std::env;
 
 fn main() -> Result<(), Error> {
-    let mut args = env::args();
-    let program_path = args.nth(1).unwrap();
+    let options = Options::parse(env::args().skip(1)).map_err(Error::Arguments)?;
+    let programs = collect_programs(&options.program_paths).map_err(Error::ProgramLoading)?;
 
-    let mut app = App::init()?;
-    app.load_program(&program_path)?;
+    let font_path = options.font_path.clone();
+    let big_font_path = options.big_font_path.clone();
+
+    let mut app = App::init(options)?;
+    if let Some(font_path) = font_path {
+        app.load_font(&font_path)?;
+    }
+    if let Some(big_font_path) = big_font_path {
+        app.load_big_font(&big_font_path)?;
+    }
+    let programs = if programs.is_empty() {
+        app.library_programs()?
+    } else {
+        programs
+    };
+    app.set_programs(programs)?;
     app.run()?;
 
     Ok(())
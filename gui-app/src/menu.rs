@@ -0,0 +1,183 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chip_8_emulator::demos::Demo;
+use sdl2::{pixels::Color, render::WindowCanvas};
+
+use crate::library;
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT};
+
+const TEXT_SCALE: usize = 2;
+const LINE_SPACING: usize = 4;
+const MARGIN: i32 = 20;
+/// Entries skipped by PageUp and PageDown.
+const PAGE: isize = 10;
+
+const BACKGROUND: Color = Color::RGB(0, 0, 0);
+const FOREGROUND: Color = Color::RGB(160, 160, 160);
+const HIGHLIGHT: Color = Color::RGB(255, 255, 255);
+
+/// Expand `paths` into program files: files are taken as they are,
+/// directories are replaced by the programs they contain, sorted by name.
+/// Description files, see [`crate::library::description`], are skipped.
+pub fn collect_programs(paths: &[impl AsRef<Path>]) -> io::Result<Vec<PathBuf>> {
+    let mut programs = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        if path.is_dir() {
+            let mut files = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .filter(|path| {
+                    path.as_ref().map_or(true, |path| {
+                        path.is_file() && path.extension().is_none_or(|ext| ext != "txt")
+                    })
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            files.sort();
+            programs.extend(files);
+        } else {
+            programs.push(path.to_path_buf());
+        }
+    }
+    Ok(programs)
+}
+
+/// Name of the program shown to the user.
+pub fn program_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Program to choose from: a file or a built-in demo.
+pub enum Source {
+    File(PathBuf),
+    Demo(Demo),
+}
+
+impl Source {
+    pub fn name(&self) -> String {
+        match self {
+            Source::File(path) => program_name(path),
+            Source::Demo(demo) => demo.name.to_string(),
+        }
+    }
+
+    pub fn description(&self) -> Option<String> {
+        match self {
+            Source::File(path) => library::description(path),
+            Source::Demo(demo) => Some(demo.description.to_string()),
+        }
+    }
+
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        match self {
+            Source::File(path) => fs::read(path),
+            Source::Demo(demo) => Ok((demo.build)()),
+        }
+    }
+}
+
+pub struct Entry {
+    pub name: String,
+    /// Short text shown right of the name.
+    pub details: String,
+    /// Longer text shown at the bottom when the entry is selected.
+    pub description: Option<String>,
+    /// Play statistics shown above the description when the entry is
+    /// selected.
+    pub stats: Option<String>,
+}
+
+/// Start screen listing programs to choose from.
+pub struct Menu {
+    entries: Vec<Entry>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(entries: Vec<Entry>, selected: usize) -> Self {
+        Self { entries, selected }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Move selection one entry up or down, wrapping around the list.
+    pub fn step(&mut self, delta: isize) {
+        let len = self.entries.len() as isize;
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        }
+    }
+
+    /// Move selection a page up or down, stopping at the list ends.
+    pub fn page(&mut self, pages: isize) {
+        self.jump(self.selected as isize + pages * PAGE);
+    }
+
+    /// Select entry `index`, clamped to the list.
+    pub fn jump(&mut self, index: isize) {
+        let last = self.entries.len().saturating_sub(1) as isize;
+        self.selected = index.clamp(0, last) as usize;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let (width, height) = canvas.output_size()?;
+        let line_height = GLYPH_HEIGHT * TEXT_SCALE + LINE_SPACING;
+
+        canvas.set_draw_color(BACKGROUND);
+        canvas.clear();
+        draw_text(
+            canvas,
+            MARGIN,
+            MARGIN,
+            TEXT_SCALE,
+            "Select program, Enter to start",
+            HIGHLIGHT,
+        )?;
+
+        // Title and an empty line above the list, an empty line, statistics
+        // and description below.
+        let list_top = MARGIN as usize + 2 * line_height;
+        let list_bottom = (height as usize).saturating_sub(MARGIN as usize + 3 * line_height);
+        let visible = (list_bottom.saturating_sub(list_top) / line_height).max(1);
+        let first = (self.selected + 1).saturating_sub(visible);
+        for (i, entry) in self.entries.iter().enumerate().skip(first).take(visible) {
+            let y = (list_top + (i - first) * line_height) as i32;
+            let (marker, color) = if i == self.selected {
+                ("> ", HIGHLIGHT)
+            } else {
+                ("  ", FOREGROUND)
+            };
+            let line = format!("{}{}", marker, entry.name);
+            draw_text(canvas, MARGIN, y, TEXT_SCALE, &line, color)?;
+            let details_x = width as i32 - MARGIN - text_width(&entry.details, TEXT_SCALE) as i32;
+            draw_text(canvas, details_x, y, TEXT_SCALE, &entry.details, color)?;
+        }
+
+        let selected = self.entries.get(self.selected);
+        let bottom_lines = [
+            selected.and_then(|entry| entry.stats.as_deref()),
+            selected.and_then(|entry| entry.description.as_deref()),
+        ];
+        for (i, line) in bottom_lines.into_iter().enumerate() {
+            if let Some(line) = line {
+                let y = (height as usize).saturating_sub(MARGIN as usize + (2 - i) * line_height);
+                draw_text(canvas, MARGIN, y as i32, TEXT_SCALE, line, FOREGROUND)?;
+            }
+        }
+        Ok(())
+    }
+}
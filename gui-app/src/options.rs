@@ -0,0 +1,146 @@
+use chip_8_emulator::memory::{FontStyle, MEMORY_SIZE};
+use chip_8_emulator::platform::Platform;
+
+use crate::display::{parse_color, DisplayOptions, Rotation, Scaling};
+use crate::keymap::Preset;
+use chip_8_emulator::blend::BlendMode;
+use chip_8_emulator::palette::Palette;
+use std::num::NonZeroU32;
+
+/// Command line options of the GUI app.
+#[derive(Default)]
+pub struct Options {
+    /// Program files or directories with them. The library directory is
+    /// used when empty.
+    pub program_paths: Vec<String>,
+    pub display: DisplayOptions,
+    pub font_style: FontStyle,
+    /// Instructions run per second, the core's default when `None`.
+    pub speed: Option<NonZeroU32>,
+    /// Keymap preset of programs without a remembered one.
+    pub keymap: Preset,
+    /// File with 80 bytes of hexadecimal digit sprites.
+    pub font_path: Option<String>,
+    /// File with 160 bytes of high-resolution digit sprites.
+    pub big_font_path: Option<String>,
+    /// IPS files or patch lists applied in order to every loaded program.
+    pub patch_paths: Vec<String>,
+    /// Memory address whose change restarts practice mode, e.g. lives left.
+    pub practice_watch: Option<usize>,
+    /// Directory crash dumps are written to when a program crashes, none
+    /// are written when `None`.
+    pub crash_dir: Option<String>,
+    /// Measure the time from key presses to the frame where the program
+    /// sees them, reported on screen and when quitting.
+    pub measure_latency: bool,
+    /// Open the debugger window at start.
+    pub debugger: bool,
+    /// Address of the HTTP server exposing emulator state.
+    #[cfg(feature = "debug-server")]
+    pub debug_server: Option<String>,
+}
+
+impl Options {
+    /// Parse arguments following the executable name.
+    ///
+    /// Usage: `[--scaling integer|fill] [--border RRGGBB]
+    /// [--rotate 0|90|180|270]
+    /// [--palette grayscale|octo|lcd|high-contrast|color-blind|RRGGBB,RRGGBB[,...]]
+    /// [--blend off|average|max]
+    /// [--profile chip8|chip48|schip|xochip]
+    /// [--font-style vip|chip48|dream6800|octo] [--speed N]
+    /// [--keymap arrows-2468|arrows-5789|wasd-space]
+    /// [--font FILE] [--big-font FILE] [--patch FILE]... [--practice-watch ADDR]
+    /// [--crash-dir DIR] [--measure-latency] [--debugger] [--debug-server ADDR]
+    /// [<program|directory>...]`
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--scaling" => {
+                    let name = value(&mut args, &arg)?;
+                    options.display.scaling = Scaling::from_name(&name)
+                        .ok_or_else(|| format!("unknown scaling: {}", name))?;
+                }
+                "--border" => {
+                    let color = value(&mut args, &arg)?;
+                    options.display.border_color = parse_color(&color)
+                        .ok_or_else(|| format!("invalid border color: {}", color))?;
+                }
+                "--palette" => {
+                    let palette = value(&mut args, &arg)?;
+                    options.display.palette = Palette::parse(&palette)
+                        .ok_or_else(|| format!("invalid palette: {}", palette))?;
+                }
+                "--blend" => {
+                    let name = value(&mut args, &arg)?;
+                    options.display.blend = BlendMode::from_name(&name)
+                        .ok_or_else(|| format!("unknown blend mode: {}", name))?;
+                }
+                "--rotate" => {
+                    let degrees = value(&mut args, &arg)?;
+                    options.display.rotation = degrees
+                        .parse()
+                        .ok()
+                        .and_then(Rotation::from_degrees)
+                        .ok_or_else(|| format!("invalid rotation: {}", degrees))?;
+                }
+                "--profile" => {
+                    let name = value(&mut args, &arg)?;
+                    let platform = Platform::from_name(&name)
+                        .ok_or_else(|| format!("unknown platform: {}", name))?;
+                    options.font_style = platform.font_style;
+                }
+                "--font-style" => {
+                    let name = value(&mut args, &arg)?;
+                    options.font_style = FontStyle::from_name(&name)
+                        .ok_or_else(|| format!("unknown font style: {}", name))?;
+                }
+                "--speed" => {
+                    let speed = value(&mut args, &arg)?;
+                    options.speed = Some(
+                        speed
+                            .parse()
+                            .map_err(|_| format!("invalid speed: {}", speed))?,
+                    );
+                }
+                "--keymap" => {
+                    let name = value(&mut args, &arg)?;
+                    options.keymap = Preset::from_name(&name)
+                        .ok_or_else(|| format!("unknown keymap: {}", name))?;
+                }
+                "--font" => options.font_path = Some(value(&mut args, &arg)?),
+                "--big-font" => options.big_font_path = Some(value(&mut args, &arg)?),
+                "--patch" => options.patch_paths.push(value(&mut args, &arg)?),
+                "--practice-watch" => {
+                    let address = value(&mut args, &arg)?;
+                    let parsed = match address.strip_prefix("0x") {
+                        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+                        None => address.parse().ok(),
+                    };
+                    options.practice_watch = Some(
+                        parsed
+                            .filter(|&address| address < MEMORY_SIZE)
+                            .ok_or_else(|| format!("invalid address: {}", address))?,
+                    );
+                }
+                "--crash-dir" => options.crash_dir = Some(value(&mut args, &arg)?),
+                "--measure-latency" => options.measure_latency = true,
+                "--debugger" => options.debugger = true,
+                #[cfg(feature = "debug-server")]
+                "--debug-server" => options.debug_server = Some(value(&mut args, &arg)?),
+                flag if flag.starts_with("--") => {
+                    return Err(format!("unknown option: {}", flag));
+                }
+                _ => options.program_paths.push(arg),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for {}", flag))
+}
@@ -0,0 +1,39 @@
+//! Practice mode: a savestate used as a loop point, reloaded every time an
+//! attempt at a difficult section ends.
+
+use chip_8_emulator::{snapshot::StateSnapshot, VM};
+
+pub struct Practice {
+    start: StateSnapshot,
+    /// Watched address with its value at the loop point, e.g. lives left.
+    watch: Option<(usize, u8)>,
+}
+
+impl Practice {
+    /// Mark the current state of `vm` as the loop point. When
+    /// `watch_address` is given, a change of the byte there ends an attempt.
+    pub fn new(vm: &VM, watch_address: Option<usize>) -> Self {
+        Self {
+            start: vm.snapshot(),
+            watch: watch_address.map(|address| (address, watched_byte(vm, address))),
+        }
+    }
+
+    /// Whether the attempt is over: the program halted or the watched byte
+    /// changed.
+    pub fn is_over(&self, vm: &VM) -> bool {
+        vm.is_halted()
+            || self
+                .watch
+                .is_some_and(|(address, value)| watched_byte(vm, address) != value)
+    }
+
+    /// Go back to the loop point.
+    pub fn restart(&self, vm: &mut VM) {
+        vm.restore(&self.start);
+    }
+}
+
+fn watched_byte(vm: &VM, address: usize) -> u8 {
+    vm.memory(address..address + 1).unwrap()[0]
+}
@@ -0,0 +1,122 @@
+//! Screen shown over a program which halted or crashed, instead of the
+//! window freezing or the app dying with a panic nobody sees.
+
+use std::any::Any;
+
+use chip_8_emulator::{
+    disasm::disassemble,
+    memory::{MEMORY_SIZE, PROGRAM_START_LOCATION},
+    VM,
+};
+use sdl2::{pixels::Color, rect::Rect, render::WindowCanvas};
+
+use crate::text::{draw_text, GLYPH_HEIGHT, GLYPH_WIDTH};
+
+const TEXT_SCALE: usize = 2;
+const LINE_SPACING: usize = 4;
+const PADDING: i32 = 10;
+const MARGIN: i32 = 10;
+/// Bytes of instructions shown before and after the program counter.
+const CONTEXT_BEFORE: usize = 6;
+const CONTEXT_AFTER: usize = 8;
+
+const BACKGROUND: Color = Color::RGB(40, 40, 40);
+const FOREGROUND: Color = Color::RGB(160, 160, 160);
+const HIGHLIGHT: Color = Color::RGB(255, 255, 255);
+
+pub enum Reason {
+    /// The program jumped to itself, see [`VM::is_halted`].
+    Halted,
+    /// An instruction failed or the emulator panicked with the message.
+    Crashed(String),
+}
+
+pub struct StopScreen {
+    lines: Vec<(String, Color)>,
+}
+
+impl StopScreen {
+    /// Describe the state `vm` stopped in. `switch_key` opens other
+    /// programs, if there are any.
+    pub fn new(vm: &VM, reason: Reason, switch_key: Option<String>) -> Self {
+        let pc = vm.registers().program_counter as usize;
+        let mut lines = vec![(
+            match reason {
+                Reason::Halted => "Program halted".to_string(),
+                Reason::Crashed(message) => format!("Program crashed: {}", message),
+            },
+            HIGHLIGHT,
+        )];
+
+        lines.push((format!("PC 0x{:03X}", pc), FOREGROUND));
+        lines.push((String::new(), FOREGROUND));
+        lines.extend(context(vm, pc, CONTEXT_BEFORE, CONTEXT_AFTER));
+        lines.push((String::new(), FOREGROUND));
+        let actions = match switch_key {
+            Some(key) => format!("R: restart   {}: other program", key),
+            None => "R: restart".to_string(),
+        };
+        lines.push((actions, FOREGROUND));
+        Self { lines }
+    }
+
+    /// Draw the screen as a panel at the top of the window, leaving the
+    /// rest of the program display visible.
+    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let (width, _) = canvas.output_size()?;
+        let line_height = GLYPH_HEIGHT * TEXT_SCALE + LINE_SPACING;
+        let panel_width = width as i32 - 2 * MARGIN;
+        let panel_height = (self.lines.len() * line_height) as i32 + 2 * PADDING;
+        canvas.set_draw_color(BACKGROUND);
+        canvas.fill_rect(Rect::new(
+            MARGIN,
+            MARGIN,
+            panel_width as u32,
+            panel_height as u32,
+        ))?;
+
+        let max_chars =
+            (panel_width - 2 * PADDING).max(0) as usize / ((GLYPH_WIDTH + 1) * TEXT_SCALE);
+        for (i, (line, color)) in self.lines.iter().enumerate() {
+            let text: String = line.chars().take(max_chars).collect();
+            let y = MARGIN + PADDING + (i * line_height) as i32;
+            draw_text(canvas, MARGIN + PADDING, y, TEXT_SCALE, &text, *color)?;
+        }
+        Ok(())
+    }
+}
+
+/// Disassembly of `before` bytes before `pc` to `after` bytes after it, the
+/// instruction at `pc` marked. It starts on the same word boundary as `pc`
+/// when `before` is even, the best guess there is at where earlier
+/// instructions begin.
+pub(crate) fn context(vm: &VM, pc: usize, before: usize, after: usize) -> Vec<(String, Color)> {
+    let start = pc.saturating_sub(before);
+    let end = (pc + after).min(MEMORY_SIZE);
+    disassemble(vm.memory(start..end).unwrap())
+        .iter()
+        .map(|line| {
+            let address = line.address - PROGRAM_START_LOCATION + start;
+            let (marker, color) = if address == pc {
+                (">", HIGHLIGHT)
+            } else {
+                (" ", FOREGROUND)
+            };
+            (
+                format!("{} {:03X}  {}", marker, address, line.text()),
+                color,
+            )
+        })
+        .collect()
+}
+
+/// Message of a caught panic.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown error".to_string()
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use sdl2::{pixels::Color, rect::Rect, render::WindowCanvas};
+
+use crate::text::{draw_text, text_width, GLYPH_HEIGHT};
+
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+const MAX_TOASTS: usize = 4;
+const TEXT_SCALE: usize = 2;
+const PADDING: i32 = 6;
+const MARGIN: i32 = 10;
+
+const BACKGROUND: Color = Color::RGBA(40, 40, 40, 255);
+const FOREGROUND: Color = Color::RGB(255, 255, 255);
+
+struct Toast {
+    message: String,
+    shown_at: Instant,
+}
+
+/// Queue of short on-screen messages, each visible for a couple of seconds.
+///
+/// Used to give visible feedback for hotkeys ("State saved to slot 2",
+/// "Speed 4x", "Muted") which otherwise change behavior silently.
+#[derive(Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Show `message`. The oldest message is dropped if too many are visible.
+    pub fn push(&mut self, message: impl Into<String>) {
+        if self.queue.len() == MAX_TOASTS {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(Toast {
+            message: message.into(),
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Forget messages that were shown long enough.
+    pub fn expire(&mut self, now: Instant) {
+        while let Some(toast) = self.queue.front() {
+            if now.duration_since(toast.shown_at) < TOAST_DURATION {
+                break;
+            }
+            self.queue.pop_front();
+        }
+    }
+
+    /// Draw visible messages stacked up from the bottom left corner.
+    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let (_, height) = canvas.output_size()?;
+        let line_height = (GLYPH_HEIGHT * TEXT_SCALE) as i32 + 2 * PADDING;
+        let mut y = height as i32 - MARGIN - line_height;
+        for toast in self.queue.iter().rev() {
+            let width = text_width(&toast.message, TEXT_SCALE) as i32 + 2 * PADDING;
+            canvas.set_draw_color(BACKGROUND);
+            canvas.fill_rect(Rect::new(MARGIN, y, width as u32, line_height as u32))?;
+            draw_text(
+                canvas,
+                MARGIN + PADDING,
+                y + PADDING,
+                TEXT_SCALE,
+                &toast.message,
+                FOREGROUND,
+            )?;
+            y -= line_height + MARGIN / 2;
+        }
+        Ok(())
+    }
+}